@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::io::{Error, Write};
+use std::path::Path;
+
+use chrono::Utc;
+use serde_json::json;
+
+use crate::{models::RoleResponse, password, services::ServiceContainer};
+
+pub const ADMIN_ROLE: &str = "admin";
+// The role of users who sign themselves up
+pub const DEFAULT_ROLE: &str = "subscriber";
+
+const DEFAULT_PERMISSIONS: [(&str, &str); 12] = [
+    ("post:create", "Create posts"),
+    ("post:read", "Read unpublished posts"),
+    ("post:update", "Update any post"),
+    ("post:delete", "Delete any post"),
+    ("post:publish", "Publish posts"),
+    ("comment:create", "Comment on posts"),
+    ("comment:moderate", "Edit and delete any comment"),
+    ("user:manage", "Create, update and delete users"),
+    ("role:manage", "Create, update and delete roles"),
+    ("audit:read", "Read the audit log"),
+    ("trash:manage", "List, restore and purge deleted content"),
+    ("webhook:manage", "Create, update and delete webhooks"),
+];
+
+const DEFAULT_ROLES: [(&str, &str, &[&str]); 4] = [
+    (
+        ADMIN_ROLE,
+        "Full access to every resource",
+        &[
+            "post:create",
+            "post:read",
+            "post:update",
+            "post:delete",
+            "post:publish",
+            "comment:create",
+            "comment:moderate",
+            "user:manage",
+            "role:manage",
+            "audit:read",
+            "trash:manage",
+            "webhook:manage",
+        ],
+    ),
+    (
+        "editor",
+        "Manages and publishes all content",
+        &[
+            "post:create",
+            "post:read",
+            "post:update",
+            "post:delete",
+            "post:publish",
+            "comment:create",
+            "comment:moderate",
+        ],
+    ),
+    (
+        "author",
+        "Writes posts",
+        &["post:create", "post:read", "comment:create"],
+    ),
+    (DEFAULT_ROLE, "Reads and comments", &["comment:create"]),
+];
+
+// Create the default roles and permissions, skipping any that already exist
+pub async fn seed(services: &ServiceContainer) -> Result<(), Error> {
+    let mut permission_ids = HashMap::new();
+    for (permission_name, description) in DEFAULT_PERMISSIONS {
+        let permission = match services.permission_service.find_by_name(permission_name).await {
+            Ok(permission) => permission,
+            Err(sqlx::Error::RowNotFound) => {
+                println!("Creating permission {}", permission_name);
+                services
+                    .permission_service
+                    .create(permission_name, description)
+                    .await
+                    .map_err(Error::other)?
+            }
+            Err(e) => return Err(Error::other(e)),
+        };
+        permission_ids.insert(permission_name, permission.id);
+    }
+
+    for (role_name, description, permissions) in DEFAULT_ROLES {
+        let role = find_or_create_role(services, role_name, description).await?;
+        for permission_name in permissions {
+            services
+                .permission_service
+                .assign_to_role(role.id, permission_ids[permission_name])
+                .await
+                .map_err(Error::other)?;
+        }
+    }
+    println!("Default roles and permissions are in place");
+    Ok(())
+}
+
+// Create a user with the admin role
+pub async fn create_admin(
+    services: &ServiceContainer,
+    username: &str,
+    email: &str,
+    password: &str,
+) -> Result<(), Error> {
+    let role = match services.role_service.find_by_name(ADMIN_ROLE).await {
+        Ok(role) => role,
+        Err(sqlx::Error::RowNotFound) => {
+            return Err(Error::other(
+                "admin role does not exist; run `blog-cms seed` first",
+            ))
+        }
+        Err(e) => return Err(Error::other(e)),
+    };
+    let password_hash = password::hash(password).map_err(Error::other)?;
+    let user = services
+        .user_service
+        .create(username, email, &password_hash, role.id)
+        .await
+        .map_err(Error::other)?;
+    println!("Created admin {} ({})", user.username, user.id);
+    Ok(())
+}
+
+// Set a new password for a user identified by username or email
+pub async fn reset_password(
+    services: &ServiceContainer,
+    login: &str,
+    password: &str,
+) -> Result<(), Error> {
+    let user = match services.user_service.find_by_login(login).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => {
+            return Err(Error::other(format!("no user matches {}", login)))
+        }
+        Err(e) => return Err(Error::other(e)),
+    };
+    let password_hash = password::hash(password).map_err(Error::other)?;
+    services
+        .user_service
+        .update_password(user.id, &password_hash)
+        .await
+        .map_err(Error::other)?;
+    println!("Password updated for {}", user.username);
+    Ok(())
+}
+
+// Print all users with their role names
+pub async fn list_users(services: &ServiceContainer) -> Result<(), Error> {
+    let roles = services.role_service.find_all().await.map_err(Error::other)?;
+    let role_names: HashMap<_, _> = roles
+        .roles
+        .into_iter()
+        .map(|role| (role.id, role.role_name))
+        .collect();
+    let users = services.user_service.find_all().await.map_err(Error::other)?;
+
+    println!("{:<36}  {:<24}  {:<32}  ROLE", "ID", "USERNAME", "EMAIL");
+    for user in users.users {
+        let role_name = role_names
+            .get(&user.role_id)
+            .map(String::as_str)
+            .unwrap_or("<unknown>");
+        println!(
+            "{:<36}  {:<24}  {:<32}  {}",
+            user.id, user.username, user.email, role_name
+        );
+    }
+    Ok(())
+}
+
+// Rebuild database indexes
+pub async fn reindex(services: &ServiceContainer) -> Result<(), Error> {
+    let tables = services
+        .maintenance_service
+        .reindex()
+        .await
+        .map_err(Error::other)?;
+    println!("Rebuilt indexes for {}", tables.join(", "));
+    Ok(())
+}
+
+// Export roles, permissions and the grants between them, users and posts as
+// JSON
+pub async fn export(services: &ServiceContainer, output: Option<&Path>) -> Result<(), Error> {
+    let roles = services.role_service.find_all().await.map_err(Error::other)?;
+    let permissions = services
+        .permission_service
+        .find_all()
+        .await
+        .map_err(Error::other)?;
+    let mut grants = HashMap::new();
+    for role in &roles.roles {
+        let granted = services
+            .permission_service
+            .find_by_role_id(role.id)
+            .await
+            .map_err(Error::other)?;
+        let ids: Vec<_> = granted
+            .permissions
+            .iter()
+            .map(|permission| permission.id)
+            .collect();
+        grants.insert(role.id, ids);
+    }
+    let users = services.user_service.find_all().await.map_err(Error::other)?;
+    let posts = services.post_service.find_all().await.map_err(Error::other)?;
+
+    let document = json!({
+        "exported_at": Utc::now(),
+        "roles": roles.roles,
+        "permissions": permissions.permissions,
+        "role_permissions": grants,
+        "users": users.users,
+        "posts": posts.posts,
+    });
+    let contents = serde_json::to_vec_pretty(&document).map_err(Error::other)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, contents)?;
+            eprintln!("Exported to {}", path.display());
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&contents)?;
+            stdout.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+async fn find_or_create_role(
+    services: &ServiceContainer,
+    role_name: &str,
+    description: &str,
+) -> Result<RoleResponse, Error> {
+    match services.role_service.find_by_name(role_name).await {
+        Ok(role) => Ok(role),
+        Err(sqlx::Error::RowNotFound) => {
+            println!("Creating role {}", role_name);
+            services
+                .role_service
+                .create(role_name, description)
+                .await
+                .map_err(Error::other)
+        }
+        Err(e) => Err(Error::other(e)),
+    }
+}
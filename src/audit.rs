@@ -0,0 +1,82 @@
+use std::future::Future;
+
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+use crate::{
+    handlers::bearer_token, proxy::TrustedProxies, services::AuthService,
+    telemetry::REQUEST_ID_HEADER,
+};
+
+// Who made the change being recorded, and from where
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+}
+
+tokio::task_local! {
+    // Set for the duration of each HTTP request
+    static CONTEXT: AuditContext;
+}
+
+// The context of the request being handled; empty outside of one, e.g. for
+// changes made by the CLI or background workers
+pub fn current() -> AuditContext {
+    CONTEXT.try_with(Clone::clone).unwrap_or_default()
+}
+
+// Run `future` with `context` as the current audit context
+pub async fn with_context<F: Future>(context: AuditContext, future: F) -> F::Output {
+    CONTEXT.scope(context, future).await
+}
+
+// What `record_context` works out the actor and their address with
+#[derive(Debug, Clone)]
+pub struct ContextSources {
+    pub auth: AuthService,
+    pub trusted_proxies: TrustedProxies,
+}
+
+// Capture the audit context of a request. The actor is the subject of a valid,
+// unrevoked bearer token; requests without one are recorded without an actor.
+pub async fn record_context(
+    State(sources): State<ContextSources>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_token);
+    let actor_id = match token {
+        Some(token) => sources
+            .auth
+            .verify(token)
+            .await
+            .ok()
+            .map(|claims| claims.sub),
+        None => None,
+    };
+    let request_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let ip = sources
+        .trusted_proxies
+        .client_ip(&request)
+        .map(|ip| ip.to_string());
+    let context = AuditContext {
+        actor_id,
+        ip,
+        request_id,
+    };
+    with_context(context, next.run(request)).await
+}
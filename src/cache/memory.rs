@@ -0,0 +1,105 @@
+use std::{
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use lru::LruCache;
+
+use super::CacheStore;
+
+// In-process store holding up to `capacity` entries, evicting the least
+// recently used one when full. Expired entries are dropped when read.
+#[derive(Debug)]
+pub struct MemoryStore {
+    entries: Mutex<LruCache<String, Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Vec<u8>,
+    expires_at: Instant,
+}
+
+impl MemoryStore {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        MemoryStore {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, LruCache<String, Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryStore {
+    async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut entries = self.entries();
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.value.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) {
+        let entry = Entry {
+            value,
+            expires_at: Instant::now() + ttl,
+        };
+        self.entries().put(key.to_string(), entry);
+    }
+
+    async fn remove(&self, keys: &[String]) {
+        let mut entries = self.entries();
+        for key in keys {
+            entries.pop(key);
+        }
+    }
+
+    async fn clear(&self, prefix: &str) {
+        let mut entries = self.entries();
+        let keys: Vec<String> = entries
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in keys {
+            entries.pop(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn least_recently_used_entry_is_evicted() {
+        let store = MemoryStore::new(2);
+        let ttl = Duration::from_secs(60);
+        store.set("a", vec![1], ttl).await;
+        store.set("b", vec![2], ttl).await;
+        store.get("a").await;
+        store.set("c", vec![3], ttl).await;
+
+        assert_eq!(store.get("a").await, Some(vec![1]));
+        assert_eq!(store.get("b").await, None);
+        assert_eq!(store.get("c").await, Some(vec![3]));
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_misses() {
+        let store = MemoryStore::new(2);
+        store.set("a", vec![1], Duration::ZERO).await;
+        assert_eq!(store.get("a").await, None);
+    }
+}
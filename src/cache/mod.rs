@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::config::CacheConfig;
+
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "redis")]
+pub use self::redis::RedisStore;
+pub use memory::MemoryStore;
+
+// Storage behind a `Cache`. Stores log their own failures and report them as
+// misses, so a broken cache only ever costs performance.
+#[async_trait]
+pub trait CacheStore: Debug + Send + Sync {
+    async fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration);
+
+    async fn remove(&self, keys: &[String]);
+
+    // Remove every key starting with `prefix`
+    async fn clear(&self, prefix: &str);
+}
+
+// Read-through cache of JSON serialized values under a named key space.
+// A disabled cache loads every value.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    inner: Option<Arc<CacheInner>>,
+}
+
+#[derive(Debug)]
+struct CacheInner {
+    name: &'static str,
+    store: Arc<dyn CacheStore>,
+    ttl: Duration,
+    // Bumped on every invalidation so loads that raced one aren't stored
+    generation: AtomicU64,
+    // One lock per key being loaded, so concurrent misses load it only once
+    loading: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Cache {
+    pub fn new(name: &'static str, store: Arc<dyn CacheStore>, ttl: Duration) -> Self {
+        Cache {
+            inner: Some(Arc::new(CacheInner {
+                name,
+                store,
+                ttl,
+                generation: AtomicU64::new(0),
+                loading: Mutex::new(HashMap::new()),
+            })),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Cache { inner: None }
+    }
+
+    // Build the store selected by the configuration: Redis when a URL is set,
+    // otherwise an in-process LRU
+    pub fn from_config(name: &'static str, config: &CacheConfig) -> Self {
+        if !config.enabled {
+            return Cache::disabled();
+        }
+        #[cfg(feature = "redis")]
+        if let Some(url) = &config.redis_url {
+            let store = RedisStore::new(url).expect("cache.redis_url is validated on load");
+            return Cache::new(name, Arc::new(store), config.ttl());
+        }
+        Cache::new(
+            name,
+            Arc::new(MemoryStore::new(config.capacity)),
+            config.ttl(),
+        )
+    }
+
+    // Return the cached value for `key`, or run `load` and cache its result
+    pub async fn get_or_load<T, E, F>(&self, key: &str, load: F) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, E>>,
+    {
+        self.get_or_load_if(key, load, |_| true).await
+    }
+
+    // Like `get_or_load`, but only caches loaded values that pass `cacheable`
+    pub async fn get_or_load_if<T, E, F>(
+        &self,
+        key: &str,
+        load: F,
+        cacheable: impl FnOnce(&T) -> bool,
+    ) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, E>>,
+    {
+        let Some(inner) = &self.inner else {
+            return load.await;
+        };
+        let key = inner.key(key);
+        if let Some(value) = inner.get(&key).await {
+            inner.record("hit");
+            return Ok(value);
+        }
+
+        let lock = inner.load_lock(&key);
+        let _loading = lock.lock().await;
+        // Whoever held the lock before us may have loaded the value already
+        if let Some(value) = inner.get(&key).await {
+            inner.record("hit");
+            return Ok(value);
+        }
+        inner.record("miss");
+        let generation = inner.generation.load(Ordering::SeqCst);
+        let result = load.await;
+        if let Ok(value) = &result {
+            if cacheable(value) && inner.generation.load(Ordering::SeqCst) == generation {
+                inner.set(&key, value).await;
+            }
+        }
+        inner.release(&key, &lock);
+        result
+    }
+
+    pub async fn invalidate(&self, keys: &[&str]) {
+        if let Some(inner) = &self.inner {
+            inner.generation.fetch_add(1, Ordering::SeqCst);
+            let keys: Vec<String> = keys.iter().map(|key| inner.key(key)).collect();
+            inner.store.remove(&keys).await;
+        }
+    }
+
+    // Invalidate every key, for changes that affect an unknown set of entries
+    pub async fn clear(&self) {
+        if let Some(inner) = &self.inner {
+            inner.generation.fetch_add(1, Ordering::SeqCst);
+            inner.store.clear(&inner.key("")).await;
+        }
+    }
+}
+
+impl CacheInner {
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.name, key)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let bytes = self.store.get(key).await?;
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                tracing::warn!(key, error = %e, "Discarding unreadable cache entry");
+                None
+            }
+        }
+    }
+
+    async fn set<T: Serialize>(&self, key: &str, value: &T) {
+        match serde_json::to_vec(value) {
+            Ok(bytes) => self.store.set(key, bytes, self.ttl).await,
+            Err(e) => tracing::warn!(key, error = %e, "Failed to serialize cache entry"),
+        }
+    }
+
+    fn load_lock(&self, key: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut loading = self.loading.lock().unwrap_or_else(|e| e.into_inner());
+        loading.entry(key.to_string()).or_default().clone()
+    }
+
+    fn release(&self, key: &str, lock: &Arc<tokio::sync::Mutex<()>>) {
+        let mut loading = self.loading.lock().unwrap_or_else(|e| e.into_inner());
+        if loading
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, lock))
+        {
+            loading.remove(key);
+        }
+    }
+
+    fn record(&self, result: &'static str) {
+        metrics::counter!("cache_requests_total", "cache" => self.name, "result" => result)
+            .increment(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn cache() -> Cache {
+        Cache::new(
+            "test",
+            Arc::new(MemoryStore::new(10)),
+            Duration::from_secs(60),
+        )
+    }
+
+    async fn load(cache: &Cache, loads: &AtomicUsize, value: u32) -> u32 {
+        cache
+            .get_or_load("key", async {
+                loads.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, ()>(value)
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn values_are_loaded_once() {
+        let cache = cache();
+        let loads = AtomicUsize::new(0);
+
+        assert_eq!(load(&cache, &loads, 1).await, 1);
+        assert_eq!(load(&cache, &loads, 2).await, 1);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn invalidated_values_are_reloaded() {
+        let cache = cache();
+        let loads = AtomicUsize::new(0);
+
+        load(&cache, &loads, 1).await;
+        cache.invalidate(&["key"]).await;
+        assert_eq!(load(&cache, &loads, 2).await, 2);
+        cache.clear().await;
+        assert_eq!(load(&cache, &loads, 3).await, 3);
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_cached() {
+        let cache = cache();
+        let result = cache
+            .get_or_load("key", async { Err::<u32, _>("down") })
+            .await;
+        assert_eq!(result, Err("down"));
+
+        let loads = AtomicUsize::new(0);
+        assert_eq!(load(&cache, &loads, 1).await, 1);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn uncacheable_values_are_not_cached() {
+        let cache = cache();
+        let value = cache
+            .get_or_load_if("key", async { Ok::<_, ()>(1) }, |value| *value > 1)
+            .await;
+        assert_eq!(value, Ok(1));
+
+        let loads = AtomicUsize::new(0);
+        assert_eq!(load(&cache, &loads, 2).await, 2);
+        assert_eq!(load(&cache, &loads, 3).await, 2);
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_misses_load_once() {
+        let cache = cache();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let cache = cache.clone();
+                let loads = loads.clone();
+                tokio::spawn(async move {
+                    cache
+                        .get_or_load("key", async {
+                            loads.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok::<_, ()>(1)
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), Ok(1));
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn disabled_cache_always_loads() {
+        let cache = Cache::disabled();
+        let loads = AtomicUsize::new(0);
+
+        load(&cache, &loads, 1).await;
+        assert_eq!(load(&cache, &loads, 2).await, 2);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+}
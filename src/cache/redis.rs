@@ -0,0 +1,146 @@
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, AsyncCommands, Client, RedisResult};
+use tokio::sync::OnceCell;
+
+use super::CacheStore;
+
+// Namespace for every key this application writes, so a Redis can be shared
+const KEY_PREFIX: &str = "blog-cms:";
+// How long a cache operation may take before it's treated as a miss
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Store shared by every instance through Redis. Connects on first use and
+// reconnects on its own after connection failures.
+pub struct RedisStore {
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl std::fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("connected", &self.connection.initialized())
+            .finish()
+    }
+}
+
+impl RedisStore {
+    pub fn new(url: &str) -> RedisResult<Self> {
+        Ok(RedisStore {
+            client: Client::open(url)?,
+            connection: OnceCell::new(),
+        })
+    }
+
+    async fn connection(&self) -> RedisResult<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+    }
+
+    // Run a command, logging failures and timeouts
+    async fn run<T, F, Fut>(&self, operation: &'static str, command: F) -> Option<T>
+    where
+        F: FnOnce(ConnectionManager) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        let result = tokio::time::timeout(COMMAND_TIMEOUT, async {
+            command(self.connection().await?).await
+        })
+        .await;
+        match result {
+            Ok(Ok(value)) => Some(value),
+            Ok(Err(e)) => {
+                tracing::warn!(operation, error = %e, "Redis cache command failed");
+                None
+            }
+            Err(_) => {
+                tracing::warn!(operation, "Redis cache command timed out");
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CacheStore for RedisStore {
+    async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        self.run("get", |mut connection| async move {
+            connection.get::<_, Option<Vec<u8>>>(key).await
+        })
+        .await
+        .flatten()
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        self.run("set", |mut connection| async move {
+            connection
+                .set_ex::<_, _, ()>(key, value, ttl.as_secs().max(1))
+                .await
+        })
+        .await;
+    }
+
+    async fn remove(&self, keys: &[String]) {
+        if keys.is_empty() {
+            return;
+        }
+        let keys: Vec<String> = keys
+            .iter()
+            .map(|key| format!("{}{}", KEY_PREFIX, key))
+            .collect();
+        self.run("del", |mut connection| async move {
+            connection.del::<_, ()>(keys).await
+        })
+        .await;
+    }
+
+    async fn clear(&self, prefix: &str) {
+        let pattern = format!("{}{}*", KEY_PREFIX, prefix);
+        self.run("clear", |mut connection| async move {
+            let keys: Vec<String> = {
+                let mut scan = connection.scan_match::<_, String>(pattern).await?;
+                let mut keys = Vec::new();
+                while let Some(key) = scan.next_item().await {
+                    keys.push(key);
+                }
+                keys
+            };
+            if !keys.is_empty() {
+                connection.del::<_, ()>(keys).await?;
+            }
+            Ok(())
+        })
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs against the Redis at TEST_REDIS_URL, and is skipped without one
+    #[tokio::test]
+    async fn values_round_trip_through_redis() {
+        let Ok(url) = std::env::var("TEST_REDIS_URL") else {
+            return;
+        };
+        let store = RedisStore::new(&url).unwrap();
+        let ttl = Duration::from_secs(60);
+        let key = format!("test:{}", uuid::Uuid::new_v4());
+
+        store.set(&key, vec![1, 2, 3], ttl).await;
+        assert_eq!(store.get(&key).await, Some(vec![1, 2, 3]));
+        store.remove(std::slice::from_ref(&key)).await;
+        assert_eq!(store.get(&key).await, None);
+
+        store.set(&key, vec![1], ttl).await;
+        store.clear("test:").await;
+        assert_eq!(store.get(&key).await, None);
+    }
+}
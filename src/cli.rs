@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+#[derive(Debug, Parser)]
+#[command(name = "blog-cms", version, about = "Blog CMS API server")]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// Flags that take precedence over the configuration file and environment
+#[derive(Debug, Default, Args)]
+pub struct ConfigArgs {
+    /// Configuration file to load instead of ./blog-cms.toml
+    #[arg(long, global = true, env = "BLOG_CMS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Address to listen on, e.g. 0.0.0.0:3000
+    #[arg(long, global = true)]
+    pub host: Option<String>,
+
+    /// Database connection URL (mysql://, postgres:// or sqlite:)
+    #[arg(long, global = true)]
+    pub database_url: Option<String>,
+
+    /// Apply pending migrations on startup
+    #[arg(long, global = true)]
+    pub run_migrations: bool,
+
+    /// Log level: trace, debug, info, warn or error
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the API server (default)
+    Serve,
+    /// Manage the database schema
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Create the default roles and permissions
+    Seed,
+    /// Create a user with the admin role
+    CreateAdmin {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long, env = "BLOG_CMS_ADMIN_PASSWORD", hide_env_values = true)]
+        password: String,
+    },
+    /// Set a new password for a user
+    ResetPassword {
+        /// Username or email of the user
+        #[arg(long)]
+        user: String,
+        #[arg(long, env = "BLOG_CMS_NEW_PASSWORD", hide_env_values = true)]
+        password: String,
+    },
+    /// List all users with their roles
+    ListUsers,
+    /// Rebuild database indexes
+    Reindex,
+    /// Export roles, permissions, users and posts as JSON
+    Export {
+        /// File to write to instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Subcommand)]
+pub enum MigrateAction {
+    /// Apply all pending migrations
+    Up,
+    /// Revert the most recently applied migration
+    Down,
+    /// Show applied and pending migrations
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, Subcommand)]
+pub enum ConfigAction {
+    /// Validate the configuration and print the effective values with secrets redacted
+    Check,
+}
@@ -1,27 +1,1287 @@
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{cli::ConfigArgs, db::Backend, mail, proxy::parse_network};
+
+const DEFAULT_CONFIG_FILE: &str = "blog-cms.toml";
+const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+const LOG_FORMATS: [&str; 2] = ["text", "json"];
+const REDACTED: &str = "<redacted>";
+
+// Effective configuration, resolved from built-in defaults, the configuration
+// file, environment variables and command line flags, in that order.
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
-    database_url: String,
-    host: String,
+    server: ServerConfig,
+    database: DatabaseConfig,
+    jwt: JwtConfig,
+    cors: CorsConfig,
+    cache: CacheConfig,
+    graphql: GraphqlConfig,
+    webhooks: WebhookConfig,
+    events: EventsConfig,
+    trash: TrashConfig,
+    rate_limit: RateLimitConfig,
+    mail: MailConfig,
+    password_reset: PasswordResetConfig,
+    log: LogConfig,
+    features: FeatureConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerConfig {
+    pub host: String,
+    pub request_timeout_secs: u64,
+    pub upload_limit_bytes: usize,
+    pub drain_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseConfig {
+    #[serde(serialize_with = "serialize_redacted_url")]
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    pub run_migrations: bool,
+    #[serde(serialize_with = "serialize_redacted_urls")]
+    pub replica_urls: Vec<String>,
+    pub read_your_writes_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JwtConfig {
+    pub secret: Option<Secret>,
+    pub expiration_secs: u64,
+    pub issuer: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub capacity: usize,
+    pub ttl_secs: u64,
+    #[serde(serialize_with = "serialize_redacted_optional_url")]
+    pub redis_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphqlConfig {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookConfig {
+    pub max_attempts: u32,
+    pub retry_base_delay_secs: u64,
+    pub timeout_secs: u64,
+    // Allow webhooks to loopback, link-local and private addresses
+    pub allow_private_targets: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventsConfig {
+    pub replay_capacity: usize,
+    pub keepalive_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashConfig {
+    // Trashed items older than this are purged; 0 keeps them until purged by hand
+    pub retention_days: u64,
+    pub purge_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    // Requests a client may make to each route group per period
+    pub requests: u32,
+    pub period_secs: u64,
+    // Stricter limits for logging in and signing up, per client address
+    pub login_requests: u32,
+    pub login_period_secs: u64,
+    pub registration_requests: u32,
+    pub registration_period_secs: u64,
+    // Addresses or CIDR blocks of proxies whose X-Forwarded-For is believed
+    pub trusted_proxies: Vec<String>,
+    #[serde(serialize_with = "serialize_redacted_optional_url")]
+    pub redis_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MailConfig {
+    // SMTP server to send mail through; without one, mail is only logged
+    #[serde(serialize_with = "serialize_redacted_optional_url")]
+    pub smtp_url: Option<String>,
+    // Send without TLS to an smtp:// server that isn't on this machine
+    pub allow_plaintext: bool,
+    pub from: String,
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PasswordResetConfig {
+    pub token_ttl_secs: u64,
+    // Link mailed to users, with `{token}` replaced by their reset token
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogConfig {
+    pub level: String,
+    pub format: String,
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureConfig {
+    pub registration: bool,
+    pub graphql: bool,
+    pub docs: bool,
+    pub metrics: bool,
+}
+
+// A configuration value that must never be printed
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Self {
+        Secret(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
 }
 
 impl Config {
-    pub fn new() -> Self {
+    pub fn load(args: &ConfigArgs) -> Result<Self, ConfigError> {
         use dotenvy::dotenv;
-        use std::env;
 
         // Load environment variables from .env file
         dotenv().ok();
 
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let host = env::var("HOST").expect("HOST must be set");
+        let mut errors = Vec::new();
+        let mut layered = PartialConfig::defaults();
+        if let Some(file) = PartialConfig::from_file(args.config.as_deref(), &mut errors) {
+            layered.merge(file);
+        }
+        layered.merge(PartialConfig::from_env(&mut errors));
+        layered.merge(PartialConfig::from_args(args));
 
-        Config { database_url, host }
+        let config = layered.resolve(&mut errors);
+        match config {
+            Some(config) if errors.is_empty() => Ok(config),
+            _ => Err(ConfigError { errors }),
+        }
     }
 
     pub fn get_database_url(&self) -> &str {
-        &self.database_url
+        &self.database.url
     }
 
     pub fn get_host(&self) -> &str {
-        &self.host
+        &self.server.host
+    }
+
+    pub fn should_run_migrations(&self) -> bool {
+        self.database.run_migrations
+    }
+
+    pub fn server(&self) -> &ServerConfig {
+        &self.server
+    }
+
+    pub fn database(&self) -> &DatabaseConfig {
+        &self.database
+    }
+
+    pub fn jwt(&self) -> &JwtConfig {
+        &self.jwt
+    }
+
+    pub fn cors(&self) -> &CorsConfig {
+        &self.cors
+    }
+
+    pub fn cache(&self) -> &CacheConfig {
+        &self.cache
+    }
+
+    pub fn graphql(&self) -> &GraphqlConfig {
+        &self.graphql
+    }
+
+    pub fn webhooks(&self) -> &WebhookConfig {
+        &self.webhooks
+    }
+
+    pub fn events(&self) -> &EventsConfig {
+        &self.events
+    }
+
+    pub fn trash(&self) -> &TrashConfig {
+        &self.trash
+    }
+
+    pub fn rate_limit(&self) -> &RateLimitConfig {
+        &self.rate_limit
+    }
+
+    pub fn mail(&self) -> &MailConfig {
+        &self.mail
+    }
+
+    pub fn password_reset(&self) -> &PasswordResetConfig {
+        &self.password_reset
+    }
+
+    pub fn log(&self) -> &LogConfig {
+        &self.log
+    }
+
+    pub fn features(&self) -> &FeatureConfig {
+        &self.features
+    }
+
+    // Render the configuration as TOML with secrets redacted
+    pub fn to_redacted_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("configuration is always serializable")
+    }
+}
+
+impl ServerConfig {
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+}
+
+impl DatabaseConfig {
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout_secs)
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_secs)
+    }
+
+    pub fn read_your_writes(&self) -> Duration {
+        Duration::from_secs(self.read_your_writes_secs)
+    }
+}
+
+impl CacheConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+}
+
+impl WebhookConfig {
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_secs(self.retry_base_delay_secs)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl EventsConfig {
+    pub fn keepalive(&self) -> Duration {
+        Duration::from_secs(self.keepalive_secs)
+    }
+}
+
+impl TrashConfig {
+    // How long trashed items are kept, or `None` when they are kept until
+    // purged by hand
+    pub fn retention(&self) -> Option<Duration> {
+        (self.retention_days > 0)
+            .then(|| Duration::from_secs(self.retention_days * 24 * 60 * 60))
+    }
+
+    pub fn purge_interval(&self) -> Duration {
+        Duration::from_secs(self.purge_interval_secs)
+    }
+}
+
+impl RateLimitConfig {
+    pub fn period(&self) -> Duration {
+        Duration::from_secs(self.period_secs)
+    }
+
+    pub fn login_period(&self) -> Duration {
+        Duration::from_secs(self.login_period_secs)
+    }
+
+    pub fn registration_period(&self) -> Duration {
+        Duration::from_secs(self.registration_period_secs)
+    }
+}
+
+impl MailConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl PasswordResetConfig {
+    pub fn token_ttl(&self) -> Duration {
+        Duration::from_secs(self.token_ttl_secs)
+    }
+}
+
+// Every problem found while loading the configuration, reported together
+#[derive(Debug)]
+pub struct ConfigError {
+    errors: Vec<String>,
+}
+
+impl ConfigError {
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for error in &self.errors {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Overwrite every field of `$target` that is set in `$source`
+macro_rules! merge_fields {
+    ($target:expr, $source:expr, $($field:ident),+ $(,)?) => {
+        $(
+            if $source.$field.is_some() {
+                $target.$field = $source.$field;
+            }
+        )+
+    };
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialConfig {
+    server: PartialServerConfig,
+    database: PartialDatabaseConfig,
+    jwt: PartialJwtConfig,
+    cors: PartialCorsConfig,
+    cache: PartialCacheConfig,
+    graphql: PartialGraphqlConfig,
+    webhooks: PartialWebhookConfig,
+    events: PartialEventsConfig,
+    trash: PartialTrashConfig,
+    rate_limit: PartialRateLimitConfig,
+    mail: PartialMailConfig,
+    password_reset: PartialPasswordResetConfig,
+    log: PartialLogConfig,
+    features: PartialFeatureConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialServerConfig {
+    host: Option<String>,
+    request_timeout_secs: Option<u64>,
+    upload_limit_bytes: Option<usize>,
+    drain_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialDatabaseConfig {
+    url: Option<String>,
+    max_connections: Option<u32>,
+    min_connections: Option<u32>,
+    acquire_timeout_secs: Option<u64>,
+    idle_timeout_secs: Option<u64>,
+    run_migrations: Option<bool>,
+    replica_urls: Option<Vec<String>>,
+    read_your_writes_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialJwtConfig {
+    secret: Option<String>,
+    expiration_secs: Option<u64>,
+    issuer: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialCorsConfig {
+    allowed_origins: Option<Vec<String>>,
+    allow_credentials: Option<bool>,
+    max_age_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialCacheConfig {
+    enabled: Option<bool>,
+    capacity: Option<usize>,
+    ttl_secs: Option<u64>,
+    redis_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialGraphqlConfig {
+    max_depth: Option<usize>,
+    max_complexity: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialWebhookConfig {
+    max_attempts: Option<u32>,
+    retry_base_delay_secs: Option<u64>,
+    timeout_secs: Option<u64>,
+    allow_private_targets: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialEventsConfig {
+    replay_capacity: Option<usize>,
+    keepalive_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialTrashConfig {
+    retention_days: Option<u64>,
+    purge_interval_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialRateLimitConfig {
+    enabled: Option<bool>,
+    requests: Option<u32>,
+    period_secs: Option<u64>,
+    login_requests: Option<u32>,
+    login_period_secs: Option<u64>,
+    registration_requests: Option<u32>,
+    registration_period_secs: Option<u64>,
+    trusted_proxies: Option<Vec<String>>,
+    redis_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialMailConfig {
+    smtp_url: Option<String>,
+    allow_plaintext: Option<bool>,
+    from: Option<String>,
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialPasswordResetConfig {
+    token_ttl_secs: Option<u64>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialLogConfig {
+    level: Option<String>,
+    format: Option<String>,
+    otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PartialFeatureConfig {
+    registration: Option<bool>,
+    graphql: Option<bool>,
+    docs: Option<bool>,
+    metrics: Option<bool>,
+}
+
+impl PartialConfig {
+    fn defaults() -> Self {
+        PartialConfig {
+            server: PartialServerConfig {
+                host: Some(String::from("127.0.0.1:3000")),
+                request_timeout_secs: Some(30),
+                upload_limit_bytes: Some(2 * 1024 * 1024),
+                drain_timeout_secs: Some(30),
+            },
+            database: PartialDatabaseConfig {
+                url: None,
+                max_connections: Some(10),
+                min_connections: Some(0),
+                acquire_timeout_secs: Some(30),
+                idle_timeout_secs: Some(600),
+                run_migrations: Some(false),
+                replica_urls: Some(Vec::new()),
+                read_your_writes_secs: Some(5),
+            },
+            jwt: PartialJwtConfig {
+                secret: None,
+                expiration_secs: Some(3600),
+                issuer: Some(String::from("blog-cms")),
+            },
+            cors: PartialCorsConfig {
+                allowed_origins: Some(Vec::new()),
+                allow_credentials: Some(false),
+                max_age_secs: Some(3600),
+            },
+            cache: PartialCacheConfig {
+                enabled: Some(true),
+                capacity: Some(1000),
+                ttl_secs: Some(60),
+                redis_url: None,
+            },
+            graphql: PartialGraphqlConfig {
+                max_depth: Some(10),
+                max_complexity: Some(250),
+            },
+            webhooks: PartialWebhookConfig {
+                max_attempts: Some(8),
+                retry_base_delay_secs: Some(30),
+                timeout_secs: Some(10),
+                allow_private_targets: Some(false),
+            },
+            events: PartialEventsConfig {
+                replay_capacity: Some(1000),
+                keepalive_secs: Some(15),
+            },
+            trash: PartialTrashConfig {
+                retention_days: Some(30),
+                purge_interval_secs: Some(3600),
+            },
+            rate_limit: PartialRateLimitConfig {
+                enabled: Some(true),
+                requests: Some(600),
+                period_secs: Some(60),
+                login_requests: Some(10),
+                login_period_secs: Some(60),
+                registration_requests: Some(10),
+                registration_period_secs: Some(3600),
+                trusted_proxies: Some(Vec::new()),
+                redis_url: None,
+            },
+            mail: PartialMailConfig {
+                smtp_url: None,
+                allow_plaintext: Some(false),
+                from: Some(String::from("blog-cms <noreply@localhost>")),
+                timeout_secs: Some(10),
+            },
+            password_reset: PartialPasswordResetConfig {
+                token_ttl_secs: Some(3600),
+                url: Some(String::from(
+                    "http://localhost:3000/reset-password?token={token}",
+                )),
+            },
+            log: PartialLogConfig {
+                level: Some(String::from("info")),
+                format: Some(String::from("text")),
+                otlp_endpoint: None,
+            },
+            features: PartialFeatureConfig {
+                registration: Some(true),
+                graphql: Some(true),
+                docs: Some(true),
+                metrics: Some(true),
+            },
+        }
+    }
+
+    // An explicitly requested file must exist; the default file is optional
+    fn from_file(path: Option<&Path>, errors: &mut Vec<String>) -> Option<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => return None,
+            Err(e) => {
+                errors.push(format!("{}: {}", path.display(), e));
+                return None;
+            }
+        };
+        match toml::from_str(&contents) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                errors.push(format!("{}: {}", path.display(), e.message()));
+                None
+            }
+        }
+    }
+
+    fn from_env(errors: &mut Vec<String>) -> Self {
+        PartialConfig {
+            server: PartialServerConfig {
+                host: env_var("HOST"),
+                request_timeout_secs: env_parse("REQUEST_TIMEOUT_SECS", errors),
+                upload_limit_bytes: env_parse("UPLOAD_LIMIT_BYTES", errors),
+                drain_timeout_secs: env_parse("DRAIN_TIMEOUT_SECS", errors),
+            },
+            database: PartialDatabaseConfig {
+                url: env_var("DATABASE_URL"),
+                max_connections: env_parse("DATABASE_MAX_CONNECTIONS", errors),
+                min_connections: env_parse("DATABASE_MIN_CONNECTIONS", errors),
+                acquire_timeout_secs: env_parse("DATABASE_ACQUIRE_TIMEOUT_SECS", errors),
+                idle_timeout_secs: env_parse("DATABASE_IDLE_TIMEOUT_SECS", errors),
+                run_migrations: env_bool("RUN_MIGRATIONS", errors),
+                replica_urls: env_list("DATABASE_REPLICA_URLS"),
+                read_your_writes_secs: env_parse("DATABASE_READ_YOUR_WRITES_SECS", errors),
+            },
+            jwt: PartialJwtConfig {
+                secret: env_var("JWT_SECRET"),
+                expiration_secs: env_parse("JWT_EXPIRATION_SECS", errors),
+                issuer: env_var("JWT_ISSUER"),
+            },
+            cors: PartialCorsConfig {
+                allowed_origins: env_list("CORS_ALLOWED_ORIGINS"),
+                allow_credentials: env_bool("CORS_ALLOW_CREDENTIALS", errors),
+                max_age_secs: env_parse("CORS_MAX_AGE_SECS", errors),
+            },
+            cache: PartialCacheConfig {
+                enabled: env_bool("CACHE_ENABLED", errors),
+                capacity: env_parse("CACHE_CAPACITY", errors),
+                ttl_secs: env_parse("CACHE_TTL_SECS", errors),
+                redis_url: env_var("CACHE_REDIS_URL"),
+            },
+            graphql: PartialGraphqlConfig {
+                max_depth: env_parse("GRAPHQL_MAX_DEPTH", errors),
+                max_complexity: env_parse("GRAPHQL_MAX_COMPLEXITY", errors),
+            },
+            webhooks: PartialWebhookConfig {
+                max_attempts: env_parse("WEBHOOK_MAX_ATTEMPTS", errors),
+                retry_base_delay_secs: env_parse("WEBHOOK_RETRY_BASE_DELAY_SECS", errors),
+                timeout_secs: env_parse("WEBHOOK_TIMEOUT_SECS", errors),
+                allow_private_targets: env_bool("WEBHOOK_ALLOW_PRIVATE_TARGETS", errors),
+            },
+            events: PartialEventsConfig {
+                replay_capacity: env_parse("EVENTS_REPLAY_CAPACITY", errors),
+                keepalive_secs: env_parse("EVENTS_KEEPALIVE_SECS", errors),
+            },
+            trash: PartialTrashConfig {
+                retention_days: env_parse("TRASH_RETENTION_DAYS", errors),
+                purge_interval_secs: env_parse("TRASH_PURGE_INTERVAL_SECS", errors),
+            },
+            rate_limit: PartialRateLimitConfig {
+                enabled: env_bool("RATE_LIMIT_ENABLED", errors),
+                requests: env_parse("RATE_LIMIT_REQUESTS", errors),
+                period_secs: env_parse("RATE_LIMIT_PERIOD_SECS", errors),
+                login_requests: env_parse("RATE_LIMIT_LOGIN_REQUESTS", errors),
+                login_period_secs: env_parse("RATE_LIMIT_LOGIN_PERIOD_SECS", errors),
+                registration_requests: env_parse("RATE_LIMIT_REGISTRATION_REQUESTS", errors),
+                registration_period_secs: env_parse("RATE_LIMIT_REGISTRATION_PERIOD_SECS", errors),
+                trusted_proxies: env_list("RATE_LIMIT_TRUSTED_PROXIES"),
+                redis_url: env_var("RATE_LIMIT_REDIS_URL"),
+            },
+            mail: PartialMailConfig {
+                smtp_url: env_var("MAIL_SMTP_URL"),
+                allow_plaintext: env_bool("MAIL_ALLOW_PLAINTEXT", errors),
+                from: env_var("MAIL_FROM"),
+                timeout_secs: env_parse("MAIL_TIMEOUT_SECS", errors),
+            },
+            password_reset: PartialPasswordResetConfig {
+                token_ttl_secs: env_parse("PASSWORD_RESET_TOKEN_TTL_SECS", errors),
+                url: env_var("PASSWORD_RESET_URL"),
+            },
+            log: PartialLogConfig {
+                level: env_var("LOG_LEVEL"),
+                format: env_var("LOG_FORMAT"),
+                otlp_endpoint: env_var("OTEL_EXPORTER_OTLP_ENDPOINT"),
+            },
+            features: PartialFeatureConfig {
+                registration: env_bool("FEATURE_REGISTRATION", errors),
+                graphql: env_bool("FEATURE_GRAPHQL", errors),
+                docs: env_bool("FEATURE_DOCS", errors),
+                metrics: env_bool("FEATURE_METRICS", errors),
+            },
+        }
+    }
+
+    fn from_args(args: &ConfigArgs) -> Self {
+        PartialConfig {
+            server: PartialServerConfig {
+                host: args.host.clone(),
+                ..Default::default()
+            },
+            database: PartialDatabaseConfig {
+                url: args.database_url.clone(),
+                run_migrations: args.run_migrations.then_some(true),
+                ..Default::default()
+            },
+            log: PartialLogConfig {
+                level: args.log_level.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn merge(&mut self, other: PartialConfig) {
+        merge_fields!(
+            self.server,
+            other.server,
+            host,
+            request_timeout_secs,
+            upload_limit_bytes,
+            drain_timeout_secs,
+        );
+        merge_fields!(
+            self.database,
+            other.database,
+            url,
+            max_connections,
+            min_connections,
+            acquire_timeout_secs,
+            idle_timeout_secs,
+            run_migrations,
+            replica_urls,
+            read_your_writes_secs,
+        );
+        merge_fields!(self.jwt, other.jwt, secret, expiration_secs, issuer);
+        merge_fields!(
+            self.cors,
+            other.cors,
+            allowed_origins,
+            allow_credentials,
+            max_age_secs,
+        );
+        merge_fields!(self.cache, other.cache, enabled, capacity, ttl_secs, redis_url);
+        merge_fields!(self.graphql, other.graphql, max_depth, max_complexity);
+        merge_fields!(
+            self.webhooks,
+            other.webhooks,
+            max_attempts,
+            retry_base_delay_secs,
+            timeout_secs,
+            allow_private_targets,
+        );
+        merge_fields!(self.events, other.events, replay_capacity, keepalive_secs);
+        merge_fields!(self.trash, other.trash, retention_days, purge_interval_secs);
+        merge_fields!(
+            self.rate_limit,
+            other.rate_limit,
+            enabled,
+            requests,
+            period_secs,
+            login_requests,
+            login_period_secs,
+            registration_requests,
+            registration_period_secs,
+            trusted_proxies,
+            redis_url,
+        );
+        merge_fields!(
+            self.mail,
+            other.mail,
+            smtp_url,
+            allow_plaintext,
+            from,
+            timeout_secs,
+        );
+        merge_fields!(self.password_reset, other.password_reset, token_ttl_secs, url);
+        merge_fields!(self.log, other.log, level, format, otlp_endpoint);
+        merge_fields!(
+            self.features,
+            other.features,
+            registration,
+            graphql,
+            docs,
+            metrics,
+        );
+    }
+
+    // Validate the merged layers, recording every problem in `errors`
+    fn resolve(self, errors: &mut Vec<String>) -> Option<Config> {
+        let server = self.server;
+        let database = self.database;
+        let jwt = self.jwt;
+        let cors = self.cors;
+        let cache = self.cache;
+        let graphql = self.graphql;
+        let webhooks = self.webhooks;
+        let events = self.events;
+        let trash = self.trash;
+        let rate_limit = self.rate_limit;
+        let mail = self.mail;
+        let password_reset = self.password_reset;
+        let log = self.log;
+        let features = self.features;
+
+        let host = required("server.host", server.host, errors);
+        if let Some(host) = &host {
+            let valid = host
+                .rsplit_once(':')
+                .is_some_and(|(name, port)| !name.is_empty() && port.parse::<u16>().is_ok());
+            if !valid {
+                errors.push(format!("server.host must be <host>:<port>, got `{}`", host));
+            }
+        }
+        let request_timeout_secs =
+            required("server.request_timeout_secs", server.request_timeout_secs, errors);
+        positive("server.request_timeout_secs", request_timeout_secs, errors);
+        let upload_limit_bytes =
+            required("server.upload_limit_bytes", server.upload_limit_bytes, errors);
+        positive("server.upload_limit_bytes", upload_limit_bytes, errors);
+        let drain_timeout_secs =
+            required("server.drain_timeout_secs", server.drain_timeout_secs, errors);
+
+        let url = required("database.url (DATABASE_URL)", database.url, errors);
+        if let Some(url) = &url {
+            match Backend::from_url(url).map(Backend::required_feature) {
+                None => errors.push(String::from(
+                    "database.url must be a mysql://, postgres:// or sqlite: URL",
+                )),
+                Some(Some(feature)) => errors.push(format!(
+                    "database.url needs a build with the `{}` feature",
+                    feature
+                )),
+                Some(None) => {}
+            }
+        }
+        let max_connections =
+            required("database.max_connections", database.max_connections, errors);
+        positive("database.max_connections", max_connections, errors);
+        let min_connections =
+            required("database.min_connections", database.min_connections, errors);
+        if let (Some(min), Some(max)) = (min_connections, max_connections) {
+            if min > max {
+                errors.push(format!(
+                    "database.min_connections ({}) exceeds database.max_connections ({})",
+                    min, max
+                ));
+            }
+        }
+        let acquire_timeout_secs =
+            required("database.acquire_timeout_secs", database.acquire_timeout_secs, errors);
+        positive("database.acquire_timeout_secs", acquire_timeout_secs, errors);
+        let idle_timeout_secs =
+            required("database.idle_timeout_secs", database.idle_timeout_secs, errors);
+        let run_migrations = required("database.run_migrations", database.run_migrations, errors);
+        let replica_urls = required("database.replica_urls", database.replica_urls, errors);
+        if let (Some(url), Some(replica_urls)) = (&url, &replica_urls) {
+            let backend = Backend::from_url(url);
+            for replica_url in replica_urls {
+                if Backend::from_url(replica_url) != backend {
+                    errors.push(format!(
+                        "database.replica_urls entry `{}` must use the same backend as database.url",
+                        redact_url(replica_url)
+                    ));
+                }
+            }
+        }
+        let read_your_writes_secs = required(
+            "database.read_your_writes_secs",
+            database.read_your_writes_secs,
+            errors,
+        );
+
+        if let Some(secret) = &jwt.secret {
+            if secret.len() < 32 {
+                errors.push(String::from("jwt.secret must be at least 32 characters"));
+            }
+        }
+        let expiration_secs = required("jwt.expiration_secs", jwt.expiration_secs, errors);
+        positive("jwt.expiration_secs", expiration_secs, errors);
+        let issuer = required("jwt.issuer", jwt.issuer, errors);
+
+        let allowed_origins = required("cors.allowed_origins", cors.allowed_origins, errors);
+        let allow_credentials = required("cors.allow_credentials", cors.allow_credentials, errors);
+        if let Some(origins) = &allowed_origins {
+            for origin in origins {
+                let valid = origin == "*"
+                    || origin.starts_with("http://")
+                    || origin.starts_with("https://");
+                if !valid {
+                    errors.push(format!(
+                        "cors.allowed_origins entry `{}` must be `*` or an http(s) origin",
+                        origin
+                    ));
+                }
+            }
+            if allow_credentials == Some(true) && origins.iter().any(|origin| origin == "*") {
+                errors.push(String::from(
+                    "cors.allow_credentials cannot be combined with a `*` origin",
+                ));
+            }
+        }
+        let max_age_secs = required("cors.max_age_secs", cors.max_age_secs, errors);
+
+        let cache_enabled = required("cache.enabled", cache.enabled, errors);
+        let capacity = required("cache.capacity", cache.capacity, errors);
+        positive("cache.capacity", capacity, errors);
+        let ttl_secs = required("cache.ttl_secs", cache.ttl_secs, errors);
+        positive("cache.ttl_secs", ttl_secs, errors);
+        if let Some(url) = &cache.redis_url {
+            if !cfg!(feature = "redis") {
+                errors.push(String::from(
+                    "cache.redis_url needs a build with the `redis` feature",
+                ));
+            } else if !url.starts_with("redis://") && !url.starts_with("rediss://") {
+                errors.push(format!(
+                    "cache.redis_url must be a redis:// or rediss:// URL, got `{}`",
+                    redact_url(url)
+                ));
+            }
+        }
+
+        let max_depth = required("graphql.max_depth", graphql.max_depth, errors);
+        positive("graphql.max_depth", max_depth, errors);
+        let max_complexity = required("graphql.max_complexity", graphql.max_complexity, errors);
+        positive("graphql.max_complexity", max_complexity, errors);
+
+        let max_attempts = required("webhooks.max_attempts", webhooks.max_attempts, errors);
+        positive("webhooks.max_attempts", max_attempts, errors);
+        let retry_base_delay_secs = required(
+            "webhooks.retry_base_delay_secs",
+            webhooks.retry_base_delay_secs,
+            errors,
+        );
+        positive("webhooks.retry_base_delay_secs", retry_base_delay_secs, errors);
+        let webhook_timeout_secs = required("webhooks.timeout_secs", webhooks.timeout_secs, errors);
+        positive("webhooks.timeout_secs", webhook_timeout_secs, errors);
+        let allow_private_targets = required(
+            "webhooks.allow_private_targets",
+            webhooks.allow_private_targets,
+            errors,
+        );
+
+        let replay_capacity = required("events.replay_capacity", events.replay_capacity, errors);
+        positive("events.replay_capacity", replay_capacity, errors);
+        let keepalive_secs = required("events.keepalive_secs", events.keepalive_secs, errors);
+        positive("events.keepalive_secs", keepalive_secs, errors);
+
+        let retention_days = required("trash.retention_days", trash.retention_days, errors);
+        let purge_interval_secs =
+            required("trash.purge_interval_secs", trash.purge_interval_secs, errors);
+        positive("trash.purge_interval_secs", purge_interval_secs, errors);
+
+        let rate_limit_enabled = required("rate_limit.enabled", rate_limit.enabled, errors);
+        let requests = required("rate_limit.requests", rate_limit.requests, errors);
+        positive("rate_limit.requests", requests, errors);
+        let period_secs = required("rate_limit.period_secs", rate_limit.period_secs, errors);
+        positive("rate_limit.period_secs", period_secs, errors);
+        let login_requests =
+            required("rate_limit.login_requests", rate_limit.login_requests, errors);
+        positive("rate_limit.login_requests", login_requests, errors);
+        let login_period_secs =
+            required("rate_limit.login_period_secs", rate_limit.login_period_secs, errors);
+        positive("rate_limit.login_period_secs", login_period_secs, errors);
+        let registration_requests = required(
+            "rate_limit.registration_requests",
+            rate_limit.registration_requests,
+            errors,
+        );
+        positive("rate_limit.registration_requests", registration_requests, errors);
+        let registration_period_secs = required(
+            "rate_limit.registration_period_secs",
+            rate_limit.registration_period_secs,
+            errors,
+        );
+        positive("rate_limit.registration_period_secs", registration_period_secs, errors);
+        let trusted_proxies =
+            required("rate_limit.trusted_proxies", rate_limit.trusted_proxies, errors);
+        for proxy in trusted_proxies.iter().flatten() {
+            if parse_network(proxy).is_none() {
+                errors.push(format!(
+                    "rate_limit.trusted_proxies entry `{}` must be an IP address or CIDR block",
+                    proxy
+                ));
+            }
+        }
+        if let Some(url) = &rate_limit.redis_url {
+            if !cfg!(feature = "redis") {
+                errors.push(String::from(
+                    "rate_limit.redis_url needs a build with the `redis` feature",
+                ));
+            } else if !url.starts_with("redis://") && !url.starts_with("rediss://") {
+                errors.push(format!(
+                    "rate_limit.redis_url must be a redis:// or rediss:// URL, got `{}`",
+                    redact_url(url)
+                ));
+            }
+        }
+
+        if let Some(url) = &mail.smtp_url {
+            if mail::parse_smtp_url(url).is_none() {
+                errors.push(format!(
+                    "mail.smtp_url must be an smtp:// or smtps:// URL, got `{}`",
+                    redact_url(url)
+                ));
+            }
+        }
+        let allow_plaintext = required("mail.allow_plaintext", mail.allow_plaintext, errors);
+        let from = required("mail.from", mail.from, errors);
+        if let Some(from) = &from {
+            if !mail::valid_address(from) {
+                errors.push(format!(
+                    "mail.from must be an address or `Name <address>`, got `{}`",
+                    from
+                ));
+            }
+        }
+        let mail_timeout_secs = required("mail.timeout_secs", mail.timeout_secs, errors);
+        positive("mail.timeout_secs", mail_timeout_secs, errors);
+
+        let token_ttl_secs =
+            required("password_reset.token_ttl_secs", password_reset.token_ttl_secs, errors);
+        positive("password_reset.token_ttl_secs", token_ttl_secs, errors);
+        let reset_url = required("password_reset.url", password_reset.url, errors);
+        if let Some(url) = &reset_url {
+            if !url.contains("{token}") {
+                errors.push(format!(
+                    "password_reset.url must contain a `{{token}}` placeholder, got `{}`",
+                    url
+                ));
+            }
+        }
+
+        let level = required("log.level", log.level, errors).map(|level| level.to_lowercase());
+        if let Some(level) = &level {
+            if !LOG_LEVELS.contains(&level.as_str()) {
+                errors.push(format!(
+                    "log.level must be one of {}, got `{}`",
+                    LOG_LEVELS.join(", "),
+                    level
+                ));
+            }
+        }
+        let format = required("log.format", log.format, errors).map(|format| format.to_lowercase());
+        if let Some(format) = &format {
+            if !LOG_FORMATS.contains(&format.as_str()) {
+                errors.push(format!(
+                    "log.format must be one of {}, got `{}`",
+                    LOG_FORMATS.join(", "),
+                    format
+                ));
+            }
+        }
+        if let Some(endpoint) = &log.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                errors.push(format!(
+                    "log.otlp_endpoint must be an http(s) URL, got `{}`",
+                    endpoint
+                ));
+            }
+        }
+        let registration = required("features.registration", features.registration, errors);
+        let graphql_enabled = required("features.graphql", features.graphql, errors);
+        let docs = required("features.docs", features.docs, errors);
+        let metrics = required("features.metrics", features.metrics, errors);
+
+        Some(Config {
+            server: ServerConfig {
+                host: host?,
+                request_timeout_secs: request_timeout_secs?,
+                upload_limit_bytes: upload_limit_bytes?,
+                drain_timeout_secs: drain_timeout_secs?,
+            },
+            database: DatabaseConfig {
+                url: url?,
+                max_connections: max_connections?,
+                min_connections: min_connections?,
+                acquire_timeout_secs: acquire_timeout_secs?,
+                idle_timeout_secs: idle_timeout_secs?,
+                run_migrations: run_migrations?,
+                replica_urls: replica_urls?,
+                read_your_writes_secs: read_your_writes_secs?,
+            },
+            jwt: JwtConfig {
+                secret: jwt.secret.map(Secret),
+                expiration_secs: expiration_secs?,
+                issuer: issuer?,
+            },
+            cors: CorsConfig {
+                allowed_origins: allowed_origins?,
+                allow_credentials: allow_credentials?,
+                max_age_secs: max_age_secs?,
+            },
+            cache: CacheConfig {
+                enabled: cache_enabled?,
+                capacity: capacity?,
+                ttl_secs: ttl_secs?,
+                redis_url: cache.redis_url,
+            },
+            graphql: GraphqlConfig {
+                max_depth: max_depth?,
+                max_complexity: max_complexity?,
+            },
+            webhooks: WebhookConfig {
+                max_attempts: max_attempts?,
+                retry_base_delay_secs: retry_base_delay_secs?,
+                timeout_secs: webhook_timeout_secs?,
+                allow_private_targets: allow_private_targets?,
+            },
+            events: EventsConfig {
+                replay_capacity: replay_capacity?,
+                keepalive_secs: keepalive_secs?,
+            },
+            trash: TrashConfig {
+                retention_days: retention_days?,
+                purge_interval_secs: purge_interval_secs?,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: rate_limit_enabled?,
+                requests: requests?,
+                period_secs: period_secs?,
+                login_requests: login_requests?,
+                login_period_secs: login_period_secs?,
+                registration_requests: registration_requests?,
+                registration_period_secs: registration_period_secs?,
+                trusted_proxies: trusted_proxies?,
+                redis_url: rate_limit.redis_url,
+            },
+            mail: MailConfig {
+                smtp_url: mail.smtp_url,
+                allow_plaintext: allow_plaintext?,
+                from: from?,
+                timeout_secs: mail_timeout_secs?,
+            },
+            password_reset: PasswordResetConfig {
+                token_ttl_secs: token_ttl_secs?,
+                url: reset_url?,
+            },
+            log: LogConfig {
+                level: level?,
+                format: format?,
+                otlp_endpoint: log.otlp_endpoint,
+            },
+            features: FeatureConfig {
+                registration: registration?,
+                graphql: graphql_enabled?,
+                docs: docs?,
+                metrics: metrics?,
+            },
+        })
+    }
+}
+
+fn required<T>(name: &str, value: Option<T>, errors: &mut Vec<String>) -> Option<T> {
+    if value.is_none() {
+        errors.push(format!("{} must be set", name));
+    }
+    value
+}
+
+fn positive<T: Default + PartialEq>(name: &str, value: Option<T>, errors: &mut Vec<String>) {
+    if value.is_some_and(|value| value == T::default()) {
+        errors.push(format!("{} must be greater than zero", name));
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// Comma separated list, ignoring empty entries
+fn env_list(name: &str) -> Option<Vec<String>> {
+    env_var(name).map(|list| {
+        list.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(String::from)
+            .collect()
+    })
+}
+
+fn env_parse<T: FromStr>(name: &str, errors: &mut Vec<String>) -> Option<T>
+where
+    T::Err: fmt::Display,
+{
+    let value = env_var(name)?;
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            errors.push(format!("{}: invalid value `{}`: {}", name, value, e));
+            None
+        }
+    }
+}
+
+fn env_bool(name: &str, errors: &mut Vec<String>) -> Option<bool> {
+    let value = env_var(name)?;
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => {
+            errors.push(format!("{}: expected a boolean, got `{}`", name, value));
+            None
+        }
+    }
+}
+
+// Print database and cache URLs without their password
+fn serialize_redacted_url<S: Serializer>(url: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&redact_url(url))
+}
+
+fn serialize_redacted_urls<S: Serializer>(urls: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(urls.iter().map(|url| redact_url(url)))
+}
+
+fn serialize_redacted_optional_url<S: Serializer>(
+    url: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match url {
+        Some(url) => serializer.serialize_some(&redact_url(url)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn redact_url(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => match rest.rsplit_once('@') {
+            Some((credentials, host)) => {
+                let user = credentials.split(':').next().unwrap_or_default();
+                format!("{}://{}:{}@{}", scheme, user, REDACTED, host)
+            }
+            None => url.to_string(),
+        },
+        None => String::from(REDACTED),
     }
 }
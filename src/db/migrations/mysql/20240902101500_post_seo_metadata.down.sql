@@ -0,0 +1,12 @@
+ALTER TABLE `posts` DROP FOREIGN KEY `fk_posts_featured_media`;
+
+ALTER TABLE `posts`
+    DROP COLUMN `og_image_url`,
+    DROP COLUMN `og_description`,
+    DROP COLUMN `og_title`,
+    DROP COLUMN `canonical_url`,
+    DROP COLUMN `meta_description`,
+    DROP COLUMN `excerpt`,
+    DROP COLUMN `featured_media_id`;
+
+DROP TABLE IF EXISTS `media`;
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS `media` (
+    `id` BINARY(16) NOT NULL,
+    `file_name` VARCHAR(255) NOT NULL,
+    `url` VARCHAR(2048) NOT NULL,
+    `mime_type` VARCHAR(255) NOT NULL,
+    `alt_text` VARCHAR(255),
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+ALTER TABLE `posts`
+    ADD COLUMN `featured_media_id` BINARY(16) NULL AFTER `published_at`,
+    ADD COLUMN `excerpt` TEXT NULL AFTER `featured_media_id`,
+    ADD COLUMN `meta_description` VARCHAR(320) NULL AFTER `excerpt`,
+    ADD COLUMN `canonical_url` VARCHAR(2048) NULL AFTER `meta_description`,
+    ADD COLUMN `og_title` VARCHAR(255) NULL AFTER `canonical_url`,
+    ADD COLUMN `og_description` TEXT NULL AFTER `og_title`,
+    ADD COLUMN `og_image_url` VARCHAR(2048) NULL AFTER `og_description`,
+    ADD CONSTRAINT `fk_posts_featured_media` FOREIGN KEY (`featured_media_id`) REFERENCES `media` (`id`) ON DELETE SET NULL;
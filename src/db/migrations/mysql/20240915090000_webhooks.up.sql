@@ -0,0 +1,28 @@
+CREATE TABLE IF NOT EXISTS `webhooks` (
+    `id` BINARY(16) NOT NULL,
+    `url` VARCHAR(2048) NOT NULL,
+    `secret` VARCHAR(255) NOT NULL,
+    `events` VARCHAR(255) NOT NULL,
+    `active` BOOLEAN DEFAULT TRUE NOT NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+CREATE TABLE IF NOT EXISTS `webhook_deliveries` (
+    `id` BINARY(16) NOT NULL,
+    `webhook_id` BINARY(16) NOT NULL,
+    `event` VARCHAR(64) NOT NULL,
+    `payload` TEXT NOT NULL,
+    `status` VARCHAR(16) DEFAULT 'pending' NOT NULL,
+    `attempts` INT DEFAULT 0 NOT NULL,
+    `response_code` INT NULL,
+    `error` TEXT NULL,
+    `next_attempt_at` TIMESTAMP NOT NULL,
+    `delivered_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    INDEX `idx_webhook_deliveries_due` (`status`, `next_attempt_at`),
+    FOREIGN KEY (`webhook_id`) REFERENCES `webhooks` (`id`) ON DELETE CASCADE
+);
@@ -0,0 +1,15 @@
+-- Domain events written in the same transaction as the change that caused
+-- them, and relayed to subscribers by a background worker
+CREATE TABLE IF NOT EXISTS `outbox` (
+    `id` BINARY(16) NOT NULL,
+    `event` VARCHAR(64) NOT NULL,
+    `aggregate_id` BINARY(16) NOT NULL,
+    `payload` TEXT NOT NULL,
+    `attempts` INT DEFAULT 0 NOT NULL,
+    `error` TEXT NULL,
+    `available_at` TIMESTAMP NOT NULL,
+    `processed_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    INDEX `idx_outbox_pending` (`processed_at`, `available_at`)
+);
@@ -0,0 +1,19 @@
+-- Append-only record of changes to users, roles and posts. The application
+-- only ever inserts into it. Times keep microseconds so entries sort in the
+-- order they were recorded
+CREATE TABLE IF NOT EXISTS `audit_log` (
+    `id` BINARY(16) NOT NULL,
+    `actor_id` BINARY(16) NULL,
+    `action` VARCHAR(16) NOT NULL,
+    `target_type` VARCHAR(16) NOT NULL,
+    `target_id` BINARY(16) NOT NULL,
+    `before_snapshot` TEXT NULL,
+    `after_snapshot` TEXT NULL,
+    `ip` VARCHAR(45) NULL,
+    `request_id` VARCHAR(64) NULL,
+    `created_at` TIMESTAMP(6) NOT NULL,
+    PRIMARY KEY (`id`),
+    INDEX `idx_audit_log_created_at` (`created_at`),
+    INDEX `idx_audit_log_actor` (`actor_id`, `created_at`),
+    INDEX `idx_audit_log_target` (`target_type`, `target_id`, `created_at`)
+);
@@ -0,0 +1,16 @@
+-- Rows still in the trash would reappear; purge them first
+DELETE FROM `comments` WHERE `deleted_at` IS NOT NULL;
+DELETE FROM `posts` WHERE `deleted_at` IS NOT NULL;
+DELETE FROM `users` WHERE `deleted_at` IS NOT NULL;
+
+ALTER TABLE `comments`
+    DROP INDEX `idx_comments_deleted_at`,
+    DROP COLUMN `deleted_at`;
+
+ALTER TABLE `posts`
+    DROP INDEX `idx_posts_deleted_at`,
+    DROP COLUMN `deleted_at`;
+
+ALTER TABLE `users`
+    DROP INDEX `idx_users_deleted_at`,
+    DROP COLUMN `deleted_at`;
@@ -0,0 +1,14 @@
+-- Deleted users, posts and comments stay in their tables, hidden from normal
+-- queries, until they are restored or purged. Rows deleted together share the
+-- same `deleted_at`, which is how they are restored together
+ALTER TABLE `users`
+    ADD COLUMN `deleted_at` TIMESTAMP(6) NULL,
+    ADD INDEX `idx_users_deleted_at` (`deleted_at`);
+
+ALTER TABLE `posts`
+    ADD COLUMN `deleted_at` TIMESTAMP(6) NULL,
+    ADD INDEX `idx_posts_deleted_at` (`deleted_at`);
+
+ALTER TABLE `comments`
+    ADD COLUMN `deleted_at` TIMESTAMP(6) NULL,
+    ADD INDEX `idx_comments_deleted_at` (`deleted_at`);
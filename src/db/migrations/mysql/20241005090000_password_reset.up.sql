@@ -0,0 +1,19 @@
+-- Bumped whenever a user's password changes; bearer tokens issued before
+-- carry an older version and are refused
+ALTER TABLE `users`
+    ADD COLUMN `session_version` BIGINT DEFAULT 0 NOT NULL;
+
+-- Password reset tokens are only stored as SHA-256 hashes, and can be used
+-- once before they expire
+CREATE TABLE IF NOT EXISTS `password_reset_tokens` (
+    `id` BINARY(16) NOT NULL,
+    `user_id` BINARY(16) NOT NULL,
+    `token_hash` CHAR(64) NOT NULL,
+    `expires_at` TIMESTAMP NOT NULL,
+    `used_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE INDEX `idx_password_reset_tokens_hash` (`token_hash`),
+    INDEX `idx_password_reset_tokens_user` (`user_id`),
+    FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS `posts_version`;
+DROP TRIGGER IF EXISTS `roles_version`;
+DROP TRIGGER IF EXISTS `users_version`;
+
+ALTER TABLE `posts` DROP COLUMN `version`;
+ALTER TABLE `roles` DROP COLUMN `version`;
+ALTER TABLE `users` DROP COLUMN `version`;
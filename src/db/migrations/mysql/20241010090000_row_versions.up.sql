@@ -0,0 +1,13 @@
+-- Every write to a user, role or post bumps its `version`, which its ETag is
+-- built from. `updated_at` can't serve: it only has whole seconds and ON UPDATE
+-- leaves it alone when no column value changes
+ALTER TABLE `users` ADD COLUMN `version` BIGINT DEFAULT 1 NOT NULL;
+ALTER TABLE `roles` ADD COLUMN `version` BIGINT DEFAULT 1 NOT NULL;
+ALTER TABLE `posts` ADD COLUMN `version` BIGINT DEFAULT 1 NOT NULL;
+
+CREATE TRIGGER `users_version` BEFORE UPDATE ON `users`
+    FOR EACH ROW SET NEW.`version` = OLD.`version` + 1;
+CREATE TRIGGER `roles_version` BEFORE UPDATE ON `roles`
+    FOR EACH ROW SET NEW.`version` = OLD.`version` + 1;
+CREATE TRIGGER `posts_version` BEFORE UPDATE ON `posts`
+    FOR EACH ROW SET NEW.`version` = OLD.`version` + 1;
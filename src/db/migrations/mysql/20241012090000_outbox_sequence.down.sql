@@ -0,0 +1,3 @@
+ALTER TABLE `outbox`
+    DROP INDEX `idx_outbox_aggregate`,
+    DROP COLUMN `sequence_number`;
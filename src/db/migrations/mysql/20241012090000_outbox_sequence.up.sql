@@ -0,0 +1,5 @@
+-- Messages are relayed in the order they were recorded, which `created_at`
+-- can't tell apart within a second
+ALTER TABLE `outbox`
+    ADD COLUMN `sequence_number` BIGINT NOT NULL AUTO_INCREMENT UNIQUE,
+    ADD INDEX `idx_outbox_aggregate` (`aggregate_id`, `sequence_number`);
@@ -0,0 +1,5 @@
+-- Deliveries queued for an outbox message remember it, so relaying the same
+-- message again doesn't queue them twice. Redeliveries leave it NULL.
+ALTER TABLE `webhook_deliveries`
+    ADD COLUMN `outbox_id` BINARY(16) NULL,
+    ADD UNIQUE INDEX `idx_webhook_deliveries_outbox` (`webhook_id`, `outbox_id`);
@@ -0,0 +1,33 @@
+CREATE TABLE IF NOT EXISTS media (
+    id UUID NOT NULL,
+    file_name VARCHAR(255) NOT NULL,
+    url VARCHAR(2048) NOT NULL,
+    mime_type VARCHAR(255) NOT NULL,
+    alt_text VARCHAR(255),
+    created_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE TRIGGER media_updated_at BEFORE UPDATE ON media
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+ALTER TABLE posts
+    ADD COLUMN featured_media_id UUID NULL,
+    ADD COLUMN excerpt TEXT NULL,
+    ADD COLUMN meta_description VARCHAR(320) NULL,
+    ADD COLUMN canonical_url VARCHAR(2048) NULL,
+    ADD COLUMN og_title VARCHAR(255) NULL,
+    ADD COLUMN og_description TEXT NULL,
+    ADD COLUMN og_image_url VARCHAR(2048) NULL,
+    ADD CONSTRAINT fk_posts_featured_media FOREIGN KEY (featured_media_id) REFERENCES media (id) ON DELETE SET NULL;
+
+-- Full-text search document: title ranks above the excerpt, which ranks above the body
+ALTER TABLE posts
+    ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', title), 'A') ||
+        setweight(to_tsvector('english', coalesce(excerpt, '')), 'B') ||
+        setweight(to_tsvector('english', content), 'C')
+    ) STORED;
+
+CREATE INDEX posts_search_vector_idx ON posts USING GIN (search_vector);
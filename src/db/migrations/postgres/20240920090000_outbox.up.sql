@@ -0,0 +1,16 @@
+-- Domain events written in the same transaction as the change that caused
+-- them, and relayed to subscribers by a background worker
+CREATE TABLE IF NOT EXISTS outbox (
+    id UUID NOT NULL,
+    event VARCHAR(64) NOT NULL,
+    aggregate_id UUID NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER DEFAULT 0 NOT NULL,
+    error TEXT NULL,
+    available_at TIMESTAMPTZ NOT NULL,
+    processed_at TIMESTAMPTZ NULL,
+    created_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX outbox_pending_idx ON outbox (processed_at, available_at);
@@ -0,0 +1,19 @@
+-- Append-only record of changes to users, roles and posts. The application
+-- only ever inserts into it
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID NOT NULL,
+    actor_id UUID NULL,
+    action VARCHAR(16) NOT NULL,
+    target_type VARCHAR(16) NOT NULL,
+    target_id UUID NOT NULL,
+    before_snapshot TEXT NULL,
+    after_snapshot TEXT NULL,
+    ip VARCHAR(45) NULL,
+    request_id VARCHAR(64) NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX audit_log_created_at_idx ON audit_log (created_at);
+CREATE INDEX audit_log_actor_idx ON audit_log (actor_id, created_at);
+CREATE INDEX audit_log_target_idx ON audit_log (target_type, target_id, created_at);
@@ -0,0 +1,10 @@
+-- Deleted users, posts and comments stay in their tables, hidden from normal
+-- queries, until they are restored or purged. Rows deleted together share the
+-- same deleted_at, which is how they are restored together
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ NULL;
+ALTER TABLE posts ADD COLUMN deleted_at TIMESTAMPTZ NULL;
+ALTER TABLE comments ADD COLUMN deleted_at TIMESTAMPTZ NULL;
+
+CREATE INDEX users_deleted_at_idx ON users (deleted_at);
+CREATE INDEX posts_deleted_at_idx ON posts (deleted_at);
+CREATE INDEX comments_deleted_at_idx ON comments (deleted_at);
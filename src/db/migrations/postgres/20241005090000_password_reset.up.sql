@@ -0,0 +1,19 @@
+-- Bumped whenever a user's password changes; bearer tokens issued before
+-- carry an older version and are refused
+ALTER TABLE users ADD COLUMN session_version BIGINT DEFAULT 0 NOT NULL;
+
+-- Password reset tokens are only stored as SHA-256 hashes, and can be used
+-- once before they expire
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ NULL,
+    created_at TIMESTAMPTZ DEFAULT now() NOT NULL,
+    PRIMARY KEY (id),
+    UNIQUE (token_hash),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX password_reset_tokens_user_idx ON password_reset_tokens (user_id);
@@ -0,0 +1,9 @@
+DROP TRIGGER IF EXISTS posts_version ON posts;
+DROP TRIGGER IF EXISTS roles_version ON roles;
+DROP TRIGGER IF EXISTS users_version ON users;
+
+ALTER TABLE posts DROP COLUMN version;
+ALTER TABLE roles DROP COLUMN version;
+ALTER TABLE users DROP COLUMN version;
+
+DROP FUNCTION IF EXISTS bump_version();
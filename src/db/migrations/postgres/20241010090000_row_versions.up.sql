@@ -0,0 +1,19 @@
+-- Every write to a user, role or post bumps its version, which its ETag is
+-- built from; two writes within the same clock tick share an updated_at
+CREATE OR REPLACE FUNCTION bump_version() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE users ADD COLUMN version BIGINT DEFAULT 1 NOT NULL;
+ALTER TABLE roles ADD COLUMN version BIGINT DEFAULT 1 NOT NULL;
+ALTER TABLE posts ADD COLUMN version BIGINT DEFAULT 1 NOT NULL;
+
+CREATE TRIGGER users_version BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION bump_version();
+CREATE TRIGGER roles_version BEFORE UPDATE ON roles
+    FOR EACH ROW EXECUTE FUNCTION bump_version();
+CREATE TRIGGER posts_version BEFORE UPDATE ON posts
+    FOR EACH ROW EXECUTE FUNCTION bump_version();
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS outbox_aggregate_idx;
+
+ALTER TABLE outbox DROP COLUMN sequence_number;
@@ -0,0 +1,5 @@
+-- Messages are relayed in the order they were recorded, which created_at
+-- can't tell apart within a transaction
+ALTER TABLE outbox ADD COLUMN sequence_number BIGINT GENERATED ALWAYS AS IDENTITY;
+
+CREATE INDEX outbox_aggregate_idx ON outbox (aggregate_id, sequence_number);
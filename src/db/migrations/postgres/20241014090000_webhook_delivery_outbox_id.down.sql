@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS webhook_deliveries_outbox_idx;
+
+ALTER TABLE webhook_deliveries DROP COLUMN outbox_id;
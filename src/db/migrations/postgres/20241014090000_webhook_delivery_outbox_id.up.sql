@@ -0,0 +1,5 @@
+-- Deliveries queued for an outbox message remember it, so relaying the same
+-- message again doesn't queue them twice. Redeliveries leave it NULL.
+ALTER TABLE webhook_deliveries ADD COLUMN outbox_id UUID NULL;
+
+CREATE UNIQUE INDEX webhook_deliveries_outbox_idx ON webhook_deliveries (webhook_id, outbox_id);
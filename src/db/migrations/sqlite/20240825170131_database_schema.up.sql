@@ -0,0 +1,105 @@
+-- SQLite has no BINARY(16), ENUM or ON UPDATE CURRENT_TIMESTAMP: ids are
+-- 16-byte BLOBs, the post status is a CHECK-constrained TEXT column and
+-- `updated_at` is maintained by triggers.
+
+CREATE TABLE IF NOT EXISTS `roles` (
+    `id` BLOB NOT NULL,
+    `role_name` TEXT UNIQUE NOT NULL,
+    `description` TEXT,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+CREATE TABLE IF NOT EXISTS `permissions` (
+    `id` BLOB NOT NULL,
+    `permission_name` TEXT UNIQUE NOT NULL,
+    `description` TEXT,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+CREATE TABLE IF NOT EXISTS `role_permissions` (
+    `role_id` BLOB NOT NULL,
+    `permission_id` BLOB NOT NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`role_id`, `permission_id`),
+    FOREIGN KEY (`role_id`) REFERENCES `roles` (`id`) ON DELETE CASCADE,
+    FOREIGN KEY (`permission_id`) REFERENCES `permissions` (`id`)
+);
+
+CREATE TABLE IF NOT EXISTS `users` (
+    `id` BLOB NOT NULL,
+    `username` TEXT UNIQUE NOT NULL,
+    `email` TEXT UNIQUE NOT NULL,
+    `password_hash` TEXT NOT NULL,
+    `role_id` BLOB NOT NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    FOREIGN KEY (`role_id`) REFERENCES `roles` (`id`)
+);
+
+CREATE TABLE IF NOT EXISTS `posts` (
+    `id` BLOB NOT NULL,
+    `title` TEXT NOT NULL,
+    `content` TEXT NOT NULL,
+    `user_id` BLOB NOT NULL,
+    `status` TEXT DEFAULT 'draft' NOT NULL CHECK (`status` IN ('draft', 'published', 'archived')),
+    `published_at` TIMESTAMP,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS `comments` (
+    `id` BLOB NOT NULL,
+    `content` TEXT NOT NULL,
+    `user_id` BLOB NOT NULL,
+    `post_id` BLOB NOT NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE,
+    FOREIGN KEY (`post_id`) REFERENCES `posts` (`id`) ON DELETE CASCADE
+);
+
+CREATE TRIGGER IF NOT EXISTS `roles_updated_at` AFTER UPDATE ON `roles`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `roles` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `permissions_updated_at` AFTER UPDATE ON `permissions`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `permissions` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `role_permissions_updated_at` AFTER UPDATE ON `role_permissions`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `role_permissions` SET `updated_at` = CURRENT_TIMESTAMP
+    WHERE `role_id` = NEW.`role_id` AND `permission_id` = NEW.`permission_id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `users_updated_at` AFTER UPDATE ON `users`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `users` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `posts_updated_at` AFTER UPDATE ON `posts`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `posts` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `comments_updated_at` AFTER UPDATE ON `comments`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `comments` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
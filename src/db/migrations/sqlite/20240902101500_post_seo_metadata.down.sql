@@ -0,0 +1,13 @@
+DROP TRIGGER IF EXISTS `fk_posts_featured_media_delete`;
+DROP TRIGGER IF EXISTS `fk_posts_featured_media_update`;
+DROP TRIGGER IF EXISTS `fk_posts_featured_media_insert`;
+
+ALTER TABLE `posts` DROP COLUMN `og_image_url`;
+ALTER TABLE `posts` DROP COLUMN `og_description`;
+ALTER TABLE `posts` DROP COLUMN `og_title`;
+ALTER TABLE `posts` DROP COLUMN `canonical_url`;
+ALTER TABLE `posts` DROP COLUMN `meta_description`;
+ALTER TABLE `posts` DROP COLUMN `excerpt`;
+ALTER TABLE `posts` DROP COLUMN `featured_media_id`;
+
+DROP TABLE IF EXISTS `media`;
@@ -0,0 +1,47 @@
+CREATE TABLE IF NOT EXISTS `media` (
+    `id` BLOB NOT NULL,
+    `file_name` TEXT NOT NULL,
+    `url` TEXT NOT NULL,
+    `mime_type` TEXT NOT NULL,
+    `alt_text` TEXT,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+CREATE TRIGGER IF NOT EXISTS `media_updated_at` AFTER UPDATE ON `media`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `media` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+ALTER TABLE `posts` ADD COLUMN `featured_media_id` BLOB NULL;
+ALTER TABLE `posts` ADD COLUMN `excerpt` TEXT NULL;
+ALTER TABLE `posts` ADD COLUMN `meta_description` TEXT NULL;
+ALTER TABLE `posts` ADD COLUMN `canonical_url` TEXT NULL;
+ALTER TABLE `posts` ADD COLUMN `og_title` TEXT NULL;
+ALTER TABLE `posts` ADD COLUMN `og_description` TEXT NULL;
+ALTER TABLE `posts` ADD COLUMN `og_image_url` TEXT NULL;
+
+-- SQLite can't drop a column that takes part in a foreign key, so the
+-- featured media reference is enforced by triggers instead, which keeps the
+-- down migration possible.
+CREATE TRIGGER IF NOT EXISTS `fk_posts_featured_media_insert` BEFORE INSERT ON `posts`
+FOR EACH ROW WHEN NEW.`featured_media_id` IS NOT NULL
+    AND NOT EXISTS (SELECT 1 FROM `media` WHERE `id` = NEW.`featured_media_id`)
+BEGIN
+    SELECT RAISE(ABORT, 'FOREIGN KEY constraint failed');
+END;
+
+CREATE TRIGGER IF NOT EXISTS `fk_posts_featured_media_update` BEFORE UPDATE OF `featured_media_id` ON `posts`
+FOR EACH ROW WHEN NEW.`featured_media_id` IS NOT NULL
+    AND NOT EXISTS (SELECT 1 FROM `media` WHERE `id` = NEW.`featured_media_id`)
+BEGIN
+    SELECT RAISE(ABORT, 'FOREIGN KEY constraint failed');
+END;
+
+CREATE TRIGGER IF NOT EXISTS `fk_posts_featured_media_delete` AFTER DELETE ON `media`
+FOR EACH ROW
+BEGIN
+    UPDATE `posts` SET `featured_media_id` = NULL WHERE `featured_media_id` = OLD.`id`;
+END;
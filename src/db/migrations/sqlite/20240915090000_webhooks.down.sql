@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS `webhook_deliveries_updated_at`;
+DROP TRIGGER IF EXISTS `webhooks_updated_at`;
+DROP TABLE IF EXISTS `webhook_deliveries`;
+DROP TABLE IF EXISTS `webhooks`;
@@ -0,0 +1,43 @@
+CREATE TABLE IF NOT EXISTS `webhooks` (
+    `id` BLOB NOT NULL,
+    `url` TEXT NOT NULL,
+    `secret` TEXT NOT NULL,
+    `events` TEXT NOT NULL,
+    `active` BOOLEAN DEFAULT TRUE NOT NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+-- `next_attempt_at` is always written by the application, so due rows can be
+-- found by comparing it with a timestamp bound in the same text format
+CREATE TABLE IF NOT EXISTS `webhook_deliveries` (
+    `id` BLOB NOT NULL,
+    `webhook_id` BLOB NOT NULL,
+    `event` TEXT NOT NULL,
+    `payload` TEXT NOT NULL,
+    `status` TEXT DEFAULT 'pending' NOT NULL,
+    `attempts` INTEGER DEFAULT 0 NOT NULL,
+    `response_code` INTEGER NULL,
+    `error` TEXT NULL,
+    `next_attempt_at` TIMESTAMP NOT NULL,
+    `delivered_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    FOREIGN KEY (`webhook_id`) REFERENCES `webhooks` (`id`) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS `idx_webhook_deliveries_due` ON `webhook_deliveries` (`status`, `next_attempt_at`);
+
+CREATE TRIGGER IF NOT EXISTS `webhooks_updated_at` AFTER UPDATE ON `webhooks`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `webhooks` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `webhook_deliveries_updated_at` AFTER UPDATE ON `webhook_deliveries`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `webhook_deliveries` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
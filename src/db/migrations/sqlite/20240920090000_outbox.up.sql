@@ -0,0 +1,18 @@
+-- Domain events written in the same transaction as the change that caused
+-- them, and relayed to subscribers by a background worker. `available_at`,
+-- `processed_at` and `created_at` are always written by the application, so
+-- they compare correctly with timestamps bound in the same text format
+CREATE TABLE IF NOT EXISTS `outbox` (
+    `id` BLOB NOT NULL,
+    `event` TEXT NOT NULL,
+    `aggregate_id` BLOB NOT NULL,
+    `payload` TEXT NOT NULL,
+    `attempts` INTEGER DEFAULT 0 NOT NULL,
+    `error` TEXT NULL,
+    `available_at` TIMESTAMP NOT NULL,
+    `processed_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+CREATE INDEX IF NOT EXISTS `idx_outbox_pending` ON `outbox` (`processed_at`, `available_at`);
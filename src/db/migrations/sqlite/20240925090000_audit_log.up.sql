@@ -0,0 +1,20 @@
+-- Append-only record of changes to users, roles and posts. The application
+-- only ever inserts into it, and always writes `created_at` itself so the
+-- time range filters compare timestamps in the same text format
+CREATE TABLE IF NOT EXISTS `audit_log` (
+    `id` BLOB NOT NULL,
+    `actor_id` BLOB NULL,
+    `action` TEXT NOT NULL,
+    `target_type` TEXT NOT NULL,
+    `target_id` BLOB NOT NULL,
+    `before_snapshot` TEXT NULL,
+    `after_snapshot` TEXT NULL,
+    `ip` TEXT NULL,
+    `request_id` TEXT NULL,
+    `created_at` TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+CREATE INDEX IF NOT EXISTS `idx_audit_log_created_at` ON `audit_log` (`created_at`);
+CREATE INDEX IF NOT EXISTS `idx_audit_log_actor` ON `audit_log` (`actor_id`, `created_at`);
+CREATE INDEX IF NOT EXISTS `idx_audit_log_target` ON `audit_log` (`target_type`, `target_id`, `created_at`);
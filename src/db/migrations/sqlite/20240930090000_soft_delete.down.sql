@@ -0,0 +1,12 @@
+-- Rows still in the trash would reappear; purge them first
+DELETE FROM `comments` WHERE `deleted_at` IS NOT NULL;
+DELETE FROM `posts` WHERE `deleted_at` IS NOT NULL;
+DELETE FROM `users` WHERE `deleted_at` IS NOT NULL;
+
+DROP INDEX IF EXISTS `idx_comments_deleted_at`;
+DROP INDEX IF EXISTS `idx_posts_deleted_at`;
+DROP INDEX IF EXISTS `idx_users_deleted_at`;
+
+ALTER TABLE `comments` DROP COLUMN `deleted_at`;
+ALTER TABLE `posts` DROP COLUMN `deleted_at`;
+ALTER TABLE `users` DROP COLUMN `deleted_at`;
@@ -0,0 +1,11 @@
+-- Deleted users, posts and comments stay in their tables, hidden from normal
+-- queries, until they are restored or purged. Rows deleted together share the
+-- same `deleted_at`, which is how they are restored together. The application
+-- always writes it itself so comparisons see the same text format
+ALTER TABLE `users` ADD COLUMN `deleted_at` TIMESTAMP NULL;
+ALTER TABLE `posts` ADD COLUMN `deleted_at` TIMESTAMP NULL;
+ALTER TABLE `comments` ADD COLUMN `deleted_at` TIMESTAMP NULL;
+
+CREATE INDEX IF NOT EXISTS `idx_users_deleted_at` ON `users` (`deleted_at`);
+CREATE INDEX IF NOT EXISTS `idx_posts_deleted_at` ON `posts` (`deleted_at`);
+CREATE INDEX IF NOT EXISTS `idx_comments_deleted_at` ON `comments` (`deleted_at`);
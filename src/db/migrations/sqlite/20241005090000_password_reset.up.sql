@@ -0,0 +1,20 @@
+-- Bumped whenever a user's password changes; bearer tokens issued before
+-- carry an older version and are refused
+ALTER TABLE `users` ADD COLUMN `session_version` INTEGER DEFAULT 0 NOT NULL;
+
+-- Password reset tokens are only stored as SHA-256 hashes, and can be used
+-- once before they expire. The timestamps are always written by the
+-- application, so they compare correctly with timestamps bound in the same
+-- text format
+CREATE TABLE IF NOT EXISTS `password_reset_tokens` (
+    `id` BLOB NOT NULL,
+    `user_id` BLOB NOT NULL,
+    `token_hash` TEXT UNIQUE NOT NULL,
+    `expires_at` TIMESTAMP NOT NULL,
+    `used_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`),
+    FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS `idx_password_reset_tokens_user` ON `password_reset_tokens` (`user_id`);
@@ -0,0 +1,25 @@
+DROP TRIGGER IF EXISTS `posts_version`;
+DROP TRIGGER IF EXISTS `roles_version`;
+DROP TRIGGER IF EXISTS `users_version`;
+
+ALTER TABLE `posts` DROP COLUMN `version`;
+ALTER TABLE `roles` DROP COLUMN `version`;
+ALTER TABLE `users` DROP COLUMN `version`;
+
+CREATE TRIGGER IF NOT EXISTS `roles_updated_at` AFTER UPDATE ON `roles`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `roles` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `users_updated_at` AFTER UPDATE ON `users`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `users` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `posts_updated_at` AFTER UPDATE ON `posts`
+FOR EACH ROW WHEN NEW.`updated_at` = OLD.`updated_at`
+BEGIN
+    UPDATE `posts` SET `updated_at` = CURRENT_TIMESTAMP WHERE `id` = NEW.`id`;
+END;
@@ -0,0 +1,40 @@
+-- Every write to a user, role or post bumps its `version`, which its ETag is
+-- built from; `CURRENT_TIMESTAMP` only has whole seconds. The triggers take
+-- over from the `updated_at` ones, which would otherwise fire each other
+ALTER TABLE `users` ADD COLUMN `version` INTEGER DEFAULT 1 NOT NULL;
+ALTER TABLE `roles` ADD COLUMN `version` INTEGER DEFAULT 1 NOT NULL;
+ALTER TABLE `posts` ADD COLUMN `version` INTEGER DEFAULT 1 NOT NULL;
+
+DROP TRIGGER IF EXISTS `users_updated_at`;
+DROP TRIGGER IF EXISTS `roles_updated_at`;
+DROP TRIGGER IF EXISTS `posts_updated_at`;
+
+CREATE TRIGGER IF NOT EXISTS `users_version` AFTER UPDATE ON `users`
+FOR EACH ROW WHEN NEW.`version` = OLD.`version`
+BEGIN
+    UPDATE `users`
+    SET `version` = OLD.`version` + 1,
+        `updated_at` = CASE WHEN NEW.`updated_at` = OLD.`updated_at`
+            THEN CURRENT_TIMESTAMP ELSE NEW.`updated_at` END
+    WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `roles_version` AFTER UPDATE ON `roles`
+FOR EACH ROW WHEN NEW.`version` = OLD.`version`
+BEGIN
+    UPDATE `roles`
+    SET `version` = OLD.`version` + 1,
+        `updated_at` = CASE WHEN NEW.`updated_at` = OLD.`updated_at`
+            THEN CURRENT_TIMESTAMP ELSE NEW.`updated_at` END
+    WHERE `id` = NEW.`id`;
+END;
+
+CREATE TRIGGER IF NOT EXISTS `posts_version` AFTER UPDATE ON `posts`
+FOR EACH ROW WHEN NEW.`version` = OLD.`version`
+BEGIN
+    UPDATE `posts`
+    SET `version` = OLD.`version` + 1,
+        `updated_at` = CASE WHEN NEW.`updated_at` = OLD.`updated_at`
+            THEN CURRENT_TIMESTAMP ELSE NEW.`updated_at` END
+    WHERE `id` = NEW.`id`;
+END;
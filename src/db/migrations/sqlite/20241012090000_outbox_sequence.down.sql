@@ -0,0 +1,23 @@
+CREATE TABLE `outbox_unsequenced` (
+    `id` BLOB NOT NULL,
+    `event` TEXT NOT NULL,
+    `aggregate_id` BLOB NOT NULL,
+    `payload` TEXT NOT NULL,
+    `attempts` INTEGER DEFAULT 0 NOT NULL,
+    `error` TEXT NULL,
+    `available_at` TIMESTAMP NOT NULL,
+    `processed_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP NOT NULL,
+    PRIMARY KEY (`id`)
+);
+
+INSERT INTO `outbox_unsequenced` (`id`, `event`, `aggregate_id`, `payload`, `attempts`, `error`,
+    `available_at`, `processed_at`, `created_at`)
+SELECT `id`, `event`, `aggregate_id`, `payload`, `attempts`, `error`, `available_at`,
+    `processed_at`, `created_at`
+FROM `outbox`;
+
+DROP TABLE `outbox`;
+ALTER TABLE `outbox_unsequenced` RENAME TO `outbox`;
+
+CREATE INDEX IF NOT EXISTS `idx_outbox_pending` ON `outbox` (`processed_at`, `available_at`);
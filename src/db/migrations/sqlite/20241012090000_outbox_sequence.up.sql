@@ -0,0 +1,28 @@
+-- Messages are relayed in the order they were recorded, which `created_at`
+-- can't tell apart within a transaction. SQLite can't add an AUTOINCREMENT
+-- column to an existing table, so the table is rebuilt around one
+CREATE TABLE `outbox_sequenced` (
+    `sequence_number` INTEGER PRIMARY KEY AUTOINCREMENT,
+    `id` BLOB NOT NULL UNIQUE,
+    `event` TEXT NOT NULL,
+    `aggregate_id` BLOB NOT NULL,
+    `payload` TEXT NOT NULL,
+    `attempts` INTEGER DEFAULT 0 NOT NULL,
+    `error` TEXT NULL,
+    `available_at` TIMESTAMP NOT NULL,
+    `processed_at` TIMESTAMP NULL,
+    `created_at` TIMESTAMP NOT NULL
+);
+
+INSERT INTO `outbox_sequenced` (`id`, `event`, `aggregate_id`, `payload`, `attempts`, `error`,
+    `available_at`, `processed_at`, `created_at`)
+SELECT `id`, `event`, `aggregate_id`, `payload`, `attempts`, `error`, `available_at`,
+    `processed_at`, `created_at`
+FROM `outbox`
+ORDER BY `created_at`, `rowid`;
+
+DROP TABLE `outbox`;
+ALTER TABLE `outbox_sequenced` RENAME TO `outbox`;
+
+CREATE INDEX IF NOT EXISTS `idx_outbox_pending` ON `outbox` (`processed_at`, `available_at`);
+CREATE INDEX IF NOT EXISTS `idx_outbox_aggregate` ON `outbox` (`aggregate_id`, `sequence_number`);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS `idx_webhook_deliveries_outbox`;
+
+ALTER TABLE `webhook_deliveries` DROP COLUMN `outbox_id`;
@@ -0,0 +1,5 @@
+-- Deliveries queued for an outbox message remember it, so relaying the same
+-- message again doesn't queue them twice. Redeliveries leave it NULL.
+ALTER TABLE `webhook_deliveries` ADD COLUMN `outbox_id` BLOB NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS `idx_webhook_deliveries_outbox` ON `webhook_deliveries` (`webhook_id`, `outbox_id`);
@@ -1,22 +1,387 @@
-use sqlx::MySqlPool;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
 
-use crate::config::Config;
+use log::LevelFilter;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::mysql::MySqlConnectOptions;
+#[cfg(feature = "postgres")]
+use sqlx::postgres::{PgConnectOptions, PgPool};
+use sqlx::pool::PoolOptions;
+#[cfg(feature = "sqlite")]
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::{ConnectOptions, MySqlPool, Pool};
+
+use crate::config::{Config, DatabaseConfig};
+
+mod replicas;
+
+pub use replicas::{read_from_primary, route_reads, Replicas};
+
+// Migrations are embedded into the binary at compile time, one set per
+// backend with matching version numbers. On MySQL and Postgres the migrator
+// takes a database advisory lock while applying or reverting, so concurrent
+// instances starting up at the same time don't race each other.
+static MYSQL_MIGRATOR: Migrator = sqlx::migrate!("./src/db/migrations/mysql");
+#[cfg(feature = "postgres")]
+static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./src/db/migrations/postgres");
+#[cfg(feature = "sqlite")]
+static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./src/db/migrations/sqlite");
+
+// Database engines, selected at runtime by the DATABASE_URL scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    MySql,
+    Postgres,
+    Sqlite,
+}
+
+impl Backend {
+    pub fn from_url(url: &str) -> Option<Self> {
+        if url.starts_with("mysql://") {
+            Some(Backend::MySql)
+        } else if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            Some(Backend::Postgres)
+        } else if url.starts_with("sqlite:") {
+            Some(Backend::Sqlite)
+        } else {
+            None
+        }
+    }
+
+    // The cargo feature that compiles in support for this backend, if any
+    pub fn required_feature(self) -> Option<&'static str> {
+        match self {
+            Backend::MySql => None,
+            Backend::Postgres if cfg!(feature = "postgres") => None,
+            Backend::Postgres => Some("postgres"),
+            Backend::Sqlite if cfg!(feature = "sqlite") => None,
+            Backend::Sqlite => Some("sqlite"),
+        }
+    }
+}
+
+// Connection pool for whichever backend the app is running on
+#[derive(Debug, Clone)]
+pub enum DbPool {
+    MySql(MySqlPool),
+    #[cfg(feature = "postgres")]
+    Postgres(PgPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqlitePool),
+}
+
+// Evaluate `$body` with `$pool` bound to the backend-specific pool. The body is
+// expanded once per backend, so it can use backend-specific types.
+macro_rules! with_pool {
+    ($db_pool:expr, $pool:ident => $body:expr) => {
+        match $db_pool {
+            $crate::db::DbPool::MySql($pool) => $body,
+            #[cfg(feature = "postgres")]
+            $crate::db::DbPool::Postgres($pool) => $body,
+            #[cfg(feature = "sqlite")]
+            $crate::db::DbPool::Sqlite($pool) => $body,
+        }
+    };
+}
+
+impl DbPool {
+    fn migrator(&self) -> &'static Migrator {
+        match self {
+            DbPool::MySql(_) => &MYSQL_MIGRATOR,
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(_) => &POSTGRES_MIGRATOR,
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(_) => &SQLITE_MIGRATOR,
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        with_pool!(self, pool => pool.size())
+    }
+
+    pub fn num_idle(&self) -> usize {
+        with_pool!(self, pool => pool.num_idle())
+    }
+
+    pub fn max_connections(&self) -> u32 {
+        with_pool!(self, pool => pool.options().get_max_connections())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        with_pool!(self, pool => pool.is_closed())
+    }
+
+    // Round trip a trivial query
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        with_pool!(self, pool => sqlx::query("SELECT 1").execute(pool).await.map(|_| ()))
+    }
+
+    // Check a connection out of the pool and return it straight away
+    pub async fn acquire(&self) -> Result<(), sqlx::Error> {
+        with_pool!(self, pool => pool.acquire().await.map(|_| ()))
+    }
+
+    // Close all connections, waiting for checked out ones to be returned
+    pub async fn close(&self) {
+        with_pool!(self, pool => pool.close().await)
+    }
+}
+
+// Backend-specific pool types that can be taken out of a `DbPool`
+pub trait BackendPool: Sized {
+    fn from_db_pool(pool: &DbPool) -> Option<&Self>;
+}
+
+impl BackendPool for MySqlPool {
+    fn from_db_pool(pool: &DbPool) -> Option<&Self> {
+        match pool {
+            DbPool::MySql(pool) => Some(pool),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl BackendPool for PgPool {
+    fn from_db_pool(pool: &DbPool) -> Option<&Self> {
+        match pool {
+            DbPool::Postgres(pool) => Some(pool),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl BackendPool for SqlitePool {
+    fn from_db_pool(pool: &DbPool) -> Option<&Self> {
+        match pool {
+            DbPool::Sqlite(pool) => Some(pool),
+            _ => None,
+        }
+    }
+}
 
 pub struct Database {
-    pool: MySqlPool,
+    pool: DbPool,
+    replicas: Replicas,
 }
 
 impl Database {
     pub async fn new(config: &Config) -> Self {
-        let database_url = config.get_database_url();
-        let pool = MySqlPool::connect(database_url)
-            .await
-            .expect("Error establishing database connection");
+        let settings = config.database();
+        let pool = connect(config.get_database_url(), settings, false).await;
+        let mut replicas = Vec::with_capacity(settings.replica_urls.len());
+        for url in &settings.replica_urls {
+            replicas.push(connect(url, settings, true).await);
+        }
 
-        Database { pool }
+        Database {
+            pool,
+            replicas: Replicas::new(replicas),
+        }
     }
 
-    pub fn get_pool(&self) -> MySqlPool {
+    pub fn get_pool(&self) -> DbPool {
         self.pool.clone()
     }
+
+    pub fn get_replicas(&self) -> Replicas {
+        self.replicas.clone()
+    }
+
+    // Close all connections, waiting for checked out ones to be returned
+    pub async fn close(&self) {
+        self.replicas.close().await;
+        self.pool.close().await;
+    }
+
+    // Apply all pending migrations
+    pub async fn migrate_up(&self) -> Result<(), MigrationError> {
+        self.check_schema_version().await?;
+        let migrator = self.pool.migrator();
+        with_pool!(&self.pool, pool => migrator.run(pool).await)?;
+        Ok(())
+    }
+
+    // Revert the most recently applied migration
+    pub async fn migrate_down(&self) -> Result<Option<i64>, MigrationError> {
+        self.check_schema_version().await?;
+        let applied = self.applied_versions().await?;
+        let Some((&latest, rest)) = applied.split_last() else {
+            return Ok(None);
+        };
+        let target = rest.last().copied().unwrap_or(0);
+        let migrator = self.pool.migrator();
+        with_pool!(&self.pool, pool => migrator.undo(pool, target).await)?;
+        Ok(Some(latest))
+    }
+
+    // List every known migration alongside whether it has been applied
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>, MigrationError> {
+        let applied = self.applied_versions().await?;
+        let migrator = self.pool.migrator();
+        let mut statuses: Vec<MigrationStatus> = migrator
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.contains(&migration.version),
+            })
+            .collect();
+        // Versions applied by a newer binary are reported too, so operators
+        // can see why startup refuses to continue.
+        statuses.extend(
+            applied
+                .iter()
+                .filter(|version| !migrator.version_exists(**version))
+                .map(|version| MigrationStatus {
+                    version: *version,
+                    description: String::from("<unknown to this binary>"),
+                    applied: true,
+                }),
+        );
+        statuses.sort_by_key(|status| status.version);
+        Ok(statuses)
+    }
+
+    // Fail when the database has migrations this binary doesn't know about
+    pub async fn check_schema_version(&self) -> Result<(), MigrationError> {
+        let applied = self.applied_versions().await?;
+        let latest_known = latest_known_version(&self.pool);
+        let migrator = self.pool.migrator();
+        match applied
+            .iter()
+            .find(|version| !migrator.version_exists(**version))
+        {
+            Some(&version) => Err(MigrationError::SchemaAhead {
+                database_version: version,
+                binary_version: latest_known,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    async fn applied_versions(&self) -> Result<Vec<i64>, MigrationError> {
+        applied_versions(&self.pool).await
+    }
+}
+
+// Versions of all migrations applied to the database, in ascending order
+pub async fn applied_versions(pool: &DbPool) -> Result<Vec<i64>, MigrationError> {
+    let mut versions: Vec<i64> = with_pool!(pool, pool => {
+        let mut conn = pool.acquire().await.map_err(MigrateError::from)?;
+        conn.ensure_migrations_table().await?;
+        conn.list_applied_migrations().await?
+    })
+    .into_iter()
+    .map(|migration| migration.version)
+    .collect();
+    versions.sort_unstable();
+    Ok(versions)
+}
+
+// Version of the newest migration embedded in this binary for the backend
+pub fn latest_known_version(pool: &DbPool) -> i64 {
+    pool.migrator()
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+// Open a pool for `url`. Replica pools connect lazily, so an unreachable
+// replica doesn't stop the app from starting.
+async fn connect(url: &str, settings: &DatabaseConfig, lazy: bool) -> DbPool {
+    match Backend::from_url(url) {
+        #[cfg(feature = "postgres")]
+        Some(Backend::Postgres) => {
+            let connect_options = PgConnectOptions::from_str(url).expect("Invalid database URL");
+            DbPool::Postgres(open(settings, log_statements(connect_options), lazy).await)
+        }
+        #[cfg(feature = "sqlite")]
+        Some(Backend::Sqlite) => {
+            // The database file is created on first start; foreign keys are off by
+            // default in SQLite and the schema relies on them for cascading deletes
+            let connect_options = SqliteConnectOptions::from_str(url)
+                .expect("Invalid database URL")
+                .create_if_missing(true)
+                .foreign_keys(true);
+            DbPool::Sqlite(open(settings, log_statements(connect_options), lazy).await)
+        }
+        _ => {
+            let connect_options = MySqlConnectOptions::from_str(url).expect("Invalid database URL");
+            DbPool::MySql(open(settings, log_statements(connect_options), lazy).await)
+        }
+    }
+}
+
+// Every statement is logged with its execution time at debug level
+fn log_statements<O: ConnectOptions>(connect_options: O) -> O {
+    connect_options
+        .log_statements(LevelFilter::Debug)
+        .log_slow_statements(LevelFilter::Warn, Duration::from_secs(1))
+}
+
+async fn open<DB: sqlx::Database>(
+    settings: &DatabaseConfig,
+    connect_options: <DB::Connection as sqlx::Connection>::Options,
+    lazy: bool,
+) -> Pool<DB> {
+    let pool_options = PoolOptions::<DB>::new()
+        .max_connections(settings.max_connections)
+        .min_connections(settings.min_connections)
+        .acquire_timeout(settings.acquire_timeout())
+        .idle_timeout(settings.idle_timeout());
+    if lazy {
+        pool_options.connect_lazy_with(connect_options)
+    } else {
+        pool_options
+            .connect_with(connect_options)
+            .await
+            .expect("Error establishing database connection")
+    }
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    SchemaAhead {
+        database_version: i64,
+        binary_version: i64,
+    },
+    Migrate(MigrateError),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::SchemaAhead {
+                database_version,
+                binary_version,
+            } => write!(
+                f,
+                "database schema is ahead of this binary: migration {} is applied but the latest \
+                 migration known to this build is {}; deploy a newer build or revert the schema",
+                database_version, binary_version
+            ),
+            MigrationError::Migrate(e) => write!(f, "migration failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<MigrateError> for MigrationError {
+    fn from(e: MigrateError) -> Self {
+        MigrationError::Migrate(e)
+    }
 }
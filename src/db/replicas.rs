@@ -0,0 +1,279 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+
+use super::{BackendPool, DbPool};
+use crate::{heartbeat::Heartbeats, shutdown::Shutdown};
+
+// How often replicas are pinged, and how long a ping may take
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+// Cookie holding the unix time of the client's last successful write
+const LAST_WRITE_COOKIE: &str = "blog_cms_last_write";
+
+tokio::task_local! {
+    // Set for requests whose reads must see the primary's latest state
+    static PRIMARY_READS: bool;
+}
+
+// Read-only copies of the primary database. Reads are spread over the healthy
+// replicas round-robin and fall back to the primary when none is healthy.
+#[derive(Debug, Clone, Default)]
+pub struct Replicas {
+    inner: Arc<ReplicaSet>,
+}
+
+#[derive(Debug, Default)]
+struct ReplicaSet {
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Replica {
+    pool: DbPool,
+    // Replicas stay out of rotation until their first health check passes
+    healthy: AtomicBool,
+}
+
+impl Replicas {
+    pub fn new(pools: Vec<DbPool>) -> Self {
+        let replicas = pools
+            .into_iter()
+            .map(|pool| Replica {
+                pool,
+                healthy: AtomicBool::new(false),
+            })
+            .collect();
+        Replicas {
+            inner: Arc::new(ReplicaSet {
+                replicas,
+                next: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.replicas.is_empty()
+    }
+
+    // Pool to run a read-only query on
+    pub fn reader<'a, P: BackendPool>(&'a self, primary: &'a P) -> &'a P {
+        if reads_from_primary() {
+            return primary;
+        }
+        self.next_healthy()
+            .and_then(P::from_db_pool)
+            .unwrap_or(primary)
+    }
+
+    fn next_healthy(&self) -> Option<&DbPool> {
+        let replicas = &self.inner.replicas;
+        if replicas.is_empty() {
+            return None;
+        }
+        let start = self.inner.next.fetch_add(1, Ordering::Relaxed);
+        (0..replicas.len())
+            .map(|offset| &replicas[(start + offset) % replicas.len()])
+            .find(|replica| replica.healthy.load(Ordering::Relaxed))
+            .map(|replica| &replica.pool)
+    }
+
+    // Ping the replicas periodically, taking failing ones out of rotation
+    // until they recover
+    pub fn spawn_monitor(&self, shutdown: &Shutdown, heartbeats: &Heartbeats) {
+        if self.is_empty() {
+            return;
+        }
+        let replicas = self.clone();
+        let heartbeat = heartbeats.register("replica_monitor", HEALTH_CHECK_INTERVAL * 3);
+        shutdown.spawn_worker(|token| async move {
+            let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        replicas.check_health().await;
+                        heartbeat.beat();
+                    }
+                    _ = token.cancelled() => break,
+                }
+            }
+            heartbeat.deregister();
+        });
+    }
+
+    async fn check_health(&self) {
+        let mut healthy = 0;
+        for (index, replica) in self.inner.replicas.iter().enumerate() {
+            let ping = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, replica.pool.ping()).await;
+            let up = matches!(ping, Ok(Ok(())));
+            if replica.healthy.swap(up, Ordering::Relaxed) != up {
+                if up {
+                    tracing::info!(replica = index, "Replica is in rotation");
+                } else {
+                    tracing::warn!(replica = index, "Replica failed its health check");
+                }
+            }
+            healthy += usize::from(up);
+        }
+        metrics::gauge!("db_replicas_healthy").set(healthy as f64);
+    }
+
+    pub async fn close(&self) {
+        for replica in &self.inner.replicas {
+            replica.pool.close().await;
+        }
+    }
+}
+
+fn reads_from_primary() -> bool {
+    PRIMARY_READS.try_with(|primary| *primary).unwrap_or(false)
+}
+
+// Run `future` with all of its reads going to the primary, e.g. to read back
+// a row that was just written
+pub async fn read_from_primary<F: Future>(future: F) -> F::Output {
+    PRIMARY_READS.scope(true, future).await
+}
+
+// Preserve read-your-writes for clients while replicas catch up. Writes read
+// from the primary throughout, and a successful write sets a cookie that keeps
+// the client's reads on the primary for `window`.
+pub async fn route_reads(State(window): State<Duration>, request: Request, next: Next) -> Response {
+    let writing = !matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let primary = writing || wrote_within(&request, window);
+    let mut response = PRIMARY_READS.scope(primary, next.run(request)).await;
+    if writing && response.status().is_success() {
+        let cookie = format!(
+            "{}={}; Max-Age={}; Path=/; HttpOnly; SameSite=Lax",
+            LAST_WRITE_COOKIE,
+            unix_now(),
+            window.as_secs().max(1)
+        );
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+fn wrote_within(request: &Request, window: Duration) -> bool {
+    let now = unix_now();
+    request
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .filter(|(name, _)| *name == LAST_WRITE_COOKIE)
+        .filter_map(|(_, written)| written.parse::<u64>().ok())
+        .any(|written| now.saturating_sub(written) < window.as_secs())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::MySqlPool;
+
+    fn pool() -> MySqlPool {
+        MySqlPool::connect_lazy("mysql://localhost/blog").unwrap()
+    }
+
+    fn replicas(count: usize, healthy: bool) -> Replicas {
+        let replicas = Replicas::new((0..count).map(|_| DbPool::MySql(pool())).collect());
+        for replica in &replicas.inner.replicas {
+            replica.healthy.store(healthy, Ordering::Relaxed);
+        }
+        replicas
+    }
+
+    fn replica(replicas: &Replicas, index: usize) -> &MySqlPool {
+        MySqlPool::from_db_pool(&replicas.inner.replicas[index].pool).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_rotate_over_healthy_replicas() {
+        let primary = pool();
+        let replicas = replicas(2, true);
+
+        let first = replicas.reader(&primary);
+        let second = replicas.reader(&primary);
+        let third = replicas.reader(&primary);
+
+        assert!(std::ptr::eq(first, replica(&replicas, 0)));
+        assert!(std::ptr::eq(second, replica(&replicas, 1)));
+        assert!(std::ptr::eq(third, replica(&replicas, 0)));
+    }
+
+    #[tokio::test]
+    async fn unhealthy_replicas_are_skipped() {
+        let primary = pool();
+        let replicas = replicas(2, true);
+        replicas.inner.replicas[0]
+            .healthy
+            .store(false, Ordering::Relaxed);
+
+        for _ in 0..3 {
+            assert!(std::ptr::eq(
+                replicas.reader(&primary),
+                replica(&replicas, 1)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_fall_back_to_the_primary() {
+        let primary = pool();
+        let replicas = replicas(2, false);
+
+        assert!(std::ptr::eq(replicas.reader(&primary), &primary));
+    }
+
+    #[tokio::test]
+    async fn primary_reads_bypass_replicas() {
+        let primary = pool();
+        let replicas = replicas(1, true);
+
+        let reader = read_from_primary(async { replicas.reader(&primary) as *const _ }).await;
+        assert!(std::ptr::eq(reader, &primary));
+    }
+
+    #[test]
+    fn recent_write_cookie_pins_reads() {
+        let window = Duration::from_secs(5);
+        let request = |cookie: String| {
+            Request::builder()
+                .header(header::COOKIE, cookie)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let recent = format!("theme=dark; {}={}", LAST_WRITE_COOKIE, unix_now());
+        assert!(wrote_within(&request(recent), window));
+        let stale = format!("{}={}", LAST_WRITE_COOKIE, unix_now() - 60);
+        assert!(!wrote_within(&request(stale), window));
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+use uuid::Uuid;
+
+use crate::models::{AuditAction, AuditEntryResponse, AuditTargetType};
+
+// One recorded change. Snapshots are stored as JSON text
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target_type: AuditTargetType,
+    pub target_id: Uuid,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub fn parse_audit_action(action: &str) -> Result<AuditAction, sqlx::Error> {
+    action
+        .parse()
+        .map_err(|e: String| sqlx::Error::Decode(e.into()))
+}
+
+pub fn parse_audit_target_type(target_type: &str) -> Result<AuditTargetType, sqlx::Error> {
+    target_type
+        .parse()
+        .map_err(|e: String| sqlx::Error::Decode(e.into()))
+}
+
+impl FromRow<'_, MySqlRow> for AuditEntry {
+    fn from_row(row: &MySqlRow) -> Result<Self, sqlx::Error> {
+        let id_bytes: Vec<u8> = row.try_get("id")?;
+        let id = Uuid::from_slice(&id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let actor_id_bytes: Option<Vec<u8>> = row.try_get("actor_id")?;
+        let actor_id = actor_id_bytes
+            .map(|bytes| Uuid::from_slice(&bytes))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let target_id_bytes: Vec<u8> = row.try_get("target_id")?;
+        let target_id =
+            Uuid::from_slice(&target_id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        Ok(Self {
+            id,
+            actor_id,
+            action: parse_audit_action(row.try_get("action")?)?,
+            target_type: parse_audit_target_type(row.try_get("target_type")?)?,
+            target_id,
+            before: row.try_get("before_snapshot")?,
+            after: row.try_get("after_snapshot")?,
+            ip: row.try_get("ip")?,
+            request_id: row.try_get("request_id")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl From<AuditEntry> for AuditEntryResponse {
+    fn from(entry: AuditEntry) -> Self {
+        // Snapshots are always written as JSON; anything else is shown verbatim
+        let snapshot = |snapshot: String| {
+            serde_json::from_str(&snapshot).unwrap_or(serde_json::Value::String(snapshot))
+        };
+        Self {
+            id: entry.id,
+            actor_id: entry.actor_id,
+            action: entry.action,
+            target_type: entry.target_type,
+            target_id: entry.target_id,
+            before: entry.before.map(snapshot),
+            after: entry.after.map(snapshot),
+            ip: entry.ip,
+            request_id: entry.request_id,
+            created_at: entry.created_at,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+use uuid::Uuid;
+
+use crate::models::CommentResponse;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Comment {
+    pub id: Uuid,
+    pub content: String,
+    pub user_id: Uuid,
+    pub post_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, MySqlRow> for Comment {
+    fn from_row(row: &MySqlRow) -> Result<Self, sqlx::Error> {
+        let id_bytes: Vec<u8> = row.try_get("id")?;
+        let id = Uuid::from_slice(&id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let user_id_bytes: Vec<u8> = row.try_get("user_id")?;
+        let user_id =
+            Uuid::from_slice(&user_id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let post_id_bytes: Vec<u8> = row.try_get("post_id")?;
+        let post_id =
+            Uuid::from_slice(&post_id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        Ok(Self {
+            id,
+            content: row.try_get("content")?,
+            user_id,
+            post_id,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+impl From<Comment> for CommentResponse {
+    fn from(comment: Comment) -> Self {
+        Self {
+            id: comment.id,
+            content: comment.content,
+            user_id: comment.user_id,
+            post_id: comment.post_id,
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
@@ -1,5 +1,25 @@
+mod audit;
+mod comment;
+mod outbox;
 mod post;
 mod role;
+mod trash;
 mod user;
+mod webhook;
 
-pub use post::PostStatus;
+pub use audit::AuditEntry;
+pub use comment::Comment;
+pub use outbox::{post_events, OutboxEvent, OutboxMessage};
+pub use post::{Post, PostStatus};
+pub use role::Role;
+pub use trash::TrashItem;
+pub use user::User;
+pub use webhook::{join_events, Webhook, WebhookDelivery};
+
+// Column parsers shared with the row mapping of the other backends
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub use audit::{parse_audit_action, parse_audit_target_type};
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub use outbox::parse_outbox_event;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub use webhook::{parse_event, parse_events, parse_status};
@@ -0,0 +1,111 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+use uuid::Uuid;
+
+use crate::{entities::PostStatus, models::PostResponse};
+
+// Domain events recorded in the outbox alongside the change that caused them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxEvent {
+    PostCreated,
+    PostUpdated,
+    PostPublished,
+    UserCreated,
+    CommentCreated,
+}
+
+impl OutboxEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxEvent::PostCreated => "post.created",
+            OutboxEvent::PostUpdated => "post.updated",
+            OutboxEvent::PostPublished => "post.published",
+            OutboxEvent::UserCreated => "user.created",
+            OutboxEvent::CommentCreated => "comment.created",
+        }
+    }
+
+    // Whether the event is about a post, whose payload is a `PostResponse`
+    pub fn is_post_event(&self) -> bool {
+        matches!(
+            self,
+            OutboxEvent::PostCreated | OutboxEvent::PostUpdated | OutboxEvent::PostPublished
+        )
+    }
+}
+
+impl fmt::Display for OutboxEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OutboxEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "post.created" => Ok(OutboxEvent::PostCreated),
+            "post.updated" => Ok(OutboxEvent::PostUpdated),
+            "post.published" => Ok(OutboxEvent::PostPublished),
+            "user.created" => Ok(OutboxEvent::UserCreated),
+            "comment.created" => Ok(OutboxEvent::CommentCreated),
+            other => Err(format!("unknown outbox event `{}`", other)),
+        }
+    }
+}
+
+// A recorded event waiting to be, or already, relayed to subscribers
+#[derive(Debug, Clone)]
+pub struct OutboxMessage {
+    pub id: Uuid,
+    pub event: OutboxEvent,
+    // Id of the row the event is about
+    pub aggregate_id: Uuid,
+    // JSON representation of that row as of the change
+    pub payload: String,
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+// Events caused by writing `post`, which had `previous` status before the
+// write or did not exist yet
+pub fn post_events(previous: Option<PostStatus>, post: &PostResponse) -> Vec<OutboxEvent> {
+    let mut events = Vec::with_capacity(2);
+    // Subscribers hear of the write itself first
+    events.push(match previous {
+        None => OutboxEvent::PostCreated,
+        Some(_) => OutboxEvent::PostUpdated,
+    });
+    // Only a transition into the published state counts as publishing
+    if post.status == PostStatus::Published && previous != Some(PostStatus::Published) {
+        events.push(OutboxEvent::PostPublished);
+    }
+    events
+}
+
+pub fn parse_outbox_event(event: &str) -> Result<OutboxEvent, sqlx::Error> {
+    event
+        .parse()
+        .map_err(|e: String| sqlx::Error::Decode(e.into()))
+}
+
+impl FromRow<'_, MySqlRow> for OutboxMessage {
+    fn from_row(row: &MySqlRow) -> Result<Self, sqlx::Error> {
+        let id_bytes: Vec<u8> = row.try_get("id")?;
+        let id = Uuid::from_slice(&id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let aggregate_id_bytes: Vec<u8> = row.try_get("aggregate_id")?;
+        let aggregate_id =
+            Uuid::from_slice(&aggregate_id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        Ok(Self {
+            id,
+            event: parse_outbox_event(row.try_get("event")?)?,
+            aggregate_id,
+            payload: row.try_get("payload")?,
+            attempts: row.try_get("attempts")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
@@ -1,10 +1,24 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{mysql::MySqlRow, FromRow, Row};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(rename_all = "lowercase")]
+use crate::models::PostResponse;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    sqlx::Type,
+    ToSchema,
+    async_graphql::Enum,
+)]
+#[sqlx(type_name = "post_status", rename_all = "lowercase")]
 pub enum PostStatus {
     Draft,
     Published,
@@ -12,7 +26,7 @@ pub enum PostStatus {
 }
 
 impl PostStatus {
-    pub fn to_str(&self) -> &str {
+    pub fn to_str(self) -> &'static str {
         match self {
             PostStatus::Draft => "draft",
             PostStatus::Published => "published",
@@ -28,9 +42,17 @@ pub struct Post {
     pub content: String,
     pub user_id: Uuid,
     pub status: PostStatus,
-    pub published_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub featured_media_id: Option<Uuid>,
+    pub excerpt: Option<String>,
+    pub meta_description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub og_image_url: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 impl FromRow<'_, MySqlRow> for Post {
@@ -46,6 +68,11 @@ impl FromRow<'_, MySqlRow> for Post {
             "archived" => PostStatus::Archived,
             e => return Err(sqlx::Error::Decode(e.into())),
         };
+        let featured_media_id_bytes: Option<Vec<u8>> = row.try_get("featured_media_id")?;
+        let featured_media_id = featured_media_id_bytes
+            .map(|bytes| Uuid::from_slice(&bytes))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(e.into()))?;
         Ok(Self {
             id,
             title: row.try_get("title")?,
@@ -53,8 +80,38 @@ impl FromRow<'_, MySqlRow> for Post {
             user_id,
             status,
             published_at: row.try_get("published_at")?,
+            featured_media_id,
+            excerpt: row.try_get("excerpt")?,
+            meta_description: row.try_get("meta_description")?,
+            canonical_url: row.try_get("canonical_url")?,
+            og_title: row.try_get("og_title")?,
+            og_description: row.try_get("og_description")?,
+            og_image_url: row.try_get("og_image_url")?,
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
+            version: row.try_get("version")?,
         })
     }
 }
+
+impl From<Post> for PostResponse {
+    fn from(post: Post) -> Self {
+        Self {
+            id: post.id,
+            title: post.title,
+            content: post.content,
+            status: post.status,
+            published_at: post.published_at,
+            user_id: post.user_id,
+            featured_media_id: post.featured_media_id,
+            excerpt: post.excerpt,
+            meta_description: post.meta_description,
+            canonical_url: post.canonical_url,
+            og_title: post.og_title,
+            og_description: post.og_description,
+            og_image_url: post.og_image_url,
+            updated_at: post.updated_at,
+            version: post.version,
+        }
+    }
+}
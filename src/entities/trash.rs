@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::{TrashItemResponse, TrashItemType};
+
+// Labels longer than this, usually comments, are cut short when listed
+const MAX_LABEL_CHARS: usize = 80;
+
+// A deleted user, post or comment
+#[derive(Debug, Clone)]
+pub struct TrashItem {
+    pub item_type: TrashItemType,
+    pub id: Uuid,
+    pub label: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl From<TrashItem> for TrashItemResponse {
+    fn from(item: TrashItem) -> Self {
+        let label = match item.label.char_indices().nth(MAX_LABEL_CHARS) {
+            Some((end, _)) => format!("{}…", &item.label[..end]),
+            None => item.label,
+        };
+        Self {
+            item_type: item.item_type,
+            id: item.id,
+            label,
+            deleted_at: item.deleted_at,
+        }
+    }
+}
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::{mysql::MySqlRow, FromRow, Row};
 use uuid::Uuid;
 
+use crate::models::UserResponse;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
@@ -12,6 +14,7 @@ pub struct User {
     pub role_id: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 impl FromRow<'_, MySqlRow> for User {
@@ -29,6 +32,20 @@ impl FromRow<'_, MySqlRow> for User {
             role_id,
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
+            version: row.try_get("version")?,
         })
     }
 }
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            role_id: user.role_id,
+            updated_at: user.updated_at,
+            version: user.version,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+use uuid::Uuid;
+
+use crate::models::{DeliveryStatus, WebhookDeliveryResponse, WebhookEvent, WebhookResponse};
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub response_code: Option<i32>,
+    pub error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.active && self.events.contains(&event)
+    }
+}
+
+// Subscribed events are stored as one comma separated column
+pub fn join_events(events: &[WebhookEvent]) -> String {
+    events
+        .iter()
+        .map(WebhookEvent::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn parse_events(events: &str) -> Result<Vec<WebhookEvent>, sqlx::Error> {
+    events
+        .split(',')
+        .filter(|event| !event.is_empty())
+        .map(|event| {
+            event
+                .parse()
+                .map_err(|e: String| sqlx::Error::Decode(e.into()))
+        })
+        .collect()
+}
+
+pub fn parse_event(event: &str) -> Result<WebhookEvent, sqlx::Error> {
+    event
+        .parse()
+        .map_err(|e: String| sqlx::Error::Decode(e.into()))
+}
+
+pub fn parse_status(status: &str) -> Result<DeliveryStatus, sqlx::Error> {
+    status
+        .parse()
+        .map_err(|e: String| sqlx::Error::Decode(e.into()))
+}
+
+impl FromRow<'_, MySqlRow> for Webhook {
+    fn from_row(row: &MySqlRow) -> Result<Self, sqlx::Error> {
+        let id_bytes: Vec<u8> = row.try_get("id")?;
+        let id = Uuid::from_slice(&id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        Ok(Self {
+            id,
+            url: row.try_get("url")?,
+            secret: row.try_get("secret")?,
+            events: parse_events(row.try_get("events")?)?,
+            active: row.try_get("active")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
+
+impl FromRow<'_, MySqlRow> for WebhookDelivery {
+    fn from_row(row: &MySqlRow) -> Result<Self, sqlx::Error> {
+        let id_bytes: Vec<u8> = row.try_get("id")?;
+        let id = Uuid::from_slice(&id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        let webhook_id_bytes: Vec<u8> = row.try_get("webhook_id")?;
+        let webhook_id =
+            Uuid::from_slice(&webhook_id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        Ok(Self {
+            id,
+            webhook_id,
+            event: parse_event(row.try_get("event")?)?,
+            payload: row.try_get("payload")?,
+            status: parse_status(row.try_get("status")?)?,
+            attempts: row.try_get("attempts")?,
+            response_code: row.try_get("response_code")?,
+            error: row.try_get("error")?,
+            next_attempt_at: row.try_get("next_attempt_at")?,
+            delivered_at: row.try_get("delivered_at")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            url: webhook.url,
+            events: webhook.events,
+            active: webhook.active,
+            created_at: webhook.created_at,
+            updated_at: webhook.updated_at,
+        }
+    }
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryResponse {
+    fn from(delivery: WebhookDelivery) -> Self {
+        // Payloads are always written as JSON; anything else is shown verbatim
+        let payload = serde_json::from_str(&delivery.payload)
+            .unwrap_or(serde_json::Value::String(delivery.payload));
+        let next_attempt_at =
+            (delivery.status == DeliveryStatus::Pending).then_some(delivery.next_attempt_at);
+        Self {
+            id: delivery.id,
+            webhook_id: delivery.webhook_id,
+            event: delivery.event,
+            payload,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            response_code: delivery.response_code,
+            error: delivery.error,
+            next_attempt_at,
+            delivered_at: delivery.delivered_at,
+            created_at: delivery.created_at,
+        }
+    }
+}
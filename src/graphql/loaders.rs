@@ -0,0 +1,91 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_graphql::dataloader::Loader;
+use uuid::Uuid;
+
+use crate::{
+    models::{CommentResponse, PostResponse, RoleResponse, UserResponse},
+    services::{CommentService, PostService, RoleService, UserService},
+};
+
+// Loader errors are handed to every waiting resolver, so they must be Clone
+type LoadError = Arc<sqlx::Error>;
+
+pub struct UserLoader(pub UserService);
+
+impl Loader<Uuid> for UserLoader {
+    type Value = UserResponse;
+    type Error = LoadError;
+
+    async fn load(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, UserResponse>, LoadError> {
+        let users = self.0.find_by_ids(ids).await?;
+        Ok(users.into_iter().map(|user| (user.id, user)).collect())
+    }
+}
+
+pub struct PostLoader(pub PostService);
+
+impl Loader<Uuid> for PostLoader {
+    type Value = PostResponse;
+    type Error = LoadError;
+
+    async fn load(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, PostResponse>, LoadError> {
+        let posts = self.0.find_by_ids(ids).await?;
+        Ok(posts.into_iter().map(|post| (post.id, post)).collect())
+    }
+}
+
+// Posts keyed by their author
+pub struct PostsByUserLoader(pub PostService);
+
+impl Loader<Uuid> for PostsByUserLoader {
+    type Value = Vec<PostResponse>;
+    type Error = LoadError;
+
+    async fn load(&self, user_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<PostResponse>>, LoadError> {
+        let posts = self.0.find_by_user_ids(user_ids).await?;
+        Ok(group_by(posts, |post| post.user_id))
+    }
+}
+
+// Comments keyed by the post they are on
+pub struct CommentsByPostLoader(pub CommentService);
+
+impl Loader<Uuid> for CommentsByPostLoader {
+    type Value = Vec<CommentResponse>;
+    type Error = LoadError;
+
+    async fn load(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<CommentResponse>>, LoadError> {
+        let comments = self.0.find_by_post_ids(post_ids).await?;
+        Ok(group_by(comments, |comment| comment.post_id))
+    }
+}
+
+// There are few roles, so a batch loads all of them in one query
+pub struct RoleLoader(pub RoleService);
+
+impl Loader<Uuid> for RoleLoader {
+    type Value = RoleResponse;
+    type Error = LoadError;
+
+    async fn load(&self, ids: &[Uuid]) -> Result<HashMap<Uuid, RoleResponse>, LoadError> {
+        let roles = self.0.find_all().await?;
+        Ok(roles
+            .roles
+            .into_iter()
+            .filter(|role| ids.contains(&role.id))
+            .map(|role| (role.id, role))
+            .collect())
+    }
+}
+
+fn group_by<T>(items: Vec<T>, key: impl Fn(&T) -> Uuid) -> HashMap<Uuid, Vec<T>> {
+    let mut groups: HashMap<Uuid, Vec<T>> = HashMap::new();
+    for item in items {
+        groups.entry(key(&item)).or_default().push(item);
+    }
+    groups
+}
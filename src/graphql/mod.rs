@@ -0,0 +1,65 @@
+// GraphQL API over the same services as the REST handlers. Related records
+// are fetched through DataLoaders, so a query touching many posts issues one
+// batched lookup per relation instead of one per post.
+use async_graphql::{dataloader::DataLoader, ErrorExtensions, Schema};
+use uuid::Uuid;
+
+use crate::{config::GraphqlConfig, services::ServiceContainer};
+use loaders::{CommentsByPostLoader, PostLoader, PostsByUserLoader, RoleLoader, UserLoader};
+use mutation::MutationRoot;
+use query::QueryRoot;
+use subscription::SubscriptionRoot;
+
+mod loaders;
+mod mutation;
+mod query;
+mod subscription;
+mod types;
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+// The user a request's bearer token belongs to
+#[derive(Debug, Clone, Copy)]
+pub struct Viewer {
+    pub user_id: Uuid,
+}
+
+pub fn build_schema(services: ServiceContainer, config: &GraphqlConfig) -> AppSchema {
+    let spawn = |future| tokio::spawn(future);
+    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(DataLoader::new(
+            UserLoader(services.user_service.clone()),
+            spawn,
+        ))
+        .data(DataLoader::new(
+            PostLoader(services.post_service.clone()),
+            spawn,
+        ))
+        .data(DataLoader::new(
+            PostsByUserLoader(services.post_service.clone()),
+            spawn,
+        ))
+        .data(DataLoader::new(
+            CommentsByPostLoader(services.comment_service.clone()),
+            spawn,
+        ))
+        .data(DataLoader::new(
+            RoleLoader(services.role_service.clone()),
+            spawn,
+        ))
+        .data(services)
+        .limit_depth(config.max_depth)
+        .limit_complexity(config.max_complexity)
+        .finish()
+}
+
+// Error with a machine readable `code` extension, like `UNAUTHENTICATED`
+pub(crate) fn coded_error(message: &str, code: &'static str) -> async_graphql::Error {
+    async_graphql::Error::new(message).extend_with(|_, extensions| extensions.set("code", code))
+}
+
+fn viewer(ctx: &async_graphql::Context<'_>) -> async_graphql::Result<Viewer> {
+    ctx.data_opt::<Viewer>()
+        .copied()
+        .ok_or_else(|| coded_error("Authentication required", "UNAUTHENTICATED"))
+}
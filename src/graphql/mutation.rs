@@ -0,0 +1,400 @@
+use async_graphql::{Context, InputObject, MaybeUndefined, Object, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{
+    coded_error,
+    types::{Comment, Post, Role, User},
+    viewer, Viewer,
+};
+use crate::{
+    entities::PostStatus,
+    models::{CreateComment, CreatePost, OwnedContent, PostResponse, UpdatePost},
+    password,
+    services::{CreateError, DeleteError, ServiceContainer, UpdateError},
+};
+
+// Permissions the user and role mutations need, as the REST user and role
+// writes do; only REST sign-up creates users without one, and then always
+// with the default role
+const USER_MANAGE: &str = "user:manage";
+const ROLE_MANAGE: &str = "role:manage";
+
+#[derive(InputObject)]
+pub struct CreatePostInput {
+    title: String,
+    content: String,
+    status: PostStatus,
+    published_at: Option<DateTime<Utc>>,
+    featured_media_id: Option<Uuid>,
+    excerpt: Option<String>,
+    meta_description: Option<String>,
+    canonical_url: Option<String>,
+    og_title: Option<String>,
+    og_description: Option<String>,
+    og_image_url: Option<String>,
+}
+
+// Omitted fields keep their current value; the optional ones are cleared by
+// an explicit null
+#[derive(InputObject)]
+pub struct UpdatePostInput {
+    title: Option<String>,
+    content: Option<String>,
+    status: Option<PostStatus>,
+    published_at: Option<DateTime<Utc>>,
+    featured_media_id: MaybeUndefined<Uuid>,
+    excerpt: MaybeUndefined<String>,
+    meta_description: MaybeUndefined<String>,
+    canonical_url: MaybeUndefined<String>,
+    og_title: MaybeUndefined<String>,
+    og_description: MaybeUndefined<String>,
+    og_image_url: MaybeUndefined<String>,
+}
+
+#[derive(InputObject)]
+pub struct CreateUserInput {
+    username: String,
+    email: String,
+    password: String,
+    role_id: Uuid,
+}
+
+// Omitted fields keep their current value
+#[derive(InputObject)]
+pub struct UpdateUserInput {
+    username: Option<String>,
+    email: Option<String>,
+    role_id: Option<Uuid>,
+}
+
+#[derive(InputObject)]
+pub struct CreateRoleInput {
+    role_name: String,
+    description: Option<String>,
+}
+
+// Omitted fields keep their current value
+#[derive(InputObject)]
+pub struct UpdateRoleInput {
+    role_name: Option<String>,
+    description: Option<String>,
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    // Create a post authored by the viewer
+    async fn create_post(&self, ctx: &Context<'_>, input: CreatePostInput) -> Result<Post> {
+        let viewer = viewer(ctx)?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        check_featured_media(services, input.featured_media_id).await?;
+        let post = services
+            .post_service
+            .create(CreatePost {
+                title: input.title,
+                content: input.content,
+                status: input.status,
+                published_at: input.published_at,
+                user_id: viewer.user_id,
+                featured_media_id: input.featured_media_id,
+                excerpt: input.excerpt,
+                meta_description: input.meta_description,
+                canonical_url: input.canonical_url,
+                og_title: input.og_title,
+                og_description: input.og_description,
+                og_image_url: input.og_image_url,
+            })
+            .await?;
+        Ok(Post(post))
+    }
+
+    // Update one of the viewer's posts
+    async fn update_post(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        input: UpdatePostInput,
+    ) -> Result<Post> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        authored_post(ctx, id).await?;
+        check_featured_media(services, input.featured_media_id.value().copied()).await?;
+        let post = services
+            .post_service
+            .update_by_id(
+                UpdatePost {
+                    id,
+                    title: input.title,
+                    content: input.content,
+                    status: input.status,
+                    published_at: input.published_at,
+                    user_id: None,
+                    featured_media_id: input.featured_media_id.into(),
+                    excerpt: input.excerpt.into(),
+                    meta_description: input.meta_description.into(),
+                    canonical_url: input.canonical_url.into(),
+                    og_title: input.og_title.into(),
+                    og_description: input.og_description.into(),
+                    og_image_url: input.og_image_url.into(),
+                },
+                None,
+            )
+            .await?;
+        Ok(Post(post))
+    }
+
+    // Delete one of the viewer's posts
+    async fn delete_post(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        authored_post(ctx, id).await?;
+        services.post_service.delete_by_id(id, None).await?;
+        Ok(true)
+    }
+
+    // Comment on a post as the viewer
+    async fn create_comment(
+        &self,
+        ctx: &Context<'_>,
+        post_id: Uuid,
+        content: String,
+    ) -> Result<Comment> {
+        let viewer = viewer(ctx)?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        if content.trim().is_empty() {
+            return Err(coded_error("A comment needs content", "BAD_USER_INPUT"));
+        }
+        find_post(services, post_id).await?;
+        let comment = services
+            .comment_service
+            .create(CreateComment {
+                content,
+                user_id: viewer.user_id,
+                post_id,
+            })
+            .await?;
+        Ok(Comment(comment))
+    }
+
+    // Delete a comment written by the viewer or on one of their posts
+    async fn delete_comment(&self, ctx: &Context<'_>, id: Uuid) -> Result<bool> {
+        let viewer = viewer(ctx)?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let comment = match services.comment_service.find_by_id(id).await {
+            Ok(comment) => comment,
+            Err(sqlx::Error::RowNotFound) => {
+                return Err(coded_error("Comment not found", "NOT_FOUND"))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if comment.user_id != viewer.user_id {
+            let post = find_post(services, comment.post_id).await?;
+            if post.user_id != viewer.user_id {
+                return Err(coded_error(
+                    "Not allowed to delete this comment",
+                    "FORBIDDEN",
+                ));
+            }
+        }
+        services.comment_service.delete_by_id(id).await?;
+        Ok(true)
+    }
+
+    async fn create_user(&self, ctx: &Context<'_>, input: CreateUserInput) -> Result<User> {
+        permitted(ctx, USER_MANAGE).await?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let password_hash = password::hash(&input.password)?;
+        let user = services
+            .user_service
+            .create(&input.username, &input.email, &password_hash, input.role_id)
+            .await
+            .map_err(|e| match e {
+                CreateError::Taken(holder) => coded_error(&holder.to_string(), "CONFLICT"),
+                e => e.into(),
+            })?;
+        Ok(User(user))
+    }
+
+    async fn update_user(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        input: UpdateUserInput,
+    ) -> Result<User> {
+        permitted(ctx, USER_MANAGE).await?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let user = services
+            .user_service
+            .update_by_id(id, input.username, input.email, input.role_id, None)
+            .await
+            .map_err(|e| update_error(e, "User not found"))?;
+        Ok(User(user))
+    }
+
+    // Delete a user, handing their posts and comments to `reassignTo` or,
+    // with `cascade`, deleting them too
+    async fn delete_user(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        reassign_to: Option<Uuid>,
+        #[graphql(default)] cascade: bool,
+    ) -> Result<bool> {
+        permitted(ctx, USER_MANAGE).await?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let content = match (reassign_to, cascade) {
+            (None, false) => OwnedContent::Refuse,
+            (Some(new_owner), false) => OwnedContent::ReassignTo(new_owner),
+            (None, true) => OwnedContent::Cascade,
+            (Some(_), true) => {
+                return Err(coded_error(
+                    "Content can either be reassigned or deleted, not both",
+                    "BAD_USER_INPUT",
+                ))
+            }
+        };
+        services
+            .user_service
+            .delete_by_id(id, content, None)
+            .await
+            .map_err(|e| {
+                delete_error(
+                    e,
+                    "User not found",
+                    "The user still owns posts or comments; pass reassignTo or cascade",
+                )
+            })?;
+        Ok(true)
+    }
+
+    async fn create_role(&self, ctx: &Context<'_>, input: CreateRoleInput) -> Result<Role> {
+        permitted(ctx, ROLE_MANAGE).await?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let role = services
+            .role_service
+            .create(&input.role_name, &input.description.unwrap_or_default())
+            .await?;
+        Ok(Role(role))
+    }
+
+    async fn update_role(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        input: UpdateRoleInput,
+    ) -> Result<Role> {
+        permitted(ctx, ROLE_MANAGE).await?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let role = services
+            .role_service
+            .update_by_id(id, input.role_name, input.description, None)
+            .await
+            .map_err(|e| update_error(e, "Role not found"))?;
+        Ok(Role(role))
+    }
+
+    // Delete a role, first moving its users to `reassignTo`
+    async fn delete_role(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        reassign_to: Option<Uuid>,
+    ) -> Result<bool> {
+        permitted(ctx, ROLE_MANAGE).await?;
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        services
+            .role_service
+            .delete_by_id(id, reassign_to, None)
+            .await
+            .map_err(|e| {
+                delete_error(
+                    e,
+                    "Role not found",
+                    "The role still has users; pass reassignTo to move them",
+                )
+            })?;
+        Ok(true)
+    }
+}
+
+// The viewer, provided their role grants `permission`
+async fn permitted(ctx: &Context<'_>, permission: &str) -> Result<Viewer> {
+    let viewer = viewer(ctx)?;
+    let services = ctx.data_unchecked::<ServiceContainer>();
+    let user = match services.user_service.find_by_id(viewer.user_id).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => {
+            return Err(coded_error(
+                "The token's user no longer exists",
+                "UNAUTHENTICATED",
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let permissions = services
+        .permission_service
+        .find_names_by_role(user.role_id)
+        .await?;
+    if !permissions.iter().any(|granted| granted == permission) {
+        return Err(coded_error(
+            &format!("The {} permission is required", permission),
+            "FORBIDDEN",
+        ));
+    }
+    Ok(viewer)
+}
+
+fn update_error(e: UpdateError, not_found: &str) -> async_graphql::Error {
+    match e {
+        UpdateError::Database(sqlx::Error::RowNotFound) => coded_error(not_found, "NOT_FOUND"),
+        UpdateError::Taken(holder) => coded_error(&holder.to_string(), "CONFLICT"),
+        e => e.into(),
+    }
+}
+
+fn delete_error(e: DeleteError, not_found: &str, in_use: &str) -> async_graphql::Error {
+    match e {
+        DeleteError::Database(sqlx::Error::RowNotFound) => coded_error(not_found, "NOT_FOUND"),
+        DeleteError::InUse => coded_error(in_use, "CONFLICT"),
+        DeleteError::InvalidReassignment(reason) => coded_error(&reason, "BAD_USER_INPUT"),
+        e => e.into(),
+    }
+}
+
+async fn find_post(services: &ServiceContainer, id: Uuid) -> Result<PostResponse> {
+    match services.post_service.find_by_id(id).await {
+        Ok(post) => Ok(post),
+        Err(sqlx::Error::RowNotFound) => Err(coded_error("Post not found", "NOT_FOUND")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// The post, provided the viewer wrote it
+async fn authored_post(ctx: &Context<'_>, id: Uuid) -> Result<PostResponse> {
+    let viewer = viewer(ctx)?;
+    let post = find_post(ctx.data_unchecked::<ServiceContainer>(), id).await?;
+    if post.user_id != viewer.user_id {
+        return Err(coded_error(
+            "Only the author can change this post",
+            "FORBIDDEN",
+        ));
+    }
+    Ok(post)
+}
+
+async fn check_featured_media(services: &ServiceContainer, media_id: Option<Uuid>) -> Result<()> {
+    let Some(media_id) = media_id else {
+        return Ok(());
+    };
+    if !services
+        .post_service
+        .featured_media_exists(media_id)
+        .await?
+    {
+        return Err(coded_error(
+            &format!("Media item {} does not exist", media_id),
+            "BAD_USER_INPUT",
+        ));
+    }
+    Ok(())
+}
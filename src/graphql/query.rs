@@ -0,0 +1,76 @@
+use async_graphql::{dataloader::DataLoader, Context, Object, Result};
+use uuid::Uuid;
+
+use super::{
+    coded_error,
+    loaders::UserLoader,
+    types::{Post, Role, User},
+    Viewer,
+};
+use crate::services::ServiceContainer;
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    // The user the bearer token belongs to, or null without one
+    async fn viewer(&self, ctx: &Context<'_>) -> Result<Option<User>> {
+        let Some(viewer) = ctx.data_opt::<Viewer>() else {
+            return Ok(None);
+        };
+        let loader = ctx.data_unchecked::<DataLoader<UserLoader>>();
+        Ok(loader.load_one(viewer.user_id).await?.map(User))
+    }
+
+    async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let users = services.user_service.find_all().await?;
+        Ok(users.users.into_iter().map(User).collect())
+    }
+
+    async fn user(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<User>> {
+        let loader = ctx.data_unchecked::<DataLoader<UserLoader>>();
+        Ok(loader.load_one(id).await?.map(User))
+    }
+
+    async fn posts(&self, ctx: &Context<'_>) -> Result<Vec<Post>> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let posts = services.post_service.find_all().await?;
+        Ok(posts.posts.into_iter().map(Post).collect())
+    }
+
+    async fn post(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Post>> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        match services.post_service.find_by_id(id).await {
+            Ok(post) => Ok(Some(Post(post))),
+            Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Search posts by title, excerpt and content
+    async fn search_posts(&self, ctx: &Context<'_>, query: String) -> Result<Vec<Post>> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(coded_error("The query must not be empty", "BAD_USER_INPUT"));
+        }
+        let posts = services.post_service.search(query).await?;
+        Ok(posts.posts.into_iter().map(Post).collect())
+    }
+
+    async fn roles(&self, ctx: &Context<'_>) -> Result<Vec<Role>> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        let roles = services.role_service.find_all().await?;
+        Ok(roles.roles.into_iter().map(Role).collect())
+    }
+
+    async fn role(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Role>> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        match services.role_service.find_by_id(id).await {
+            Ok(role) => Ok(Some(Role(role))),
+            Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
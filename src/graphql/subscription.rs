@@ -0,0 +1,22 @@
+use async_graphql::{Context, Subscription};
+use futures_util::{Stream, StreamExt};
+use tokio_stream::wrappers::BroadcastStream;
+use uuid::Uuid;
+
+use super::types::Comment;
+use crate::services::ServiceContainer;
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    // Comments added to a post from now on
+    async fn comment_added(&self, ctx: &Context<'_>, post_id: Uuid) -> impl Stream<Item = Comment> {
+        let services = ctx.data_unchecked::<ServiceContainer>();
+        // A subscriber too slow to keep up skips the comments it missed
+        BroadcastStream::new(services.comment_service.subscribe()).filter_map(move |comment| {
+            let comment = comment.ok().filter(|comment| comment.post_id == post_id);
+            async move { comment.map(Comment) }
+        })
+    }
+}
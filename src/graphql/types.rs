@@ -0,0 +1,164 @@
+use async_graphql::{dataloader::DataLoader, Context, Object, Result};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::loaders::{CommentsByPostLoader, PostLoader, PostsByUserLoader, RoleLoader, UserLoader};
+use crate::{
+    entities::PostStatus,
+    models::{CommentResponse, PostResponse, RoleResponse, UserResponse},
+};
+
+// GraphQL views of the REST models; relations resolve through the loaders
+
+pub struct User(pub UserResponse);
+
+#[Object]
+impl User {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn username(&self) -> &str {
+        &self.0.username
+    }
+
+    async fn email(&self) -> &str {
+        &self.0.email
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    async fn role(&self, ctx: &Context<'_>) -> Result<Option<Role>> {
+        let loader = ctx.data_unchecked::<DataLoader<RoleLoader>>();
+        Ok(loader.load_one(self.0.role_id).await?.map(Role))
+    }
+
+    async fn posts(&self, ctx: &Context<'_>) -> Result<Vec<Post>> {
+        let loader = ctx.data_unchecked::<DataLoader<PostsByUserLoader>>();
+        let posts = loader.load_one(self.0.id).await?.unwrap_or_default();
+        Ok(posts.into_iter().map(Post).collect())
+    }
+}
+
+pub struct Post(pub PostResponse);
+
+#[Object]
+impl Post {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn status(&self) -> PostStatus {
+        self.0.status
+    }
+
+    async fn published_at(&self) -> Option<DateTime<Utc>> {
+        self.0.published_at
+    }
+
+    async fn featured_media_id(&self) -> Option<Uuid> {
+        self.0.featured_media_id
+    }
+
+    async fn excerpt(&self) -> Option<&str> {
+        self.0.excerpt.as_deref()
+    }
+
+    async fn meta_description(&self) -> Option<&str> {
+        self.0.meta_description.as_deref()
+    }
+
+    async fn canonical_url(&self) -> Option<&str> {
+        self.0.canonical_url.as_deref()
+    }
+
+    async fn og_title(&self) -> Option<&str> {
+        self.0.og_title.as_deref()
+    }
+
+    async fn og_description(&self) -> Option<&str> {
+        self.0.og_description.as_deref()
+    }
+
+    async fn og_image_url(&self) -> Option<&str> {
+        self.0.og_image_url.as_deref()
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    async fn author(&self, ctx: &Context<'_>) -> Result<Option<User>> {
+        let loader = ctx.data_unchecked::<DataLoader<UserLoader>>();
+        Ok(loader.load_one(self.0.user_id).await?.map(User))
+    }
+
+    async fn comments(&self, ctx: &Context<'_>) -> Result<Vec<Comment>> {
+        let loader = ctx.data_unchecked::<DataLoader<CommentsByPostLoader>>();
+        let comments = loader.load_one(self.0.id).await?.unwrap_or_default();
+        Ok(comments.into_iter().map(Comment).collect())
+    }
+}
+
+pub struct Role(pub RoleResponse);
+
+#[Object]
+impl Role {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn role_name(&self) -> &str {
+        &self.0.role_name
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+}
+
+#[derive(Clone)]
+pub struct Comment(pub CommentResponse);
+
+#[Object]
+impl Comment {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    async fn author(&self, ctx: &Context<'_>) -> Result<Option<User>> {
+        let loader = ctx.data_unchecked::<DataLoader<UserLoader>>();
+        Ok(loader.load_one(self.0.user_id).await?.map(User))
+    }
+
+    async fn post(&self, ctx: &Context<'_>) -> Result<Option<Post>> {
+        let loader = ctx.data_unchecked::<DataLoader<PostLoader>>();
+        Ok(loader.load_one(self.0.post_id).await?.map(Post))
+    }
+}
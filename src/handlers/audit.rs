@@ -0,0 +1,78 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use tracing::instrument;
+
+use crate::{
+    handlers::{
+        auth::{caller_permissions, error_response, request_token},
+        openapi::{ApiResponse, ErrorResponse},
+    },
+    models::{AuditListResponse, AuditQuery},
+    services::ServiceContainer,
+};
+
+// Permission needed to read the audit log
+const AUDIT_READ: &str = "audit:read";
+
+// List recorded changes to users, roles and posts
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    tag = "audit",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Matching entries, newest first", body = ApiResponse<AuditListResponse>),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the audit:read permission", body = ErrorResponse),
+        (status = 500, description = "The audit log could not be read", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_audit_log(
+    State(service): State<ServiceContainer>,
+    Query(params): Query<AuditQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let permissions = match caller_permissions(&service, request_token(&headers, None)).await {
+        Ok(permissions) => permissions,
+        Err(response) => return response,
+    };
+    if !permissions
+        .iter()
+        .any(|permission| permission == AUDIT_READ)
+    {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            "Permission denied",
+            format!(
+                "Reading the audit log requires the {} permission",
+                AUDIT_READ
+            ),
+        );
+    }
+    match service.audit_service.find(params).await {
+        Ok(entries) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Audit log retrieved successfully",
+                "data": entries,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to retrieve audit log",
+            e.to_string(),
+        ),
+    }
+}
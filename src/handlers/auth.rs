@@ -0,0 +1,303 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use tracing::instrument;
+
+use crate::{
+    handlers::openapi::{ApiResponse, ErrorResponse, MessageResponse},
+    models::{
+        ChangePasswordRequest, ForgotPasswordRequest, LoginRequest, ResetPasswordRequest,
+        TokenResponse,
+    },
+    services::{AuthError, Claims, PasswordError, ServiceContainer},
+};
+
+// Exchange credentials for a bearer token
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = ApiResponse<TokenResponse>),
+        (status = 401, description = "Unknown login or wrong password", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn login(
+    State(service): State<ServiceContainer>,
+    Json(payload): Json<LoginRequest>,
+) -> Response {
+    let login_result = service
+        .auth_service
+        .login(&payload.login, &payload.password)
+        .await;
+    match login_result {
+        Ok(token) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Logged in successfully",
+                "data": token,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => {
+            let (status_code, message) = match e {
+                AuthError::InvalidCredentials => {
+                    (StatusCode::UNAUTHORIZED, "Invalid login or password")
+                }
+                AuthError::Disabled => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Authentication is not configured",
+                ),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to log in"),
+            };
+            let body = Json(json!({
+                "status": status_code.to_string(),
+                "code": status_code.as_u16(),
+                "message": message,
+                "errors": e.to_string(),
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+    }
+}
+
+// Mail a password reset link to the account with this email address
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/forgot",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 202, description = "A reset link is mailed if the address belongs to an account", body = MessageResponse),
+        (status = 500, description = "The reset could not be started", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn forgot_password(
+    State(service): State<ServiceContainer>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Response {
+    match service.password_service.forgot(&payload.email).await {
+        Ok(()) => {
+            let status_code = StatusCode::ACCEPTED;
+            let body = Json(json!({
+                "status": StatusCode::ACCEPTED.to_string(),
+                "code": StatusCode::ACCEPTED.as_u16(),
+                "message": "If the address belongs to an account, a reset link is on its way",
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to start password reset",
+            e.to_string(),
+        ),
+    }
+}
+
+// Set a new password with the token from a reset mail
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/reset",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password changed; every earlier token is revoked", body = MessageResponse),
+        (status = 400, description = "The token is unknown, used or expired", body = ErrorResponse),
+        (status = 500, description = "The password could not be changed", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn reset_password(
+    State(service): State<ServiceContainer>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Response {
+    let reset_result = service
+        .password_service
+        .reset(&payload.token, &payload.new_password)
+        .await;
+    match reset_result {
+        Ok(()) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Password reset successfully",
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e @ PasswordError::InvalidToken) => error_response(
+            StatusCode::BAD_REQUEST,
+            "Invalid reset token",
+            e.to_string(),
+        ),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to reset password",
+            e.to_string(),
+        ),
+    }
+}
+
+// Change the caller's password, given their current one
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/change",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed; every earlier token is revoked and a new one issued", body = ApiResponse<TokenResponse>),
+        (status = 401, description = "Missing or invalid bearer token, or wrong current password", body = ErrorResponse),
+        (status = 500, description = "The password could not be changed", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn change_password(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Response {
+    let user_id = match caller(&service, request_token(&headers, None)).await {
+        Ok(claims) => claims.sub,
+        Err(response) => return response,
+    };
+    let change_result = service
+        .password_service
+        .change(user_id, &payload.current_password, &payload.new_password)
+        .await;
+    match change_result {
+        Ok(token) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Password changed successfully",
+                "data": token,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e @ PasswordError::InvalidCredentials) => error_response(
+            StatusCode::UNAUTHORIZED,
+            "Invalid current password",
+            e.to_string(),
+        ),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to change password",
+            e.to_string(),
+        ),
+    }
+}
+
+// The token of an `Authorization: Bearer <token>` header value
+pub(crate) fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+// The bearer token of a request, from the Authorization header or, for
+// clients that can't set headers, the `access_token` query parameter
+pub(crate) fn request_token<'a>(
+    headers: &'a HeaderMap,
+    access_token: Option<&'a str>,
+) -> Option<&'a str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(bearer_token)
+        .or(access_token)
+}
+
+// The claims of a bearer token that is valid and not revoked, or the
+// response to send when it isn't
+async fn caller(service: &ServiceContainer, token: Option<&str>) -> Result<Claims, Response> {
+    let Some(token) = token else {
+        return Err(error_response(
+            StatusCode::UNAUTHORIZED,
+            "Authentication required",
+            String::from("A bearer token is required"),
+        ));
+    };
+    match service.auth_service.verify(token).await {
+        Ok(claims) => Ok(claims),
+        Err(AuthError::Disabled) => Err(error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Authentication is not configured",
+            AuthError::Disabled.to_string(),
+        )),
+        Err(e @ AuthError::Database(_)) => Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to check token",
+            e.to_string(),
+        )),
+        Err(e) => Err(error_response(
+            StatusCode::UNAUTHORIZED,
+            "Invalid token",
+            e.to_string(),
+        )),
+    }
+}
+
+// The names of the permissions granted to the user a bearer token belongs to,
+// or the response to send when the token doesn't identify a user
+pub(crate) async fn caller_permissions(
+    service: &ServiceContainer,
+    token: Option<&str>,
+) -> Result<Vec<String>, Response> {
+    let claims = caller(service, token).await?;
+    let user = match service.user_service.find_by_id(claims.sub).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => {
+            return Err(error_response(
+                StatusCode::UNAUTHORIZED,
+                "Invalid token",
+                String::from("The token's user no longer exists"),
+            ))
+        }
+        Err(e) => {
+            return Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load permissions",
+                e.to_string(),
+            ))
+        }
+    };
+    service
+        .permission_service
+        .find_names_by_role(user.role_id)
+        .await
+        .map_err(|e| {
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load permissions",
+                e.to_string(),
+            )
+        })
+}
+
+pub(crate) fn error_response(status_code: StatusCode, message: &str, errors: String) -> Response {
+    let body = Json(json!({
+        "status": status_code.to_string(),
+        "code": status_code.as_u16(),
+        "message": message,
+        "errors": errors,
+        "timestamp": Utc::now(),
+    }));
+    (status_code, body).into_response()
+}
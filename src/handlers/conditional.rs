@@ -0,0 +1,244 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use utoipa::IntoParams;
+
+// HTTP-date format for Last-Modified, always in GMT
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+// Validators of a resource for conditional requests: a strong ETag and, for
+// single resources, their last modification time and row version
+#[derive(Debug)]
+pub struct Validators {
+    etag: String,
+    last_modified: Option<DateTime<Utc>>,
+    version: Option<i64>,
+}
+
+impl Validators {
+    // Validators of a list, whose ETag is computed from its JSON representation
+    pub fn new<T: Serialize>(resource: &T, last_modified: Option<DateTime<Utc>>) -> Self {
+        let json = serde_json::to_vec(resource).unwrap_or_default();
+        let digest = Sha256::digest(&json);
+        let hex: String = digest[..16]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Validators {
+            etag: format!("\"{}\"", hex),
+            last_modified,
+            version: None,
+        }
+    }
+
+    // Validators of a single row, whose ETag is its version: every write bumps
+    // it, while `updated_at` may only have whole seconds
+    pub fn versioned(version: i64, last_modified: DateTime<Utc>) -> Self {
+        Validators {
+            etag: format!("\"v{}\"", version),
+            last_modified: Some(last_modified),
+            version: Some(version),
+        }
+    }
+
+    // Whether a GET can be answered with 304 Not Modified. If-Modified-Since
+    // is only consulted without If-None-Match.
+    pub fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(tags) = header_str(headers, header::IF_NONE_MATCH) {
+            return tags.trim() == "*"
+                || entity_tags(tags).any(|tag| opaque_tag(tag) == opaque_tag(&self.etag));
+        }
+        let since = header_str(headers, header::IF_MODIFIED_SINCE)
+            .and_then(|since| DateTime::parse_from_rfc2822(since).ok());
+        match (since, self.last_modified) {
+            (Some(since), Some(modified)) => modified.timestamp() <= since.timestamp(),
+            _ => false,
+        }
+    }
+
+    // Writes must name the version they were based on in If-Match, so
+    // concurrent editors can't overwrite each other's changes unseen
+    pub fn check_precondition(&self, headers: &HeaderMap) -> Option<Response> {
+        let Some(tags) = header_str(headers, header::IF_MATCH) else {
+            let status_code = StatusCode::PRECONDITION_REQUIRED;
+            let body = Json(json!({
+                "status": StatusCode::PRECONDITION_REQUIRED.to_string(),
+                "code": StatusCode::PRECONDITION_REQUIRED.as_u16(),
+                "message": "Missing If-Match header",
+                "errors": "Send the ETag of the version being changed in If-Match",
+                "timestamp": Utc::now(),
+            }));
+            return Some(self.apply((status_code, body).into_response()));
+        };
+        // Weak tags never match strongly
+        if tags.trim() == "*" || entity_tags(tags).any(|tag| tag == self.etag) {
+            return None;
+        }
+        Some(self.apply(precondition_failed()))
+    }
+
+    // The version a write that passed `check_precondition` has to find the
+    // resource at, so a change made after the check is still caught; None
+    // when If-Match is `*` and any version will do
+    pub fn expected_version(&self, headers: &HeaderMap) -> Option<i64> {
+        match header_str(headers, header::IF_MATCH) {
+            Some(tags) if tags.trim() == "*" => None,
+            _ => self.version,
+        }
+    }
+
+    pub fn not_modified_response(&self) -> Response {
+        self.apply(StatusCode::NOT_MODIFIED.into_response())
+    }
+
+    // Add the ETag and Last-Modified headers to a response
+    pub fn apply(&self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(modified) = self.last_modified {
+            let modified = modified.format(HTTP_DATE_FORMAT).to_string();
+            if let Ok(modified) = HeaderValue::from_str(&modified) {
+                headers.insert(header::LAST_MODIFIED, modified);
+            }
+        }
+        response
+    }
+}
+
+// Request headers of a conditional GET, as described in the OpenAPI document
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header)]
+#[allow(dead_code)]
+pub struct ConditionalGetHeaders {
+    /// ETags of cached copies; a match is answered with 304 Not Modified
+    #[param(rename = "If-None-Match")]
+    if_none_match: Option<String>,
+    /// HTTP date of a cached copy, ignored when If-None-Match is sent
+    #[param(rename = "If-Modified-Since")]
+    if_modified_since: Option<String>,
+}
+
+// Request headers of a conditional write, as described in the OpenAPI document
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header)]
+#[allow(dead_code)]
+pub struct ConditionalWriteHeaders {
+    /// ETag of the version being changed, or * to skip the check
+    #[param(rename = "If-Match")]
+    if_match: String,
+}
+
+// 412 for a write whose If-Match no longer names the resource's current version
+pub fn precondition_failed() -> Response {
+    let status_code = StatusCode::PRECONDITION_FAILED;
+    let body = Json(json!({
+        "status": StatusCode::PRECONDITION_FAILED.to_string(),
+        "code": StatusCode::PRECONDITION_FAILED.as_u16(),
+        "message": "Resource has been modified",
+        "errors": "If-Match does not match the current ETag",
+        "timestamp": Utc::now(),
+    }));
+    (status_code, body).into_response()
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn entity_tags(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+// The tag without its weakness indicator, for weak comparison
+fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn modified() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 9, 2, 10, 15, 0).unwrap()
+    }
+
+    #[test]
+    fn etag_changes_with_the_representation() {
+        let first = Validators::new(&json!({ "title": "Hello" }), None);
+        let same = Validators::new(&json!({ "title": "Hello" }), None);
+        let changed = Validators::new(&json!({ "title": "Bye" }), None);
+        assert_eq!(first.etag, same.etag);
+        assert_ne!(first.etag, changed.etag);
+    }
+
+    #[test]
+    fn single_resources_are_tagged_with_their_version() {
+        let validators = Validators::versioned(3, modified());
+        let etag = headers(header::IF_MATCH, "\"v3\"");
+        assert_eq!(
+            validators.check_precondition(&etag).map(|r| r.status()),
+            None
+        );
+        assert_eq!(validators.expected_version(&etag), Some(3));
+        assert_eq!(
+            validators.expected_version(&headers(header::IF_MATCH, "*")),
+            None
+        );
+    }
+
+    #[test]
+    fn matching_etag_is_not_modified() {
+        let validators = Validators::new(&json!({ "title": "Hello" }), None);
+        let tags = format!("\"other\", W/{}", validators.etag);
+        assert!(validators.not_modified(&headers(header::IF_NONE_MATCH, &tags)));
+        assert!(!validators.not_modified(&headers(header::IF_NONE_MATCH, "\"other\"")));
+    }
+
+    #[test]
+    fn modified_since_compares_whole_seconds() {
+        let validators = Validators::new(&json!({}), Some(modified()));
+        let same = "Mon, 02 Sep 2024 10:15:00 GMT";
+        let before = "Mon, 02 Sep 2024 10:14:59 GMT";
+        assert!(validators.not_modified(&headers(header::IF_MODIFIED_SINCE, same)));
+        assert!(!validators.not_modified(&headers(header::IF_MODIFIED_SINCE, before)));
+        let response = validators.not_modified_response();
+        assert_eq!(response.headers()[header::LAST_MODIFIED], same);
+    }
+
+    #[test]
+    fn writes_need_a_strongly_matching_etag() {
+        let validators = Validators::new(&json!({ "title": "Hello" }), None);
+        let status = |headers: &HeaderMap| {
+            validators
+                .check_precondition(headers)
+                .map(|response| response.status())
+        };
+        let weak = format!("W/{}", validators.etag);
+
+        assert_eq!(status(&headers(header::IF_MATCH, &validators.etag)), None);
+        assert_eq!(status(&headers(header::IF_MATCH, "*")), None);
+        assert_eq!(
+            status(&headers(header::IF_MATCH, &weak)),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+        assert_eq!(
+            status(&HeaderMap::new()),
+            Some(StatusCode::PRECONDITION_REQUIRED)
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures_util::{stream, StreamExt};
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::instrument;
+
+use crate::{
+    handlers::{
+        auth::{caller_permissions, error_response, request_token},
+        openapi::ErrorResponse,
+    },
+    models::{EventStreamQuery, EventTopic, LiveEvent},
+    services::ServiceContainer,
+};
+
+const LAST_EVENT_ID: &str = "last-event-id";
+
+// Stream live changes as Server-Sent Events
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "events",
+    params(
+        EventStreamQuery,
+        ("Last-Event-ID" = Option<u64>, Header,
+            description = "Id of the last event received, to resume after a reconnect"),
+    ),
+    responses(
+        (status = 200, description = "An endless stream of events", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Unknown topic", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "A requested topic needs a permission the caller lacks", body = ErrorResponse),
+        (status = 500, description = "The caller's permissions could not be loaded", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn stream_events(
+    State(service): State<ServiceContainer>,
+    Query(params): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let token = request_token(&headers, params.access_token.as_deref());
+    let permissions = match caller_permissions(&service, token).await {
+        Ok(permissions) => permissions,
+        Err(response) => return response,
+    };
+    let granted = move |permission: Option<&str>| {
+        permission.is_none_or(|permission| permissions.iter().any(|granted| granted == permission))
+    };
+
+    let topics = match &params.topics {
+        // Every topic the caller may see
+        None => EventTopic::ALL
+            .into_iter()
+            .filter(|topic| granted(topic.permission()))
+            .collect(),
+        Some(topics) => {
+            let topics: Result<Vec<EventTopic>, String> = topics
+                .split(',')
+                .map(|topic| topic.trim().parse())
+                .collect();
+            let topics = match topics {
+                Ok(topics) => topics,
+                Err(e) => return error_response(StatusCode::BAD_REQUEST, "Invalid topics", e),
+            };
+            if let Some(topic) = topics.iter().find(|topic| !granted(topic.permission())) {
+                return error_response(
+                    StatusCode::FORBIDDEN,
+                    "Permission denied",
+                    format!(
+                        "The {} topic requires the {} permission",
+                        topic,
+                        topic.permission().unwrap_or_default()
+                    ),
+                );
+            }
+            topics
+        }
+    };
+    let visible =
+        move |event: &LiveEvent| topics.contains(&event.topic) && granted(event.permission);
+
+    // An id the server cannot parse resumes nothing, like an unknown one
+    let last_event_id = headers.get(LAST_EVENT_ID).map(|value| {
+        value
+            .to_str()
+            .ok()
+            .and_then(|id| id.trim().parse().ok())
+            .unwrap_or(u64::MAX)
+    });
+    let subscription = service.event_service.subscribe(last_event_id);
+    // Tell a resuming client it missed events that can no longer be replayed,
+    // so it refetches what it shows
+    let resync = (!subscription.complete).then(|| Event::default().event("resync").data("{}"));
+    let missed = stream::iter(subscription.missed);
+    // A subscriber that falls too far behind is disconnected; it reconnects
+    // with the id of the last event it got and catches up from the buffer
+    let live = BroadcastStream::new(subscription.receiver)
+        .take_while(|event| futures_util::future::ready(event.is_ok()))
+        .filter_map(|event| futures_util::future::ready(event.ok()));
+    let events = missed
+        .chain(live)
+        .filter(move |event| futures_util::future::ready(visible(event)))
+        .map(|event| to_sse_event(&event));
+    let stream = stream::iter(resync)
+        .chain(events)
+        .map(Ok::<_, Infallible>)
+        .take_until(subscription.closed.cancelled_owned());
+    Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(service.event_service.keepalive())
+                .text("ping"),
+        )
+        .into_response()
+}
+
+fn to_sse_event(event: &Arc<LiveEvent>) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(event.event)
+        .data(&event.data)
+}
@@ -0,0 +1,157 @@
+use async_graphql::{
+    http::{GraphiQLSource, WebSocket, WebSocketProtocols, WsMessage, ALL_WEBSOCKET_PROTOCOLS},
+    Data,
+};
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocketUpgrade},
+        State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tracing::instrument;
+
+use crate::{
+    graphql::{AppSchema, Viewer},
+    handlers::auth::bearer_token,
+    services::AuthService,
+};
+
+#[derive(Clone)]
+pub struct GraphqlState {
+    pub schema: AppSchema,
+    pub auth_service: AuthService,
+}
+
+// Execute a query or mutation
+#[instrument(skip_all)]
+pub async fn graphql_handler(
+    State(state): State<GraphqlState>,
+    headers: HeaderMap,
+    Json(request): Json<async_graphql::Request>,
+) -> Response {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    let viewer = match authorization {
+        Some(value) => Some(viewer(&state.auth_service, value).await),
+        None => None,
+    };
+    let request = match viewer {
+        None => request,
+        Some(Ok(viewer)) => request.data(viewer),
+        // A bad token is refused outright rather than treated as anonymous
+        Some(Err(message)) => {
+            let body = Json(json!({
+                "errors": [{
+                    "message": message,
+                    "extensions": { "code": "UNAUTHENTICATED" },
+                }],
+            }));
+            return (StatusCode::UNAUTHORIZED, body).into_response();
+        }
+    };
+    Json(state.schema.execute(request).await).into_response()
+}
+
+// In-browser IDE for exploring the schema
+pub async fn graphiql() -> Html<String> {
+    Html(
+        GraphiQLSource::build()
+            .endpoint("/api/graphql")
+            .subscription_endpoint("/api/graphql/ws")
+            .finish(),
+    )
+}
+
+// Subscriptions over the graphql-transport-ws or legacy graphql-ws protocol.
+// Browsers can't set headers on a WebSocket, so the token may also arrive as
+// `Authorization` in the connection_init payload.
+pub async fn graphql_ws(
+    State(state): State<GraphqlState>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let protocol = headers
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|protocols| {
+            protocols
+                .split(',')
+                .find_map(|protocol| protocol.trim().parse::<WebSocketProtocols>().ok())
+        });
+    let Some(protocol) = protocol else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Unsupported GraphQL WebSocket protocol",
+        )
+            .into_response();
+    };
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| async move {
+            let (mut sink, stream) = socket.split();
+            let input = stream
+                .take_while(|message| futures_util::future::ready(message.is_ok()))
+                .map(Result::unwrap)
+                .filter_map(|message| {
+                    futures_util::future::ready(match message {
+                        Message::Text(text) => Some(text.into_bytes()),
+                        Message::Binary(bytes) => Some(bytes),
+                        _ => None,
+                    })
+                });
+            let auth_service = state.auth_service.clone();
+            let mut output = WebSocket::new(state.schema.clone(), input, protocol)
+                .on_connection_init(move |payload| async move {
+                    let authorization = payload
+                        .get("Authorization")
+                        .or_else(|| payload.get("authorization"))
+                        .and_then(|value| value.as_str())
+                        .map(String::from)
+                        .or(authorization);
+                    let mut data = Data::default();
+                    if let Some(authorization) = authorization {
+                        data.insert(
+                            viewer(&auth_service, &authorization)
+                                .await
+                                .map_err(async_graphql::Error::new)?,
+                        );
+                    }
+                    Ok(data)
+                });
+            while let Some(message) = output.next().await {
+                let message = match message {
+                    WsMessage::Text(text) => Message::Text(text),
+                    WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                        code,
+                        reason: reason.into(),
+                    })),
+                };
+                if sink.send(message).await.is_err() {
+                    break;
+                }
+            }
+        })
+}
+
+async fn viewer(auth_service: &AuthService, authorization: &str) -> Result<Viewer, String> {
+    let token = bearer_token(authorization)
+        .ok_or_else(|| String::from("Authorization must be a bearer token"))?;
+    let claims = auth_service
+        .verify(token)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Viewer {
+        user_id: claims.sub,
+    })
+}
@@ -1,4 +1,5 @@
 use axum::{
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
@@ -6,6 +7,18 @@ use axum::{
 use chrono::Utc;
 use serde_json::json;
 
+use crate::{
+    handlers::openapi::{MessageResponse, ReadinessCheckResponse},
+    services::ServiceContainer,
+};
+
+// Liveness: the process is up and serving requests
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "health",
+    responses((status = 200, description = "App is running", body = MessageResponse))
+)]
 pub async fn check_app_health() -> Response {
     let status_code = StatusCode::OK;
     let body = Json(json!({
@@ -16,3 +29,41 @@ pub async fn check_app_health() -> Response {
     }));
     (status_code, body).into_response()
 }
+
+// Liveness probe for orchestrators, answered like the health check
+#[utoipa::path(
+    get,
+    path = "/api/health/live",
+    tag = "health",
+    responses((status = 200, description = "App is running", body = MessageResponse))
+)]
+pub async fn check_app_liveness() -> Response {
+    check_app_health().await
+}
+
+// Readiness: every dependency needed to serve traffic is available
+#[utoipa::path(
+    get,
+    path = "/api/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "App is ready", body = ReadinessCheckResponse),
+        (status = 503, description = "A dependency is down", body = ReadinessCheckResponse),
+    )
+)]
+pub async fn check_app_readiness(State(service): State<ServiceContainer>) -> Response {
+    let readiness = service.health_service.check_readiness().await;
+    let (status_code, message) = if readiness.is_ready() {
+        (StatusCode::OK, "App is ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "App is not ready")
+    };
+    let body = Json(json!({
+        "status": status_code.to_string(),
+        "code": status_code.as_u16(),
+        "message": message,
+        "checks": readiness,
+        "timestamp": Utc::now()
+    }));
+    (status_code, body).into_response()
+}
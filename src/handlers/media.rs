@@ -0,0 +1,90 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use tracing::instrument;
+
+use crate::{
+    handlers::{
+        auth::{caller_permissions, request_token},
+        error_response,
+        openapi::{ApiResponse, ErrorResponse},
+    },
+    models::{CreateMedia, MediaResponse},
+    services::ServiceContainer,
+};
+
+// Media items are registered by those who write the posts featuring them
+const POST_CREATE: &str = "post:create";
+
+// Register a media item that posts can feature
+#[utoipa::path(
+    post,
+    path = "/api/media",
+    tag = "media",
+    request_body = CreateMedia,
+    responses(
+        (status = 201, description = "Media item registered", body = ApiResponse<MediaResponse>),
+        (status = 400, description = "A required field is blank", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the post:create permission", body = ErrorResponse),
+        (status = 500, description = "Media item could not be registered", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn create_media(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateMedia>,
+) -> Response {
+    let permissions = match caller_permissions(&service, request_token(&headers, None)).await {
+        Ok(permissions) => permissions,
+        Err(response) => return response,
+    };
+    if !permissions
+        .iter()
+        .any(|permission| permission == POST_CREATE)
+    {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            "Permission denied",
+            format!("Registering media requires the {} permission", POST_CREATE),
+        );
+    }
+    for (field, value) in [
+        ("file_name", &payload.file_name),
+        ("url", &payload.url),
+        ("mime_type", &payload.mime_type),
+    ] {
+        if value.trim().is_empty() {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "Invalid media item",
+                format!("{} must not be blank", field),
+            );
+        }
+    }
+    match service.post_service.create_media(payload).await {
+        Ok(media) => {
+            let status_code = StatusCode::CREATED;
+            let body = Json(json!({
+                "status": StatusCode::CREATED.to_string(),
+                "code": StatusCode::CREATED.as_u16(),
+                "message": "Media item registered successfully",
+                "data": media,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to register media item",
+            e.to_string(),
+        ),
+    }
+}
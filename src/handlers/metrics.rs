@@ -0,0 +1,28 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use crate::{metrics, services::ServiceContainer};
+
+// Prometheus scrape endpoint
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses((
+        status = 200,
+        description = "Metrics in the Prometheus text format",
+        body = String,
+        content_type = "text/plain; version=0.0.4"
+    ))
+)]
+pub async fn get_metrics(State(service): State<ServiceContainer>) -> Response {
+    service.health_service.record_pool_metrics().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+        .into_response()
+}
@@ -1,9 +1,32 @@
+mod audit;
+mod auth;
+mod conditional;
+mod events;
+mod graphql;
 mod health;
+mod media;
+mod metrics;
+mod openapi;
 mod post;
 mod role;
+mod trash;
 mod user;
+mod webhook;
 
-pub use health::check_app_health;
-pub use post::{create_post, delete_post_by_id, get_post_by_id, get_posts, get_posts_by_user_id, update_post_by_id};
+pub use audit::get_audit_log;
+pub(crate) use auth::{bearer_token, error_response};
+pub use auth::{change_password, forgot_password, login, reset_password};
+pub use events::stream_events;
+pub use graphql::{graphiql, graphql_handler, graphql_ws, GraphqlState};
+pub use health::{check_app_health, check_app_liveness, check_app_readiness};
+pub use media::create_media;
+pub use metrics::get_metrics;
+pub use openapi::create_openapi_spec;
+pub use post::{create_post, delete_post_by_id, get_post_by_id, get_posts, get_posts_by_user_id, search_posts, update_post_by_id};
 pub use role::{create_role, delete_role_by_id, get_role_by_id, get_roles, update_role_by_id};
+pub use trash::{get_trash, purge_trash_item, restore_trash_item};
 pub use user::{create_user, delete_user_by_id, get_user_by_id, get_users, update_user_by_id};
+pub use webhook::{
+    create_webhook, delete_webhook_by_id, get_webhook_by_id, get_webhook_deliveries, get_webhooks,
+    redeliver_webhook_delivery, update_webhook_by_id,
+};
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use utoipa::{openapi::OpenApi as OpenApiSpec, OpenApi, ToSchema};
+
+use super::{audit, auth, events, health, media, metrics, post, role, trash, user, webhook};
+use crate::{config::FeatureConfig, models::ReadinessResponse};
+
+// The envelope every JSON response is wrapped in. Handlers build it inline
+// with `json!`; these types only describe it in the OpenAPI document.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ApiResponse<T> {
+    #[schema(example = "200 OK")]
+    status: String,
+    #[schema(example = 200)]
+    code: u16,
+    message: String,
+    data: T,
+    timestamp: DateTime<Utc>,
+}
+
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorResponse {
+    #[schema(example = "500 Internal Server Error")]
+    status: String,
+    #[schema(example = 500)]
+    code: u16,
+    message: String,
+    // Details of what went wrong
+    errors: String,
+    timestamp: DateTime<Utc>,
+}
+
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct MessageResponse {
+    #[schema(example = "200 OK")]
+    status: String,
+    #[schema(example = 200)]
+    code: u16,
+    message: String,
+    timestamp: DateTime<Utc>,
+}
+
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ReadinessCheckResponse {
+    #[schema(example = "200 OK")]
+    status: String,
+    #[schema(example = 200)]
+    code: u16,
+    message: String,
+    checks: ReadinessResponse,
+    timestamp: DateTime<Utc>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Blog CMS API",
+        description = "REST API for managing the posts, users and roles of a blog."
+    ),
+    paths(
+        post::create_post,
+        post::get_posts,
+        post::search_posts,
+        post::get_post_by_id,
+        post::update_post_by_id,
+        post::delete_post_by_id,
+        media::create_media,
+        user::create_user,
+        user::get_users,
+        user::get_user_by_id,
+        user::update_user_by_id,
+        user::delete_user_by_id,
+        post::get_posts_by_user_id,
+        role::create_role,
+        role::get_roles,
+        role::get_role_by_id,
+        role::update_role_by_id,
+        role::delete_role_by_id,
+        auth::login,
+        auth::forgot_password,
+        auth::reset_password,
+        auth::change_password,
+        webhook::create_webhook,
+        webhook::get_webhooks,
+        webhook::get_webhook_by_id,
+        webhook::update_webhook_by_id,
+        webhook::delete_webhook_by_id,
+        webhook::get_webhook_deliveries,
+        webhook::redeliver_webhook_delivery,
+        events::stream_events,
+        audit::get_audit_log,
+        trash::get_trash,
+        trash::restore_trash_item,
+        trash::purge_trash_item,
+        health::check_app_health,
+        health::check_app_liveness,
+        health::check_app_readiness,
+        metrics::get_metrics,
+    ),
+    tags(
+        (name = "posts", description = "Blog posts"),
+        (name = "media", description = "Media items posts can feature"),
+        (name = "users", description = "User accounts"),
+        (name = "roles", description = "Roles users are assigned"),
+        (name = "auth", description = "Bearer tokens"),
+        (name = "webhooks", description = "Outgoing event notifications"),
+        (name = "events", description = "Live updates over Server-Sent Events"),
+        (name = "audit", description = "Record of changes to users, roles and posts"),
+        (name = "trash", description = "Deleted users, posts and comments"),
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "metrics", description = "Prometheus metrics"),
+    )
+)]
+struct ApiDoc;
+
+// The OpenAPI document of the routes served with these feature settings
+pub fn create_openapi_spec(features: &FeatureConfig) -> OpenApiSpec {
+    let mut spec = ApiDoc::openapi();
+    if !features.registration {
+        if let Some(users) = spec.paths.paths.get_mut("/api/user") {
+            users.post = None;
+        }
+    }
+    if !features.metrics {
+        spec.paths.paths.remove("/metrics");
+    }
+    spec
+}
@@ -1,31 +1,46 @@
-use crate::models::{CreatePost, UpdatePost};
-use crate::services::ServiceContainer;
-use axum::extract::Path;
+use crate::handlers::conditional::{
+    precondition_failed, ConditionalGetHeaders, ConditionalWriteHeaders, Validators,
+};
+use crate::handlers::error_response;
+use crate::handlers::openapi::{ApiResponse, ErrorResponse};
+use crate::models::{CreatePost, PostListResponse, PostResponse, PostSearchQuery, UpdatePost};
+use crate::services::{DeleteError, ServiceContainer, UpdateError};
+use axum::extract::{Path, Query};
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use chrono::Utc;
 use serde_json::json;
+use tracing::instrument;
 use uuid::Uuid;
 
 // Create a new post
+#[utoipa::path(
+    post,
+    path = "/api/post",
+    tag = "posts",
+    request_body = CreatePost,
+    responses(
+        (status = 201, description = "Post created", body = ApiResponse<PostResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 400, description = "Featured media does not exist", body = ErrorResponse),
+        (status = 500, description = "Post could not be created", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn create_post(
     State(service): State<ServiceContainer>,
     Json(payload): Json<CreatePost>,
 ) -> Response {
-    let post_result = service
-        .post_service
-        .create(
-            payload.title,
-            payload.content,
-            payload.status,
-            payload.published_at,
-            payload.user_id,
-        )
-        .await;
+    if let Some(response) =
+        validate_featured_media(&service, payload.featured_media_id).await
+    {
+        return response;
+    }
+    let post_result = service.post_service.create(payload).await;
     match post_result {
         Ok(post) => {
             let status_code = StatusCode::CREATED;
@@ -36,7 +51,8 @@ pub async fn create_post(
                 "data": post,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            let validators = Validators::versioned(post.version, post.updated_at);
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -53,10 +69,27 @@ pub async fn create_post(
 }
 
 // Find all posts
-pub async fn get_posts(State(service): State<ServiceContainer>) -> Response {
+#[utoipa::path(
+    get,
+    path = "/api/post",
+    tag = "posts",
+    params(ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "All posts", body = ApiResponse<PostListResponse>,
+            headers(("ETag" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 500, description = "Posts could not be retrieved", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_posts(State(service): State<ServiceContainer>, headers: HeaderMap) -> Response {
     let posts_result = service.post_service.find_all().await;
     match posts_result {
         Ok(posts) => {
+            let validators = Validators::new(&posts, None);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
             let status_code = StatusCode::OK;
             let body = Json(json!({
                 "status": StatusCode::OK.to_string(),
@@ -65,7 +98,7 @@ pub async fn get_posts(State(service): State<ServiceContainer>) -> Response {
                 "data": posts,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -82,13 +115,32 @@ pub async fn get_posts(State(service): State<ServiceContainer>) -> Response {
 }
 
 // Find a post by id
+#[utoipa::path(
+    get,
+    path = "/api/post/{id}",
+    tag = "posts",
+    params(("id" = Uuid, Path, description = "Post id"), ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "The post", body = ApiResponse<PostResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 500, description = "Post does not exist or could not be retrieved",
+            body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn get_post_by_id(
     State(service): State<ServiceContainer>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Response {
     let post_result = service.post_service.find_by_id(id).await;
     match post_result {
         Ok(post) => {
+            let validators = Validators::versioned(post.version, post.updated_at);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
             let status_code = StatusCode::OK;
             let body = Json(json!({
                 "status": StatusCode::OK.to_string(),
@@ -97,7 +149,7 @@ pub async fn get_post_by_id(
                 "data": post,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -114,21 +166,41 @@ pub async fn get_post_by_id(
 }
 
 // Update a post by id
+#[utoipa::path(
+    put,
+    path = "/api/post/{id}",
+    tag = "posts",
+    params(("id" = Uuid, Path, description = "Post id"), ConditionalWriteHeaders),
+    request_body(
+        content = UpdatePost,
+        description = "The post to update is the one named by `id` in the body"
+    ),
+    responses(
+        (status = 200, description = "Post updated", body = ApiResponse<PostResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 400, description = "Featured media does not exist", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+        (status = 412, description = "The post changed since it was read", body = ErrorResponse),
+        (status = 428, description = "If-Match header is missing", body = ErrorResponse),
+        (status = 500, description = "Post could not be updated", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn update_post_by_id(
     State(service): State<ServiceContainer>,
+    headers: HeaderMap,
     Json(payload): Json<UpdatePost>,
 ) -> Response {
-    let post_result = service
-        .post_service
-        .update_by_id(
-            payload.id,
-            payload.title,
-            payload.content,
-            payload.status,
-            payload.published_at,
-            payload.user_id,
-        )
-        .await;
+    let expected = match check_post_precondition(&service, payload.id, &headers).await {
+        Ok(expected) => expected,
+        Err(response) => return response,
+    };
+    if let Some(response) =
+        validate_featured_media(&service, payload.featured_media_id.flatten()).await
+    {
+        return response;
+    }
+    let post_result = service.post_service.update_by_id(payload, expected).await;
     match post_result {
         Ok(post) => {
             let status_code = StatusCode::OK;
@@ -139,8 +211,11 @@ pub async fn update_post_by_id(
                 "data": post,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            let validators = Validators::versioned(post.version, post.updated_at);
+            validators.apply((status_code, body).into_response())
         }
+        Err(UpdateError::Modified) => precondition_failed(),
+        Err(UpdateError::Database(sqlx::Error::RowNotFound)) => post_not_found(),
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
             let body = Json(json!({
@@ -156,11 +231,30 @@ pub async fn update_post_by_id(
 }
 
 // Delete a post by id
+#[utoipa::path(
+    delete,
+    path = "/api/post/{id}",
+    tag = "posts",
+    params(("id" = Uuid, Path, description = "Post id"), ConditionalWriteHeaders),
+    responses(
+        (status = 204, description = "Post deleted"),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+        (status = 412, description = "The post changed since it was read", body = ErrorResponse),
+        (status = 428, description = "If-Match header is missing", body = ErrorResponse),
+        (status = 500, description = "Post could not be deleted", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn delete_post_by_id(
     State(service): State<ServiceContainer>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Response {
-    let post_result = service.post_service.delete_by_id(id).await;
+    let expected = match check_post_precondition(&service, id, &headers).await {
+        Ok(expected) => expected,
+        Err(response) => return response,
+    };
+    let post_result = service.post_service.delete_by_id(id, expected).await;
     match post_result {
         Ok(_) => {
             let status_code = StatusCode::NO_CONTENT;
@@ -172,6 +266,8 @@ pub async fn delete_post_by_id(
             }));
             (status_code, body).into_response()
         }
+        Err(DeleteError::Modified) => precondition_failed(),
+        Err(DeleteError::Database(sqlx::Error::RowNotFound)) => post_not_found(),
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
             let body = Json(json!({
@@ -187,13 +283,31 @@ pub async fn delete_post_by_id(
 }
 
 // Find all posts by user id
+#[utoipa::path(
+    get,
+    path = "/api/user/{id}/posts",
+    tag = "posts",
+    params(("id" = Uuid, Path, description = "Author id"), ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "Posts of the user", body = ApiResponse<PostListResponse>,
+            headers(("ETag" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 500, description = "Posts could not be retrieved", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn get_posts_by_user_id(
     State(service): State<ServiceContainer>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Response {
     let posts_result = service.post_service.find_all_by_user_id(id).await;
     match posts_result {
         Ok(posts) => {
+            let validators = Validators::new(&posts, None);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
             let status_code = StatusCode::OK;
             let body = Json(json!({
                 "status": StatusCode::OK.to_string(),
@@ -202,7 +316,7 @@ pub async fn get_posts_by_user_id(
                 "data": posts,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -216,4 +330,138 @@ pub async fn get_posts_by_user_id(
             (status_code, body).into_response()
         }
     }
-}
\ No newline at end of file
+}
+
+// Full-text search over posts
+#[utoipa::path(
+    get,
+    path = "/api/post/search",
+    tag = "posts",
+    params(PostSearchQuery, ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "Matching posts", body = ApiResponse<PostListResponse>,
+            headers(("ETag" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 400, description = "The query is empty", body = ErrorResponse),
+        (status = 500, description = "Posts could not be searched", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn search_posts(
+    State(service): State<ServiceContainer>,
+    Query(params): Query<PostSearchQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let query = params.q.trim();
+    if query.is_empty() {
+        let status_code = StatusCode::BAD_REQUEST;
+        let body = Json(json!({
+            "status": StatusCode::BAD_REQUEST.to_string(),
+            "code": StatusCode::BAD_REQUEST.as_u16(),
+            "message": "Invalid search query",
+            "errors": "The q parameter must not be empty",
+            "timestamp": Utc::now(),
+        }));
+        return (status_code, body).into_response();
+    }
+    let posts_result = service.post_service.search(query).await;
+    match posts_result {
+        Ok(posts) => {
+            let validators = Validators::new(&posts, None);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Posts retrieved successfully",
+                "data": posts,
+                "timestamp": Utc::now(),
+            }));
+            validators.apply((status_code, body).into_response())
+        }
+        Err(e) => {
+            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+            let body = Json(json!({
+                "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
+                "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                "message": "Failed to search posts",
+                "errors": e.to_string(),
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+    }
+}
+
+// Reject a featured media id that does not reference an existing media item
+async fn validate_featured_media(
+    service: &ServiceContainer,
+    featured_media_id: Option<Uuid>,
+) -> Option<Response> {
+    let media_id = featured_media_id?;
+    match service.post_service.featured_media_exists(media_id).await {
+        Ok(true) => None,
+        Ok(false) => {
+            let status_code = StatusCode::BAD_REQUEST;
+            let body = Json(json!({
+                "status": StatusCode::BAD_REQUEST.to_string(),
+                "code": StatusCode::BAD_REQUEST.as_u16(),
+                "message": "Invalid featured media id",
+                "errors": format!("Media item {} does not exist", media_id),
+                "timestamp": Utc::now(),
+            }));
+            Some((status_code, body).into_response())
+        }
+        Err(e) => {
+            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+            let body = Json(json!({
+                "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
+                "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                "message": "Failed to validate featured media",
+                "errors": e.to_string(),
+                "timestamp": Utc::now(),
+            }));
+            Some((status_code, body).into_response())
+        }
+    }
+}
+
+// Reject a write whose If-Match header doesn't name the post's current
+// version, or return the version the write has to find the post at
+async fn check_post_precondition(
+    service: &ServiceContainer,
+    id: Uuid,
+    headers: &HeaderMap,
+) -> Result<Option<i64>, Response> {
+    match service.post_service.find_by_id(id).await {
+        Ok(post) => {
+            let validators = Validators::versioned(post.version, post.updated_at);
+            match validators.check_precondition(headers) {
+                Some(response) => Err(response),
+                None => Ok(validators.expected_version(headers)),
+            }
+        }
+        Err(sqlx::Error::RowNotFound) => Err(post_not_found()),
+        Err(e) => {
+            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+            let body = Json(json!({
+                "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
+                "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                "message": "Failed to retrieve post",
+                "errors": e.to_string(),
+                "timestamp": Utc::now(),
+            }));
+            Err((status_code, body).into_response())
+        }
+    }
+}
+
+fn post_not_found() -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        "Post not found",
+        String::from("No post with this id"),
+    )
+}
@@ -1,23 +1,70 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use chrono::Utc;
 use serde_json::json;
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
-    models::{CreateRole, UpdateRole},
-    services::ServiceContainer,
+    handlers::{
+        auth::{caller_permissions, request_token},
+        conditional::{
+            precondition_failed, ConditionalGetHeaders, ConditionalWriteHeaders, Validators,
+        },
+        error_response,
+        openapi::{ApiResponse, ErrorResponse},
+    },
+    models::{CreateRole, DeleteRoleQuery, RoleListResponse, RoleResponse, UpdateRole},
+    services::{DeleteError, ServiceContainer, UpdateError},
 };
 
+// Permission needed to create, change and delete roles
+const ROLE_MANAGE: &str = "role:manage";
+
+// Check the caller may manage roles, or respond with why not
+async fn authorize(service: &ServiceContainer, headers: &HeaderMap) -> Result<(), Response> {
+    let permissions = caller_permissions(service, request_token(headers, None)).await?;
+    if !permissions
+        .iter()
+        .any(|permission| permission == ROLE_MANAGE)
+    {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "Permission denied",
+            format!("Managing roles requires the {} permission", ROLE_MANAGE),
+        ));
+    }
+    Ok(())
+}
+
 //Create a new role
+#[utoipa::path(
+    post,
+    path = "/api/role",
+    tag = "roles",
+    request_body = CreateRole,
+    responses(
+        (status = 201, description = "Role created", body = ApiResponse<RoleResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the role:manage permission", body = ErrorResponse),
+        (status = 500, description = "Role could not be created", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn create_role(
     State(service): State<ServiceContainer>,
+    headers: HeaderMap,
     Json(payload): Json<CreateRole>,
 ) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
     let role_result = service
         .role_service
         .create(&payload.role_name, &payload.description.unwrap_or_default())
@@ -32,7 +79,8 @@ pub async fn create_role(
                 "data": role,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            let validators = Validators::versioned(role.version, role.updated_at);
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -49,10 +97,27 @@ pub async fn create_role(
 }
 
 //Get all roles
-pub async fn get_roles(State(service): State<ServiceContainer>) -> Response {
+#[utoipa::path(
+    get,
+    path = "/api/role",
+    tag = "roles",
+    params(ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "All roles", body = ApiResponse<RoleListResponse>,
+            headers(("ETag" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 500, description = "Roles could not be retrieved", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_roles(State(service): State<ServiceContainer>, headers: HeaderMap) -> Response {
     let roles_result = service.role_service.find_all().await;
     match roles_result {
         Ok(roles) => {
+            let validators = Validators::new(&roles, None);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
             let status_code = StatusCode::OK;
             let body = Json(json!({
                 "status": StatusCode::OK.to_string(),
@@ -61,7 +126,7 @@ pub async fn get_roles(State(service): State<ServiceContainer>) -> Response {
                 "data": roles,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -78,9 +143,25 @@ pub async fn get_roles(State(service): State<ServiceContainer>) -> Response {
 }
 
 // Get role by id
+#[utoipa::path(
+    get,
+    path = "/api/role/{id}",
+    tag = "roles",
+    params(("id" = Uuid, Path, description = "Role id"), ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "The role", body = ApiResponse<RoleResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 400, description = "The id is not a UUID", body = ErrorResponse),
+        (status = 500, description = "Role does not exist or could not be retrieved",
+            body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn get_role_by_id(
     State(service): State<ServiceContainer>,
     Path(id): Path<String>,
+    headers: HeaderMap,
 ) -> Response {
     let role_id_result = Uuid::parse_str(&id);
     let role_id = match role_id_result {
@@ -100,6 +181,10 @@ pub async fn get_role_by_id(
     let role_result = service.role_service.find_by_id(role_id).await;
     match role_result {
         Ok(role) => {
+            let validators = Validators::versioned(role.version, role.updated_at);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
             let status_code = StatusCode::OK;
             let body = Json(json!({
                 "status": StatusCode::OK.to_string(),
@@ -108,7 +193,7 @@ pub async fn get_role_by_id(
                 "data": role,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -125,11 +210,35 @@ pub async fn get_role_by_id(
 }
 
 // Update role by id
+#[utoipa::path(
+    put,
+    path = "/api/role/{id}",
+    tag = "roles",
+    params(("id" = Uuid, Path, description = "Role id"), ConditionalWriteHeaders),
+    request_body = UpdateRole,
+    responses(
+        (status = 200, description = "Role updated", body = ApiResponse<RoleResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 400, description = "The id is not a UUID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the role:manage permission", body = ErrorResponse),
+        (status = 404, description = "Role not found", body = ErrorResponse),
+        (status = 412, description = "The role changed since it was read", body = ErrorResponse),
+        (status = 428, description = "If-Match header is missing", body = ErrorResponse),
+        (status = 500, description = "Role could not be updated", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn update_role_by_id(
     State(service): State<ServiceContainer>,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateRole>,
 ) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
     let role_id_result = Uuid::parse_str(&id);
     let role_id = match role_id_result {
         Ok(role_id) => role_id,
@@ -145,9 +254,30 @@ pub async fn update_role_by_id(
             return (status_code, body).into_response();
         }
     };
+    let expected = match service.role_service.find_by_id(role_id).await {
+        Ok(role) => {
+            let validators = Validators::versioned(role.version, role.updated_at);
+            if let Some(response) = validators.check_precondition(&headers) {
+                return response;
+            }
+            validators.expected_version(&headers)
+        }
+        Err(sqlx::Error::RowNotFound) => return role_not_found(),
+        Err(e) => {
+            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+            let body = Json(json!({
+                "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
+                "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                "message": "Failed to get role",
+                "errors": e.to_string(),
+                "timestamp": Utc::now(),
+            }));
+            return (status_code, body).into_response();
+        }
+    };
     let role_result = service
         .role_service
-        .update_by_id(role_id, payload.role_name, payload.description)
+        .update_by_id(role_id, payload.role_name, payload.description, expected)
         .await;
     match role_result {
         Ok(role) => {
@@ -159,8 +289,11 @@ pub async fn update_role_by_id(
                 "data": role,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            let validators = Validators::versioned(role.version, role.updated_at);
+            validators.apply((status_code, body).into_response())
         }
+        Err(UpdateError::Modified) => precondition_failed(),
+        Err(UpdateError::Database(sqlx::Error::RowNotFound)) => role_not_found(),
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
             let body = Json(json!({
@@ -176,10 +309,40 @@ pub async fn update_role_by_id(
 }
 
 // Delete role by id
+#[utoipa::path(
+    delete,
+    path = "/api/role/{id}",
+    tag = "roles",
+    params(
+        ("id" = Uuid, Path, description = "Role id"),
+        DeleteRoleQuery,
+        ConditionalWriteHeaders,
+    ),
+    responses(
+        (status = 200, description = "Role deleted", body = ApiResponse<RoleResponse>),
+        (status = 400, description = "The id is not a UUID, or the users can't be reassigned as asked",
+            body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the role:manage permission", body = ErrorResponse),
+        (status = 409, description = "The role still has users and no reassign_to was given",
+            body = ErrorResponse),
+        (status = 404, description = "Role not found", body = ErrorResponse),
+        (status = 412, description = "The role changed since it was read", body = ErrorResponse),
+        (status = 428, description = "If-Match header is missing", body = ErrorResponse),
+        (status = 500, description = "Role could not be deleted", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn delete_role_by_id(
     State(services): State<ServiceContainer>,
     Path(id): Path<String>,
+    Query(params): Query<DeleteRoleQuery>,
+    headers: HeaderMap,
 ) -> Response {
+    if let Err(response) = authorize(&services, &headers).await {
+        return response;
+    }
     let role_id_result = Uuid::parse_str(&id);
     let role_id = match role_id_result {
         Ok(role_id) => role_id,
@@ -198,7 +361,15 @@ pub async fn delete_role_by_id(
     let role_result = services.role_service.find_by_id(role_id).await;
     match role_result {
         Ok(role) => {
-            let delete_result = services.role_service.delete_by_id(role_id).await;
+            let validators = Validators::versioned(role.version, role.updated_at);
+            if let Some(response) = validators.check_precondition(&headers) {
+                return response;
+            }
+            let expected = validators.expected_version(&headers);
+            let delete_result = services
+                .role_service
+                .delete_by_id(role_id, params.reassign_to, expected)
+                .await;
             match delete_result {
                 Ok(_) => {
                     let status_code = StatusCode::OK;
@@ -211,25 +382,42 @@ pub async fn delete_role_by_id(
                     }));
                     (status_code, body).into_response()
                 }
+                Err(DeleteError::Modified) => precondition_failed(),
+                Err(DeleteError::Database(sqlx::Error::RowNotFound)) => role_not_found(),
                 Err(e) => {
-                    let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+                    let (status_code, errors) = match e {
+                        DeleteError::InUse => (
+                            StatusCode::CONFLICT,
+                            String::from(
+                                "The role still has users, including any in the trash; pass \
+                                 reassign_to=<role id> to move them to another role",
+                            ),
+                        ),
+                        DeleteError::InvalidReassignment(reason) => {
+                            (StatusCode::BAD_REQUEST, reason)
+                        }
+                        e @ (DeleteError::Modified | DeleteError::Database(_)) => {
+                            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                        }
+                    };
                     let body = Json(json!({
-                        "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
-                        "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                        "status": status_code.to_string(),
+                        "code": status_code.as_u16(),
                         "message": "Failed to delete role",
-                        "errors": e.to_string(),
+                        "errors": errors,
                         "timestamp": Utc::now(),
                     }));
                     (status_code, body).into_response()
                 }
             }
         }
+        Err(sqlx::Error::RowNotFound) => role_not_found(),
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
             let body = Json(json!({
                 "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
                 "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                "message": "Role not found",
+                "message": "Failed to get role",
                 "errors": e.to_string(),
                 "timestamp": Utc::now(),
             }));
@@ -237,3 +425,11 @@ pub async fn delete_role_by_id(
         }
     }
 }
+
+fn role_not_found() -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        "Role not found",
+        String::from("No role with this id"),
+    )
+}
@@ -0,0 +1,216 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    handlers::{
+        auth::{caller_permissions, error_response, request_token},
+        openapi::{ApiResponse, ErrorResponse, MessageResponse},
+    },
+    models::{TrashItemType, TrashListResponse, TrashQuery},
+    services::ServiceContainer,
+};
+
+// Permission needed to see, restore and purge trashed items
+const TRASH_MANAGE: &str = "trash:manage";
+
+// Check the caller may manage the trash, or respond with why not
+async fn authorize(service: &ServiceContainer, headers: &HeaderMap) -> Result<(), Response> {
+    let permissions = caller_permissions(service, request_token(headers, None)).await?;
+    if !permissions
+        .iter()
+        .any(|permission| permission == TRASH_MANAGE)
+    {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "Permission denied",
+            format!(
+                "Managing the trash requires the {} permission",
+                TRASH_MANAGE
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// The item a restore or purge refers to
+#[allow(clippy::result_large_err)]
+fn parse_item(item_type: &str, id: &str) -> Result<(TrashItemType, Uuid), Response> {
+    let item_type = item_type
+        .parse()
+        .map_err(|e: String| error_response(StatusCode::BAD_REQUEST, "Invalid item type", e))?;
+    let id = Uuid::parse_str(id)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, "Invalid item id", e.to_string()))?;
+    Ok((item_type, id))
+}
+
+// List deleted users, posts and comments
+#[utoipa::path(
+    get,
+    path = "/api/trash",
+    tag = "trash",
+    params(TrashQuery),
+    responses(
+        (status = 200, description = "Trashed items, most recently deleted first", body = ApiResponse<TrashListResponse>),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the trash:manage permission", body = ErrorResponse),
+        (status = 500, description = "The trash could not be read", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_trash(
+    State(service): State<ServiceContainer>,
+    Query(params): Query<TrashQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    match service.trash_service.find(params).await {
+        Ok(items) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Trash retrieved successfully",
+                "data": items,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to retrieve trash",
+            e.to_string(),
+        ),
+    }
+}
+
+// Take an item, and whatever was deleted along with it, out of the trash
+#[utoipa::path(
+    post,
+    path = "/api/trash/{item_type}/{id}/restore",
+    tag = "trash",
+    params(
+        ("item_type" = TrashItemType, Path, description = "What kind of item to restore"),
+        ("id" = Uuid, Path, description = "Id of the item"),
+    ),
+    responses(
+        (status = 200, description = "The item was restored", body = MessageResponse),
+        (status = 400, description = "Unknown item type or the id is not a UUID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the trash:manage permission", body = ErrorResponse),
+        (status = 404, description = "The item is not in the trash", body = ErrorResponse),
+        (status = 409, description = "The item's author or post is still in the trash", body = ErrorResponse),
+        (status = 500, description = "The item could not be restored", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn restore_trash_item(
+    State(service): State<ServiceContainer>,
+    Path((item_type, id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let (item_type, id) = match parse_item(&item_type, &id) {
+        Ok(item) => item,
+        Err(response) => return response,
+    };
+    match service.trash_service.restore(item_type, id).await {
+        Ok(true) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": format!("Restored {} with id {}", item_type, id),
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Ok(false) => error_response(
+            StatusCode::CONFLICT,
+            "Failed to restore item",
+            format!(
+                "The {} belongs to an item that is still in the trash; restore that first",
+                item_type
+            ),
+        ),
+        Err(sqlx::Error::RowNotFound) => error_response(
+            StatusCode::NOT_FOUND,
+            "Item not found",
+            format!("No {} with id {} is in the trash", item_type, id),
+        ),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to restore item",
+            e.to_string(),
+        ),
+    }
+}
+
+// Permanently delete a trashed item, along with everything that belongs to it
+#[utoipa::path(
+    delete,
+    path = "/api/trash/{item_type}/{id}",
+    tag = "trash",
+    params(
+        ("item_type" = TrashItemType, Path, description = "What kind of item to purge"),
+        ("id" = Uuid, Path, description = "Id of the item"),
+    ),
+    responses(
+        (status = 200, description = "The item was purged", body = MessageResponse),
+        (status = 400, description = "Unknown item type or the id is not a UUID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the trash:manage permission", body = ErrorResponse),
+        (status = 404, description = "The item is not in the trash", body = ErrorResponse),
+        (status = 500, description = "The item could not be purged", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn purge_trash_item(
+    State(service): State<ServiceContainer>,
+    Path((item_type, id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let (item_type, id) = match parse_item(&item_type, &id) {
+        Ok(item) => item,
+        Err(response) => return response,
+    };
+    match service.trash_service.purge(item_type, id).await {
+        Ok(()) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": format!("Purged {} with id {}", item_type, id),
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(sqlx::Error::RowNotFound) => error_response(
+            StatusCode::NOT_FOUND,
+            "Item not found",
+            format!("No {} with id {} is in the trash", item_type, id),
+        ),
+        Err(e) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to purge item",
+            e.to_string(),
+        ),
+    }
+}
@@ -1,25 +1,111 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use bcrypt::DEFAULT_COST;
 use chrono::Utc;
 use serde_json::json;
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
-    models::{CreateUser, UpdateUser},
-    services::ServiceContainer,
+    admin::DEFAULT_ROLE,
+    handlers::{
+        auth::{caller_permissions, request_token},
+        conditional::{
+            precondition_failed, ConditionalGetHeaders, ConditionalWriteHeaders, Validators,
+        },
+        error_response,
+        openapi::{ApiResponse, ErrorResponse, MessageResponse},
+    },
+    models::{
+        CreateUser, DeleteUserQuery, OwnedContent, UpdateUser, UserListResponse, UserResponse,
+    },
+    password,
+    services::{CreateError, DeleteError, LoginHolder, ServiceContainer, UpdateError},
 };
 
+// Permission needed to give new users any role, and to change and delete users
+const USER_MANAGE: &str = "user:manage";
+
+// Check the caller may manage users, or respond with why not
+async fn authorize(service: &ServiceContainer, headers: &HeaderMap) -> Result<(), Response> {
+    let permissions = caller_permissions(service, request_token(headers, None)).await?;
+    if !permissions
+        .iter()
+        .any(|permission| permission == USER_MANAGE)
+    {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "Permission denied",
+            format!("Managing users requires the {} permission", USER_MANAGE),
+        ));
+    }
+    Ok(())
+}
+
+// The role a new user gets: the requested one when the caller may manage
+// users, and the default role for anyone signing themselves up
+async fn new_user_role(
+    service: &ServiceContainer,
+    headers: &HeaderMap,
+    requested: Option<Uuid>,
+) -> Result<Uuid, Response> {
+    if let Some(token) = request_token(headers, None) {
+        let permissions = caller_permissions(service, Some(token)).await?;
+        let manager = permissions
+            .iter()
+            .any(|permission| permission == USER_MANAGE);
+        if let (true, Some(role_id)) = (manager, requested) {
+            return Ok(role_id);
+        }
+    }
+    match service.role_service.find_by_name(DEFAULT_ROLE).await {
+        Ok(role) => Ok(role.id),
+        Err(sqlx::Error::RowNotFound) => Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create user",
+            format!(
+                "The {} role does not exist; run `blog-cms seed` first",
+                DEFAULT_ROLE
+            ),
+        )),
+        Err(e) => Err(error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create user",
+            e.to_string(),
+        )),
+    }
+}
+
 //Create a new user
+#[utoipa::path(
+    post,
+    path = "/api/user",
+    tag = "users",
+    request_body = CreateUser,
+    responses(
+        (status = 201, description = "User created", body = ApiResponse<UserResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 401, description = "The bearer token is invalid", body = ErrorResponse),
+        (status = 409, description = "The username or email is taken, possibly by a user in the trash",
+            body = ErrorResponse),
+        (status = 500, description = "User could not be created", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn create_user(
     State(service): State<ServiceContainer>,
+    headers: HeaderMap,
     Json(payload): Json<CreateUser>,
 ) -> Response {
-    let password_hash = match bcrypt::hash(&payload.password, DEFAULT_COST) {
+    let role_id = match new_user_role(&service, &headers, payload.role_id).await {
+        Ok(role_id) => role_id,
+        Err(response) => return response,
+    };
+    let password_hash = match password::hash(&payload.password) {
         Ok(hash) => hash,
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -35,12 +121,7 @@ pub async fn create_user(
     };
     let user_result = service
         .user_service
-        .create(
-            &payload.username,
-            &payload.email,
-            &password_hash,
-            payload.role_id,
-        )
+        .create(&payload.username, &payload.email, &password_hash, role_id)
         .await;
     match user_result {
         Ok(user) => {
@@ -52,8 +133,10 @@ pub async fn create_user(
                 "data": user,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            let validators = Validators::versioned(user.version, user.updated_at);
+            validators.apply((status_code, body).into_response())
         }
+        Err(CreateError::Taken(holder)) => login_taken("Failed to create user", holder),
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
             let body = Json(json!({
@@ -69,10 +152,27 @@ pub async fn create_user(
 }
 
 // Get all users
-pub async fn get_users(State(service): State<ServiceContainer>) -> Response {
+#[utoipa::path(
+    get,
+    path = "/api/user",
+    tag = "users",
+    params(ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "All users", body = ApiResponse<UserListResponse>,
+            headers(("ETag" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 500, description = "Users could not be retrieved", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_users(State(service): State<ServiceContainer>, headers: HeaderMap) -> Response {
     let user_result = service.user_service.find_all().await;
     match user_result {
         Ok(users) => {
+            let validators = Validators::new(&users, None);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
             let status_code = StatusCode::OK;
             let body = Json(json!({
                 "status": StatusCode::OK.to_string(),
@@ -81,7 +181,7 @@ pub async fn get_users(State(service): State<ServiceContainer>) -> Response {
                 "data": users,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -98,9 +198,25 @@ pub async fn get_users(State(service): State<ServiceContainer>) -> Response {
 }
 
 // Get a user by id
+#[utoipa::path(
+    get,
+    path = "/api/user/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User id"), ConditionalGetHeaders),
+    responses(
+        (status = 200, description = "The user", body = ApiResponse<UserResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 304, description = "The cached copy is current"),
+        (status = 400, description = "The id is not a UUID", body = ErrorResponse),
+        (status = 500, description = "User does not exist or could not be retrieved",
+            body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn get_user_by_id(
     State(service): State<ServiceContainer>,
     Path(id): Path<String>,
+    headers: HeaderMap,
 ) -> Response {
     let id = match Uuid::parse_str(&id) {
         Ok(id) => id,
@@ -119,6 +235,10 @@ pub async fn get_user_by_id(
     let user_result = service.user_service.find_by_id(id).await;
     match user_result {
         Ok(user) => {
+            let validators = Validators::versioned(user.version, user.updated_at);
+            if validators.not_modified(&headers) {
+                return validators.not_modified_response();
+            }
             let status_code = StatusCode::OK;
             let body = Json(json!({
                 "status": StatusCode::OK.to_string(),
@@ -127,7 +247,7 @@ pub async fn get_user_by_id(
                 "data": user,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            validators.apply((status_code, body).into_response())
         }
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
@@ -144,13 +264,51 @@ pub async fn get_user_by_id(
 }
 
 // Update a user by id
+#[utoipa::path(
+    put,
+    path = "/api/user/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User id"), ConditionalWriteHeaders),
+    request_body(
+        content = UpdateUser,
+        description = "The user to update is the one named by `id` in the body"
+    ),
+    responses(
+        (status = 200, description = "User updated", body = ApiResponse<UserResponse>,
+            headers(("ETag" = String), ("Last-Modified" = String))),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the user:manage permission", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 409, description = "The username or email is taken, possibly by a user in the trash",
+            body = ErrorResponse),
+        (status = 412, description = "The user changed since it was read", body = ErrorResponse),
+        (status = 428, description = "If-Match header is missing", body = ErrorResponse),
+        (status = 500, description = "User could not be updated", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn update_user_by_id(
     State(service): State<ServiceContainer>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateUser>,
 ) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let expected = match check_user_precondition(&service, payload.id, &headers).await {
+        Ok(expected) => expected,
+        Err(response) => return response,
+    };
     let user_result = service
         .user_service
-        .update_by_id(payload.id, payload.username, payload.email, payload.role_id)
+        .update_by_id(
+            payload.id,
+            payload.username,
+            payload.email,
+            payload.role_id,
+            expected,
+        )
         .await;
     match user_result {
         Ok(user) => {
@@ -162,8 +320,12 @@ pub async fn update_user_by_id(
                 "data": user,
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            let validators = Validators::versioned(user.version, user.updated_at);
+            validators.apply((status_code, body).into_response())
         }
+        Err(UpdateError::Modified) => precondition_failed(),
+        Err(UpdateError::Database(sqlx::Error::RowNotFound)) => user_not_found(),
+        Err(UpdateError::Taken(holder)) => login_taken("Failed to update user", holder),
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
             let body = Json(json!({
@@ -179,10 +341,40 @@ pub async fn update_user_by_id(
 }
 
 // Delete a user by id
+#[utoipa::path(
+    delete,
+    path = "/api/user/{id}",
+    tag = "users",
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        DeleteUserQuery,
+        ConditionalWriteHeaders,
+    ),
+    responses(
+        (status = 200, description = "User moved to the trash", body = MessageResponse),
+        (status = 400, description = "The id is not a UUID, or the content can't be reassigned as asked",
+            body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the user:manage permission", body = ErrorResponse),
+        (status = 409, description = "The user owns posts or comments and neither reassign_to nor cascade was given",
+            body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 412, description = "The user changed since it was read", body = ErrorResponse),
+        (status = 428, description = "If-Match header is missing", body = ErrorResponse),
+        (status = 500, description = "User could not be deleted", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn delete_user_by_id(
     State(service): State<ServiceContainer>,
     Path(id): Path<String>,
+    Query(params): Query<DeleteUserQuery>,
+    headers: HeaderMap,
 ) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
     let id = match Uuid::parse_str(&id) {
         Ok(id) => id,
         Err(e) => {
@@ -197,7 +389,30 @@ pub async fn delete_user_by_id(
             return (status_code, body).into_response();
         }
     };
-    let user_result = service.user_service.delete_by_id(id).await;
+    let content = match (params.reassign_to, params.cascade.unwrap_or(false)) {
+        (None, false) => OwnedContent::Refuse,
+        (Some(new_owner), false) => OwnedContent::ReassignTo(new_owner),
+        (None, true) => OwnedContent::Cascade,
+        (Some(_), true) => {
+            let status_code = StatusCode::BAD_REQUEST;
+            let body = Json(json!({
+                "status": StatusCode::BAD_REQUEST.to_string(),
+                "code": StatusCode::BAD_REQUEST.as_u16(),
+                "message": "Invalid user deletion",
+                "errors": "Content can either be reassigned or deleted, not both",
+                "timestamp": Utc::now(),
+            }));
+            return (status_code, body).into_response();
+        }
+    };
+    let expected = match check_user_precondition(&service, id, &headers).await {
+        Ok(expected) => expected,
+        Err(response) => return response,
+    };
+    let user_result = service
+        .user_service
+        .delete_by_id(id, content, expected)
+        .await;
     match user_result {
         Ok(user) => {
             let status_code = StatusCode::OK;
@@ -210,16 +425,83 @@ pub async fn delete_user_by_id(
             }));
             (status_code, body).into_response()
         }
+        Err(DeleteError::Modified) => precondition_failed(),
+        Err(DeleteError::Database(sqlx::Error::RowNotFound)) => user_not_found(),
+        Err(e) => {
+            let (status_code, errors) = match &e {
+                DeleteError::InUse => (
+                    StatusCode::CONFLICT,
+                    String::from(
+                        "The user still owns posts or comments; pass reassign_to=<user id> to \
+                         hand them to another user or cascade=true to delete them too",
+                    ),
+                ),
+                DeleteError::InvalidReassignment(reason) => {
+                    (StatusCode::BAD_REQUEST, reason.clone())
+                }
+                DeleteError::Modified | DeleteError::Database(_) => {
+                    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                }
+            };
+            let body = Json(json!({
+                "status": status_code.to_string(),
+                "code": status_code.as_u16(),
+                "message": "Failed to delete user",
+                "errors": errors,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+    }
+}
+
+// Reject a write whose If-Match header doesn't name the user's current
+// version, or return the version the write has to find the user at
+async fn check_user_precondition(
+    service: &ServiceContainer,
+    id: Uuid,
+    headers: &HeaderMap,
+) -> Result<Option<i64>, Response> {
+    match service.user_service.find_by_id(id).await {
+        Ok(user) => {
+            let validators = Validators::versioned(user.version, user.updated_at);
+            match validators.check_precondition(headers) {
+                Some(response) => Err(response),
+                None => Ok(validators.expected_version(headers)),
+            }
+        }
+        Err(sqlx::Error::RowNotFound) => Err(user_not_found()),
         Err(e) => {
             let status_code = StatusCode::INTERNAL_SERVER_ERROR;
             let body = Json(json!({
                 "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
                 "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                "message": "Failed to delete user",
+                "message": "Failed to fetch user",
                 "errors": e.to_string(),
                 "timestamp": Utc::now(),
             }));
-            (status_code, body).into_response()
+            Err((status_code, body).into_response())
         }
     }
 }
+
+fn user_not_found() -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        "User not found",
+        String::from("No user with this id"),
+    )
+}
+
+// A trashed user keeps its username and email until it is purged, so say
+// which one it is and how to free them
+fn login_taken(message: &str, holder: LoginHolder) -> Response {
+    let errors = match holder {
+        LoginHolder::Active => holder.to_string(),
+        LoginHolder::Trashed(id) => format!(
+            "User {id} in the trash has this username or email; restore it with \
+             POST /api/trash/user/{id}/restore or purge it with DELETE /api/trash/user/{id}"
+        ),
+    };
+    error_response(StatusCode::CONFLICT, message, errors)
+}
@@ -0,0 +1,449 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    handlers::{
+        auth::{caller_permissions, error_response, request_token},
+        openapi::{ApiResponse, ErrorResponse},
+    },
+    models::{
+        CreateWebhook, UpdateWebhook, WebhookDeliveryListResponse, WebhookDeliveryResponse,
+        WebhookListResponse, WebhookResponse,
+    },
+    services::{ServiceContainer, WebhookError},
+};
+
+// Permission needed to see and change webhooks, which receive user data
+const WEBHOOK_MANAGE: &str = "webhook:manage";
+
+// Check the caller may manage webhooks, or respond with why not
+async fn authorize(service: &ServiceContainer, headers: &HeaderMap) -> Result<(), Response> {
+    let permissions = caller_permissions(service, request_token(headers, None)).await?;
+    if !permissions
+        .iter()
+        .any(|permission| permission == WEBHOOK_MANAGE)
+    {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            "Permission denied",
+            format!(
+                "Managing webhooks requires the {} permission",
+                WEBHOOK_MANAGE
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Create a new webhook
+#[utoipa::path(
+    post,
+    path = "/api/webhook",
+    tag = "webhooks",
+    request_body = CreateWebhook,
+    responses(
+        (status = 201, description = "Webhook created", body = ApiResponse<WebhookResponse>),
+        (status = 400, description = "The URL, events or secret are invalid", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the webhook:manage permission",
+            body = ErrorResponse),
+        (status = 500, description = "Webhook could not be created", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn create_webhook(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateWebhook>,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let webhook_result = service.webhook_service.create(payload).await;
+    match webhook_result {
+        Ok(webhook) => {
+            let status_code = StatusCode::CREATED;
+            let body = Json(json!({
+                "status": StatusCode::CREATED.to_string(),
+                "code": StatusCode::CREATED.as_u16(),
+                "message": "Webhook created successfully",
+                "data": webhook,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => {
+            let (status_code, message) = match e {
+                WebhookError::Invalid(_) => (StatusCode::BAD_REQUEST, "Invalid webhook"),
+                WebhookError::Database(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to create webhook",
+                ),
+            };
+            let body = Json(json!({
+                "status": status_code.to_string(),
+                "code": status_code.as_u16(),
+                "message": message,
+                "errors": e.to_string(),
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+    }
+}
+
+// Get all webhooks
+#[utoipa::path(
+    get,
+    path = "/api/webhook",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "All webhooks", body = ApiResponse<WebhookListResponse>),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the webhook:manage permission",
+            body = ErrorResponse),
+        (status = 500, description = "Webhooks could not be retrieved", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_webhooks(State(service): State<ServiceContainer>, headers: HeaderMap) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let webhooks_result = service.webhook_service.find_all().await;
+    match webhooks_result {
+        Ok(webhooks) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Webhooks fetched successfully",
+                "data": webhooks,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => {
+            let status_code = StatusCode::INTERNAL_SERVER_ERROR;
+            let body = Json(json!({
+                "status": StatusCode::INTERNAL_SERVER_ERROR.to_string(),
+                "code": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                "message": "Failed to get webhooks",
+                "errors": e.to_string(),
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+    }
+}
+
+// Get webhook by id
+#[utoipa::path(
+    get,
+    path = "/api/webhook/{id}",
+    tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "The webhook", body = ApiResponse<WebhookResponse>),
+        (status = 400, description = "The id is not a UUID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the webhook:manage permission",
+            body = ErrorResponse),
+        (status = 404, description = "Webhook does not exist", body = ErrorResponse),
+        (status = 500, description = "Webhook could not be retrieved", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_webhook_by_id(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let webhook_id = match parse_id(&id, "Invalid webhook id") {
+        Ok(webhook_id) => webhook_id,
+        Err(response) => return response,
+    };
+    let webhook_result = service.webhook_service.find_by_id(webhook_id).await;
+    match webhook_result {
+        Ok(webhook) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Webhook fetched successfully",
+                "data": webhook,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => database_error(e, "Webhook not found", "Failed to get webhook"),
+    }
+}
+
+// Update webhook by id
+#[utoipa::path(
+    put,
+    path = "/api/webhook/{id}",
+    tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    request_body = UpdateWebhook,
+    responses(
+        (status = 200, description = "Webhook updated", body = ApiResponse<WebhookResponse>),
+        (status = 400, description = "The id is not a UUID or the changes are invalid",
+            body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the webhook:manage permission",
+            body = ErrorResponse),
+        (status = 404, description = "Webhook does not exist", body = ErrorResponse),
+        (status = 500, description = "Webhook could not be updated", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn update_webhook_by_id(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateWebhook>,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let webhook_id = match parse_id(&id, "Invalid webhook id") {
+        Ok(webhook_id) => webhook_id,
+        Err(response) => return response,
+    };
+    let webhook_result = service
+        .webhook_service
+        .update_by_id(webhook_id, payload)
+        .await;
+    match webhook_result {
+        Ok(webhook) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Webhook updated successfully",
+                "data": webhook,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(WebhookError::Invalid(reason)) => {
+            let status_code = StatusCode::BAD_REQUEST;
+            let body = Json(json!({
+                "status": StatusCode::BAD_REQUEST.to_string(),
+                "code": StatusCode::BAD_REQUEST.as_u16(),
+                "message": "Invalid webhook",
+                "errors": reason,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(WebhookError::Database(e)) => {
+            database_error(e, "Webhook not found", "Failed to update webhook")
+        }
+    }
+}
+
+// Delete webhook by id, along with its delivery log
+#[utoipa::path(
+    delete,
+    path = "/api/webhook/{id}",
+    tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "Webhook deleted", body = ApiResponse<WebhookResponse>),
+        (status = 400, description = "The id is not a UUID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the webhook:manage permission",
+            body = ErrorResponse),
+        (status = 404, description = "Webhook does not exist", body = ErrorResponse),
+        (status = 500, description = "Webhook could not be deleted", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn delete_webhook_by_id(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let webhook_id = match parse_id(&id, "Invalid webhook id") {
+        Ok(webhook_id) => webhook_id,
+        Err(response) => return response,
+    };
+    let webhook = match service.webhook_service.find_by_id(webhook_id).await {
+        Ok(webhook) => webhook,
+        Err(e) => return database_error(e, "Webhook not found", "Failed to get webhook"),
+    };
+    let delete_result = service.webhook_service.delete_by_id(webhook_id).await;
+    match delete_result {
+        Ok(_) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Webhook deleted successfully",
+                "data": webhook,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => database_error(e, "Webhook not found", "Failed to delete webhook"),
+    }
+}
+
+// Get the delivery log of a webhook
+#[utoipa::path(
+    get,
+    path = "/api/webhook/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "The latest 100 deliveries, newest first",
+            body = ApiResponse<WebhookDeliveryListResponse>),
+        (status = 400, description = "The id is not a UUID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the webhook:manage permission",
+            body = ErrorResponse),
+        (status = 404, description = "Webhook does not exist", body = ErrorResponse),
+        (status = 500, description = "Deliveries could not be retrieved", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_webhook_deliveries(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let webhook_id = match parse_id(&id, "Invalid webhook id") {
+        Ok(webhook_id) => webhook_id,
+        Err(response) => return response,
+    };
+    let deliveries_result = service.webhook_service.find_deliveries(webhook_id).await;
+    match deliveries_result {
+        Ok(deliveries) => {
+            let status_code = StatusCode::OK;
+            let body = Json(json!({
+                "status": StatusCode::OK.to_string(),
+                "code": StatusCode::OK.as_u16(),
+                "message": "Deliveries fetched successfully",
+                "data": deliveries,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => database_error(e, "Webhook not found", "Failed to get deliveries"),
+    }
+}
+
+// Queue an earlier delivery's payload to be sent again
+#[utoipa::path(
+    post,
+    path = "/api/webhook/{id}/deliveries/{delivery_id}/redeliver",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook id"),
+        ("delivery_id" = Uuid, Path, description = "Id of the delivery to repeat"),
+    ),
+    responses(
+        (status = 202, description = "A new delivery was queued",
+            body = ApiResponse<WebhookDeliveryResponse>),
+        (status = 400, description = "An id is not a UUID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid bearer token", body = ErrorResponse),
+        (status = 403, description = "The caller lacks the webhook:manage permission",
+            body = ErrorResponse),
+        (status = 404, description = "Delivery does not exist", body = ErrorResponse),
+        (status = 500, description = "Delivery could not be queued", body = ErrorResponse),
+        (status = 503, description = "No JWT secret is configured", body = ErrorResponse),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn redeliver_webhook_delivery(
+    State(service): State<ServiceContainer>,
+    headers: HeaderMap,
+    Path((id, delivery_id)): Path<(String, String)>,
+) -> Response {
+    if let Err(response) = authorize(&service, &headers).await {
+        return response;
+    }
+    let webhook_id = match parse_id(&id, "Invalid webhook id") {
+        Ok(webhook_id) => webhook_id,
+        Err(response) => return response,
+    };
+    let delivery_id = match parse_id(&delivery_id, "Invalid delivery id") {
+        Ok(delivery_id) => delivery_id,
+        Err(response) => return response,
+    };
+    let delivery_result = service
+        .webhook_service
+        .redeliver(webhook_id, delivery_id)
+        .await;
+    match delivery_result {
+        Ok(delivery) => {
+            let status_code = StatusCode::ACCEPTED;
+            let body = Json(json!({
+                "status": StatusCode::ACCEPTED.to_string(),
+                "code": StatusCode::ACCEPTED.as_u16(),
+                "message": "Delivery queued successfully",
+                "data": delivery,
+                "timestamp": Utc::now(),
+            }));
+            (status_code, body).into_response()
+        }
+        Err(e) => database_error(e, "Delivery not found", "Failed to queue delivery"),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_id(id: &str, message: &str) -> Result<Uuid, Response> {
+    Uuid::parse_str(id).map_err(|e| {
+        let status_code = StatusCode::BAD_REQUEST;
+        let body = Json(json!({
+            "status": StatusCode::BAD_REQUEST.to_string(),
+            "code": StatusCode::BAD_REQUEST.as_u16(),
+            "message": message,
+            "errors": e.to_string(),
+            "timestamp": Utc::now(),
+        }));
+        (status_code, body).into_response()
+    })
+}
+
+// 404 for a missing row, 500 for anything else
+fn database_error(e: sqlx::Error, not_found: &str, failed: &str) -> Response {
+    let (status_code, message) = match e {
+        sqlx::Error::RowNotFound => (StatusCode::NOT_FOUND, not_found),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, failed),
+    };
+    let body = Json(json!({
+        "status": status_code.to_string(),
+        "code": status_code.as_u16(),
+        "message": message,
+        "errors": e.to_string(),
+        "timestamp": Utc::now(),
+    }));
+    (status_code, body).into_response()
+}
@@ -0,0 +1,90 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// Registry of background worker heartbeats, used by the readiness probe to
+// detect workers that have stalled or died.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeats {
+    workers: Arc<Mutex<HashMap<String, Heartbeat>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Heartbeat {
+    last_beat: Instant,
+    max_interval: Duration,
+}
+
+// Handle held by a worker to report that it is still alive
+#[derive(Debug, Clone)]
+pub struct HeartbeatHandle {
+    name: String,
+    heartbeats: Heartbeats,
+}
+
+// Point-in-time view of one worker's heartbeat
+#[derive(Debug, Clone)]
+pub struct HeartbeatStatus {
+    pub name: String,
+    pub since_last_beat: Duration,
+    pub max_interval: Duration,
+}
+
+impl Heartbeats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Register a worker that promises to beat at least every `max_interval`
+    pub fn register(&self, name: &str, max_interval: Duration) -> HeartbeatHandle {
+        self.workers.lock().unwrap().insert(
+            name.to_string(),
+            Heartbeat {
+                last_beat: Instant::now(),
+                max_interval,
+            },
+        );
+        HeartbeatHandle {
+            name: name.to_string(),
+            heartbeats: self.clone(),
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<HeartbeatStatus> {
+        let now = Instant::now();
+        let mut statuses: Vec<HeartbeatStatus> = self
+            .workers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, heartbeat)| HeartbeatStatus {
+                name: name.clone(),
+                since_last_beat: now.duration_since(heartbeat.last_beat),
+                max_interval: heartbeat.max_interval,
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+}
+
+impl HeartbeatHandle {
+    pub fn beat(&self) {
+        if let Some(heartbeat) = self.heartbeats.workers.lock().unwrap().get_mut(&self.name) {
+            heartbeat.last_beat = Instant::now();
+        }
+    }
+
+    // Stop tracking the worker, e.g. when it exits during shutdown
+    pub fn deregister(self) {
+        self.heartbeats.workers.lock().unwrap().remove(&self.name);
+    }
+}
+
+impl HeartbeatStatus {
+    pub fn is_alive(&self) -> bool {
+        self.since_last_beat <= self.max_interval
+    }
+}
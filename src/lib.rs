@@ -1,29 +1,175 @@
+use axum::Router;
+use cli::{Cli, Command, ConfigAction, ConfigArgs, MigrateAction};
 use config::Config;
-use db::Database;
+use heartbeat::Heartbeats;
+use services::ServiceContainer;
+use shutdown::Shutdown;
 
-mod config;
+mod admin;
+mod audit;
+mod cache;
+pub mod cli;
+pub mod config;
 mod db;
 mod entities;
+mod graphql;
 mod handlers;
+pub mod heartbeat;
+mod mail;
+pub mod metrics;
 mod models;
+mod password;
+mod proxy;
+mod ratelimit;
 mod repositories;
 mod routes;
 mod services;
+pub mod shutdown;
+pub mod telemetry;
+
+pub use db::{Database, MigrationError};
+
+pub async fn run(cli: Cli) -> Result<(), std::io::Error> {
+    let command = cli.command.unwrap_or(Command::Serve);
+    if let Command::Config {
+        action: ConfigAction::Check,
+    } = command
+    {
+        return check_config(&cli.config);
+    }
+
+    let config = Config::load(&cli.config).map_err(std::io::Error::other)?;
+    let _telemetry = telemetry::init(config.log());
+    match command {
+        Command::Serve => serve(config).await,
+        Command::Migrate { action } => run_migrations(&config, action).await,
+        command => run_admin_command(&config, command).await,
+    }
+}
 
 pub async fn run_app() -> Result<(), std::io::Error> {
     // Load configuration
-    let config = Config::new();
+    let config = Config::load(&ConfigArgs::default()).map_err(std::io::Error::other)?;
+    let _telemetry = telemetry::init(config.log());
+    serve(config).await
+}
 
+async fn serve(config: Config) -> Result<(), std::io::Error> {
     // Create database connection
     let db = Database::new(&config).await;
 
-    let repository_container = repositories::RepositoryContainer::new(db.get_pool());
-    let service_container = services::ServiceContainer::new(repository_container);
+    // Bring the schema up to date, or at least make sure it isn't newer than this build
+    if config.should_run_migrations() {
+        db.migrate_up().await.map_err(std::io::Error::other)?;
+    } else {
+        db.check_schema_version()
+            .await
+            .map_err(std::io::Error::other)?;
+    }
 
-    let app_routes = routes::create_api_routes(service_container);
+    let shutdown = Shutdown::new();
+    let heartbeats = Heartbeats::new();
+    metrics::install();
+    metrics::spawn_upkeep(&shutdown, &heartbeats);
+    db.get_replicas().spawn_monitor(&shutdown, &heartbeats);
+    let app_routes = app(&db, heartbeats, &shutdown, &config);
     let listener = tokio::net::TcpListener::bind(config.get_host())
         .await
         .expect("Error binding to port");
 
-    axum::serve(listener, app_routes.into_make_service()).await
+    tracing::info!(host = config.get_host(), "Listening for requests");
+    shutdown::trigger_on_signal(shutdown.clone());
+    let result = shutdown::serve(
+        listener,
+        app_routes,
+        shutdown,
+        config.server().drain_timeout(),
+    )
+    .await;
+
+    db.close().await;
+    result
+}
+
+async fn run_migrations(config: &Config, action: MigrateAction) -> Result<(), std::io::Error> {
+    let db = Database::new(config).await;
+
+    match action {
+        MigrateAction::Up => {
+            db.migrate_up().await.map_err(std::io::Error::other)?;
+            println!("Database schema is up to date");
+        }
+        MigrateAction::Down => match db.migrate_down().await.map_err(std::io::Error::other)? {
+            Some(version) => println!("Reverted migration {}", version),
+            None => println!("No applied migrations to revert"),
+        },
+        MigrateAction::Status => {
+            for status in db.migration_status().await.map_err(std::io::Error::other)? {
+                let state = if status.applied { "applied" } else { "pending" };
+                println!("{:<16} {:<8} {}", status.version, state, status.description);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_admin_command(config: &Config, command: Command) -> Result<(), std::io::Error> {
+    let db = Database::new(config).await;
+    db.check_schema_version()
+        .await
+        .map_err(std::io::Error::other)?;
+    let services = create_services(&db, Heartbeats::new(), &Shutdown::new(), config);
+
+    match command {
+        Command::Seed => admin::seed(&services).await,
+        Command::CreateAdmin {
+            username,
+            email,
+            password,
+        } => admin::create_admin(&services, &username, &email, &password).await,
+        Command::ResetPassword { user, password } => {
+            admin::reset_password(&services, &user, &password).await
+        }
+        Command::ListUsers => admin::list_users(&services).await,
+        Command::Reindex => admin::reindex(&services).await,
+        Command::Export { output } => admin::export(&services, output.as_deref()).await,
+        Command::Serve | Command::Migrate { .. } | Command::Config { .. } => {
+            unreachable!("handled by run")
+        }
+    }
+}
+
+// Print the effective configuration, or every problem preventing it from loading
+fn check_config(args: &ConfigArgs) -> Result<(), std::io::Error> {
+    let config = Config::load(args).map_err(std::io::Error::other)?;
+    println!("# Configuration is valid\n");
+    print!("{}", config.to_redacted_toml());
+    Ok(())
+}
+
+// Build the HTTP application on top of an already connected database, along
+// with the background workers it relies on
+pub fn app(db: &Database, heartbeats: Heartbeats, shutdown: &Shutdown, config: &Config) -> Router {
+    let services = create_services(db, heartbeats.clone(), shutdown, config);
+    services
+        .webhook_service
+        .spawn_worker(shutdown, &heartbeats);
+    services
+        .outbox_relay
+        .spawn_worker(services.outbox_subscribers(), shutdown, &heartbeats);
+    services.trash_service.spawn_worker(shutdown, &heartbeats);
+    services.event_service.close_on_shutdown(shutdown);
+    routes::create_api_routes(services, config)
+}
+
+fn create_services(
+    db: &Database,
+    heartbeats: Heartbeats,
+    shutdown: &Shutdown,
+    config: &Config,
+) -> ServiceContainer {
+    let repository_container =
+        repositories::RepositoryContainer::new(db.get_pool(), db.get_replicas());
+    let post_cache = cache::Cache::from_config("posts", config.cache());
+    ServiceContainer::new(repository_container, heartbeats, post_cache, shutdown, config)
 }
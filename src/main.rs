@@ -1,4 +1,15 @@
+use std::process::ExitCode;
+
+use blog_cms::cli::Cli;
+use clap::Parser;
+
 #[tokio::main]
-async fn main() -> Result<(), std::io::Error> {
-    blog_cms::run_app().await
+async fn main() -> ExitCode {
+    match blog_cms::run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,97 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::{heartbeat::Heartbeats, shutdown::Shutdown};
+
+// Latency buckets in seconds, shared by every `*_duration_seconds` histogram
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+// How often buffered histogram samples are folded into the exported buckets
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+// Install the global Prometheus recorder. Safe to call more than once; every
+// app in the process shares the same registry.
+pub fn install() -> PrometheusHandle {
+    HANDLE
+        .get_or_init(|| {
+            let builder = PrometheusBuilder::new()
+                .set_buckets_for_metric(
+                    Matcher::Suffix(String::from("duration_seconds")),
+                    DURATION_BUCKETS,
+                )
+                .expect("Duration buckets are not empty");
+            let recorder = builder.build_recorder();
+            let handle = recorder.handle();
+            // Another recorder may already be installed (e.g. by a test harness);
+            // the handle then renders an empty registry rather than failing startup
+            if metrics::set_global_recorder(recorder).is_err() {
+                tracing::warn!("A metrics recorder is already installed");
+            }
+            handle
+        })
+        .clone()
+}
+
+// Render every metric in the Prometheus text exposition format
+pub fn render() -> String {
+    install().render()
+}
+
+// Periodically drain histogram samples so memory stays bounded between scrapes
+pub fn spawn_upkeep(shutdown: &Shutdown, heartbeats: &Heartbeats) {
+    let handle = install();
+    let heartbeat = heartbeats.register("metrics_upkeep", UPKEEP_INTERVAL * 3);
+    shutdown.spawn_worker(|token| async move {
+        let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    handle.run_upkeep();
+                    heartbeat.beat();
+                }
+                _ = token.cancelled() => break,
+            }
+        }
+        heartbeat.deregister();
+    });
+}
+
+// Count requests and record their latency, labelled by route template rather
+// than the raw path so ids don't explode the label cardinality
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| String::from("unmatched"));
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels).record(started.elapsed());
+    response
+}
+
+// Report the number of jobs waiting in a background queue
+pub fn set_queue_depth(queue: &'static str, depth: usize) {
+    metrics::gauge!("job_queue_depth", "queue" => queue).set(depth as f64);
+}
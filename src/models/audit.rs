@@ -0,0 +1,126 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    // Taken back out of the trash
+    Restore,
+    // Removed from the trash for good
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(AuditAction::Create),
+            "update" => Ok(AuditAction::Update),
+            "delete" => Ok(AuditAction::Delete),
+            "restore" => Ok(AuditAction::Restore),
+            "purge" => Ok(AuditAction::Purge),
+            _ => Err(format!("unknown audit action `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditTargetType {
+    User,
+    Role,
+    Post,
+}
+
+impl AuditTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditTargetType::User => "user",
+            AuditTargetType::Role => "role",
+            AuditTargetType::Post => "post",
+        }
+    }
+}
+
+impl fmt::Display for AuditTargetType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditTargetType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(AuditTargetType::User),
+            "role" => Ok(AuditTargetType::Role),
+            "post" => Ok(AuditTargetType::Post),
+            _ => Err(format!("unknown audit target type `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntryResponse {
+    pub id: Uuid,
+    /// User whose bearer token authorized the change, if any
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target_type: AuditTargetType,
+    pub target_id: Uuid,
+    /// The target as it was before the change; absent for creations
+    pub before: Option<serde_json::Value>,
+    /// The target as it was after the change; absent for deletions
+    pub after: Option<serde_json::Value>,
+    /// Address of the client that made the request
+    pub ip: Option<String>,
+    /// The X-Request-Id of the request that made the change
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuditListResponse {
+    pub entries: Vec<AuditEntryResponse>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub actor_id: Option<Uuid>,
+    pub target_type: Option<AuditTargetType>,
+    pub target_id: Option<Uuid>,
+    /// Only entries recorded at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries recorded before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Maximum number of entries, newest first (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
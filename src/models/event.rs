@@ -0,0 +1,79 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EventTopic {
+    /// post.created, post.updated and post.published
+    Posts,
+    /// comment.created
+    Comments,
+    /// user.created and user.updated
+    Users,
+}
+
+impl EventTopic {
+    pub const ALL: [EventTopic; 3] = [EventTopic::Posts, EventTopic::Comments, EventTopic::Users];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventTopic::Posts => "posts",
+            EventTopic::Comments => "comments",
+            EventTopic::Users => "users",
+        }
+    }
+
+    // Permission needed to subscribe to the topic at all
+    pub fn permission(&self) -> Option<&'static str> {
+        match self {
+            EventTopic::Users => Some("user:manage"),
+            EventTopic::Posts | EventTopic::Comments => None,
+        }
+    }
+}
+
+impl fmt::Display for EventTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventTopic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "posts" => Ok(EventTopic::Posts),
+            "comments" => Ok(EventTopic::Comments),
+            "users" => Ok(EventTopic::Users),
+            _ => Err(format!("unknown event topic `{}`", s)),
+        }
+    }
+}
+
+// A change announced to live event subscribers
+#[derive(Debug, Clone)]
+pub struct LiveEvent {
+    // Increases by one with every event published by this process
+    pub id: u64,
+    pub topic: EventTopic,
+    pub event: &'static str,
+    // JSON representation of the changed resource
+    pub data: String,
+    // Permission a subscriber needs to receive the event, on top of the
+    // topic's own
+    pub permission: Option<&'static str>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventStreamQuery {
+    /// Comma separated topics to receive: posts, comments and users. Every
+    /// topic the caller may see when omitted
+    pub topics: Option<String>,
+    /// Bearer token, for clients such as `EventSource` that cannot send an
+    /// Authorization header
+    pub access_token: Option<String>,
+}
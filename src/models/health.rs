@@ -0,0 +1,61 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DatabaseCheck {
+    pub status: CheckStatus,
+    pub latency_ms: Option<u128>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationCheck {
+    pub status: CheckStatus,
+    pub applied_version: Option<i64>,
+    pub expected_version: i64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolCheck {
+    pub status: CheckStatus,
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+    pub saturation: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WorkerCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub seconds_since_heartbeat: f64,
+    pub max_interval_seconds: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    pub database: DatabaseCheck,
+    pub migrations: MigrationCheck,
+    pub pool: PoolCheck,
+    pub workers: Vec<WorkerCheck>,
+}
+
+impl ReadinessResponse {
+    pub fn is_ready(&self) -> bool {
+        self.database.status == CheckStatus::Up
+            && self.migrations.status == CheckStatus::Up
+            && self.pool.status == CheckStatus::Up
+            && self
+                .workers
+                .iter()
+                .all(|worker| worker.status == CheckStatus::Up)
+    }
+}
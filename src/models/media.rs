@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// A media item is registered by its metadata; the file itself is hosted
+// elsewhere and referenced by `url`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateMedia {
+    pub file_name: String,
+    pub url: String,
+    pub mime_type: String,
+    pub alt_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MediaResponse {
+    pub id: Uuid,
+    pub file_name: String,
+    pub url: String,
+    pub mime_type: String,
+    pub alt_text: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,7 +1,36 @@
+mod audit;
+mod auth;
+mod comment;
+mod event;
+mod health;
+mod media;
+mod permission;
 mod post;
 mod role;
+mod trash;
 mod user;
+mod webhook;
 
-pub use post::{CreatePost, PostListResponse, PostResponse, UpdatePost};
-pub use role::{CreateRole, RoleListResponse, RoleResponse, UpdateRole};
-pub use user::{CreateUser, UpdateUser, UserListResponse, UserResponse};
+pub use audit::{
+    AuditAction, AuditEntryResponse, AuditListResponse, AuditQuery, AuditTargetType,
+};
+pub use auth::{
+    ChangePasswordRequest, ForgotPasswordRequest, LoginRequest, ResetPasswordRequest, TokenResponse,
+};
+pub use comment::{CommentResponse, CreateComment};
+pub use event::{EventStreamQuery, EventTopic, LiveEvent};
+pub use health::{
+    CheckStatus, DatabaseCheck, MigrationCheck, PoolCheck, ReadinessResponse, WorkerCheck,
+};
+pub use media::{CreateMedia, MediaResponse};
+pub use permission::{PermissionListResponse, PermissionResponse};
+pub use post::{CreatePost, PostListResponse, PostResponse, PostSearchQuery, UpdatePost};
+pub use role::{CreateRole, DeleteRoleQuery, RoleListResponse, RoleResponse, UpdateRole};
+pub use trash::{TrashItemResponse, TrashItemType, TrashListResponse, TrashQuery};
+pub use user::{
+    CreateUser, DeleteUserQuery, OwnedContent, UpdateUser, UserListResponse, UserResponse,
+};
+pub use webhook::{
+    CreateWebhook, DeliveryStatus, UpdateWebhook, WebhookDeliveryListResponse,
+    WebhookDeliveryResponse, WebhookEvent, WebhookListResponse, WebhookResponse,
+};
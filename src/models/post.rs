@@ -1,19 +1,27 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::entities::PostStatus;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreatePost {
     pub title: String,
     pub content: String,
     pub status: PostStatus,
     pub published_at: Option<DateTime<Utc>>,
     pub user_id: Uuid,
+    pub featured_media_id: Option<Uuid>,
+    pub excerpt: Option<String>,
+    pub meta_description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub og_image_url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdatePost {
     pub id: Uuid,
     pub title: Option<String>,
@@ -21,9 +29,47 @@ pub struct UpdatePost {
     pub status: Option<PostStatus>,
     pub published_at: Option<DateTime<Utc>>,
     pub user_id: Option<Uuid>,
+    /// Left out to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<Uuid>)]
+    pub featured_media_id: Option<Option<Uuid>>,
+    /// Left out to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub excerpt: Option<Option<String>>,
+    /// Left out to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub meta_description: Option<Option<String>>,
+    /// Left out to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub canonical_url: Option<Option<String>>,
+    /// Left out to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub og_title: Option<Option<String>>,
+    /// Left out to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub og_description: Option<Option<String>>,
+    /// Left out to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub og_image_url: Option<Option<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// An explicit null comes through as `Some(None)`, while a field that was left
+// out falls back to `None` through `#[serde(default)]`
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PostResponse {
     pub id: Uuid,
     pub title: String,
@@ -31,9 +77,27 @@ pub struct PostResponse {
     pub status: PostStatus,
     pub published_at: Option<DateTime<Utc>>,
     pub user_id: Uuid,
+    pub featured_media_id: Option<Uuid>,
+    pub excerpt: Option<String>,
+    pub meta_description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub og_image_url: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    /// Bumped by every change; the ETag is derived from it
+    pub version: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PostListResponse {
     pub posts: Vec<PostResponse>,
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PostSearchQuery {
+    /// Words to look for in the title, excerpt and content
+    #[param(min_length = 1)]
+    pub q: String,
+}
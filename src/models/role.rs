@@ -1,27 +1,39 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateRole {
     pub role_name: String,
     pub description: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateRole {
     pub id: Uuid,
     pub role_name: Option<String>,
     pub description: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RoleResponse {
     pub id: Uuid,
     pub role_name: String,
     pub description: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    /// Bumped by every change; the ETag is derived from it
+    pub version: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RoleListResponse {
     pub roles: Vec<RoleResponse>,
 }
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteRoleQuery {
+    /// Role to move the deleted role's users to
+    pub reassign_to: Option<Uuid>,
+}
@@ -0,0 +1,68 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashItemType {
+    User,
+    Post,
+    Comment,
+}
+
+impl TrashItemType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrashItemType::User => "user",
+            TrashItemType::Post => "post",
+            TrashItemType::Comment => "comment",
+        }
+    }
+}
+
+impl fmt::Display for TrashItemType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TrashItemType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(TrashItemType::User),
+            "post" => Ok(TrashItemType::Post),
+            "comment" => Ok(TrashItemType::Comment),
+            _ => Err(format!("unknown trash item type `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrashItemResponse {
+    #[serde(rename = "type")]
+    pub item_type: TrashItemType,
+    pub id: Uuid,
+    /// Username, post title or the start of the comment
+    pub label: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrashListResponse {
+    pub items: Vec<TrashItemResponse>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrashQuery {
+    /// Only items of this type
+    #[serde(rename = "type")]
+    pub item_type: Option<TrashItemType>,
+    /// Maximum number of items, most recently deleted first (default 100, at most 1000)
+    pub limit: Option<i64>,
+}
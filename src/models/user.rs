@@ -1,15 +1,19 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateUser {
     pub username: String,
     pub email: String,
     pub password: String,
-    pub role_id: Uuid,
+    /// Role of the new user; only honoured for callers with the user:manage
+    /// permission, everyone else gets the subscriber role
+    pub role_id: Option<Uuid>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateUser {
     pub id: Uuid,
     pub username: Option<String>,
@@ -17,15 +21,38 @@ pub struct UpdateUser {
     pub role_id: Option<Uuid>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub username: String,
     pub email: String,
     pub role_id: Uuid,
+    pub updated_at: DateTime<Utc>,
+    /// Bumped by every change; the ETag is derived from it
+    pub version: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserListResponse {
     pub users: Vec<UserResponse>,
 }
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteUserQuery {
+    /// User to hand the deleted user's posts and comments to
+    pub reassign_to: Option<Uuid>,
+    /// Move the user's posts and comments to the trash along with them
+    pub cascade: Option<bool>,
+}
+
+// What happens to the posts and comments of a user being deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnedContent {
+    // Refuse to delete a user who still owns any
+    Refuse,
+    // Hand all of them, including those in the trash, to another user
+    ReassignTo(Uuid),
+    // Move them to the trash along with the user
+    Cascade,
+}
@@ -0,0 +1,17 @@
+use std::time::Instant;
+
+use bcrypt::{BcryptError, DEFAULT_COST};
+
+// Hash a password with bcrypt, recording how long it took. Hashing is
+// deliberately slow, so its duration is worth watching when tuning the cost.
+pub fn hash(password: &str) -> Result<String, BcryptError> {
+    let started = Instant::now();
+    let hash = bcrypt::hash(password, DEFAULT_COST);
+    metrics::histogram!("bcrypt_hash_duration_seconds").record(started.elapsed());
+    hash
+}
+
+// Check a password against a stored bcrypt hash
+pub fn verify(password: &str, hash: &str) -> Result<bool, BcryptError> {
+    bcrypt::verify(password, hash)
+}
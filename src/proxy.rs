@@ -0,0 +1,94 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::HeaderMap,
+};
+use ipnet::IpNet;
+
+const FORWARDED_FOR: &str = "x-forwarded-for";
+
+// The load balancers and reverse proxies whose X-Forwarded-For headers are
+// believed when working out where a request came from
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    // Entries that are neither an address nor a CIDR block are skipped; config
+    // validation reports them
+    pub fn new(entries: &[String]) -> Self {
+        TrustedProxies(
+            entries
+                .iter()
+                .filter_map(|entry| parse_network(entry))
+                .collect(),
+        )
+    }
+
+    // The address a request came from; `None` when the server wasn't started
+    // with peer addresses
+    pub fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip())?;
+        Some(client_ip(peer, request.headers(), &self.0))
+    }
+}
+
+// Requests relayed by a trusted proxy are attributed to the last
+// X-Forwarded-For hop that isn't a trusted proxy itself; earlier hops could
+// have been written by the client.
+fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpNet]) -> IpAddr {
+    let trusted = |ip: &IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+    if !trusted(&peer) {
+        return peer;
+    }
+    let hops: Vec<IpAddr> = headers
+        .get_all(FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    hops.iter()
+        .rev()
+        .find(|hop| !trusted(hop))
+        .or(hops.first())
+        .copied()
+        .unwrap_or(peer)
+}
+
+// A trusted proxy entry: a single address or a CIDR block
+pub(crate) fn parse_network(entry: &str) -> Option<IpNet> {
+    entry
+        .parse()
+        .ok()
+        .or_else(|| entry.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn forwarded_addresses_are_only_believed_from_trusted_proxies() {
+        let proxies = vec![parse_network("10.0.0.0/8").unwrap()];
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            FORWARDED_FOR,
+            HeaderValue::from_static("1.1.1.1, 2.2.2.2, 10.0.0.2"),
+        );
+        let peer: IpAddr = "10.0.0.1".parse().unwrap();
+        assert_eq!(
+            client_ip(peer, &headers, &proxies),
+            "2.2.2.2".parse::<IpAddr>().unwrap()
+        );
+
+        let untrusted: IpAddr = "3.3.3.3".parse().unwrap();
+        assert_eq!(client_ip(untrusted, &headers, &proxies), untrusted);
+        assert_eq!(client_ip(peer, &HeaderMap::new(), &proxies), peer);
+    }
+}
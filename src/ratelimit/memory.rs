@@ -0,0 +1,58 @@
+use std::{num::NonZeroUsize, sync::Mutex, time::Instant};
+
+use async_trait::async_trait;
+use lru::LruCache;
+
+use super::{gcra, Decision, Quota, RateLimitStore};
+
+// In-process store of the arrival times of up to `capacity` clients. Each
+// instance counts only the requests it serves itself.
+#[derive(Debug)]
+pub struct MemoryStore {
+    started: Instant,
+    // Theoretical arrival times in milliseconds since `started`
+    tats: Mutex<LruCache<String, u64>>,
+}
+
+impl MemoryStore {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        MemoryStore {
+            started: Instant::now(),
+            tats: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn acquire(&self, key: &str, quota: Quota) -> Option<Decision> {
+        let now = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let mut tats = self.tats.lock().unwrap_or_else(|e| e.into_inner());
+        let (decision, tat) = gcra(tats.get(key).copied(), now, quota);
+        if let Some(tat) = tat {
+            tats.put(key.to_string(), tat);
+        }
+        Some(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn clients_have_separate_quotas() {
+        let store = MemoryStore::new(10);
+        let quota = Quota {
+            limit: 1,
+            period: Duration::from_secs(60),
+        };
+
+        assert!(store.acquire("a", quota).await.unwrap().allowed);
+        assert!(!store.acquire("a", quota).await.unwrap().allowed);
+        assert!(store.acquire("b", quota).await.unwrap().allowed);
+    }
+}
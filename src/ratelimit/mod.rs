@@ -0,0 +1,297 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    config::RateLimitConfig,
+    handlers::{bearer_token, error_response},
+    proxy::TrustedProxies,
+    services::AuthService,
+};
+
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "redis")]
+pub use self::redis::RedisStore;
+pub use memory::MemoryStore;
+
+// Clients tracked by the in-process store before the least recently seen is
+// forgotten, which only ever gives that client a fresh quota
+const MEMORY_CAPACITY: usize = 100_000;
+
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+// How many requests a client may make per period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub limit: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    fn period_ms(&self) -> u64 {
+        u64::try_from(self.period.as_millis()).unwrap_or(u64::MAX)
+    }
+
+    // Time between requests at the sustained rate
+    fn increment_ms(&self) -> u64 {
+        (self.period_ms() / u64::from(self.limit.max(1))).max(1)
+    }
+}
+
+// The verdict on one request, and what to tell the client about its quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    // Until the full quota is available again
+    pub reset: Duration,
+    // Until the next request would be allowed; zero when this one was
+    pub retry_after: Duration,
+}
+
+impl Decision {
+    // `backlog_ms` is how far the client's theoretical arrival time is ahead
+    // of now: after the request when it was allowed, before it when not
+    fn new(quota: Quota, allowed: bool, backlog_ms: u64) -> Self {
+        let period = quota.period_ms();
+        let increment = quota.increment_ms();
+        let (remaining, retry_after) = if allowed {
+            (period.saturating_sub(backlog_ms) / increment, 0)
+        } else {
+            (0, (backlog_ms + increment).saturating_sub(period))
+        };
+        Decision {
+            allowed,
+            limit: quota.limit,
+            remaining: u32::try_from(remaining)
+                .unwrap_or(u32::MAX)
+                .min(quota.limit),
+            reset: Duration::from_millis(backlog_ms),
+            retry_after: Duration::from_millis(retry_after),
+        }
+    }
+}
+
+// Generic cell rate algorithm. A client's state is its theoretical arrival
+// time (TAT): when its quota would be fully available again if every request
+// used up an equal share of the period. A request is allowed as long as that
+// leaves the TAT at most one period ahead of now. Times are in milliseconds;
+// returns the decision and, when allowed, the TAT to store.
+pub(crate) fn gcra(tat: Option<u64>, now: u64, quota: Quota) -> (Decision, Option<u64>) {
+    let tat = tat.unwrap_or(now).max(now);
+    let new_tat = tat + quota.increment_ms();
+    if new_tat - now > quota.period_ms() {
+        return (Decision::new(quota, false, tat - now), None);
+    }
+    (Decision::new(quota, true, new_tat - now), Some(new_tat))
+}
+
+// Storage of the clients' arrival times. Stores log their own failures and
+// return `None`, in which case the request is let through: a broken store
+// must not lock every client out.
+#[async_trait]
+pub trait RateLimitStore: Debug + Send + Sync {
+    // Count a request against `quota` for `key`
+    async fn acquire(&self, key: &str, quota: Quota) -> Option<Decision>;
+}
+
+// Limits how often each client may call the API. Clients are the user of a
+// valid bearer token, or else the address the request came from.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    inner: Arc<RateLimiterInner>,
+}
+
+#[derive(Debug)]
+struct RateLimiterInner {
+    store: Arc<dyn RateLimitStore>,
+    auth: AuthService,
+    trusted_proxies: TrustedProxies,
+    default: Quota,
+    login: Quota,
+    registration: Quota,
+}
+
+impl RateLimiter {
+    // `None` when rate limiting is switched off
+    pub fn from_config(config: &RateLimitConfig, auth: AuthService) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let store: Arc<dyn RateLimitStore> = match &config.redis_url {
+            #[cfg(feature = "redis")]
+            Some(url) => {
+                Arc::new(RedisStore::new(url).expect("rate_limit.redis_url is validated on load"))
+            }
+            _ => Arc::new(MemoryStore::new(MEMORY_CAPACITY)),
+        };
+        Some(RateLimiter {
+            inner: Arc::new(RateLimiterInner {
+                store,
+                auth,
+                trusted_proxies: TrustedProxies::new(&config.trusted_proxies),
+                default: Quota {
+                    limit: config.requests,
+                    period: config.period(),
+                },
+                login: Quota {
+                    limit: config.login_requests,
+                    period: config.login_period(),
+                },
+                registration: Quota {
+                    limit: config.registration_requests,
+                    period: config.registration_period(),
+                },
+            }),
+        })
+    }
+}
+
+impl RateLimiterInner {
+    // The group a route is limited under and its quota. Logins and sign-ups
+    // have quotas of their own, and password resets share the login quota
+    // since they too can be used to guess at accounts; every other route
+    // shares its group's quota with the routes under the same path segment,
+    // e.g. all of /api/post.
+    fn route_group<'a>(&self, method: &Method, path: &'a str) -> (&'a str, Quota) {
+        let path = path.strip_prefix("/api").unwrap_or(path);
+        let trimmed = path.trim_end_matches('/');
+        if method == Method::POST && trimmed == "/auth/login" {
+            return ("login", self.login);
+        }
+        if method == Method::POST
+            && matches!(trimmed, "/auth/password/forgot" | "/auth/password/reset")
+        {
+            return ("password", self.login);
+        }
+        if method == Method::POST && trimmed == "/user" {
+            return ("registration", self.registration);
+        }
+        let segment = path.trim_start_matches('/').split('/').next();
+        (segment.unwrap_or_default(), self.default)
+    }
+}
+
+// Count the request against its client's quota for the route group, and
+// reject it once the quota is used up. Applied as a route layer, so the
+// matched route decides the group.
+pub async fn limit(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    let limiter = &limiter.inner;
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let (group, quota) = limiter.route_group(request.method(), &path);
+    let ip = limiter
+        .trusted_proxies
+        .client_ip(&request)
+        .map_or_else(|| String::from("unknown"), |ip| ip.to_string());
+    // Logins, sign-ups and resets come without a token, so they always go by
+    // address
+    let user = match group {
+        "login" | "registration" | "password" => None,
+        _ => request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(bearer_token)
+            .and_then(|token| limiter.auth.authenticate(token).ok())
+            .map(|claims| claims.sub),
+    };
+    let client = match user {
+        Some(user_id) => format!("user:{}", user_id),
+        None => format!("ip:{}", ip),
+    };
+    let key = format!("{}:{}", group, client);
+
+    let Some(decision) = limiter.store.acquire(&key, quota).await else {
+        return next.run(request).await;
+    };
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        metrics::counter!("rate_limited_requests_total", "group" => group.to_string()).increment(1);
+        let retry_after = seconds(decision.retry_after);
+        let mut response = error_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many requests",
+            format!(
+                "At most {} requests are allowed every {} seconds; retry in {} seconds",
+                quota.limit,
+                quota.period.as_secs(),
+                retry_after
+            ),
+        );
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        response
+    };
+    let headers = response.headers_mut();
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATELIMIT_RESET, HeaderValue::from(seconds(decision.reset)));
+    response
+}
+
+// Whole seconds, rounded up so clients never retry too early
+fn seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(limit: u32, period_secs: u64) -> Quota {
+        Quota {
+            limit,
+            period: Duration::from_secs(period_secs),
+        }
+    }
+
+    #[test]
+    fn requests_are_allowed_up_to_the_limit() {
+        let quota = quota(3, 60);
+        let mut tat = None;
+        for remaining in [2, 1, 0] {
+            let (decision, new_tat) = gcra(tat, 0, quota);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+            tat = new_tat;
+        }
+
+        let (decision, new_tat) = gcra(tat, 0, quota);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_secs(20));
+        assert_eq!(decision.reset, Duration::from_secs(60));
+        assert_eq!(new_tat, None);
+    }
+
+    #[test]
+    fn quota_is_replenished_over_time() {
+        let quota = quota(2, 60);
+        let (_, tat) = gcra(None, 0, quota);
+        let (_, tat) = gcra(tat, 0, quota);
+        assert!(!gcra(tat, 29_999, quota).0.allowed);
+
+        let (decision, _) = gcra(tat, 30_000, quota);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        let (decision, _) = gcra(tat, 120_000, quota);
+        assert_eq!(decision.remaining, 1);
+    }
+}
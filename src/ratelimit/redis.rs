@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, Client, RedisResult, Script};
+use tokio::sync::OnceCell;
+
+use super::{Decision, Quota, RateLimitStore};
+
+// Namespace for every key this store writes, so a Redis can be shared
+const KEY_PREFIX: &str = "blog-cms:ratelimit:";
+// How long a check may take before the request is let through unchecked
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The same algorithm as `gcra`, run atomically on the Redis server against its
+// own clock so that instances with drifting clocks agree. Returns whether the
+// request is allowed and the backlog `Decision::new` expects; the key expires
+// once the client's quota is fully available again.
+const GCRA_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local period = tonumber(ARGV[1])
+local increment = tonumber(ARGV[2])
+local tat = math.max(tonumber(redis.call('GET', KEYS[1]) or now), now)
+local new_tat = tat + increment
+if new_tat - now > period then
+    return {0, tat - now}
+end
+redis.call('SET', KEYS[1], new_tat, 'PX', new_tat - now)
+return {1, new_tat - now}
+";
+
+// Store shared by every instance through Redis, so a client's quota covers
+// all of them. Connects on first use and reconnects on its own after
+// connection failures.
+pub struct RedisStore {
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+    script: Script,
+}
+
+impl std::fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("connected", &self.connection.initialized())
+            .finish()
+    }
+}
+
+impl RedisStore {
+    pub fn new(url: &str) -> RedisResult<Self> {
+        Ok(RedisStore {
+            client: Client::open(url)?,
+            connection: OnceCell::new(),
+            script: Script::new(GCRA_SCRIPT),
+        })
+    }
+
+    async fn connection(&self) -> RedisResult<ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+    }
+
+    async fn run(&self, key: &str, quota: Quota) -> RedisResult<(bool, u64)> {
+        let mut connection = self.connection().await?;
+        let (allowed, backlog): (u8, u64) = self
+            .script
+            .key(format!("{}{}", KEY_PREFIX, key))
+            .arg(quota.period_ms())
+            .arg(quota.increment_ms())
+            .invoke_async(&mut connection)
+            .await?;
+        Ok((allowed == 1, backlog))
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for RedisStore {
+    async fn acquire(&self, key: &str, quota: Quota) -> Option<Decision> {
+        match tokio::time::timeout(COMMAND_TIMEOUT, self.run(key, quota)).await {
+            Ok(Ok((allowed, backlog))) => Some(Decision::new(quota, allowed, backlog)),
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Redis rate limit check failed");
+                None
+            }
+            Err(_) => {
+                tracing::warn!("Redis rate limit check timed out");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs against the Redis at TEST_REDIS_URL, and is skipped without one
+    #[tokio::test]
+    async fn quotas_are_counted_in_redis() {
+        let Ok(url) = std::env::var("TEST_REDIS_URL") else {
+            return;
+        };
+        let store = RedisStore::new(&url).unwrap();
+        let quota = Quota {
+            limit: 2,
+            period: Duration::from_secs(60),
+        };
+        let key = format!("test:{}", uuid::Uuid::new_v4());
+
+        let first = store.acquire(&key, quota).await.unwrap();
+        assert!(first.allowed);
+        assert_eq!(first.remaining, 1);
+        assert!(store.acquire(&key, quota).await.unwrap().allowed);
+        let rejected = store.acquire(&key, quota).await.unwrap();
+        assert!(!rejected.allowed);
+        assert!(rejected.retry_after > Duration::ZERO);
+    }
+}
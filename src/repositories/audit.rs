@@ -0,0 +1,139 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{MySql, MySqlConnection, MySqlPool, QueryBuilder};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    audit,
+    entities::AuditEntry,
+    models::{AuditAction, AuditTargetType},
+};
+
+pub(crate) const AUDIT_COLUMNS: &str = "id, actor_id, action, target_type, target_id, \
+     before_snapshot, after_snapshot, ip, request_id, created_at";
+
+// An entry recording a change made in the current request, for the
+// repository making the change to insert in the same transaction
+pub(crate) fn audit_entry<T: Serialize>(
+    action: AuditAction,
+    target_type: AuditTargetType,
+    target_id: Uuid,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> AuditEntry {
+    let context = audit::current();
+    let snapshot = |value: Option<&T>| value.and_then(|v| serde_json::to_string(v).ok());
+    AuditEntry {
+        id: Uuid::new_v4(),
+        actor_id: context.actor_id,
+        action,
+        target_type,
+        target_id,
+        before: snapshot(before),
+        after: snapshot(after),
+        ip: context.ip,
+        request_id: context.request_id,
+        created_at: Utc::now(),
+    }
+}
+
+// Append `entry` as part of the caller's transaction
+pub(crate) async fn insert_audit(
+    conn: &mut MySqlConnection,
+    entry: &AuditEntry,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (id, actor_id, action, target_type, target_id,
+            before_snapshot, after_snapshot, ip, request_id, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(entry.id.as_bytes().to_vec())
+    .bind(entry.actor_id.map(|id| id.as_bytes().to_vec()))
+    .bind(entry.action.as_str())
+    .bind(entry.target_type.as_str())
+    .bind(entry.target_id.as_bytes().to_vec())
+    .bind(&entry.before)
+    .bind(&entry.after)
+    .bind(&entry.ip)
+    .bind(&entry.request_id)
+    .bind(entry.created_at)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// Which entries to find; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    pub target_type: Option<AuditTargetType>,
+    pub target_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
+
+// Entries can only be added and read, never changed or removed. They are
+// added by the repositories making the changes, in the same transaction.
+#[async_trait]
+pub trait AuditRepository: Debug + Send + Sync {
+    // Find the entries matching `filter`, newest first
+    async fn find(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, sqlx::Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MySqlAuditRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlAuditRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        MySqlAuditRepository { pool }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MySqlAuditRepository {
+    // Find the entries matching `filter`, newest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let mut query = QueryBuilder::<MySql>::new(format!(
+            "SELECT {} FROM audit_log WHERE 1 = 1",
+            AUDIT_COLUMNS
+        ));
+        if let Some(actor_id) = filter.actor_id {
+            query
+                .push(" AND actor_id = ")
+                .push_bind(actor_id.as_bytes().to_vec());
+        }
+        if let Some(target_type) = filter.target_type {
+            query
+                .push(" AND target_type = ")
+                .push_bind(target_type.as_str());
+        }
+        if let Some(target_id) = filter.target_id {
+            query
+                .push(" AND target_id = ")
+                .push_bind(target_id.as_bytes().to_vec());
+        }
+        if let Some(from) = filter.from {
+            query.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(to) = filter.to {
+            query.push(" AND created_at < ").push_bind(to);
+        }
+        query
+            .push(" ORDER BY created_at DESC LIMIT ")
+            .push_bind(filter.limit);
+        query
+            .build_query_as::<AuditEntry>()
+            .fetch_all(&self.pool)
+            .await
+    }
+}
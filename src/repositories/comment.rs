@@ -0,0 +1,138 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::{Comment, OutboxEvent},
+    models::{CommentResponse, CreateComment},
+    repositories::outbox::insert_events,
+};
+
+#[async_trait]
+pub trait CommentRepository: Debug + Send + Sync {
+    // Create Comment
+    async fn create(&self, comment: CreateComment) -> Result<CommentResponse, sqlx::Error>;
+
+    // Find comment by id
+    async fn find_by_id(&self, id: Uuid) -> Result<CommentResponse, sqlx::Error>;
+
+    // Find the comments on several posts at once, oldest first
+    async fn find_by_post_ids(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<CommentResponse>, sqlx::Error>;
+
+    // Move comment to the trash
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MySqlCommentRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlCommentRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CommentRepository for MySqlCommentRepository {
+    // Create Comment
+    #[instrument(level = "debug", skip(self, comment))]
+    async fn create(&self, comment: CreateComment) -> Result<CommentResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT INTO comments (id, content, user_id, post_id) VALUES (?, ?, ?, ?)")
+            .bind(id.as_bytes().to_vec())
+            .bind(comment.content)
+            .bind(comment.user_id.as_bytes().to_vec())
+            .bind(comment.post_id.as_bytes().to_vec())
+            .execute(&mut *tx)
+            .await?;
+        let comment: CommentResponse = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT id AS 'id: Uuid', content, user_id AS 'user_id: Uuid',
+                post_id AS 'post_id: Uuid', created_at, updated_at
+            FROM comments
+            WHERE id = ?
+            "#,
+            id.as_bytes().to_vec()
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        insert_events(
+            &mut tx,
+            &[OutboxEvent::CommentCreated],
+            comment.id,
+            &comment,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(comment)
+    }
+
+    // Find comment by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<CommentResponse, sqlx::Error> {
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT id AS 'id: Uuid', content, user_id AS 'user_id: Uuid',
+                post_id AS 'post_id: Uuid', created_at, updated_at
+            FROM comments
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+            id.as_bytes().to_vec()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(comment.into())
+    }
+
+    // Find the comments on several posts at once, oldest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_post_ids(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<CommentResponse>, sqlx::Error> {
+        if post_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<MySql>::new(
+            "SELECT * FROM comments WHERE deleted_at IS NULL AND post_id IN (",
+        );
+        let mut separated = query.separated(", ");
+        for post_id in post_ids {
+            separated.push_bind(post_id.as_bytes().to_vec());
+        }
+        separated.push_unseparated(") ORDER BY created_at");
+        let comments = query
+            .build_query_as::<Comment>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(comments.into_iter().map(CommentResponse::from).collect())
+    }
+
+    // Move comment to the trash
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let result =
+            sqlx::query("UPDATE comments SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+                .bind(Utc::now())
+                .bind(id.as_bytes().to_vec())
+                .execute(&self.pool)
+                .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    db::{self, DbPool},
+    models::{CheckStatus, DatabaseCheck, MigrationCheck, PoolCheck},
+};
+
+// How long a dependency check may take before it counts as failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub struct HealthRepository {
+    pool: DbPool,
+}
+
+impl HealthRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl HealthRepository {
+    // Round trip a trivial query through the pool
+    pub async fn check_database(&self) -> DatabaseCheck {
+        let started = Instant::now();
+        let ping = self.pool.ping();
+        match tokio::time::timeout(CHECK_TIMEOUT, ping).await {
+            Ok(Ok(_)) => DatabaseCheck {
+                status: CheckStatus::Up,
+                latency_ms: Some(started.elapsed().as_millis()),
+                error: None,
+            },
+            Ok(Err(e)) => DatabaseCheck {
+                status: CheckStatus::Down,
+                latency_ms: None,
+                error: Some(e.to_string()),
+            },
+            Err(_) => DatabaseCheck {
+                status: CheckStatus::Down,
+                latency_ms: None,
+                error: Some(format!("no response within {:?}", CHECK_TIMEOUT)),
+            },
+        }
+    }
+
+    // The schema must be exactly at the version this binary was built for
+    pub async fn check_migrations(&self) -> MigrationCheck {
+        let expected_version = db::latest_known_version(&self.pool);
+        let applied = tokio::time::timeout(CHECK_TIMEOUT, db::applied_versions(&self.pool)).await;
+        match applied {
+            Ok(Ok(versions)) => {
+                let applied_version = versions.last().copied();
+                let (status, error) = match applied_version {
+                    Some(version) if version == expected_version => (CheckStatus::Up, None),
+                    Some(version) if version > expected_version => (
+                        CheckStatus::Down,
+                        Some(String::from("database schema is ahead of this binary")),
+                    ),
+                    _ => (
+                        CheckStatus::Down,
+                        Some(String::from("database has pending migrations")),
+                    ),
+                };
+                MigrationCheck {
+                    status,
+                    applied_version,
+                    expected_version,
+                    error,
+                }
+            }
+            Ok(Err(e)) => MigrationCheck {
+                status: CheckStatus::Down,
+                applied_version: None,
+                expected_version,
+                error: Some(e.to_string()),
+            },
+            Err(_) => MigrationCheck {
+                status: CheckStatus::Down,
+                applied_version: None,
+                expected_version,
+                error: Some(format!("no response within {:?}", CHECK_TIMEOUT)),
+            },
+        }
+    }
+
+    // Report how much of the pool is in use; only a closed pool is a failure
+    pub fn check_pool(&self) -> PoolCheck {
+        let size = self.pool.size();
+        let idle = self.pool.num_idle();
+        let max_connections = self.pool.max_connections();
+        let in_use = (size as usize).saturating_sub(idle);
+        PoolCheck {
+            status: if self.pool.is_closed() {
+                CheckStatus::Down
+            } else {
+                CheckStatus::Up
+            },
+            size,
+            idle,
+            max_connections,
+            saturation: in_use as f64 / max_connections.max(1) as f64,
+        }
+    }
+
+    // Time how long it takes to check a connection out of the pool
+    pub async fn measure_acquire_wait(&self) -> Option<Duration> {
+        let started = Instant::now();
+        match tokio::time::timeout(CHECK_TIMEOUT, self.pool.acquire()).await {
+            Ok(Ok(())) => Some(started.elapsed()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use tracing::instrument;
+
+use crate::db::DbPool;
+
+// Tables whose indexes are rebuilt by `rebuild_indexes`
+const INDEXED_TABLES: [&str; 11] = [
+    "roles",
+    "permissions",
+    "role_permissions",
+    "users",
+    "media",
+    "posts",
+    "comments",
+    "webhooks",
+    "webhook_deliveries",
+    "outbox",
+    "audit_log",
+];
+
+#[derive(Debug, Clone)]
+pub struct MaintenanceRepository {
+    pool: DbPool,
+}
+
+impl MaintenanceRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl MaintenanceRepository {
+    // Rebuild table data and indexes, returning the tables that were processed
+    #[instrument(level = "debug", skip(self))]
+    pub async fn rebuild_indexes(&self) -> Result<Vec<String>, sqlx::Error> {
+        let mut rebuilt = Vec::with_capacity(INDEXED_TABLES.len());
+        for table in INDEXED_TABLES {
+            // Table names can't be bound as parameters; they come from the constant above.
+            match &self.pool {
+                DbPool::MySql(pool) => {
+                    sqlx::query(&format!("OPTIMIZE TABLE `{}`", table))
+                        .execute(pool)
+                        .await?;
+                    sqlx::query(&format!("ANALYZE TABLE `{}`", table))
+                        .execute(pool)
+                        .await?;
+                }
+                #[cfg(feature = "postgres")]
+                DbPool::Postgres(pool) => {
+                    sqlx::query(&format!("REINDEX TABLE \"{}\"", table))
+                        .execute(pool)
+                        .await?;
+                    sqlx::query(&format!("ANALYZE \"{}\"", table))
+                        .execute(pool)
+                        .await?;
+                }
+                #[cfg(feature = "sqlite")]
+                DbPool::Sqlite(pool) => {
+                    sqlx::query(&format!("REINDEX `{}`", table))
+                        .execute(pool)
+                        .await?;
+                    sqlx::query(&format!("ANALYZE `{}`", table))
+                        .execute(pool)
+                        .await?;
+                }
+            }
+            rebuilt.push(table.to_string());
+        }
+        Ok(rebuilt)
+    }
+}
@@ -0,0 +1,1104 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        post_events, AuditEntry, OutboxEvent, OutboxMessage, PostStatus, Webhook, WebhookDelivery,
+    },
+    models::{
+        AuditAction, AuditTargetType, CommentResponse, CreateComment, CreateMedia, CreatePost,
+        DeliveryStatus, MediaResponse, OwnedContent, PermissionListResponse, PermissionResponse,
+        PostListResponse, PostResponse, RoleListResponse, RoleResponse, UpdatePost, UpdateWebhook,
+        UserListResponse, UserResponse, WebhookEvent,
+    },
+};
+
+use super::{
+    audit::audit_entry,
+    outbox::outbox_payload,
+    post::{media_response, updated},
+    AuditFilter, AuditRepository, CommentRepository, DeleteOutcome, DeliveryAttempt,
+    OutboxRepository, PermissionRepository, PostRepository, RoleRepository, UserRepository,
+    WebhookRepository,
+};
+
+// In-memory repositories for unit testing services without a database. They
+// mirror the MySQL implementations' observable behavior: missing rows surface
+// as `sqlx::Error::RowNotFound` wherever the SQL version would report them.
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap()
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryPostRepository {
+    posts: Mutex<Vec<PostResponse>>,
+    media: Mutex<HashSet<Uuid>>,
+    outbox: Arc<InMemoryOutboxRepository>,
+    audit: Arc<InMemoryAuditRepository>,
+}
+
+impl InMemoryPostRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A repository recording its events in `outbox`
+    pub fn with_outbox(outbox: Arc<InMemoryOutboxRepository>) -> Self {
+        Self {
+            outbox,
+            ..Self::default()
+        }
+    }
+
+    // A repository recording its changes in `audit`
+    pub fn with_audit(audit: Arc<InMemoryAuditRepository>) -> Self {
+        Self {
+            audit,
+            ..Self::default()
+        }
+    }
+}
+
+#[async_trait]
+impl PostRepository for InMemoryPostRepository {
+    async fn find_all(&self, published: bool) -> Result<PostListResponse, sqlx::Error> {
+        let posts = lock(&self.posts)
+            .iter()
+            .filter(|post| (post.status == PostStatus::Published) == published)
+            .cloned()
+            .collect();
+        Ok(PostListResponse { posts })
+    }
+
+    async fn create(&self, post: CreatePost) -> Result<PostResponse, sqlx::Error> {
+        let response = PostResponse {
+            id: Uuid::new_v4(),
+            title: post.title,
+            content: post.content,
+            status: post.status,
+            published_at: post.published_at,
+            user_id: post.user_id,
+            featured_media_id: post.featured_media_id,
+            excerpt: post.excerpt,
+            meta_description: post.meta_description,
+            canonical_url: post.canonical_url,
+            og_title: post.og_title,
+            og_description: post.og_description,
+            og_image_url: post.og_image_url,
+            updated_at: Utc::now(),
+            version: 1,
+        };
+        lock(&self.posts).push(response.clone());
+        self.outbox
+            .record(&post_events(None, &response), response.id, &response)?;
+        self.audit.record(
+            AuditAction::Create,
+            AuditTargetType::Post,
+            response.id,
+            None,
+            Some(&response),
+        );
+        Ok(response)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<PostResponse, sqlx::Error> {
+        lock(&self.posts)
+            .iter()
+            .find(|post| post.id == id)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn find_by_user_id(&self, user_id: Uuid) -> Result<PostListResponse, sqlx::Error> {
+        let posts = lock(&self.posts)
+            .iter()
+            .filter(|post| post.user_id == user_id)
+            .cloned()
+            .collect();
+        Ok(PostListResponse { posts })
+    }
+
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error> {
+        Ok(lock(&self.posts)
+            .iter()
+            .filter(|post| ids.contains(&post.id))
+            .cloned()
+            .collect())
+    }
+
+    async fn find_by_user_ids(&self, user_ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error> {
+        Ok(lock(&self.posts)
+            .iter()
+            .filter(|post| user_ids.contains(&post.user_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn update(
+        &self,
+        update: UpdatePost,
+        expected: Option<i64>,
+    ) -> Result<Option<PostResponse>, sqlx::Error> {
+        let mut posts = lock(&self.posts);
+        let post = posts
+            .iter_mut()
+            .find(|post| post.id == update.id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if expected.is_some_and(|expected| expected != post.version) {
+            return Ok(None);
+        }
+        let before = post.clone();
+        // Same semantics as the SQL: only provided fields change, and the
+        // optional ones are cleared by an explicit null
+        if let Some(title) = update.title {
+            post.title = title;
+        }
+        if let Some(content) = update.content {
+            post.content = content;
+        }
+        if let Some(status) = update.status {
+            post.status = status;
+        }
+        if let Some(user_id) = update.user_id {
+            post.user_id = user_id;
+        }
+        post.published_at = update.published_at.or(post.published_at);
+        post.featured_media_id = updated(update.featured_media_id, &post.featured_media_id);
+        post.excerpt = updated(update.excerpt, &post.excerpt);
+        post.meta_description = updated(update.meta_description, &post.meta_description);
+        post.canonical_url = updated(update.canonical_url, &post.canonical_url);
+        post.og_title = updated(update.og_title, &post.og_title);
+        post.og_description = updated(update.og_description, &post.og_description);
+        post.og_image_url = updated(update.og_image_url, &post.og_image_url);
+        post.updated_at = Utc::now();
+        post.version += 1;
+        self.outbox
+            .record(&post_events(Some(before.status), post), post.id, post)?;
+        self.audit.record(
+            AuditAction::Update,
+            AuditTargetType::Post,
+            post.id,
+            Some(&before),
+            Some(post),
+        );
+        Ok(Some(post.clone()))
+    }
+
+    async fn delete(&self, id: Uuid, expected: Option<i64>) -> Result<DeleteOutcome, sqlx::Error> {
+        let mut posts = lock(&self.posts);
+        let index = posts
+            .iter()
+            .position(|post| post.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if expected.is_some_and(|expected| expected != posts[index].version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        let before = posts.remove(index);
+        self.audit.record(
+            AuditAction::Delete,
+            AuditTargetType::Post,
+            id,
+            Some(&before),
+            None,
+        );
+        Ok(DeleteOutcome::Deleted)
+    }
+
+    async fn create_media(&self, media: CreateMedia) -> Result<MediaResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        lock(&self.media).insert(id);
+        Ok(media_response(id, media, Utc::now()))
+    }
+
+    async fn media_exists(&self, media_id: Uuid) -> Result<bool, sqlx::Error> {
+        Ok(lock(&self.media).contains(&media_id))
+    }
+
+    async fn search(&self, query: &str) -> Result<PostListResponse, sqlx::Error> {
+        let query = query.to_lowercase();
+        let matches = |text: &str| text.to_lowercase().contains(&query);
+        let posts = lock(&self.posts)
+            .iter()
+            .filter(|post| {
+                matches(&post.title)
+                    || post.excerpt.as_deref().is_some_and(matches)
+                    || matches(&post.content)
+            })
+            .cloned()
+            .collect();
+        Ok(PostListResponse { posts })
+    }
+}
+
+#[derive(Debug)]
+struct StoredUser {
+    user: UserResponse,
+    password_hash: String,
+    session_version: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryUserRepository {
+    users: Mutex<Vec<StoredUser>>,
+    outbox: Arc<InMemoryOutboxRepository>,
+    audit: Arc<InMemoryAuditRepository>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A repository recording its events in `outbox`
+    pub fn with_outbox(outbox: Arc<InMemoryOutboxRepository>) -> Self {
+        Self {
+            outbox,
+            ..Self::default()
+        }
+    }
+
+    // A repository recording its changes in `audit`
+    pub fn with_audit(audit: Arc<InMemoryAuditRepository>) -> Self {
+        Self {
+            audit,
+            ..Self::default()
+        }
+    }
+
+    // The stored password hash, which the repository API never returns
+    pub fn password_hash(&self, id: Uuid) -> Option<String> {
+        lock(&self.users)
+            .iter()
+            .find(|stored| stored.user.id == id)
+            .map(|stored| stored.password_hash.clone())
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn find_all(&self) -> Result<UserListResponse, sqlx::Error> {
+        let users = lock(&self.users)
+            .iter()
+            .map(|stored| stored.user.clone())
+            .collect();
+        Ok(UserListResponse { users })
+    }
+
+    async fn create(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        role_id: Uuid,
+    ) -> Result<UserResponse, sqlx::Error> {
+        let user = UserResponse {
+            id: Uuid::new_v4(),
+            username: username.to_string(),
+            email: email.to_string(),
+            role_id,
+            updated_at: Utc::now(),
+            version: 1,
+        };
+        lock(&self.users).push(StoredUser {
+            user: user.clone(),
+            password_hash: password.to_string(),
+            session_version: 0,
+        });
+        self.outbox
+            .record(&[OutboxEvent::UserCreated], user.id, &user)?;
+        self.audit.record(
+            AuditAction::Create,
+            AuditTargetType::User,
+            user.id,
+            None,
+            Some(&user),
+        );
+        Ok(user)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<UserResponse, sqlx::Error> {
+        lock(&self.users)
+            .iter()
+            .find(|stored| stored.user.id == id)
+            .map(|stored| stored.user.clone())
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn find_by_login(&self, login: &str) -> Result<UserResponse, sqlx::Error> {
+        lock(&self.users)
+            .iter()
+            .find(|stored| stored.user.username == login || stored.user.email == login)
+            .map(|stored| stored.user.clone())
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<UserResponse>, sqlx::Error> {
+        Ok(lock(&self.users)
+            .iter()
+            .filter(|stored| ids.contains(&stored.user.id))
+            .map(|stored| stored.user.clone())
+            .collect())
+    }
+
+    async fn find_password_hash(&self, id: Uuid) -> Result<String, sqlx::Error> {
+        self.password_hash(id).ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn find_session_version(&self, id: Uuid) -> Result<i64, sqlx::Error> {
+        lock(&self.users)
+            .iter()
+            .find(|stored| stored.user.id == id)
+            .map(|stored| stored.session_version)
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    // Deleted users are dropped rather than kept in a trash
+    async fn find_trashed_by_login(&self, _login: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        Ok(None)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        username: Option<String>,
+        email: Option<String>,
+        role_id: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<Option<UserResponse>, sqlx::Error> {
+        let mut users = lock(&self.users);
+        let stored = users
+            .iter_mut()
+            .find(|stored| stored.user.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if expected.is_some_and(|expected| expected != stored.user.version) {
+            return Ok(None);
+        }
+        let before = stored.user.clone();
+        if let Some(username) = username {
+            stored.user.username = username;
+        }
+        if let Some(email) = email {
+            stored.user.email = email;
+        }
+        if let Some(role_id) = role_id {
+            stored.user.role_id = role_id;
+        }
+        stored.user.updated_at = Utc::now();
+        stored.user.version += 1;
+        self.audit.record(
+            AuditAction::Update,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            Some(&stored.user),
+        );
+        Ok(Some(stored.user.clone()))
+    }
+
+    async fn update_password(&self, id: Uuid, password_hash: &str) -> Result<(), sqlx::Error> {
+        let mut users = lock(&self.users);
+        let stored = users
+            .iter_mut()
+            .find(|stored| stored.user.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        stored.password_hash = password_hash.to_string();
+        stored.session_version += 1;
+        stored.user.version += 1;
+        self.audit
+            .record::<()>(AuditAction::Update, AuditTargetType::User, id, None, None);
+        Ok(())
+    }
+
+    // Holds no posts or comments, so there is never any content to keep
+    async fn delete(
+        &self,
+        id: Uuid,
+        _content: OwnedContent,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error> {
+        let mut users = lock(&self.users);
+        let index = users
+            .iter()
+            .position(|stored| stored.user.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if expected.is_some_and(|expected| expected != users[index].user.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        let before = users.remove(index).user;
+        self.audit.record(
+            AuditAction::Delete,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            None,
+        );
+        Ok(DeleteOutcome::Deleted)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryRoleRepository {
+    roles: Mutex<Vec<RoleResponse>>,
+    audit: Arc<InMemoryAuditRepository>,
+}
+
+impl InMemoryRoleRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A repository recording its changes in `audit`
+    pub fn with_audit(audit: Arc<InMemoryAuditRepository>) -> Self {
+        Self {
+            audit,
+            ..Self::default()
+        }
+    }
+}
+
+#[async_trait]
+impl RoleRepository for InMemoryRoleRepository {
+    async fn find_all(&self) -> Result<RoleListResponse, sqlx::Error> {
+        let roles = lock(&self.roles).clone();
+        Ok(RoleListResponse { roles })
+    }
+
+    async fn create(
+        &self,
+        role_name: &str,
+        description: &str,
+    ) -> Result<RoleResponse, sqlx::Error> {
+        let role = RoleResponse {
+            id: Uuid::new_v4(),
+            role_name: role_name.to_string(),
+            description: Some(description.to_string()),
+            updated_at: Utc::now(),
+            version: 1,
+        };
+        lock(&self.roles).push(role.clone());
+        self.audit.record(
+            AuditAction::Create,
+            AuditTargetType::Role,
+            role.id,
+            None,
+            Some(&role),
+        );
+        Ok(role)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<RoleResponse, sqlx::Error> {
+        lock(&self.roles)
+            .iter()
+            .find(|role| role.id == id)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn find_by_name(&self, role_name: &str) -> Result<RoleResponse, sqlx::Error> {
+        lock(&self.roles)
+            .iter()
+            .find(|role| role.role_name == role_name)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        role_name: Option<String>,
+        description: Option<String>,
+        expected: Option<i64>,
+    ) -> Result<Option<RoleResponse>, sqlx::Error> {
+        let mut roles = lock(&self.roles);
+        let role = roles
+            .iter_mut()
+            .find(|role| role.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if expected.is_some_and(|expected| expected != role.version) {
+            return Ok(None);
+        }
+        let before = role.clone();
+        if let Some(role_name) = role_name {
+            role.role_name = role_name;
+        }
+        role.description = description.or(role.description.take());
+        role.updated_at = Utc::now();
+        role.version += 1;
+        self.audit.record(
+            AuditAction::Update,
+            AuditTargetType::Role,
+            id,
+            Some(&before),
+            Some(role),
+        );
+        Ok(Some(role.clone()))
+    }
+
+    // Knows nothing of users, so a role never has any to keep it
+    async fn delete(
+        &self,
+        id: Uuid,
+        _reassign_to: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error> {
+        let mut roles = lock(&self.roles);
+        let index = roles
+            .iter()
+            .position(|role| role.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if expected.is_some_and(|expected| expected != roles[index].version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        let before = roles.remove(index);
+        self.audit.record(
+            AuditAction::Delete,
+            AuditTargetType::Role,
+            id,
+            Some(&before),
+            None,
+        );
+        Ok(DeleteOutcome::Deleted)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryPermissionRepository {
+    permissions: Mutex<Vec<PermissionResponse>>,
+    grants: Mutex<HashSet<(Uuid, Uuid)>>,
+}
+
+impl InMemoryPermissionRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_granted(&self, role_id: Uuid, permission_id: Uuid) -> bool {
+        lock(&self.grants).contains(&(role_id, permission_id))
+    }
+}
+
+#[async_trait]
+impl PermissionRepository for InMemoryPermissionRepository {
+    async fn find_all(&self) -> Result<PermissionListResponse, sqlx::Error> {
+        let permissions = lock(&self.permissions).clone();
+        Ok(PermissionListResponse { permissions })
+    }
+
+    async fn create(
+        &self,
+        permission_name: &str,
+        description: &str,
+    ) -> Result<PermissionResponse, sqlx::Error> {
+        let permission = PermissionResponse {
+            id: Uuid::new_v4(),
+            permission_name: permission_name.to_string(),
+            description: Some(description.to_string()),
+        };
+        lock(&self.permissions).push(permission.clone());
+        Ok(permission)
+    }
+
+    async fn find_by_name(&self, permission_name: &str) -> Result<PermissionResponse, sqlx::Error> {
+        lock(&self.permissions)
+            .iter()
+            .find(|permission| permission.permission_name == permission_name)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn assign_to_role(&self, role_id: Uuid, permission_id: Uuid) -> Result<(), sqlx::Error> {
+        lock(&self.grants).insert((role_id, permission_id));
+        Ok(())
+    }
+
+    async fn find_by_role_id(&self, role_id: Uuid) -> Result<PermissionListResponse, sqlx::Error> {
+        let grants = lock(&self.grants);
+        let permissions = lock(&self.permissions)
+            .iter()
+            .filter(|permission| grants.contains(&(role_id, permission.id)))
+            .cloned()
+            .collect();
+        Ok(PermissionListResponse { permissions })
+    }
+
+    async fn find_names_by_role(&self, role_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        let grants = lock(&self.grants);
+        let names = lock(&self.permissions)
+            .iter()
+            .filter(|permission| grants.contains(&(role_id, permission.id)))
+            .map(|permission| permission.permission_name.clone())
+            .collect();
+        Ok(names)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryCommentRepository {
+    comments: Mutex<Vec<CommentResponse>>,
+    outbox: Arc<InMemoryOutboxRepository>,
+}
+
+impl InMemoryCommentRepository {
+    // A repository recording its events in `outbox`
+    pub fn with_outbox(outbox: Arc<InMemoryOutboxRepository>) -> Self {
+        Self {
+            outbox,
+            ..Self::default()
+        }
+    }
+}
+
+#[async_trait]
+impl CommentRepository for InMemoryCommentRepository {
+    async fn create(&self, comment: CreateComment) -> Result<CommentResponse, sqlx::Error> {
+        let now = Utc::now();
+        let comment = CommentResponse {
+            id: Uuid::new_v4(),
+            content: comment.content,
+            user_id: comment.user_id,
+            post_id: comment.post_id,
+            created_at: now,
+            updated_at: now,
+        };
+        lock(&self.comments).push(comment.clone());
+        self.outbox
+            .record(&[OutboxEvent::CommentCreated], comment.id, &comment)?;
+        Ok(comment)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<CommentResponse, sqlx::Error> {
+        lock(&self.comments)
+            .iter()
+            .find(|comment| comment.id == id)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn find_by_post_ids(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<CommentResponse>, sqlx::Error> {
+        Ok(lock(&self.comments)
+            .iter()
+            .filter(|comment| post_ids.contains(&comment.post_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let mut comments = lock(&self.comments);
+        let index = comments
+            .iter()
+            .position(|comment| comment.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        comments.remove(index);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryWebhookRepository {
+    webhooks: Mutex<Vec<Webhook>>,
+    deliveries: Mutex<Vec<WebhookDelivery>>,
+    // The webhook and outbox message of each delivery queued from the outbox
+    outbox_deliveries: Mutex<HashSet<(Uuid, Uuid)>>,
+}
+
+impl InMemoryWebhookRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for InMemoryWebhookRepository {
+    async fn create(
+        &self,
+        url: &str,
+        secret: &str,
+        events: &[WebhookEvent],
+        active: bool,
+    ) -> Result<Webhook, sqlx::Error> {
+        let now = Utc::now();
+        let webhook = Webhook {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            secret: secret.to_string(),
+            events: events.to_vec(),
+            active,
+            created_at: now,
+            updated_at: now,
+        };
+        lock(&self.webhooks).push(webhook.clone());
+        Ok(webhook)
+    }
+
+    async fn find_all(&self) -> Result<Vec<Webhook>, sqlx::Error> {
+        Ok(lock(&self.webhooks).clone())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Webhook, sqlx::Error> {
+        lock(&self.webhooks)
+            .iter()
+            .find(|webhook| webhook.id == id)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn update(&self, id: Uuid, update: UpdateWebhook) -> Result<Webhook, sqlx::Error> {
+        let mut webhooks = lock(&self.webhooks);
+        let webhook = webhooks
+            .iter_mut()
+            .find(|webhook| webhook.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if let Some(url) = update.url {
+            webhook.url = url;
+        }
+        if let Some(secret) = update.secret {
+            webhook.secret = secret;
+        }
+        if let Some(events) = update.events {
+            webhook.events = events;
+        }
+        if let Some(active) = update.active {
+            webhook.active = active;
+        }
+        webhook.updated_at = Utc::now();
+        Ok(webhook.clone())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let mut webhooks = lock(&self.webhooks);
+        let index = webhooks
+            .iter()
+            .position(|webhook| webhook.id == id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        webhooks.remove(index);
+        lock(&self.deliveries).retain(|delivery| delivery.webhook_id != id);
+        lock(&self.outbox_deliveries).retain(|(webhook_id, _)| *webhook_id != id);
+        Ok(())
+    }
+
+    async fn create_delivery(
+        &self,
+        webhook_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        let delivery = WebhookDelivery {
+            id: Uuid::new_v4(),
+            webhook_id,
+            event,
+            payload: payload.to_string(),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            response_code: None,
+            error: None,
+            next_attempt_at,
+            delivered_at: None,
+            created_at: Utc::now(),
+        };
+        lock(&self.deliveries).push(delivery.clone());
+        Ok(delivery)
+    }
+
+    async fn create_outbox_delivery(
+        &self,
+        webhook_id: Uuid,
+        outbox_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        if lock(&self.outbox_deliveries).insert((webhook_id, outbox_id)) {
+            self.create_delivery(webhook_id, event, payload, next_attempt_at)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn find_delivery(
+        &self,
+        webhook_id: Uuid,
+        id: Uuid,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        lock(&self.deliveries)
+            .iter()
+            .find(|delivery| delivery.id == id && delivery.webhook_id == webhook_id)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    async fn find_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        Ok(lock(&self.deliveries)
+            .iter()
+            .rev()
+            .filter(|delivery| delivery.webhook_id == webhook_id)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        let mut due: Vec<WebhookDelivery> = lock(&self.deliveries)
+            .iter()
+            .filter(|delivery| {
+                delivery.status == DeliveryStatus::Pending && delivery.next_attempt_at <= now
+            })
+            .cloned()
+            .collect();
+        due.sort_by_key(|delivery| delivery.next_attempt_at);
+        due.truncate(limit as usize);
+        Ok(due)
+    }
+
+    async fn claim_delivery(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let mut deliveries = lock(&self.deliveries);
+        let delivery = deliveries.iter_mut().find(|delivery| {
+            delivery.id == id
+                && delivery.status == DeliveryStatus::Pending
+                && delivery.next_attempt_at <= now
+        });
+        Ok(match delivery {
+            Some(delivery) => {
+                delivery.next_attempt_at = lease_until;
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn record_attempt(&self, id: Uuid, attempt: DeliveryAttempt) -> Result<(), sqlx::Error> {
+        if let Some(delivery) = lock(&self.deliveries)
+            .iter_mut()
+            .find(|delivery| delivery.id == id)
+        {
+            delivery.status = attempt.status;
+            delivery.attempts += 1;
+            delivery.response_code = attempt.response_code;
+            delivery.error = attempt.error;
+            delivery.next_attempt_at = attempt.next_attempt_at;
+            delivery.delivered_at = attempt.delivered_at;
+        }
+        Ok(())
+    }
+
+    async fn count_pending(&self) -> Result<i64, sqlx::Error> {
+        Ok(lock(&self.deliveries)
+            .iter()
+            .filter(|delivery| delivery.status == DeliveryStatus::Pending)
+            .count() as i64)
+    }
+}
+
+#[derive(Debug)]
+struct StoredMessage {
+    message: OutboxMessage,
+    available_at: DateTime<Utc>,
+    processed_at: Option<DateTime<Utc>>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryOutboxRepository {
+    messages: Mutex<Vec<StoredMessage>>,
+}
+
+impl InMemoryOutboxRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Record `events` about the row `aggregate_id`, as the SQL repositories
+    // do within the write's transaction
+    fn record<T: Serialize>(
+        &self,
+        events: &[OutboxEvent],
+        aggregate_id: Uuid,
+        data: &T,
+    ) -> Result<(), sqlx::Error> {
+        let payload = outbox_payload(data)?;
+        let now = Utc::now();
+        let mut messages = lock(&self.messages);
+        for &event in events {
+            messages.push(StoredMessage {
+                message: OutboxMessage {
+                    id: Uuid::new_v4(),
+                    event,
+                    aggregate_id,
+                    payload: payload.clone(),
+                    attempts: 0,
+                    created_at: now,
+                },
+                available_at: now,
+                processed_at: None,
+                error: None,
+            });
+        }
+        Ok(())
+    }
+
+    // The last error recorded for a message
+    pub fn error(&self, id: Uuid) -> Option<String> {
+        lock(&self.messages)
+            .iter()
+            .find(|stored| stored.message.id == id)
+            .and_then(|stored| stored.error.clone())
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for InMemoryOutboxRepository {
+    async fn find_due(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<OutboxMessage>, sqlx::Error> {
+        // Messages are kept in the order they were recorded
+        let messages = lock(&self.messages);
+        let mut waiting = HashSet::new();
+        let mut due = Vec::new();
+        for stored in messages
+            .iter()
+            .filter(|stored| stored.processed_at.is_none())
+        {
+            let aggregate_id = stored.message.aggregate_id;
+            if stored.available_at > now {
+                waiting.insert(aggregate_id);
+            } else if !waiting.contains(&aggregate_id) && due.len() < limit as usize {
+                due.push(stored.message.clone());
+            }
+        }
+        Ok(due)
+    }
+
+    async fn claim(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let mut messages = lock(&self.messages);
+        let stored = messages.iter_mut().find(|stored| {
+            stored.message.id == id && stored.processed_at.is_none() && stored.available_at <= now
+        });
+        Ok(match stored {
+            Some(stored) => {
+                stored.available_at = lease_until;
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn mark_processed(
+        &self,
+        id: Uuid,
+        processed_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        if let Some(stored) = lock(&self.messages)
+            .iter_mut()
+            .find(|stored| stored.message.id == id)
+        {
+            stored.message.attempts += 1;
+            stored.processed_at = Some(processed_at);
+            stored.error = None;
+        }
+        Ok(())
+    }
+
+    async fn record_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+        available_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        if let Some(stored) = lock(&self.messages)
+            .iter_mut()
+            .find(|stored| stored.message.id == id)
+        {
+            stored.message.attempts += 1;
+            stored.error = Some(error.to_string());
+            stored.available_at = available_at;
+        }
+        Ok(())
+    }
+
+    async fn delete_processed(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let mut messages = lock(&self.messages);
+        let count = messages.len();
+        messages.retain(|stored| stored.processed_at.is_none_or(|at| at >= before));
+        Ok((count - messages.len()) as u64)
+    }
+
+    async fn count_pending(&self) -> Result<i64, sqlx::Error> {
+        Ok(lock(&self.messages)
+            .iter()
+            .filter(|stored| stored.processed_at.is_none())
+            .count() as i64)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryAuditRepository {
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl InMemoryAuditRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Append an entry, as the SQL repositories do within the change's
+    // transaction
+    fn record<T: Serialize>(
+        &self,
+        action: AuditAction,
+        target_type: AuditTargetType,
+        target_id: Uuid,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) {
+        lock(&self.entries).push(audit_entry(action, target_type, target_id, before, after));
+    }
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn find(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let entries = lock(&self.entries)
+            .iter()
+            .rev()
+            .filter(|entry| filter.actor_id.is_none_or(|id| entry.actor_id == Some(id)))
+            .filter(|entry| filter.target_type.is_none_or(|t| entry.target_type == t))
+            .filter(|entry| filter.target_id.is_none_or(|id| entry.target_id == id))
+            .filter(|entry| filter.from.is_none_or(|from| entry.created_at >= from))
+            .filter(|entry| filter.to.is_none_or(|to| entry.created_at < to))
+            .take(filter.limit as usize)
+            .cloned()
+            .collect();
+        Ok(entries)
+    }
+}
@@ -1,25 +1,143 @@
-use sqlx::MySqlPool;
+use std::sync::Arc;
 
+use crate::db::{DbPool, Replicas};
+
+// Repositories use the runtime query API rather than the compile-time checked
+// `query!` macros, so building needs no live database of any backend. The
+// MySQL implementations live next to their traits.
+mod audit;
+mod comment;
+mod health;
+mod maintenance;
+#[cfg(test)]
+pub mod memory;
+mod outbox;
+mod password_reset;
+mod permission;
 mod post;
+#[cfg(feature = "postgres")]
+mod postgres;
 mod role;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod trash;
 mod user;
+mod webhook;
+
+pub use audit::{AuditFilter, AuditRepository, MySqlAuditRepository};
+pub use comment::{CommentRepository, MySqlCommentRepository};
+pub use health::HealthRepository;
+pub use maintenance::MaintenanceRepository;
+pub use outbox::{MySqlOutboxRepository, OutboxRepository};
+pub use password_reset::{MySqlPasswordResetRepository, PasswordResetRepository};
+pub use permission::{MySqlPermissionRepository, PermissionRepository};
+pub use post::{MySqlPostRepository, PostRepository};
+#[cfg(feature = "postgres")]
+pub use postgres::{
+    PostgresAuditRepository, PostgresCommentRepository, PostgresOutboxRepository,
+    PostgresPasswordResetRepository, PostgresPermissionRepository, PostgresPostRepository, PostgresRoleRepository,
+    PostgresTrashRepository, PostgresUserRepository, PostgresWebhookRepository,
+};
+pub use role::{MySqlRoleRepository, RoleRepository};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{
+    SqliteAuditRepository, SqliteCommentRepository, SqliteOutboxRepository,
+    SqlitePasswordResetRepository, SqlitePermissionRepository, SqlitePostRepository, SqliteRoleRepository,
+    SqliteTrashRepository, SqliteUserRepository, SqliteWebhookRepository,
+};
+pub use trash::{MySqlTrashRepository, TrashRepository};
+pub use user::{MySqlUserRepository, UserRepository};
+pub use webhook::{DeliveryAttempt, MySqlWebhookRepository, WebhookRepository};
+
+// What became of a delete that is made only while nothing stands in its way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteOutcome {
+    Deleted,
+    // Other records still belong to the row
+    InUse,
+    // The row changed since the `version` the delete was based on
+    Modified,
+}
 
-pub use post::PostRepository;
-pub use role::RoleRepository;
-pub use user::UserRepository;
+// LIKE pattern matching `query` anywhere in a column, with `!` escaping the
+// wildcard characters so they match literally
+pub(crate) fn like_pattern(query: &str) -> String {
+    let mut pattern = String::with_capacity(query.len() + 2);
+    pattern.push('%');
+    for c in query.chars() {
+        if matches!(c, '!' | '%' | '_') {
+            pattern.push('!');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
 
 pub struct RepositoryContainer {
-    pub role_repository: RoleRepository,
-    pub user_repository: UserRepository,
-    pub post_repository: PostRepository,
+    pub role_repository: Arc<dyn RoleRepository>,
+    pub user_repository: Arc<dyn UserRepository>,
+    pub post_repository: Arc<dyn PostRepository>,
+    pub comment_repository: Arc<dyn CommentRepository>,
+    pub permission_repository: Arc<dyn PermissionRepository>,
+    pub webhook_repository: Arc<dyn WebhookRepository>,
+    pub outbox_repository: Arc<dyn OutboxRepository>,
+    pub audit_repository: Arc<dyn AuditRepository>,
+    pub trash_repository: Arc<dyn TrashRepository>,
+    pub password_reset_repository: Arc<dyn PasswordResetRepository>,
+    pub maintenance_repository: MaintenanceRepository,
+    pub health_repository: HealthRepository,
 }
 
 impl RepositoryContainer {
-    pub fn new(pool: MySqlPool) -> Self {
-        RepositoryContainer {
-            role_repository: RoleRepository::new(pool.clone()),
-            user_repository: UserRepository::new(pool.clone()),
-            post_repository: PostRepository::new(pool.clone()),
+    pub fn new(pool: DbPool, replicas: Replicas) -> Self {
+        let maintenance_repository = MaintenanceRepository::new(pool.clone());
+        let health_repository = HealthRepository::new(pool.clone());
+        match pool {
+            DbPool::MySql(pool) => RepositoryContainer {
+                role_repository: Arc::new(MySqlRoleRepository::new(pool.clone())),
+                user_repository: Arc::new(MySqlUserRepository::new(pool.clone())),
+                post_repository: Arc::new(MySqlPostRepository::new(pool.clone(), replicas)),
+                comment_repository: Arc::new(MySqlCommentRepository::new(pool.clone())),
+                permission_repository: Arc::new(MySqlPermissionRepository::new(pool.clone())),
+                webhook_repository: Arc::new(MySqlWebhookRepository::new(pool.clone())),
+                outbox_repository: Arc::new(MySqlOutboxRepository::new(pool.clone())),
+                audit_repository: Arc::new(MySqlAuditRepository::new(pool.clone())),
+                trash_repository: Arc::new(MySqlTrashRepository::new(pool.clone())),
+                password_reset_repository: Arc::new(MySqlPasswordResetRepository::new(pool)),
+                maintenance_repository,
+                health_repository,
+            },
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => RepositoryContainer {
+                role_repository: Arc::new(PostgresRoleRepository::new(pool.clone())),
+                user_repository: Arc::new(PostgresUserRepository::new(pool.clone())),
+                post_repository: Arc::new(PostgresPostRepository::new(pool.clone(), replicas)),
+                comment_repository: Arc::new(PostgresCommentRepository::new(pool.clone())),
+                permission_repository: Arc::new(PostgresPermissionRepository::new(pool.clone())),
+                webhook_repository: Arc::new(PostgresWebhookRepository::new(pool.clone())),
+                outbox_repository: Arc::new(PostgresOutboxRepository::new(pool.clone())),
+                audit_repository: Arc::new(PostgresAuditRepository::new(pool.clone())),
+                trash_repository: Arc::new(PostgresTrashRepository::new(pool.clone())),
+                password_reset_repository: Arc::new(PostgresPasswordResetRepository::new(pool)),
+                maintenance_repository,
+                health_repository,
+            },
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => RepositoryContainer {
+                role_repository: Arc::new(SqliteRoleRepository::new(pool.clone())),
+                user_repository: Arc::new(SqliteUserRepository::new(pool.clone())),
+                post_repository: Arc::new(SqlitePostRepository::new(pool.clone(), replicas)),
+                comment_repository: Arc::new(SqliteCommentRepository::new(pool.clone())),
+                permission_repository: Arc::new(SqlitePermissionRepository::new(pool.clone())),
+                webhook_repository: Arc::new(SqliteWebhookRepository::new(pool.clone())),
+                outbox_repository: Arc::new(SqliteOutboxRepository::new(pool.clone())),
+                audit_repository: Arc::new(SqliteAuditRepository::new(pool.clone())),
+                trash_repository: Arc::new(SqliteTrashRepository::new(pool.clone())),
+                password_reset_repository: Arc::new(SqlitePasswordResetRepository::new(pool)),
+                maintenance_repository,
+                health_repository,
+            },
         }
     }
 }
@@ -0,0 +1,221 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{MySqlConnection, MySqlPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::entities::{OutboxEvent, OutboxMessage};
+
+pub(crate) const OUTBOX_COLUMNS: &str = "id, event, aggregate_id, payload, attempts, created_at";
+
+// Payload recorded for events about a row: its JSON representation
+pub(crate) fn outbox_payload<T: Serialize>(data: &T) -> Result<String, sqlx::Error> {
+    serde_json::to_string(data).map_err(|e| sqlx::Error::Encode(e.into()))
+}
+
+#[async_trait]
+pub trait OutboxRepository: Debug + Send + Sync {
+    // Find unprocessed messages that are due, in the order they were
+    // recorded, leaving out those queued behind an earlier message about the
+    // same row that isn't due yet
+    async fn find_due(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<OutboxMessage>, sqlx::Error>;
+
+    // Push a due message's availability back to `lease_until`, returning
+    // false when another relay claimed it first
+    async fn claim(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error>;
+
+    // Mark a message as relayed to every subscriber
+    async fn mark_processed(
+        &self,
+        id: Uuid,
+        processed_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    // Count a failed relay attempt and retry at `available_at`
+    async fn record_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+        available_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    // Delete messages processed before `before`, returning how many were deleted
+    async fn delete_processed(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error>;
+
+    // Count messages that have not been relayed yet
+    async fn count_pending(&self) -> Result<i64, sqlx::Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MySqlOutboxRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlOutboxRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        MySqlOutboxRepository { pool }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for MySqlOutboxRepository {
+    // Find unprocessed messages that are due, in the order they were
+    // recorded, leaving out those queued behind an earlier message about the
+    // same row that isn't due yet
+    #[instrument(level = "debug", skip(self))]
+    async fn find_due(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<OutboxMessage>, sqlx::Error> {
+        sqlx::query_as::<_, OutboxMessage>(&format!(
+            "SELECT {} FROM outbox o WHERE processed_at IS NULL AND available_at <= ? \
+             AND NOT EXISTS (SELECT 1 FROM outbox e WHERE e.aggregate_id = o.aggregate_id \
+                 AND e.processed_at IS NULL AND e.available_at > ? \
+                 AND e.sequence_number < o.sequence_number) \
+             ORDER BY sequence_number LIMIT ?",
+            OUTBOX_COLUMNS
+        ))
+        .bind(now)
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Push a due message's availability back to `lease_until`, returning
+    // false when another relay claimed it first
+    #[instrument(level = "debug", skip(self))]
+    async fn claim(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE outbox SET available_at = ?
+            WHERE id = ? AND processed_at IS NULL AND available_at <= ?
+            "#,
+        )
+        .bind(lease_until)
+        .bind(id.as_bytes().to_vec())
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    // Mark a message as relayed to every subscriber
+    #[instrument(level = "debug", skip(self))]
+    async fn mark_processed(
+        &self,
+        id: Uuid,
+        processed_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE outbox SET processed_at = ?, attempts = attempts + 1, error = NULL
+            WHERE id = ?
+            "#,
+        )
+        .bind(processed_at)
+        .bind(id.as_bytes().to_vec())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Count a failed relay attempt and retry at `available_at`
+    #[instrument(level = "debug", skip(self, error))]
+    async fn record_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+        available_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE outbox SET attempts = attempts + 1, error = ?, available_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(error)
+        .bind(available_at)
+        .bind(id.as_bytes().to_vec())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Delete messages processed before `before`, returning how many were deleted
+    #[instrument(level = "debug", skip(self))]
+    async fn delete_processed(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM outbox
+            WHERE processed_at IS NOT NULL AND processed_at < ?
+            "#,
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    // Count messages that have not been relayed yet
+    #[instrument(level = "debug", skip(self))]
+    async fn count_pending(&self) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) AS count
+            FROM outbox
+            WHERE processed_at IS NULL
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+}
+
+// Record `events` about the row `aggregate_id`, as `data`, as part of the
+// caller's transaction
+pub(crate) async fn insert_events<T: Serialize>(
+    conn: &mut MySqlConnection,
+    events: &[OutboxEvent],
+    aggregate_id: Uuid,
+    data: &T,
+) -> Result<(), sqlx::Error> {
+    let payload = outbox_payload(data)?;
+    let now = Utc::now();
+    for event in events {
+        sqlx::query(
+            r#"
+            INSERT INTO outbox (id, event, aggregate_id, payload, available_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().as_bytes().to_vec())
+        .bind(event.as_str())
+        .bind(aggregate_id.as_bytes().to_vec())
+        .bind(&payload)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{AuditAction, AuditTargetType},
+    repositories::audit::{audit_entry, insert_audit},
+};
+
+// Password reset tokens, looked up by the SHA-256 hash of the token that was
+// mailed out; the tokens themselves are never stored
+#[async_trait]
+pub trait PasswordResetRepository: Debug + Send + Sync {
+    // Store a token for a user, dropping their expired and used ones
+    async fn create(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    // Use up an unexpired token and give its user a new password hash,
+    // revoking their sessions and every other token they were sent. Returns
+    // the user's id, or `RowNotFound` when the token is unknown, used or
+    // expired, or its user is in the trash.
+    async fn reset_password(
+        &self,
+        token_hash: &str,
+        password_hash: &str,
+    ) -> Result<Uuid, sqlx::Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MySqlPasswordResetRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlPasswordResetRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        MySqlPasswordResetRepository { pool }
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for MySqlPasswordResetRepository {
+    // Store a token for a user
+    #[instrument(level = "debug", skip(self, token_hash))]
+    async fn create(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let user_id_bytes = user_id.as_bytes().to_vec();
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            DELETE FROM password_reset_tokens
+            WHERE user_id = ? AND (used_at IS NOT NULL OR expires_at <= ?)
+            "#,
+        )
+        .bind(&user_id_bytes)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().as_bytes().to_vec())
+        .bind(&user_id_bytes)
+        .bind(token_hash)
+        .bind(expires_at)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Use up a token and set its user's new password
+    #[instrument(level = "debug", skip_all)]
+    async fn reset_password(
+        &self,
+        token_hash: &str,
+        password_hash: &str,
+    ) -> Result<Uuid, sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = ?
+            WHERE token_hash = ? AND used_at IS NULL AND expires_at > ?
+            "#,
+        )
+        .bind(now)
+        .bind(token_hash)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        let user_id_bytes: Vec<u8> = sqlx::query_scalar(
+            r#"
+            SELECT user_id
+            FROM password_reset_tokens
+            WHERE token_hash = ?
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = ?
+            WHERE user_id = ? AND used_at IS NULL
+            "#,
+        )
+        .bind(now)
+        .bind(&user_id_bytes)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = ?, session_version = session_version + 1
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(password_hash)
+        .bind(&user_id_bytes)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        let user_id =
+            Uuid::from_slice(&user_id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        // Password hashes are never snapshotted
+        let entry = audit_entry::<()>(
+            AuditAction::Update,
+            AuditTargetType::User,
+            user_id,
+            None,
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(user_id)
+    }
+}
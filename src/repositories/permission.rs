@@ -0,0 +1,152 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use sqlx::{
+    mysql::{MySqlPool, MySqlRow},
+    Row,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::models::{PermissionListResponse, PermissionResponse};
+
+#[async_trait]
+pub trait PermissionRepository: Debug + Send + Sync {
+    // Find all permissions
+    async fn find_all(&self) -> Result<PermissionListResponse, sqlx::Error>;
+
+    // Create permission
+    async fn create(
+        &self,
+        permission_name: &str,
+        description: &str,
+    ) -> Result<PermissionResponse, sqlx::Error>;
+
+    // Find permission by name
+    async fn find_by_name(&self, permission_name: &str) -> Result<PermissionResponse, sqlx::Error>;
+
+    // Find all permissions granted to a role
+    async fn find_by_role_id(&self, role_id: Uuid) -> Result<PermissionListResponse, sqlx::Error>;
+
+    // Grant a permission to a role, ignoring grants that already exist
+    async fn assign_to_role(&self, role_id: Uuid, permission_id: Uuid) -> Result<(), sqlx::Error>;
+
+    // Names of the permissions granted to a role
+    async fn find_names_by_role(&self, role_id: Uuid) -> Result<Vec<String>, sqlx::Error>;
+}
+
+fn permission_from_row(row: &MySqlRow) -> Result<PermissionResponse, sqlx::Error> {
+    let id_bytes: Vec<u8> = row.try_get("id")?;
+    Ok(PermissionResponse {
+        id: Uuid::from_slice(&id_bytes).map_err(|e| sqlx::Error::Decode(e.into()))?,
+        permission_name: row.try_get("permission_name")?,
+        description: row.try_get("description")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct MySqlPermissionRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlPermissionRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PermissionRepository for MySqlPermissionRepository {
+    // Find all permissions
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<PermissionListResponse, sqlx::Error> {
+        let permissions = sqlx::query("SELECT id, permission_name, description FROM permissions")
+            .try_map(|row: MySqlRow| permission_from_row(&row))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(PermissionListResponse { permissions })
+    }
+
+    // Create permission
+    #[instrument(level = "debug", skip(self, description))]
+    async fn create(
+        &self,
+        permission_name: &str,
+        description: &str,
+    ) -> Result<PermissionResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query("INSERT INTO permissions (id, permission_name, description) VALUES (?, ?, ?)")
+            .bind(id.as_bytes().to_vec())
+            .bind(permission_name)
+            .bind(description)
+            .execute(&self.pool)
+            .await?;
+        let response = PermissionResponse {
+            id,
+            permission_name: permission_name.to_string(),
+            description: Some(description.to_string()),
+        };
+        Ok(response)
+    }
+
+    // Find permission by name
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_name(&self, permission_name: &str) -> Result<PermissionResponse, sqlx::Error> {
+        let permission = sqlx::query(
+            "SELECT id, permission_name, description FROM permissions WHERE permission_name = ?",
+        )
+        .bind(permission_name)
+        .try_map(|row: MySqlRow| permission_from_row(&row))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(permission)
+    }
+
+    // Find all permissions granted to a role
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_role_id(&self, role_id: Uuid) -> Result<PermissionListResponse, sqlx::Error> {
+        let permissions = sqlx::query(
+            r#"
+            SELECT p.id, p.permission_name, p.description
+            FROM permissions p
+            INNER JOIN role_permissions rp ON rp.permission_id = p.id
+            WHERE rp.role_id = ?
+            "#,
+        )
+        .bind(role_id.as_bytes().to_vec())
+        .try_map(|row: MySqlRow| permission_from_row(&row))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PermissionListResponse { permissions })
+    }
+
+    // Grant a permission to a role, ignoring grants that already exist
+    #[instrument(level = "debug", skip(self))]
+    async fn assign_to_role(&self, role_id: Uuid, permission_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT IGNORE INTO role_permissions (role_id, permission_id) VALUES (?, ?)")
+            .bind(role_id.as_bytes().to_vec())
+            .bind(permission_id.as_bytes().to_vec())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // Names of the permissions granted to a role
+    #[instrument(level = "debug", skip(self))]
+    async fn find_names_by_role(&self, role_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT p.permission_name
+            FROM permissions p
+            JOIN role_permissions rp ON rp.permission_id = p.id
+            WHERE rp.role_id = ?
+            "#,
+        )
+        .bind(role_id.as_bytes().to_vec())
+        .fetch_all(&self.pool)
+        .await
+    }
+}
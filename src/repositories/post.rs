@@ -1,122 +1,243 @@
+use std::fmt::Debug;
+
 use crate::{
-    entities::PostStatus,
-    models::{PostListResponse, PostResponse},
+    db::Replicas,
+    entities::{post_events, Post, PostStatus},
+    models::{
+        AuditAction, AuditTargetType, CreateMedia, CreatePost, MediaResponse, PostListResponse,
+        PostResponse, UpdatePost,
+    },
+    repositories::{
+        audit::{audit_entry, insert_audit},
+        outbox::insert_events,
+        DeleteOutcome,
+    },
 };
-use chrono::{DateTime, Utc};
-use sqlx::MySqlPool;
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use sqlx::{MySql, MySqlConnection, MySqlPool, QueryBuilder};
+use tracing::instrument;
 use uuid::Uuid;
 
+// The stored form of a media item registered as `media`
+pub(crate) fn media_response(
+    id: Uuid,
+    media: CreateMedia,
+    created_at: DateTime<Utc>,
+) -> MediaResponse {
+    MediaResponse {
+        id,
+        file_name: media.file_name,
+        url: media.url,
+        mime_type: media.mime_type,
+        alt_text: media.alt_text,
+        created_at,
+    }
+}
+
+// What an optional column is set to by an update that left the field out
+// (`None`), cleared it (`Some(None)`) or set it, given its current value
+pub(crate) fn updated<T: Clone>(field: Option<Option<T>>, current: &Option<T>) -> Option<T> {
+    field.unwrap_or_else(|| current.clone())
+}
+
+#[async_trait]
+pub trait PostRepository: Debug + Send + Sync {
+    // Find all published posts, or with `published` false all the others
+    async fn find_all(&self, published: bool) -> Result<PostListResponse, sqlx::Error>;
+
+    // Create Post, recording its events in the outbox in the same transaction
+    async fn create(&self, post: CreatePost) -> Result<PostResponse, sqlx::Error>;
+
+    // Find post by id
+    async fn find_by_id(&self, id: Uuid) -> Result<PostResponse, sqlx::Error>;
+
+    // Find Post by user_id
+    async fn find_by_user_id(&self, user_id: Uuid) -> Result<PostListResponse, sqlx::Error>;
+
+    // Find posts by ids, skipping ids that don't exist
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error>;
+
+    // Find the posts of several users at once
+    async fn find_by_user_ids(&self, user_ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error>;
+
+    // Update Post, recording its events in the outbox in the same transaction.
+    // With `expected`, returns None and changes nothing unless the post's
+    // `version` still equals it.
+    async fn update(
+        &self,
+        post: UpdatePost,
+        expected: Option<i64>,
+    ) -> Result<Option<PostResponse>, sqlx::Error>;
+
+    // Move Post and its comments to the trash, unless it changed since
+    // `expected`
+    async fn delete(&self, id: Uuid, expected: Option<i64>) -> Result<DeleteOutcome, sqlx::Error>;
+
+    // Register a media item that posts may feature
+    async fn create_media(&self, media: CreateMedia) -> Result<MediaResponse, sqlx::Error>;
+
+    // Check that a media item exists
+    async fn media_exists(&self, media_id: Uuid) -> Result<bool, sqlx::Error>;
+
+    // Search posts by title, excerpt and content
+    async fn search(&self, query: &str) -> Result<PostListResponse, sqlx::Error>;
+}
+
 #[derive(Debug, Clone)]
-pub struct PostRepository {
+pub struct MySqlPostRepository {
     pool: MySqlPool,
+    replicas: Replicas,
 }
 
-impl PostRepository {
-    pub fn new(pool: MySqlPool) -> Self {
-        PostRepository { pool }
+impl MySqlPostRepository {
+    pub fn new(pool: MySqlPool, replicas: Replicas) -> Self {
+        MySqlPostRepository { pool, replicas }
     }
 }
 
-impl PostRepository {
-    // Find all posts
-    pub async fn find_all(&self) -> Result<PostListResponse, sqlx::Error> {
+#[async_trait]
+impl PostRepository for MySqlPostRepository {
+    // Find all published posts, or all the others
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self, published: bool) -> Result<PostListResponse, sqlx::Error> {
         let posts = sqlx::query_as!(
-            PostResponse,
+            Post,
             r#"
-            SELECT id AS 'id:Uuid', title, content, user_id AS 'user_id:Uuid', status as 'status:PostStatus', published_at AS 'published_at:DateTime<Utc>'
+            SELECT id AS 'id: Uuid', title, content, user_id AS 'user_id: Uuid',
+                status AS 'status: PostStatus', published_at,
+                featured_media_id AS 'featured_media_id: Uuid', excerpt, meta_description,
+                canonical_url, og_title, og_description, og_image_url, created_at, updated_at,
+                version
             FROM posts
-            "#
+            WHERE deleted_at IS NULL AND (status = 'published') = ?
+            "#,
+            published
         )
-            .fetch_all(&self.pool)
-            .await?;
+        .fetch_all(self.replicas.reader(&self.pool))
+        .await?;
 
-        Ok(PostListResponse { posts })
+        Ok(PostListResponse {
+            posts: posts.into_iter().map(PostResponse::from).collect(),
+        })
     }
 
     // Create Post
-    pub async fn create(
-        &self,
-        title: String,
-        content: String,
-        status: PostStatus,
-        published_at: Option<DateTime<Utc>>,
-        user_id: Uuid,
-    ) -> Result<PostResponse, sqlx::Error> {
+    #[instrument(level = "debug", skip(self, post))]
+    async fn create(&self, post: CreatePost) -> Result<PostResponse, sqlx::Error> {
         let id = Uuid::new_v4();
-        let id_bytes = id.as_bytes().to_vec();
-        let user_id_bytes = user_id.as_bytes().to_vec();
-        sqlx::query!(
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
             r#"
-            INSERT INTO posts (id, title, content, status, published_at, user_id)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO posts (id, title, content, status, published_at, user_id, featured_media_id,
+                excerpt, meta_description, canonical_url, og_title, og_description, og_image_url)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-            id_bytes,
-            &title,
-            &content,
-            status.to_str(),
-            &published_at,
-            user_id_bytes
         )
-            .execute(&self.pool)
-            .await?;
-        let response = PostResponse {
+        .bind(id.as_bytes().to_vec())
+        .bind(&post.title)
+        .bind(&post.content)
+        .bind(post.status.to_str())
+        .bind(post.published_at)
+        .bind(post.user_id.as_bytes().to_vec())
+        .bind(
+            post.featured_media_id
+                .map(|media_id| media_id.as_bytes().to_vec()),
+        )
+        .bind(&post.excerpt)
+        .bind(&post.meta_description)
+        .bind(&post.canonical_url)
+        .bind(&post.og_title)
+        .bind(&post.og_description)
+        .bind(&post.og_image_url)
+        .execute(&mut *tx)
+        .await?;
+        let post = PostResponse::from(find_in(&mut tx, id, false).await?);
+        insert_events(&mut tx, &post_events(None, &post), post.id, &post).await?;
+        let entry = audit_entry(
+            AuditAction::Create,
+            AuditTargetType::Post,
             id,
-            title,
-            content,
-            status,
-            published_at,
-            user_id,
-        };
-        Ok(response)
+            None,
+            Some(&post),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(post)
     }
 
     // Find post by id
-    pub async fn find_by_id(&self, id: Uuid) -> Result<PostResponse, sqlx::Error> {
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<PostResponse, sqlx::Error> {
         let post = sqlx::query_as!(
-            PostResponse,
+            Post,
             r#"
-            SELECT id AS 'id:Uuid', title, content, user_id AS 'user_id:Uuid', status as 'status:PostStatus', published_at AS 'published_at:DateTime<Utc>'
+            SELECT id AS 'id: Uuid', title, content, user_id AS 'user_id: Uuid',
+                status AS 'status: PostStatus', published_at,
+                featured_media_id AS 'featured_media_id: Uuid', excerpt, meta_description,
+                canonical_url, og_title, og_description, og_image_url, created_at, updated_at,
+                version
             FROM posts
-            WHERE id = ?
+            WHERE id = ? AND deleted_at IS NULL
             "#,
             id.as_bytes().to_vec()
         )
-            .fetch_one(&self.pool)
-            .await?;
+        .fetch_one(self.replicas.reader(&self.pool))
+        .await?;
 
-        Ok(post)
+        Ok(post.into())
     }
 
     // Find Post by user_id
-    pub async fn find_by_user_id(&self, user_id: Uuid) -> Result<PostListResponse, sqlx::Error> {
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user_id(&self, user_id: Uuid) -> Result<PostListResponse, sqlx::Error> {
         let posts = sqlx::query_as!(
-            PostResponse,
+            Post,
             r#"
-            SELECT id AS 'id:Uuid', title, content, user_id AS 'user_id:Uuid', status as 'status:PostStatus', published_at AS 'published_at:DateTime<Utc>'
+            SELECT id AS 'id: Uuid', title, content, user_id AS 'user_id: Uuid',
+                status AS 'status: PostStatus', published_at,
+                featured_media_id AS 'featured_media_id: Uuid', excerpt, meta_description,
+                canonical_url, og_title, og_description, og_image_url, created_at, updated_at,
+                version
             FROM posts
-            WHERE user_id = ?
+            WHERE user_id = ? AND deleted_at IS NULL
             "#,
             user_id.as_bytes().to_vec()
         )
-            .fetch_all(&self.pool)
-            .await?;
+        .fetch_all(self.replicas.reader(&self.pool))
+        .await?;
 
-        Ok(PostListResponse { posts })
+        Ok(PostListResponse {
+            posts: posts.into_iter().map(PostResponse::from).collect(),
+        })
+    }
+
+    // Find posts by ids, skipping ids that don't exist
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error> {
+        self.find_where_in("id", ids).await
+    }
+
+    // Find the posts of several users at once
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user_ids(&self, user_ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error> {
+        self.find_where_in("user_id", user_ids).await
     }
 
     // Update Post
-    pub async fn update(
+    #[instrument(level = "debug", skip(self, post), fields(post_id = %post.id))]
+    async fn update(
         &self,
-        id: Uuid,
-        title: Option<String>,
-        content: Option<String>,
-        status: Option<PostStatus>,
-        published_at: Option<DateTime<Utc>>,
-        user_id: Option<Uuid>,
-    ) -> Result<PostResponse, sqlx::Error> {
-        let status = status.unwrap();
-        let user_id = user_id.unwrap();
-        sqlx::query!(
+        post: UpdatePost,
+        expected: Option<i64>,
+    ) -> Result<Option<PostResponse>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        // Lock the row so the previous status and version can't change under us
+        let before = PostResponse::from(find_in(&mut tx, post.id, true).await?);
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(None);
+        }
+        sqlx::query(
             r#"
             UPDATE posts
             SET
@@ -124,33 +245,188 @@ impl PostRepository {
                 content = COALESCE(?, content),
                 status = COALESCE(?, status),
                 published_at = COALESCE(?, published_at),
-                user_id = COALESCE(?, user_id)
+                user_id = COALESCE(?, user_id),
+                featured_media_id = ?,
+                excerpt = ?,
+                meta_description = ?,
+                canonical_url = ?,
+                og_title = ?,
+                og_description = ?,
+                og_image_url = ?
             WHERE id = ?
             "#,
-            title,
-            content,
-            status.to_str(),
-            published_at,
-            Some(user_id.as_bytes().to_vec()),
-            id.as_bytes().to_vec()
         )
-            .execute(&self.pool)
+        .bind(&post.title)
+        .bind(&post.content)
+        .bind(post.status.as_ref().map(|status| status.to_str()))
+        .bind(post.published_at)
+        .bind(post.user_id.map(|user_id| user_id.as_bytes().to_vec()))
+        .bind(
+            updated(post.featured_media_id, &before.featured_media_id)
+                .map(|media_id| media_id.as_bytes().to_vec()),
+        )
+        .bind(updated(post.excerpt, &before.excerpt))
+        .bind(updated(post.meta_description, &before.meta_description))
+        .bind(updated(post.canonical_url, &before.canonical_url))
+        .bind(updated(post.og_title, &before.og_title))
+        .bind(updated(post.og_description, &before.og_description))
+        .bind(updated(post.og_image_url, &before.og_image_url))
+        .bind(post.id.as_bytes().to_vec())
+        .execute(&mut *tx)
+        .await?;
+        let post = PostResponse::from(find_in(&mut tx, post.id, false).await?);
+        insert_events(
+            &mut tx,
+            &post_events(Some(before.status), &post),
+            post.id,
+            &post,
+        )
+        .await?;
+        let entry = audit_entry(
+            AuditAction::Update,
+            AuditTargetType::Post,
+            post.id,
+            Some(&before),
+            Some(&post),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(Some(post))
+    }
+
+    // Move Post and its comments to the trash
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, id: Uuid, expected: Option<i64>) -> Result<DeleteOutcome, sqlx::Error> {
+        let id_bytes = id.as_bytes().to_vec();
+        let deleted_at = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let before = PostResponse::from(find_in(&mut tx, id, true).await?);
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        sqlx::query("UPDATE posts SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(&id_bytes)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE comments SET deleted_at = ? WHERE post_id = ? AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(&id_bytes)
+            .execute(&mut *tx)
             .await?;
-        let response = self.find_by_id(id).await?;
-        Ok(response)
+        let entry = audit_entry(
+            AuditAction::Delete,
+            AuditTargetType::Post,
+            id,
+            Some(&before),
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(DeleteOutcome::Deleted)
     }
 
-    // Delete Post
-    pub async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+    // Register a media item
+    #[instrument(level = "debug", skip(self))]
+    async fn create_media(&self, media: CreateMedia) -> Result<MediaResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        // TIMESTAMP columns keep whole seconds
+        let created_at = Utc::now().trunc_subsecs(0);
+        sqlx::query(
             r#"
-            DELETE FROM posts
-            WHERE id = ?
+            INSERT INTO media (id, file_name, url, mime_type, alt_text, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#,
-            id.as_bytes().to_vec()
         )
-            .execute(&self.pool)
+        .bind(id.as_bytes().to_vec())
+        .bind(&media.file_name)
+        .bind(&media.url)
+        .bind(&media.mime_type)
+        .bind(&media.alt_text)
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(media_response(id, media, created_at))
+    }
+
+    // Check that a media item exists
+    #[instrument(level = "debug", skip(self))]
+    async fn media_exists(&self, media_id: Uuid) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM media WHERE id = ?)")
+            .bind(media_id.as_bytes().to_vec())
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    // Search posts; there is no full-text index on MySQL, so this is a substring match
+    #[instrument(level = "debug", skip(self))]
+    async fn search(&self, query: &str) -> Result<PostListResponse, sqlx::Error> {
+        let pattern = super::like_pattern(query);
+        let posts = sqlx::query_as!(
+            Post,
+            r#"
+            SELECT id AS 'id: Uuid', title, content, user_id AS 'user_id: Uuid',
+                status AS 'status: PostStatus', published_at,
+                featured_media_id AS 'featured_media_id: Uuid', excerpt, meta_description,
+                canonical_url, og_title, og_description, og_image_url, created_at, updated_at,
+                version
+            FROM posts
+            WHERE deleted_at IS NULL
+                AND (title LIKE ? ESCAPE '!' OR excerpt LIKE ? ESCAPE '!' OR content LIKE ? ESCAPE '!')
+            ORDER BY created_at DESC
+            "#,
+            &pattern,
+            &pattern,
+            &pattern
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PostListResponse {
+            posts: posts.into_iter().map(PostResponse::from).collect(),
+        })
+    }
+}
+
+impl MySqlPostRepository {
+    // Posts whose `column` holds one of `ids`
+    async fn find_where_in(
+        &self,
+        column: &str,
+        ids: &[Uuid],
+    ) -> Result<Vec<PostResponse>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<MySql>::new(format!(
+            "SELECT * FROM posts WHERE deleted_at IS NULL AND {} IN (",
+            column
+        ));
+        let mut separated = query.separated(", ");
+        for id in ids {
+            separated.push_bind(id.as_bytes().to_vec());
+        }
+        separated.push_unseparated(")");
+        let posts = query
+            .build_query_as::<Post>()
+            .fetch_all(self.replicas.reader(&self.pool))
             .await?;
-        Ok(())
+        Ok(posts.into_iter().map(PostResponse::from).collect())
     }
 }
+
+// Read a post within a transaction, optionally locking it until the end of it
+async fn find_in(
+    conn: &mut MySqlConnection,
+    id: Uuid,
+    for_update: bool,
+) -> Result<Post, sqlx::Error> {
+    let lock = if for_update { " FOR UPDATE" } else { "" };
+    sqlx::query_as::<_, Post>(&format!(
+        "SELECT * FROM posts WHERE id = ? AND deleted_at IS NULL{}",
+        lock
+    ))
+    .bind(id.as_bytes().to_vec())
+    .fetch_one(conn)
+    .await
+}
@@ -0,0 +1,22 @@
+// PostgreSQL implementations of the repository traits, mapping rows by hand
+mod audit;
+mod comment;
+mod outbox;
+mod password_reset;
+mod permission;
+mod post;
+mod role;
+mod trash;
+mod user;
+mod webhook;
+
+pub use audit::PostgresAuditRepository;
+pub use comment::PostgresCommentRepository;
+pub use outbox::PostgresOutboxRepository;
+pub use password_reset::PostgresPasswordResetRepository;
+pub use permission::PostgresPermissionRepository;
+pub use post::PostgresPostRepository;
+pub use role::PostgresRoleRepository;
+pub use trash::PostgresTrashRepository;
+pub use user::PostgresUserRepository;
+pub use webhook::PostgresWebhookRepository;
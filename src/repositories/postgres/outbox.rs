@@ -0,0 +1,169 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{
+    postgres::{PgPool, PgRow},
+    PgConnection, Row,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::{parse_outbox_event, OutboxEvent, OutboxMessage},
+    repositories::outbox::{outbox_payload, OUTBOX_COLUMNS},
+    repositories::OutboxRepository,
+};
+
+fn message_from_row(row: &PgRow) -> Result<OutboxMessage, sqlx::Error> {
+    Ok(OutboxMessage {
+        id: row.try_get("id")?,
+        event: parse_outbox_event(row.try_get("event")?)?,
+        aggregate_id: row.try_get("aggregate_id")?,
+        payload: row.try_get("payload")?,
+        attempts: row.try_get("attempts")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+// Record `events` about the row `aggregate_id`, as `data`, as part of the
+// caller's transaction
+pub(crate) async fn insert_events<T: Serialize>(
+    conn: &mut PgConnection,
+    events: &[OutboxEvent],
+    aggregate_id: Uuid,
+    data: &T,
+) -> Result<(), sqlx::Error> {
+    let payload = outbox_payload(data)?;
+    let now = Utc::now();
+    for event in events {
+        sqlx::query(
+            "INSERT INTO outbox (id, event, aggregate_id, payload, available_at, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(event.as_str())
+        .bind(aggregate_id)
+        .bind(&payload)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct PostgresOutboxRepository {
+    pool: PgPool,
+}
+
+impl PostgresOutboxRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for PostgresOutboxRepository {
+    // Find unprocessed messages that are due, in the order they were
+    // recorded, leaving out those queued behind an earlier message about the
+    // same row that isn't due yet
+    #[instrument(level = "debug", skip(self))]
+    async fn find_due(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<OutboxMessage>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {} FROM outbox o WHERE processed_at IS NULL AND available_at <= $1 \
+             AND NOT EXISTS (SELECT 1 FROM outbox e WHERE e.aggregate_id = o.aggregate_id \
+                 AND e.processed_at IS NULL AND e.available_at > $1 \
+                 AND e.sequence_number < o.sequence_number) \
+             ORDER BY sequence_number LIMIT $2",
+            OUTBOX_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit)
+        .try_map(|row: PgRow| message_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Push a due message's availability back to `lease_until`, returning
+    // false when another relay claimed it first
+    #[instrument(level = "debug", skip(self))]
+    async fn claim(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE outbox SET available_at = $1 \
+             WHERE id = $2 AND processed_at IS NULL AND available_at <= $3",
+        )
+        .bind(lease_until)
+        .bind(id)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    // Mark a message as relayed to every subscriber
+    #[instrument(level = "debug", skip(self))]
+    async fn mark_processed(
+        &self,
+        id: Uuid,
+        processed_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE outbox SET processed_at = $1, attempts = attempts + 1, error = NULL \
+             WHERE id = $2",
+        )
+        .bind(processed_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Count a failed relay attempt and retry at `available_at`
+    #[instrument(level = "debug", skip(self, error))]
+    async fn record_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+        available_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE outbox SET attempts = attempts + 1, error = $1, available_at = $2 \
+             WHERE id = $3",
+        )
+        .bind(error)
+        .bind(available_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Delete messages processed before `before`, returning how many were deleted
+    #[instrument(level = "debug", skip(self))]
+    async fn delete_processed(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result =
+            sqlx::query("DELETE FROM outbox WHERE processed_at IS NOT NULL AND processed_at < $1")
+                .bind(before)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected())
+    }
+
+    // Count messages that have not been relayed yet
+    #[instrument(level = "debug", skip(self))]
+    async fn count_pending(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM outbox WHERE processed_at IS NULL")
+            .fetch_one(&self.pool)
+            .await
+    }
+}
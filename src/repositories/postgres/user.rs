@@ -0,0 +1,366 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{
+    postgres::{PgPool, PgRow},
+    Row,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::OutboxEvent,
+    models::{AuditAction, AuditTargetType, OwnedContent, UserListResponse, UserResponse},
+    repositories::{
+        audit::audit_entry,
+        postgres::{audit::insert_audit, outbox::insert_events},
+        DeleteOutcome, UserRepository,
+    },
+};
+
+fn user_from_row(row: &PgRow) -> Result<UserResponse, sqlx::Error> {
+    Ok(UserResponse {
+        id: row.try_get("id")?,
+        username: row.try_get("username")?,
+        email: row.try_get("email")?,
+        role_id: row.try_get("role_id")?,
+        updated_at: row.try_get("updated_at")?,
+        version: row.try_get("version")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct PostgresUserRepository {
+    pool: PgPool,
+}
+
+impl PostgresUserRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for PostgresUserRepository {
+    // Find all users
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<UserListResponse, sqlx::Error> {
+        let users = sqlx::query(
+            r#"
+            SELECT id, username, email, role_id, updated_at, version
+            FROM users
+            WHERE deleted_at IS NULL
+            "#,
+        )
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(UserListResponse { users })
+    }
+
+    // Create User
+    #[instrument(level = "debug", skip(self, password))]
+    async fn create(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        role_id: Uuid,
+    ) -> Result<UserResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, username, email, password_hash, role_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(id)
+        .bind(username)
+        .bind(email)
+        .bind(password)
+        .bind(role_id)
+        .execute(&mut *tx)
+        .await?;
+        let user = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version FROM users WHERE id = $1",
+        )
+        .bind(id)
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_events(&mut tx, &[OutboxEvent::UserCreated], user.id, &user).await?;
+        let entry = audit_entry(
+            AuditAction::Create,
+            AuditTargetType::User,
+            id,
+            None,
+            Some(&user),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(user)
+    }
+
+    // Find user by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<UserResponse, sqlx::Error> {
+        let user = sqlx::query(
+            r#"
+            SELECT id, username, email, role_id, updated_at, version
+            FROM users
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(user)
+    }
+
+    // Find user by username or email
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_login(&self, login: &str) -> Result<UserResponse, sqlx::Error> {
+        let user = sqlx::query(
+            r#"
+            SELECT id, username, email, role_id, updated_at, version
+            FROM users
+            WHERE (username = $1 OR email = $2) AND deleted_at IS NULL
+            "#,
+        )
+        .bind(login)
+        .bind(login)
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(user)
+    }
+
+    // Find users by ids, skipping ids that don't exist
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<UserResponse>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            SELECT id, username, email, role_id, updated_at, version
+            FROM users
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+        )
+        .bind(ids)
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Find the password hash of a user
+    #[instrument(level = "debug", skip(self))]
+    async fn find_password_hash(&self, id: Uuid) -> Result<String, sqlx::Error> {
+        sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    // Find the session version of a user
+    #[instrument(level = "debug", skip(self))]
+    async fn find_session_version(&self, id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT session_version FROM users WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    // Find the id of a user in the trash by username or email
+    #[instrument(level = "debug", skip(self))]
+    async fn find_trashed_by_login(&self, login: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT id FROM users \
+             WHERE (username = $1 OR email = $2) AND deleted_at IS NOT NULL LIMIT 1",
+        )
+        .bind(login)
+        .bind(login)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Update user
+    #[instrument(level = "debug", skip(self))]
+    async fn update(
+        &self,
+        id: Uuid,
+        username: Option<String>,
+        email: Option<String>,
+        role_id: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<Option<UserResponse>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let before = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version \
+             FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(id)
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(None);
+        }
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET username = COALESCE($1, username),
+                email = COALESCE($2, email),
+                role_id = COALESCE($3, role_id)
+            WHERE id = $4
+            "#,
+        )
+        .bind(username)
+        .bind(email)
+        .bind(role_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        let user = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version FROM users WHERE id = $1",
+        )
+        .bind(id)
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        let entry = audit_entry(
+            AuditAction::Update,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            Some(&user),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(Some(user))
+    }
+
+    // Update user password hash
+    #[instrument(level = "debug", skip(self, password_hash))]
+    async fn update_password(&self, id: Uuid, password_hash: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $1, session_version = session_version + 1
+            WHERE id = $2 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(password_hash)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        sqlx::query(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = $1
+            WHERE user_id = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        // Password hashes are never snapshotted
+        let entry = audit_entry::<()>(AuditAction::Update, AuditTargetType::User, id, None, None);
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await
+    }
+
+    // Move user to the trash, handing over or trashing their content
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(
+        &self,
+        id: Uuid,
+        content: OwnedContent,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error> {
+        let deleted_at = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let before = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version \
+             FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+        )
+        .bind(id)
+        .try_map(|row: PgRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        sqlx::query("UPDATE users SET deleted_at = $1 WHERE id = $2")
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        match content {
+            OwnedContent::Refuse => {
+                let posts: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM posts WHERE user_id = $1 AND deleted_at IS NULL",
+                )
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+                let comments: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM comments WHERE user_id = $1 AND deleted_at IS NULL",
+                )
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+                if posts + comments > 0 {
+                    // Dropping the transaction takes the user back out of the trash
+                    return Ok(DeleteOutcome::InUse);
+                }
+            }
+            OwnedContent::ReassignTo(new_owner) => {
+                sqlx::query("UPDATE posts SET user_id = $1 WHERE user_id = $2")
+                    .bind(new_owner)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("UPDATE comments SET user_id = $1 WHERE user_id = $2")
+                    .bind(new_owner)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            OwnedContent::Cascade => {}
+        }
+        sqlx::query(
+            r#"
+            UPDATE comments
+            SET deleted_at = $1
+            WHERE deleted_at IS NULL
+                AND (user_id = $2 OR post_id IN (SELECT id FROM posts WHERE user_id = $3))
+            "#,
+        )
+        .bind(deleted_at)
+        .bind(id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("UPDATE posts SET deleted_at = $1 WHERE user_id = $2 AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let entry = audit_entry(
+            AuditAction::Delete,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(DeleteOutcome::Deleted)
+    }
+}
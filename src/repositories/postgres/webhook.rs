@@ -0,0 +1,306 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgPool, PgRow},
+    Row,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::{join_events, parse_event, parse_events, parse_status, Webhook, WebhookDelivery},
+    models::{UpdateWebhook, WebhookEvent},
+    repositories::webhook::{DeliveryAttempt, DELIVERY_COLUMNS, WEBHOOK_COLUMNS},
+    repositories::WebhookRepository,
+};
+
+fn webhook_from_row(row: &PgRow) -> Result<Webhook, sqlx::Error> {
+    Ok(Webhook {
+        id: row.try_get("id")?,
+        url: row.try_get("url")?,
+        secret: row.try_get("secret")?,
+        events: parse_events(row.try_get("events")?)?,
+        active: row.try_get("active")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn delivery_from_row(row: &PgRow) -> Result<WebhookDelivery, sqlx::Error> {
+    Ok(WebhookDelivery {
+        id: row.try_get("id")?,
+        webhook_id: row.try_get("webhook_id")?,
+        event: parse_event(row.try_get("event")?)?,
+        payload: row.try_get("payload")?,
+        status: parse_status(row.try_get("status")?)?,
+        attempts: row.try_get("attempts")?,
+        response_code: row.try_get("response_code")?,
+        error: row.try_get("error")?,
+        next_attempt_at: row.try_get("next_attempt_at")?,
+        delivered_at: row.try_get("delivered_at")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct PostgresWebhookRepository {
+    pool: PgPool,
+}
+
+impl PostgresWebhookRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for PostgresWebhookRepository {
+    // Create webhook
+    #[instrument(level = "debug", skip(self, secret))]
+    async fn create(
+        &self,
+        url: &str,
+        secret: &str,
+        events: &[WebhookEvent],
+        active: bool,
+    ) -> Result<Webhook, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO webhooks (id, url, secret, events, active) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(id)
+        .bind(url)
+        .bind(secret)
+        .bind(join_events(events))
+        .bind(active)
+        .execute(&self.pool)
+        .await?;
+        self.find_by_id(id).await
+    }
+
+    // Find all webhooks
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<Vec<Webhook>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {} FROM webhooks ORDER BY created_at",
+            WEBHOOK_COLUMNS
+        ))
+        .try_map(|row: PgRow| webhook_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Find webhook by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<Webhook, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {} FROM webhooks WHERE id = $1",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(id)
+        .try_map(|row: PgRow| webhook_from_row(&row))
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // Update webhook, leaving fields that are not provided unchanged
+    #[instrument(level = "debug", skip(self, webhook))]
+    async fn update(&self, id: Uuid, webhook: UpdateWebhook) -> Result<Webhook, sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE webhooks
+            SET
+                url = COALESCE($1, url),
+                secret = COALESCE($2, secret),
+                events = COALESCE($3, events),
+                active = COALESCE($4, active)
+            WHERE id = $5
+            "#,
+        )
+        .bind(webhook.url)
+        .bind(webhook.secret)
+        .bind(webhook.events.as_deref().map(join_events))
+        .bind(webhook.active)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        self.find_by_id(id).await
+    }
+
+    // Delete webhook along with its deliveries
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    // Queue a delivery of `payload` to a webhook
+    #[instrument(level = "debug", skip(self, payload))]
+    async fn create_delivery(
+        &self,
+        webhook_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO webhook_deliveries (id, webhook_id, event, payload, next_attempt_at) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(id)
+        .bind(webhook_id)
+        .bind(event.as_str())
+        .bind(payload)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+        self.find_delivery(webhook_id, id).await
+    }
+
+    // Queue a delivery of outbox message `outbox_id` to a webhook, unless one
+    // was queued for it already
+    #[instrument(level = "debug", skip(self, payload))]
+    async fn create_outbox_delivery(
+        &self,
+        webhook_id: Uuid,
+        outbox_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO webhook_deliveries \
+                 (id, webhook_id, outbox_id, event, payload, next_attempt_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (webhook_id, outbox_id) DO NOTHING",
+        )
+        .bind(Uuid::new_v4())
+        .bind(webhook_id)
+        .bind(outbox_id)
+        .bind(event.as_str())
+        .bind(payload)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Find one delivery of a webhook
+    #[instrument(level = "debug", skip(self))]
+    async fn find_delivery(
+        &self,
+        webhook_id: Uuid,
+        id: Uuid,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE id = $1 AND webhook_id = $2",
+            DELIVERY_COLUMNS
+        ))
+        .bind(id)
+        .bind(webhook_id)
+        .try_map(|row: PgRow| delivery_from_row(&row))
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // Find the latest deliveries of a webhook, newest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE webhook_id = $1 \
+             ORDER BY created_at DESC LIMIT $2",
+            DELIVERY_COLUMNS
+        ))
+        .bind(webhook_id)
+        .bind(limit)
+        .try_map(|row: PgRow| delivery_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Find pending deliveries whose next attempt is due, oldest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE status = 'pending' AND next_attempt_at <= $1 \
+             ORDER BY next_attempt_at LIMIT $2",
+            DELIVERY_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit)
+        .try_map(|row: PgRow| delivery_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Push a due delivery's next attempt back to `lease_until`, returning false
+    // when another worker claimed it first
+    #[instrument(level = "debug", skip(self))]
+    async fn claim_delivery(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE webhook_deliveries SET next_attempt_at = $1 \
+             WHERE id = $2 AND status = 'pending' AND next_attempt_at <= $3",
+        )
+        .bind(lease_until)
+        .bind(id)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    // Record the outcome of an attempt and count it
+    #[instrument(level = "debug", skip(self, attempt))]
+    async fn record_attempt(&self, id: Uuid, attempt: DeliveryAttempt) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET
+                status = $1,
+                attempts = attempts + 1,
+                response_code = $2,
+                error = $3,
+                next_attempt_at = $4,
+                delivered_at = $5
+            WHERE id = $6
+            "#,
+        )
+        .bind(attempt.status.as_str())
+        .bind(attempt.response_code)
+        .bind(attempt.error)
+        .bind(attempt.next_attempt_at)
+        .bind(attempt.delivered_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Count the deliveries still waiting to succeed or give up
+    #[instrument(level = "debug", skip(self))]
+    async fn count_pending(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries WHERE status = 'pending'")
+            .fetch_one(&self.pool)
+            .await
+    }
+}
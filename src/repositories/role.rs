@@ -1,68 +1,132 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
 use sqlx::MySqlPool;
+use tracing::instrument;
 use uuid::Uuid;
 
-use crate::models::{RoleListResponse, RoleResponse};
+use crate::{
+    entities::Role,
+    models::{AuditAction, AuditTargetType, RoleListResponse, RoleResponse},
+    repositories::{
+        audit::{audit_entry, insert_audit},
+        DeleteOutcome,
+    },
+};
+
+#[async_trait]
+pub trait RoleRepository: Debug + Send + Sync {
+    // Find all roles
+    async fn find_all(&self) -> Result<RoleListResponse, sqlx::Error>;
+
+    // Create Role
+    async fn create(&self, role_name: &str, description: &str)
+        -> Result<RoleResponse, sqlx::Error>;
+
+    // Find role by id
+    async fn find_by_id(&self, id: Uuid) -> Result<RoleResponse, sqlx::Error>;
+
+    // Find role by name
+    async fn find_by_name(&self, role_name: &str) -> Result<RoleResponse, sqlx::Error>;
+
+    // Update role by id. With `expected`, returns None and changes nothing
+    // unless the role's `version` still equals it.
+    async fn update(
+        &self,
+        id: Uuid,
+        role_name: Option<String>,
+        description: Option<String>,
+        expected: Option<i64>,
+    ) -> Result<Option<RoleResponse>, sqlx::Error>;
+
+    // Delete role by id, first moving its users, including those in the
+    // trash, to `reassign_to`. Deletes nothing when no role to reassign to is
+    // given and the role still has users, or when it changed since `expected`.
+    async fn delete(
+        &self,
+        id: Uuid,
+        reassign_to: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error>;
+}
 
 #[derive(Debug, Clone)]
-pub struct RoleRepository {
+pub struct MySqlRoleRepository {
     pool: MySqlPool,
 }
 
-impl RoleRepository {
+impl MySqlRoleRepository {
     pub fn new(pool: MySqlPool) -> Self {
         Self { pool }
     }
 }
 
-impl RoleRepository {
+#[async_trait]
+impl RoleRepository for MySqlRoleRepository {
     // Find all roles
-    pub async fn find_all(&self) -> Result<RoleListResponse, sqlx::Error> {
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<RoleListResponse, sqlx::Error> {
         let roles = sqlx::query_as!(
-            RoleResponse,
+            Role,
             r#"
-            SELECT id AS 'id:Uuid', role_name, description
+            SELECT id AS 'id: Uuid', role_name, description, created_at, updated_at, version
             FROM roles
             "#
         )
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(RoleListResponse { roles })
+        Ok(RoleListResponse {
+            roles: roles.into_iter().map(RoleResponse::from).collect(),
+        })
     }
 
     // Create Role
-    pub async fn create(
+    #[instrument(level = "debug", skip(self, description))]
+    async fn create(
         &self,
         role_name: &str,
         description: &str,
     ) -> Result<RoleResponse, sqlx::Error> {
         let id = Uuid::new_v4();
-        let id_bytes = id.as_bytes().to_vec();
-        sqlx::query!(
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT INTO roles (id, role_name, description) VALUES (?, ?, ?)")
+            .bind(id.as_bytes().to_vec())
+            .bind(role_name)
+            .bind(description)
+            .execute(&mut *tx)
+            .await?;
+        let role: RoleResponse = sqlx::query_as!(
+            Role,
             r#"
-            INSERT INTO roles (id, role_name, description)
-            VALUES (?, ?, ?)
+            SELECT id AS 'id: Uuid', role_name, description, created_at, updated_at, version
+            FROM roles
+            WHERE id = ?
             "#,
-            id_bytes,
-            role_name,
-            description
+            id.as_bytes().to_vec()
         )
-        .execute(&self.pool)
-        .await?;
-        let response = RoleResponse {
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        let entry = audit_entry(
+            AuditAction::Create,
+            AuditTargetType::Role,
             id,
-            role_name: role_name.to_string(),
-            description: Some(description.to_string()),
-        };
-        Ok(response)
+            None,
+            Some(&role),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(role)
     }
 
     // Find role by id
-    pub async fn find_by_id(&self, id: Uuid) -> Result<RoleResponse, sqlx::Error> {
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<RoleResponse, sqlx::Error> {
         let role = sqlx::query_as!(
-            RoleResponse,
+            Role,
             r#"
-            SELECT id AS 'id:Uuid', role_name, description
+            SELECT id AS 'id: Uuid', role_name, description, created_at, updated_at, version
             FROM roles
             WHERE id = ?
             "#,
@@ -71,17 +135,54 @@ impl RoleRepository {
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(role)
+        Ok(role.into())
+    }
+
+    // Find role by name
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_name(&self, role_name: &str) -> Result<RoleResponse, sqlx::Error> {
+        let role = sqlx::query_as!(
+            Role,
+            r#"
+            SELECT id AS 'id: Uuid', role_name, description, created_at, updated_at, version
+            FROM roles
+            WHERE role_name = ?
+            "#,
+            role_name
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(role.into())
     }
 
     // Update role by id
-    pub async fn update(
+    #[instrument(level = "debug", skip(self))]
+    async fn update(
         &self,
         id: Uuid,
         role_name: Option<String>,
         description: Option<String>,
-    ) -> Result<RoleResponse, sqlx::Error> {
-        sqlx::query!(
+        expected: Option<i64>,
+    ) -> Result<Option<RoleResponse>, sqlx::Error> {
+        let id_bytes = id.as_bytes().to_vec();
+        let mut tx = self.pool.begin().await?;
+        let before: RoleResponse = sqlx::query_as!(
+            Role,
+            r#"
+            SELECT id AS 'id: Uuid', role_name, description, created_at, updated_at, version
+            FROM roles
+            WHERE id = ? FOR UPDATE
+            "#,
+            &id_bytes
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(None);
+        }
+        sqlx::query(
             r#"
             UPDATE roles
             SET
@@ -89,27 +190,92 @@ impl RoleRepository {
                 description = COALESCE(?, description)
             WHERE id = ?
             "#,
-            role_name,
-            description,
-            id.as_bytes().to_vec()
         )
-        .execute(&self.pool)
+        .bind(role_name)
+        .bind(description)
+        .bind(&id_bytes)
+        .execute(&mut *tx)
         .await?;
-        let response = self.find_by_id(id).await?;
-        Ok(response)
+        let role: RoleResponse = sqlx::query_as!(
+            Role,
+            r#"
+            SELECT id AS 'id: Uuid', role_name, description, created_at, updated_at, version
+            FROM roles
+            WHERE id = ?
+            "#,
+            &id_bytes
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        let entry = audit_entry(
+            AuditAction::Update,
+            AuditTargetType::Role,
+            id,
+            Some(&before),
+            Some(&role),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(Some(role))
     }
 
-    // Delete role by id
-    pub async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+    // Delete role by id, moving its users to `reassign_to`
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(
+        &self,
+        id: Uuid,
+        reassign_to: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error> {
+        let id_bytes = id.as_bytes().to_vec();
+        let mut tx = self.pool.begin().await?;
+        let before: RoleResponse = sqlx::query_as!(
+            Role,
             r#"
-            DELETE FROM roles
-            WHERE id = ?
+            SELECT id AS 'id: Uuid', role_name, description, created_at, updated_at, version
+            FROM roles
+            WHERE id = ? FOR UPDATE
             "#,
-            id.as_bytes().to_vec()
+            &id_bytes
         )
-        .execute(&self.pool)
-        .await?;
-        Ok(())
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        match reassign_to {
+            Some(new_role) => {
+                sqlx::query("UPDATE users SET role_id = ? WHERE role_id = ?")
+                    .bind(new_role.as_bytes().to_vec())
+                    .bind(&id_bytes)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            None => {
+                let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE role_id = ?")
+                    .bind(&id_bytes)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                if users > 0 {
+                    return Ok(DeleteOutcome::InUse);
+                }
+            }
+        }
+        sqlx::query("DELETE FROM roles WHERE id = ?")
+            .bind(&id_bytes)
+            .execute(&mut *tx)
+            .await?;
+        let entry = audit_entry(
+            AuditAction::Delete,
+            AuditTargetType::Role,
+            id,
+            Some(&before),
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(DeleteOutcome::Deleted)
     }
 }
@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use sqlx::{
+    sqlite::{SqlitePool, SqliteRow},
+    QueryBuilder, Row, Sqlite, SqliteConnection,
+};
+use tracing::instrument;
+
+use crate::{
+    entities::{parse_audit_action, parse_audit_target_type, AuditEntry},
+    repositories::audit::{AuditFilter, AUDIT_COLUMNS},
+    repositories::AuditRepository,
+};
+
+fn entry_from_row(row: &SqliteRow) -> Result<AuditEntry, sqlx::Error> {
+    Ok(AuditEntry {
+        id: row.try_get("id")?,
+        actor_id: row.try_get("actor_id")?,
+        action: parse_audit_action(row.try_get("action")?)?,
+        target_type: parse_audit_target_type(row.try_get("target_type")?)?,
+        target_id: row.try_get("target_id")?,
+        before: row.try_get("before_snapshot")?,
+        after: row.try_get("after_snapshot")?,
+        ip: row.try_get("ip")?,
+        request_id: row.try_get("request_id")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+// Append `entry` as part of the caller's transaction
+pub(crate) async fn insert_audit(
+    conn: &mut SqliteConnection,
+    entry: &AuditEntry,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO audit_log (id, actor_id, action, target_type, target_id, \
+         before_snapshot, after_snapshot, ip, request_id, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(entry.id)
+    .bind(entry.actor_id)
+    .bind(entry.action.as_str())
+    .bind(entry.target_type.as_str())
+    .bind(entry.target_id)
+    .bind(&entry.before)
+    .bind(&entry.after)
+    .bind(&entry.ip)
+    .bind(&entry.request_id)
+    .bind(entry.created_at)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct SqliteAuditRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAuditRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for SqliteAuditRepository {
+    // Find the entries matching `filter`, newest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM audit_log WHERE 1 = 1",
+            AUDIT_COLUMNS
+        ));
+        if let Some(actor_id) = filter.actor_id {
+            query.push(" AND actor_id = ").push_bind(actor_id);
+        }
+        if let Some(target_type) = filter.target_type {
+            query
+                .push(" AND target_type = ")
+                .push_bind(target_type.as_str());
+        }
+        if let Some(target_id) = filter.target_id {
+            query.push(" AND target_id = ").push_bind(target_id);
+        }
+        if let Some(from) = filter.from {
+            query.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(to) = filter.to {
+            query.push(" AND created_at < ").push_bind(to);
+        }
+        query
+            .push(" ORDER BY created_at DESC LIMIT ")
+            .push_bind(filter.limit);
+        query
+            .build()
+            .try_map(|row: SqliteRow| entry_from_row(&row))
+            .fetch_all(&self.pool)
+            .await
+    }
+}
@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{
+    sqlite::{SqlitePool, SqliteRow},
+    QueryBuilder, Row, Sqlite,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::OutboxEvent,
+    models::{CommentResponse, CreateComment},
+    repositories::{sqlite::outbox::insert_events, CommentRepository},
+};
+
+const COMMENT_COLUMNS: &str = "id, content, user_id, post_id, created_at, updated_at";
+
+fn comment_from_row(row: &SqliteRow) -> Result<CommentResponse, sqlx::Error> {
+    Ok(CommentResponse {
+        id: row.try_get("id")?,
+        content: row.try_get("content")?,
+        user_id: row.try_get("user_id")?,
+        post_id: row.try_get("post_id")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct SqliteCommentRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteCommentRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CommentRepository for SqliteCommentRepository {
+    // Create Comment
+    #[instrument(level = "debug", skip(self, comment))]
+    async fn create(&self, comment: CreateComment) -> Result<CommentResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT INTO comments (id, content, user_id, post_id) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(comment.content)
+            .bind(comment.user_id)
+            .bind(comment.post_id)
+            .execute(&mut *tx)
+            .await?;
+        let comment = sqlx::query(&format!(
+            "SELECT {} FROM comments WHERE id = ?",
+            COMMENT_COLUMNS
+        ))
+        .bind(id)
+        .try_map(|row: SqliteRow| comment_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_events(
+            &mut tx,
+            &[OutboxEvent::CommentCreated],
+            comment.id,
+            &comment,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(comment)
+    }
+
+    // Find comment by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<CommentResponse, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {} FROM comments WHERE id = ? AND deleted_at IS NULL",
+            COMMENT_COLUMNS
+        ))
+        .bind(id)
+        .try_map(|row: SqliteRow| comment_from_row(&row))
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // Find the comments on several posts at once, oldest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_post_ids(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<CommentResponse>, sqlx::Error> {
+        if post_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM comments WHERE deleted_at IS NULL AND post_id IN (",
+            COMMENT_COLUMNS
+        ));
+        let mut separated = query.separated(", ");
+        for post_id in post_ids {
+            separated.push_bind(*post_id);
+        }
+        separated.push_unseparated(") ORDER BY created_at");
+        query
+            .build()
+            .try_map(|row: SqliteRow| comment_from_row(&row))
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Move comment to the trash
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let result =
+            sqlx::query("UPDATE comments SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+                .bind(Utc::now())
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+// SQLite implementations of the repository traits, mapping rows by hand
+mod audit;
+mod comment;
+mod outbox;
+mod password_reset;
+mod permission;
+mod post;
+mod role;
+mod trash;
+mod user;
+mod webhook;
+
+pub use audit::SqliteAuditRepository;
+pub use comment::SqliteCommentRepository;
+pub use outbox::SqliteOutboxRepository;
+pub use password_reset::SqlitePasswordResetRepository;
+pub use permission::SqlitePermissionRepository;
+pub use post::SqlitePostRepository;
+pub use role::SqliteRoleRepository;
+pub use trash::SqliteTrashRepository;
+pub use user::SqliteUserRepository;
+pub use webhook::SqliteWebhookRepository;
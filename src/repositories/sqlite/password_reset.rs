@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqlitePool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{AuditAction, AuditTargetType},
+    repositories::sqlite::audit::insert_audit,
+    repositories::{audit::audit_entry, PasswordResetRepository},
+};
+
+#[derive(Debug, Clone)]
+pub struct SqlitePasswordResetRepository {
+    pool: SqlitePool,
+}
+
+impl SqlitePasswordResetRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for SqlitePasswordResetRepository {
+    // Store a token for a user, dropping their expired and used ones
+    #[instrument(level = "debug", skip(self, token_hash))]
+    async fn create(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "DELETE FROM password_reset_tokens \
+             WHERE user_id = ? AND (used_at IS NOT NULL OR expires_at <= ?)",
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at, created_at) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Use up a token and set its user's new password
+    #[instrument(level = "debug", skip_all)]
+    async fn reset_password(
+        &self,
+        token_hash: &str,
+        password_hash: &str,
+    ) -> Result<Uuid, sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let user_id: Uuid = sqlx::query_scalar(
+            "UPDATE password_reset_tokens SET used_at = ? \
+             WHERE token_hash = ? AND used_at IS NULL AND expires_at > ? \
+             RETURNING user_id",
+        )
+        .bind(now)
+        .bind(token_hash)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(
+            "UPDATE password_reset_tokens SET used_at = ? \
+             WHERE user_id = ? AND used_at IS NULL",
+        )
+        .bind(now)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query(
+            "UPDATE users SET password_hash = ?, session_version = session_version + 1 \
+             WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        // Password hashes are never snapshotted
+        let entry = audit_entry::<()>(
+            AuditAction::Update,
+            AuditTargetType::User,
+            user_id,
+            None,
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(user_id)
+    }
+}
@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use sqlx::{
+    sqlite::{SqlitePool, SqliteRow},
+    Row,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{PermissionListResponse, PermissionResponse},
+    repositories::PermissionRepository,
+};
+
+fn permission_from_row(row: &SqliteRow) -> Result<PermissionResponse, sqlx::Error> {
+    Ok(PermissionResponse {
+        id: row.try_get("id")?,
+        permission_name: row.try_get("permission_name")?,
+        description: row.try_get("description")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct SqlitePermissionRepository {
+    pool: SqlitePool,
+}
+
+impl SqlitePermissionRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PermissionRepository for SqlitePermissionRepository {
+    // Find all permissions
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<PermissionListResponse, sqlx::Error> {
+        let permissions = sqlx::query("SELECT id, permission_name, description FROM permissions")
+            .try_map(|row: SqliteRow| permission_from_row(&row))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(PermissionListResponse { permissions })
+    }
+
+    // Create permission
+    #[instrument(level = "debug", skip(self, description))]
+    async fn create(
+        &self,
+        permission_name: &str,
+        description: &str,
+    ) -> Result<PermissionResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query("INSERT INTO permissions (id, permission_name, description) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(permission_name)
+            .bind(description)
+            .execute(&self.pool)
+            .await?;
+        let response = PermissionResponse {
+            id,
+            permission_name: permission_name.to_string(),
+            description: Some(description.to_string()),
+        };
+        Ok(response)
+    }
+
+    // Find permission by name
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_name(&self, permission_name: &str) -> Result<PermissionResponse, sqlx::Error> {
+        let permission = sqlx::query(
+            "SELECT id, permission_name, description FROM permissions WHERE permission_name = ?",
+        )
+        .bind(permission_name)
+        .try_map(|row: SqliteRow| permission_from_row(&row))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(permission)
+    }
+
+    // Find all permissions granted to a role
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_role_id(&self, role_id: Uuid) -> Result<PermissionListResponse, sqlx::Error> {
+        let permissions = sqlx::query(
+            "SELECT p.id, p.permission_name, p.description FROM permissions p \
+             JOIN role_permissions rp ON rp.permission_id = p.id WHERE rp.role_id = ?",
+        )
+        .bind(role_id)
+        .try_map(|row: SqliteRow| permission_from_row(&row))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PermissionListResponse { permissions })
+    }
+
+    // Grant a permission to a role, ignoring grants that already exist
+    #[instrument(level = "debug", skip(self))]
+    async fn assign_to_role(&self, role_id: Uuid, permission_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO role_permissions (role_id, permission_id) VALUES (?, ?)",
+        )
+        .bind(role_id)
+        .bind(permission_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Names of the permissions granted to a role
+    #[instrument(level = "debug", skip(self))]
+    async fn find_names_by_role(&self, role_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT p.permission_name FROM permissions p \
+             JOIN role_permissions rp ON rp.permission_id = p.id WHERE rp.role_id = ?",
+        )
+        .bind(role_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
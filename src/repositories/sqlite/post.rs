@@ -0,0 +1,359 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{
+    sqlite::{SqlitePool, SqliteRow},
+    QueryBuilder, Row, Sqlite,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    db::Replicas,
+    entities::post_events,
+    models::{
+        AuditAction, AuditTargetType, CreateMedia, CreatePost, MediaResponse, PostListResponse,
+        PostResponse, UpdatePost,
+    },
+    repositories::sqlite::{audit::insert_audit, outbox::insert_events},
+    repositories::{
+        audit::audit_entry,
+        post::{media_response, updated},
+        DeleteOutcome, PostRepository,
+    },
+};
+
+const POST_COLUMNS: &str = "id, title, content, user_id, status, published_at, featured_media_id, \
+     excerpt, meta_description, canonical_url, og_title, og_description, og_image_url, updated_at, version";
+
+fn post_from_row(row: &SqliteRow) -> Result<PostResponse, sqlx::Error> {
+    Ok(PostResponse {
+        id: row.try_get("id")?,
+        title: row.try_get("title")?,
+        content: row.try_get("content")?,
+        user_id: row.try_get("user_id")?,
+        status: row.try_get("status")?,
+        published_at: row.try_get("published_at")?,
+        featured_media_id: row.try_get("featured_media_id")?,
+        excerpt: row.try_get("excerpt")?,
+        meta_description: row.try_get("meta_description")?,
+        canonical_url: row.try_get("canonical_url")?,
+        og_title: row.try_get("og_title")?,
+        og_description: row.try_get("og_description")?,
+        og_image_url: row.try_get("og_image_url")?,
+        updated_at: row.try_get("updated_at")?,
+        version: row.try_get("version")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct SqlitePostRepository {
+    pool: SqlitePool,
+    replicas: Replicas,
+}
+
+impl SqlitePostRepository {
+    pub fn new(pool: SqlitePool, replicas: Replicas) -> Self {
+        Self { pool, replicas }
+    }
+
+    // Posts whose `column` holds one of `ids`
+    async fn find_where_in(
+        &self,
+        column: &str,
+        ids: &[Uuid],
+    ) -> Result<Vec<PostResponse>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM posts WHERE deleted_at IS NULL AND {} IN (",
+            POST_COLUMNS, column
+        ));
+        let mut separated = query.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+        query
+            .build()
+            .try_map(|row: SqliteRow| post_from_row(&row))
+            .fetch_all(self.replicas.reader(&self.pool))
+            .await
+    }
+}
+
+#[async_trait]
+impl PostRepository for SqlitePostRepository {
+    // Find all published posts, or all the others
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self, published: bool) -> Result<PostListResponse, sqlx::Error> {
+        let posts = sqlx::query(&format!(
+            "SELECT {} FROM posts WHERE deleted_at IS NULL AND (status = 'published') = ?",
+            POST_COLUMNS
+        ))
+        .bind(published)
+        .try_map(|row: SqliteRow| post_from_row(&row))
+        .fetch_all(self.replicas.reader(&self.pool))
+        .await?;
+
+        Ok(PostListResponse { posts })
+    }
+
+    // Create Post
+    #[instrument(level = "debug", skip(self, post))]
+    async fn create(&self, post: CreatePost) -> Result<PostResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+        let post = sqlx::query(&format!(
+            r#"
+            INSERT INTO posts (id, title, content, status, published_at, user_id, featured_media_id,
+                excerpt, meta_description, canonical_url, og_title, og_description, og_image_url)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING {}
+            "#,
+            POST_COLUMNS
+        ))
+        .bind(id)
+        .bind(&post.title)
+        .bind(&post.content)
+        .bind(post.status)
+        .bind(post.published_at)
+        .bind(post.user_id)
+        .bind(post.featured_media_id)
+        .bind(&post.excerpt)
+        .bind(&post.meta_description)
+        .bind(&post.canonical_url)
+        .bind(&post.og_title)
+        .bind(&post.og_description)
+        .bind(&post.og_image_url)
+        .try_map(|row: SqliteRow| post_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_events(&mut tx, &post_events(None, &post), post.id, &post).await?;
+        let entry = audit_entry(
+            AuditAction::Create,
+            AuditTargetType::Post,
+            id,
+            None,
+            Some(&post),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(post)
+    }
+
+    // Find post by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<PostResponse, sqlx::Error> {
+        let post = sqlx::query(&format!(
+            "SELECT {} FROM posts WHERE id = ? AND deleted_at IS NULL",
+            POST_COLUMNS
+        ))
+        .bind(id)
+        .try_map(|row: SqliteRow| post_from_row(&row))
+        .fetch_one(self.replicas.reader(&self.pool))
+        .await?;
+
+        Ok(post)
+    }
+
+    // Find Post by user_id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user_id(&self, user_id: Uuid) -> Result<PostListResponse, sqlx::Error> {
+        let posts = sqlx::query(&format!(
+            "SELECT {} FROM posts WHERE user_id = ? AND deleted_at IS NULL",
+            POST_COLUMNS
+        ))
+        .bind(user_id)
+        .try_map(|row: SqliteRow| post_from_row(&row))
+        .fetch_all(self.replicas.reader(&self.pool))
+        .await?;
+
+        Ok(PostListResponse { posts })
+    }
+
+    // Find posts by ids, skipping ids that don't exist
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error> {
+        self.find_where_in("id", ids).await
+    }
+
+    // Find the posts of several users at once
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_user_ids(&self, user_ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error> {
+        self.find_where_in("user_id", user_ids).await
+    }
+
+    // Update Post
+    #[instrument(level = "debug", skip(self, post), fields(post_id = %post.id))]
+    async fn update(
+        &self,
+        post: UpdatePost,
+        expected: Option<i64>,
+    ) -> Result<Option<PostResponse>, sqlx::Error> {
+        // Take the write lock up front so the previous status and version
+        // can't change before the update
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let before = sqlx::query(&format!(
+            "SELECT {} FROM posts WHERE id = ? AND deleted_at IS NULL",
+            POST_COLUMNS
+        ))
+        .bind(post.id)
+        .try_map(|row: SqliteRow| post_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(None);
+        }
+        let id = post.id;
+        sqlx::query(
+            r#"
+            UPDATE posts
+            SET
+                title = COALESCE(?, title),
+                content = COALESCE(?, content),
+                status = COALESCE(?, status),
+                published_at = COALESCE(?, published_at),
+                user_id = COALESCE(?, user_id),
+                featured_media_id = ?,
+                excerpt = ?,
+                meta_description = ?,
+                canonical_url = ?,
+                og_title = ?,
+                og_description = ?,
+                og_image_url = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(post.title)
+        .bind(post.content)
+        .bind(post.status)
+        .bind(post.published_at)
+        .bind(post.user_id)
+        .bind(updated(post.featured_media_id, &before.featured_media_id))
+        .bind(updated(post.excerpt, &before.excerpt))
+        .bind(updated(post.meta_description, &before.meta_description))
+        .bind(updated(post.canonical_url, &before.canonical_url))
+        .bind(updated(post.og_title, &before.og_title))
+        .bind(updated(post.og_description, &before.og_description))
+        .bind(updated(post.og_image_url, &before.og_image_url))
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        // Read the row back rather than use RETURNING, which doesn't see the
+        // version and `updated_at` the trigger sets afterwards
+        let post = sqlx::query(&format!("SELECT {} FROM posts WHERE id = ?", POST_COLUMNS))
+            .bind(id)
+            .try_map(|row: SqliteRow| post_from_row(&row))
+            .fetch_one(&mut *tx)
+            .await?;
+        insert_events(
+            &mut tx,
+            &post_events(Some(before.status), &post),
+            post.id,
+            &post,
+        )
+        .await?;
+        let entry = audit_entry(
+            AuditAction::Update,
+            AuditTargetType::Post,
+            post.id,
+            Some(&before),
+            Some(&post),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(Some(post))
+    }
+
+    // Move Post and its comments to the trash
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, id: Uuid, expected: Option<i64>) -> Result<DeleteOutcome, sqlx::Error> {
+        let deleted_at = Utc::now();
+        // Take the write lock up front so the row can't change between the
+        // version check and the write
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let before = sqlx::query(&format!(
+            "SELECT {} FROM posts WHERE id = ? AND deleted_at IS NULL",
+            POST_COLUMNS
+        ))
+        .bind(id)
+        .try_map(|row: SqliteRow| post_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        sqlx::query("UPDATE posts SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE comments SET deleted_at = ? WHERE post_id = ? AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let entry = audit_entry(
+            AuditAction::Delete,
+            AuditTargetType::Post,
+            id,
+            Some(&before),
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(DeleteOutcome::Deleted)
+    }
+
+    // Register a media item
+    #[instrument(level = "debug", skip(self))]
+    async fn create_media(&self, media: CreateMedia) -> Result<MediaResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let created_at = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO media (id, file_name, url, mime_type, alt_text, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id)
+        .bind(&media.file_name)
+        .bind(&media.url)
+        .bind(&media.mime_type)
+        .bind(&media.alt_text)
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(media_response(id, media, created_at))
+    }
+
+    // Check that a media item exists
+    #[instrument(level = "debug", skip(self))]
+    async fn media_exists(&self, media_id: Uuid) -> Result<bool, sqlx::Error> {
+        let (exists,): (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM media WHERE id = ?)")
+            .bind(media_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(exists)
+    }
+
+    // Search posts with a case-insensitive substring match
+    #[instrument(level = "debug", skip(self))]
+    async fn search(&self, query: &str) -> Result<PostListResponse, sqlx::Error> {
+        let pattern = crate::repositories::like_pattern(query);
+        let posts = sqlx::query(&format!(
+            "SELECT {} FROM posts \
+             WHERE deleted_at IS NULL \
+                 AND (title LIKE ?1 ESCAPE '!' OR excerpt LIKE ?1 ESCAPE '!' OR content LIKE ?1 ESCAPE '!') \
+             ORDER BY created_at DESC",
+            POST_COLUMNS
+        ))
+        .bind(pattern)
+        .try_map(|row: SqliteRow| post_from_row(&row))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PostListResponse { posts })
+    }
+}
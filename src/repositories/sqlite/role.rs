@@ -0,0 +1,202 @@
+use async_trait::async_trait;
+use sqlx::{
+    sqlite::{SqlitePool, SqliteRow},
+    Row,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{AuditAction, AuditTargetType, RoleListResponse, RoleResponse},
+    repositories::{
+        audit::audit_entry, sqlite::audit::insert_audit, DeleteOutcome, RoleRepository,
+    },
+};
+
+fn role_from_row(row: &SqliteRow) -> Result<RoleResponse, sqlx::Error> {
+    Ok(RoleResponse {
+        id: row.try_get("id")?,
+        role_name: row.try_get("role_name")?,
+        description: row.try_get("description")?,
+        updated_at: row.try_get("updated_at")?,
+        version: row.try_get("version")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct SqliteRoleRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRoleRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RoleRepository for SqliteRoleRepository {
+    // Find all roles
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<RoleListResponse, sqlx::Error> {
+        let roles = sqlx::query("SELECT id, role_name, description, updated_at, version FROM roles")
+            .try_map(|row: SqliteRow| role_from_row(&row))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(RoleListResponse { roles })
+    }
+
+    // Create Role
+    #[instrument(level = "debug", skip(self, description))]
+    async fn create(
+        &self,
+        role_name: &str,
+        description: &str,
+    ) -> Result<RoleResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT INTO roles (id, role_name, description) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(role_name)
+            .bind(description)
+            .execute(&mut *tx)
+            .await?;
+        let role =
+            sqlx::query("SELECT id, role_name, description, updated_at, version FROM roles WHERE id = ?")
+                .bind(id)
+                .try_map(|row: SqliteRow| role_from_row(&row))
+                .fetch_one(&mut *tx)
+                .await?;
+        let entry = audit_entry(AuditAction::Create, AuditTargetType::Role, id, None, Some(&role));
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(role)
+    }
+
+    // Find role by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<RoleResponse, sqlx::Error> {
+        let role = sqlx::query("SELECT id, role_name, description, updated_at, version FROM roles WHERE id = ?")
+            .bind(id)
+            .try_map(|row: SqliteRow| role_from_row(&row))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(role)
+    }
+
+    // Find role by name
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_name(&self, role_name: &str) -> Result<RoleResponse, sqlx::Error> {
+        let role = sqlx::query("SELECT id, role_name, description, updated_at, version FROM roles WHERE role_name = ?")
+            .bind(role_name)
+            .try_map(|row: SqliteRow| role_from_row(&row))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(role)
+    }
+
+    // Update role by id
+    #[instrument(level = "debug", skip(self))]
+    async fn update(
+        &self,
+        id: Uuid,
+        role_name: Option<String>,
+        description: Option<String>,
+        expected: Option<i64>,
+    ) -> Result<Option<RoleResponse>, sqlx::Error> {
+        // Take the write lock up front so the row can't change between the
+        // version check and the write
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let before =
+            sqlx::query("SELECT id, role_name, description, updated_at, version FROM roles WHERE id = ?")
+                .bind(id)
+                .try_map(|row: SqliteRow| role_from_row(&row))
+                .fetch_one(&mut *tx)
+                .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(None);
+        }
+        sqlx::query(
+            r#"
+            UPDATE roles
+            SET
+                role_name = COALESCE(?, role_name),
+                description = COALESCE(?, description)
+            WHERE id = ?
+            "#,
+        )
+        .bind(role_name)
+        .bind(description)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        let role =
+            sqlx::query("SELECT id, role_name, description, updated_at, version FROM roles WHERE id = ?")
+                .bind(id)
+                .try_map(|row: SqliteRow| role_from_row(&row))
+                .fetch_one(&mut *tx)
+                .await?;
+        let entry = audit_entry(
+            AuditAction::Update,
+            AuditTargetType::Role,
+            id,
+            Some(&before),
+            Some(&role),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(Some(role))
+    }
+
+    // Delete role by id, moving its users to `reassign_to`
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(
+        &self,
+        id: Uuid,
+        reassign_to: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error> {
+        // Take the write lock up front so the row can't change between the
+        // version check and the write
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let before =
+            sqlx::query("SELECT id, role_name, description, updated_at, version FROM roles WHERE id = ?")
+                .bind(id)
+                .try_map(|row: SqliteRow| role_from_row(&row))
+                .fetch_one(&mut *tx)
+                .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        match reassign_to {
+            Some(new_role) => {
+                sqlx::query("UPDATE users SET role_id = ? WHERE role_id = ?")
+                    .bind(new_role)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            None => {
+                let users: i64 =
+                    sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE role_id = ?")
+                        .bind(id)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                if users > 0 {
+                    return Ok(DeleteOutcome::InUse);
+                }
+            }
+        }
+        sqlx::query("DELETE FROM roles WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let entry = audit_entry(AuditAction::Delete, AuditTargetType::Role, id, Some(&before), None);
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(DeleteOutcome::Deleted)
+    }
+}
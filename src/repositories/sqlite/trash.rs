@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    sqlite::{SqlitePool, SqliteRow},
+    Row,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::TrashItem,
+    models::{AuditAction, TrashItemType},
+    repositories::sqlite::audit::insert_audit,
+    repositories::trash::{merge_trash_items, trash_audit_entry, trash_item_types, trash_source},
+    repositories::TrashRepository,
+};
+
+#[derive(Debug, Clone)]
+pub struct SqliteTrashRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteTrashRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TrashRepository for SqliteTrashRepository {
+    // Find trashed items, most recently deleted first
+    #[instrument(level = "debug", skip(self))]
+    async fn find(
+        &self,
+        item_type: Option<TrashItemType>,
+        limit: i64,
+    ) -> Result<Vec<TrashItem>, sqlx::Error> {
+        let mut items = Vec::new();
+        for item_type in trash_item_types(item_type) {
+            let (table, label) = trash_source(item_type);
+            let query = format!(
+                "SELECT id, {} AS label, deleted_at FROM {} \
+                 WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC LIMIT ?",
+                label, table
+            );
+            let found = sqlx::query(&query)
+                .bind(limit)
+                .try_map(|row: SqliteRow| {
+                    Ok(TrashItem {
+                        item_type,
+                        id: row.try_get("id")?,
+                        label: row.try_get("label")?,
+                        deleted_at: row.try_get("deleted_at")?,
+                    })
+                })
+                .fetch_all(&self.pool)
+                .await?;
+            items.extend(found);
+        }
+        Ok(merge_trash_items(items, limit))
+    }
+
+    // Take an item, and whatever was deleted along with it, out of the trash
+    #[instrument(level = "debug", skip(self))]
+    async fn restore(&self, item_type: TrashItemType, id: Uuid) -> Result<bool, sqlx::Error> {
+        let entry = trash_audit_entry(AuditAction::Restore, item_type, id);
+        let mut tx = self.pool.begin().await?;
+        match item_type {
+            TrashItemType::User => {
+                sqlx::query("SELECT id FROM users WHERE id = ? AND deleted_at IS NOT NULL")
+                    .bind(id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or(sqlx::Error::RowNotFound)?;
+                sqlx::query(
+                    "UPDATE comments SET deleted_at = NULL \
+                     WHERE deleted_at = (SELECT deleted_at FROM users WHERE id = ?) \
+                     AND (user_id = ? OR post_id IN (SELECT id FROM posts WHERE user_id = ?))",
+                )
+                .bind(id)
+                .bind(id)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query(
+                    "UPDATE posts SET deleted_at = NULL \
+                     WHERE user_id = ? AND deleted_at = (SELECT deleted_at FROM users WHERE id = ?)",
+                )
+                .bind(id)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query("UPDATE users SET deleted_at = NULL WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            TrashItemType::Post => {
+                let author_deleted_at: Option<DateTime<Utc>> = sqlx::query(
+                    "SELECT u.deleted_at FROM posts p JOIN users u ON u.id = p.user_id \
+                     WHERE p.id = ? AND p.deleted_at IS NOT NULL",
+                )
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound)?
+                .try_get("deleted_at")?;
+                if author_deleted_at.is_some() {
+                    return Ok(false);
+                }
+                sqlx::query(
+                    "UPDATE comments SET deleted_at = NULL \
+                     WHERE post_id = ? AND deleted_at = (SELECT deleted_at FROM posts WHERE id = ?)",
+                )
+                .bind(id)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query("UPDATE posts SET deleted_at = NULL WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            TrashItemType::Comment => {
+                let row = sqlx::query(
+                    "SELECT p.deleted_at AS post_deleted_at, u.deleted_at AS author_deleted_at \
+                     FROM comments c \
+                     JOIN posts p ON p.id = c.post_id \
+                     JOIN users u ON u.id = c.user_id \
+                     WHERE c.id = ? AND c.deleted_at IS NOT NULL",
+                )
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound)?;
+                let post_deleted_at: Option<DateTime<Utc>> = row.try_get("post_deleted_at")?;
+                let author_deleted_at: Option<DateTime<Utc>> = row.try_get("author_deleted_at")?;
+                if post_deleted_at.is_some() || author_deleted_at.is_some() {
+                    return Ok(false);
+                }
+                sqlx::query("UPDATE comments SET deleted_at = NULL WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        if let Some(entry) = entry {
+            insert_audit(&mut tx, &entry).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Permanently delete a trashed item
+    #[instrument(level = "debug", skip(self))]
+    async fn purge(&self, item_type: TrashItemType, id: Uuid) -> Result<(), sqlx::Error> {
+        let (table, _) = trash_source(item_type);
+        let query = format!(
+            "DELETE FROM {} WHERE id = ? AND deleted_at IS NOT NULL",
+            table
+        );
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(&query).bind(id).execute(&mut *tx).await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        if let Some(entry) = trash_audit_entry(AuditAction::Purge, item_type, id) {
+            insert_audit(&mut tx, &entry).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Permanently delete everything trashed before `cutoff`
+    #[instrument(level = "debug", skip(self))]
+    async fn purge_deleted_before(&self, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut purged = 0;
+        // Comments, then posts, then users, so each count only covers rows that
+        // were trashed themselves rather than removed by a cascade
+        for item_type in trash_item_types(None).into_iter().rev() {
+            let (table, _) = trash_source(item_type);
+            let query = format!("DELETE FROM {} WHERE deleted_at < ?", table);
+            purged += sqlx::query(&query)
+                .bind(cutoff)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(purged)
+    }
+}
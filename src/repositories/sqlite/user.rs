@@ -0,0 +1,375 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{
+    sqlite::{SqlitePool, SqliteRow},
+    QueryBuilder, Row, Sqlite,
+};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::OutboxEvent,
+    models::{AuditAction, AuditTargetType, OwnedContent, UserListResponse, UserResponse},
+    repositories::{
+        audit::audit_entry,
+        sqlite::{audit::insert_audit, outbox::insert_events},
+        DeleteOutcome, UserRepository,
+    },
+};
+
+fn user_from_row(row: &SqliteRow) -> Result<UserResponse, sqlx::Error> {
+    Ok(UserResponse {
+        id: row.try_get("id")?,
+        username: row.try_get("username")?,
+        email: row.try_get("email")?,
+        role_id: row.try_get("role_id")?,
+        updated_at: row.try_get("updated_at")?,
+        version: row.try_get("version")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct SqliteUserRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteUserRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for SqliteUserRepository {
+    // Find all users
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<UserListResponse, sqlx::Error> {
+        let users = sqlx::query(
+            r#"
+            SELECT id, username, email, role_id, updated_at, version
+            FROM users
+            WHERE deleted_at IS NULL
+            "#,
+        )
+        .try_map(|row: SqliteRow| user_from_row(&row))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(UserListResponse { users })
+    }
+
+    // Create User
+    #[instrument(level = "debug", skip(self, password))]
+    async fn create(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        role_id: Uuid,
+    ) -> Result<UserResponse, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, username, email, password_hash, role_id)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id)
+        .bind(username)
+        .bind(email)
+        .bind(password)
+        .bind(role_id)
+        .execute(&mut *tx)
+        .await?;
+        let user = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version FROM users WHERE id = ?",
+        )
+        .bind(id)
+        .try_map(|row: SqliteRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_events(&mut tx, &[OutboxEvent::UserCreated], user.id, &user).await?;
+        let entry = audit_entry(
+            AuditAction::Create,
+            AuditTargetType::User,
+            id,
+            None,
+            Some(&user),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(user)
+    }
+
+    // Find user by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<UserResponse, sqlx::Error> {
+        let user = sqlx::query(
+            r#"
+            SELECT id, username, email, role_id, updated_at, version
+            FROM users
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .try_map(|row: SqliteRow| user_from_row(&row))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(user)
+    }
+
+    // Find user by username or email
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_login(&self, login: &str) -> Result<UserResponse, sqlx::Error> {
+        let user = sqlx::query(
+            r#"
+            SELECT id, username, email, role_id, updated_at, version
+            FROM users
+            WHERE (username = ? OR email = ?) AND deleted_at IS NULL
+            "#,
+        )
+        .bind(login)
+        .bind(login)
+        .try_map(|row: SqliteRow| user_from_row(&row))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(user)
+    }
+
+    // Find users by ids, skipping ids that don't exist
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<UserResponse>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT id, username, email, role_id, updated_at, version FROM users WHERE deleted_at IS NULL AND id IN (",
+        );
+        let mut separated = query.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+        query
+            .build()
+            .try_map(|row: SqliteRow| user_from_row(&row))
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Find the password hash of a user
+    #[instrument(level = "debug", skip(self))]
+    async fn find_password_hash(&self, id: Uuid) -> Result<String, sqlx::Error> {
+        sqlx::query_scalar("SELECT password_hash FROM users WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    // Find the session version of a user
+    #[instrument(level = "debug", skip(self))]
+    async fn find_session_version(&self, id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT session_version FROM users WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    // Find the id of a user in the trash by username or email
+    #[instrument(level = "debug", skip(self))]
+    async fn find_trashed_by_login(&self, login: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT id FROM users \
+             WHERE (username = ? OR email = ?) AND deleted_at IS NOT NULL LIMIT 1",
+        )
+        .bind(login)
+        .bind(login)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Update user
+    #[instrument(level = "debug", skip(self))]
+    async fn update(
+        &self,
+        id: Uuid,
+        username: Option<String>,
+        email: Option<String>,
+        role_id: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<Option<UserResponse>, sqlx::Error> {
+        // Take the write lock up front so the row can't change between the
+        // version check and the write
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let before = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version \
+             FROM users WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .try_map(|row: SqliteRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(None);
+        }
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET username = COALESCE(?, username),
+                email = COALESCE(?, email),
+                role_id = COALESCE(?, role_id)
+            WHERE id = ?
+            "#,
+        )
+        .bind(username)
+        .bind(email)
+        .bind(role_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        let user = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version FROM users WHERE id = ?",
+        )
+        .bind(id)
+        .try_map(|row: SqliteRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        let entry = audit_entry(
+            AuditAction::Update,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            Some(&user),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(Some(user))
+    }
+
+    // Update user password hash
+    #[instrument(level = "debug", skip(self, password_hash))]
+    async fn update_password(&self, id: Uuid, password_hash: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = ?, session_version = session_version + 1
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(password_hash)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        sqlx::query(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = ?
+            WHERE user_id = ? AND used_at IS NULL
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        // Password hashes are never snapshotted
+        let entry = audit_entry::<()>(AuditAction::Update, AuditTargetType::User, id, None, None);
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await
+    }
+
+    // Move user to the trash, handing over or trashing their content
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(
+        &self,
+        id: Uuid,
+        content: OwnedContent,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error> {
+        let deleted_at = Utc::now();
+        // Take the write lock up front so the row can't change between the
+        // version check and the write
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let before = sqlx::query(
+            "SELECT id, username, email, role_id, updated_at, version \
+             FROM users WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .try_map(|row: SqliteRow| user_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        sqlx::query("UPDATE users SET deleted_at = ? WHERE id = ?")
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        match content {
+            OwnedContent::Refuse => {
+                let posts: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM posts WHERE user_id = ? AND deleted_at IS NULL",
+                )
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+                let comments: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM comments WHERE user_id = ? AND deleted_at IS NULL",
+                )
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+                if posts + comments > 0 {
+                    // Dropping the transaction takes the user back out of the trash
+                    return Ok(DeleteOutcome::InUse);
+                }
+            }
+            OwnedContent::ReassignTo(new_owner) => {
+                sqlx::query("UPDATE posts SET user_id = ? WHERE user_id = ?")
+                    .bind(new_owner)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("UPDATE comments SET user_id = ? WHERE user_id = ?")
+                    .bind(new_owner)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            OwnedContent::Cascade => {}
+        }
+        sqlx::query(
+            r#"
+            UPDATE comments
+            SET deleted_at = ?
+            WHERE deleted_at IS NULL
+                AND (user_id = ? OR post_id IN (SELECT id FROM posts WHERE user_id = ?))
+            "#,
+        )
+        .bind(deleted_at)
+        .bind(id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("UPDATE posts SET deleted_at = ? WHERE user_id = ? AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let entry = audit_entry(
+            AuditAction::Delete,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(DeleteOutcome::Deleted)
+    }
+}
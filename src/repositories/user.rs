@@ -1,37 +1,117 @@
-use sqlx::MySqlPool;
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{MySql, MySqlPool, QueryBuilder};
+use tracing::instrument;
 use uuid::Uuid;
 
-use crate::models::{UserListResponse, UserResponse};
+use crate::{
+    entities::{OutboxEvent, User},
+    models::{AuditAction, AuditTargetType, OwnedContent, UserListResponse, UserResponse},
+    repositories::{
+        audit::{audit_entry, insert_audit},
+        outbox::insert_events,
+        DeleteOutcome,
+    },
+};
+
+#[async_trait]
+pub trait UserRepository: Debug + Send + Sync {
+    // Find all users
+    async fn find_all(&self) -> Result<UserListResponse, sqlx::Error>;
+
+    // Create User
+    async fn create(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        role_id: Uuid,
+    ) -> Result<UserResponse, sqlx::Error>;
+
+    // Find user by id
+    async fn find_by_id(&self, id: Uuid) -> Result<UserResponse, sqlx::Error>;
+
+    // Find user by username or email
+    async fn find_by_login(&self, login: &str) -> Result<UserResponse, sqlx::Error>;
+
+    // Find users by ids, skipping ids that don't exist
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<UserResponse>, sqlx::Error>;
+
+    // Find the password hash of a user
+    async fn find_password_hash(&self, id: Uuid) -> Result<String, sqlx::Error>;
+
+    // Find the session version of a user, bumped by every password change
+    async fn find_session_version(&self, id: Uuid) -> Result<i64, sqlx::Error>;
+
+    // Find the id of a user in the trash by username or email
+    async fn find_trashed_by_login(&self, login: &str) -> Result<Option<Uuid>, sqlx::Error>;
+
+    // Update user. With `expected`, returns None and changes nothing unless
+    // the user's `version` still equals it.
+    async fn update(
+        &self,
+        id: Uuid,
+        username: Option<String>,
+        email: Option<String>,
+        role_id: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<Option<UserResponse>, sqlx::Error>;
+
+    // Update user password hash, revoking the sessions issued before and any
+    // password reset tokens the user was mailed
+    async fn update_password(&self, id: Uuid, password_hash: &str) -> Result<(), sqlx::Error>;
+
+    // Move user to the trash. Their posts and comments, and the comments on
+    // their posts, are handed over or go along with them as `content` says.
+    // Deletes nothing when `content` is `Refuse` and the user still owns
+    // posts or comments outside the trash, or when the user changed since
+    // `expected`.
+    async fn delete(
+        &self,
+        id: Uuid,
+        content: OwnedContent,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error>;
+}
 
 #[derive(Debug, Clone)]
-pub struct UserRepository {
+pub struct MySqlUserRepository {
     pool: MySqlPool,
 }
 
-impl UserRepository {
+impl MySqlUserRepository {
     pub fn new(pool: MySqlPool) -> Self {
-        UserRepository { pool }
+        MySqlUserRepository { pool }
     }
 }
 
-impl UserRepository {
+#[async_trait]
+impl UserRepository for MySqlUserRepository {
     // Find all users
-    pub async fn find_all(&self) -> Result<UserListResponse, sqlx::Error> {
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<UserListResponse, sqlx::Error> {
         let users = sqlx::query_as!(
-            UserResponse,
+            User,
             r#"
-            SELECT id AS 'id:Uuid', username, email, role_id AS 'role_id:Uuid'
+            SELECT id AS 'id: Uuid', username, email, password_hash, role_id AS 'role_id: Uuid',
+                created_at, updated_at, version
             FROM users
+            WHERE deleted_at IS NULL
             "#
         )
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(UserListResponse { users })
+        Ok(UserListResponse {
+            users: users.into_iter().map(UserResponse::from).collect(),
+        })
     }
 
     // Create User
-    pub async fn create(
+    #[instrument(level = "debug", skip(self, password))]
+    async fn create(
         &self,
         username: &str,
         email: &str,
@@ -39,67 +119,167 @@ impl UserRepository {
         role_id: Uuid,
     ) -> Result<UserResponse, sqlx::Error> {
         let id = Uuid::new_v4();
-        let id_bytes = id.as_bytes().to_vec();
-        let role_id_bytes = role_id.as_bytes().to_vec();
-        sqlx::query!(
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
             r#"
             INSERT INTO users (id, username, email, password_hash, role_id)
             VALUES (?, ?, ?, ?, ?)
             "#,
-            id_bytes,
-            username,
-            email,
-            password,
-            role_id_bytes
         )
-        .execute(&self.pool)
+        .bind(id.as_bytes().to_vec())
+        .bind(username)
+        .bind(email)
+        .bind(password)
+        .bind(role_id.as_bytes().to_vec())
+        .execute(&mut *tx)
         .await?;
-        let response = UserResponse {
+        let user: UserResponse = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id AS 'id: Uuid', username, email, password_hash, role_id AS 'role_id: Uuid',
+                created_at, updated_at, version
+            FROM users
+            WHERE id = ?
+            "#,
+            id.as_bytes().to_vec()
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        insert_events(&mut tx, &[OutboxEvent::UserCreated], user.id, &user).await?;
+        let entry = audit_entry(
+            AuditAction::Create,
+            AuditTargetType::User,
             id,
-            username: username.to_string(),
-            email: email.to_string(),
-            role_id,
-        };
-        Ok(response)
+            None,
+            Some(&user),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(user)
     }
 
     // Find user by id
-    pub async fn find_by_id(&self, id: Uuid) -> Result<UserResponse, sqlx::Error> {
-        let id_bytes = id.as_bytes().to_vec();
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<UserResponse, sqlx::Error> {
         let user = sqlx::query_as!(
-            UserResponse,
+            User,
             r#"
-            SELECT id AS 'id:Uuid', username, email, role_id AS 'role_id:Uuid'
+            SELECT id AS 'id: Uuid', username, email, password_hash, role_id AS 'role_id: Uuid',
+                created_at, updated_at, version
             FROM users
-            WHERE id = ?
+            WHERE id = ? AND deleted_at IS NULL
             "#,
-            id_bytes
+            id.as_bytes().to_vec()
         )
         .fetch_one(&self.pool)
         .await?;
-        Ok(user)
+        Ok(user.into())
+    }
+
+    // Find user by username or email
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_login(&self, login: &str) -> Result<UserResponse, sqlx::Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id AS 'id: Uuid', username, email, password_hash, role_id AS 'role_id: Uuid',
+                created_at, updated_at, version
+            FROM users
+            WHERE (username = ? OR email = ?) AND deleted_at IS NULL
+            "#,
+            login,
+            login
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(user.into())
+    }
+
+    // Find users by ids, skipping ids that don't exist
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<UserResponse>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query =
+            QueryBuilder::<MySql>::new("SELECT * FROM users WHERE deleted_at IS NULL AND id IN (");
+        let mut separated = query.separated(", ");
+        for id in ids {
+            separated.push_bind(id.as_bytes().to_vec());
+        }
+        separated.push_unseparated(")");
+        let users = query.build_query_as::<User>().fetch_all(&self.pool).await?;
+        Ok(users.into_iter().map(UserResponse::from).collect())
+    }
+
+    // Find the password hash of a user
+    #[instrument(level = "debug", skip(self))]
+    async fn find_password_hash(&self, id: Uuid) -> Result<String, sqlx::Error> {
+        sqlx::query_scalar("SELECT password_hash FROM users WHERE id = ? AND deleted_at IS NULL")
+            .bind(id.as_bytes().to_vec())
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    // Find the session version of a user
+    #[instrument(level = "debug", skip(self))]
+    async fn find_session_version(&self, id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT session_version FROM users WHERE id = ? AND deleted_at IS NULL")
+            .bind(id.as_bytes().to_vec())
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    // Find the id of a user in the trash by username or email
+    #[instrument(level = "debug", skip(self))]
+    async fn find_trashed_by_login(&self, login: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        let id_bytes: Option<Vec<u8>> = sqlx::query_scalar(
+            r#"
+            SELECT id
+            FROM users
+            WHERE (username = ? OR email = ?) AND deleted_at IS NOT NULL
+            LIMIT 1
+            "#,
+        )
+        .bind(login)
+        .bind(login)
+        .fetch_optional(&self.pool)
+        .await?;
+        id_bytes
+            .map(|bytes| Uuid::from_slice(&bytes).map_err(|e| sqlx::Error::Decode(e.into())))
+            .transpose()
     }
 
     // Update user
-    pub async fn update(
+    #[instrument(level = "debug", skip(self))]
+    async fn update(
         &self,
         id: Uuid,
         username: Option<String>,
         email: Option<String>,
         role_id: Option<Uuid>,
-    ) -> Result<UserResponse, sqlx::Error> {
-        // Check if the user exists
-        self.find_by_id(id)
-            .await
-            .map_err(|_| sqlx::Error::RowNotFound)?;
-
+        expected: Option<i64>,
+    ) -> Result<Option<UserResponse>, sqlx::Error> {
         let id_bytes = id.as_bytes().to_vec();
-        let role_id_bytes = match role_id {
-            Some(role_id) => Some(role_id.as_bytes().to_vec()),
-            None => None,
-        };
-        sqlx::query_as!(
-            UserResponse,
+        let mut tx = self.pool.begin().await?;
+        let before: UserResponse = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id AS 'id: Uuid', username, email, password_hash, role_id AS 'role_id: Uuid',
+                created_at, updated_at, version
+            FROM users
+            WHERE id = ? AND deleted_at IS NULL FOR UPDATE
+            "#,
+            &id_bytes
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(None);
+        }
+        sqlx::query(
             r#"
             UPDATE users
             SET username = COALESCE(?, username),
@@ -107,34 +287,166 @@ impl UserRepository {
                 role_id = COALESCE(?, role_id)
             WHERE id = ?
             "#,
-            username,
-            email,
-            role_id_bytes,
-            id_bytes
         )
-        .execute(&self.pool)
+        .bind(username)
+        .bind(email)
+        .bind(role_id.map(|role_id| role_id.as_bytes().to_vec()))
+        .bind(&id_bytes)
+        .execute(&mut *tx)
         .await?;
-        let user = self.find_by_id(id).await?;
-        Ok(user)
+        let user: UserResponse = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id AS 'id: Uuid', username, email, password_hash, role_id AS 'role_id: Uuid',
+                created_at, updated_at, version
+            FROM users
+            WHERE id = ?
+            "#,
+            &id_bytes
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        let entry = audit_entry(
+            AuditAction::Update,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            Some(&user),
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(Some(user))
     }
 
-    // Delete user
-    pub async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
-        // Check if the user exists
-        self.find_by_id(id)
-            .await
-            .map_err(|_| sqlx::Error::RowNotFound)?;
+    // Update user password hash
+    #[instrument(level = "debug", skip(self, password_hash))]
+    async fn update_password(&self, id: Uuid, password_hash: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = ?, session_version = session_version + 1
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(password_hash)
+        .bind(id.as_bytes().to_vec())
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        sqlx::query(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = ?
+            WHERE user_id = ? AND used_at IS NULL
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(id.as_bytes().to_vec())
+        .execute(&mut *tx)
+        .await?;
+        // Password hashes are never snapshotted
+        let entry = audit_entry::<()>(AuditAction::Update, AuditTargetType::User, id, None, None);
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await
+    }
 
+    // Move user to the trash, handing over or trashing their content
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(
+        &self,
+        id: Uuid,
+        content: OwnedContent,
+        expected: Option<i64>,
+    ) -> Result<DeleteOutcome, sqlx::Error> {
         let id_bytes = id.as_bytes().to_vec();
-        sqlx::query!(
+        let deleted_at = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let before: UserResponse = sqlx::query_as!(
+            User,
             r#"
-            DELETE FROM users
-            WHERE id = ?
+            SELECT id AS 'id: Uuid', username, email, password_hash, role_id AS 'role_id: Uuid',
+                created_at, updated_at, version
+            FROM users
+            WHERE id = ? AND deleted_at IS NULL FOR UPDATE
             "#,
-            id_bytes
+            &id_bytes
         )
-        .execute(&self.pool)
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+        if expected.is_some_and(|expected| expected != before.version) {
+            return Ok(DeleteOutcome::Modified);
+        }
+        sqlx::query("UPDATE users SET deleted_at = ? WHERE id = ?")
+            .bind(deleted_at)
+            .bind(&id_bytes)
+            .execute(&mut *tx)
+            .await?;
+        match content {
+            OwnedContent::Refuse => {
+                let posts: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM posts WHERE user_id = ? AND deleted_at IS NULL",
+                )
+                .bind(&id_bytes)
+                .fetch_one(&mut *tx)
+                .await?;
+                let comments: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM comments WHERE user_id = ? AND deleted_at IS NULL",
+                )
+                .bind(&id_bytes)
+                .fetch_one(&mut *tx)
+                .await?;
+                if posts + comments > 0 {
+                    // Dropping the transaction takes the user back out of the trash
+                    return Ok(DeleteOutcome::InUse);
+                }
+            }
+            OwnedContent::ReassignTo(new_owner) => {
+                let new_owner_bytes = new_owner.as_bytes().to_vec();
+                sqlx::query("UPDATE posts SET user_id = ? WHERE user_id = ?")
+                    .bind(&new_owner_bytes)
+                    .bind(&id_bytes)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("UPDATE comments SET user_id = ? WHERE user_id = ?")
+                    .bind(&new_owner_bytes)
+                    .bind(&id_bytes)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            OwnedContent::Cascade => {}
+        }
+        sqlx::query(
+            r#"
+            UPDATE comments
+            SET deleted_at = ?
+            WHERE deleted_at IS NULL
+                AND (user_id = ? OR post_id IN (SELECT id FROM posts WHERE user_id = ?))
+            "#,
+        )
+        .bind(deleted_at)
+        .bind(&id_bytes)
+        .bind(&id_bytes)
+        .execute(&mut *tx)
         .await?;
-        Ok(())
+        sqlx::query("UPDATE posts SET deleted_at = ? WHERE user_id = ? AND deleted_at IS NULL")
+            .bind(deleted_at)
+            .bind(&id_bytes)
+            .execute(&mut *tx)
+            .await?;
+        let entry = audit_entry(
+            AuditAction::Delete,
+            AuditTargetType::User,
+            id,
+            Some(&before),
+            None,
+        );
+        insert_audit(&mut tx, &entry).await?;
+        tx.commit().await?;
+        Ok(DeleteOutcome::Deleted)
     }
 }
@@ -0,0 +1,395 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    entities::{join_events, Webhook, WebhookDelivery},
+    models::{DeliveryStatus, UpdateWebhook, WebhookEvent},
+};
+
+pub(crate) const WEBHOOK_COLUMNS: &str = "id, url, secret, events, active, created_at, updated_at";
+pub(crate) const DELIVERY_COLUMNS: &str = "id, webhook_id, event, payload, status, attempts, \
+    response_code, error, next_attempt_at, delivered_at, created_at";
+
+// Outcome of one delivery attempt
+#[derive(Debug, Clone)]
+pub struct DeliveryAttempt {
+    pub status: DeliveryStatus,
+    pub response_code: Option<i32>,
+    pub error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[async_trait]
+pub trait WebhookRepository: Debug + Send + Sync {
+    // Create webhook
+    async fn create(
+        &self,
+        url: &str,
+        secret: &str,
+        events: &[WebhookEvent],
+        active: bool,
+    ) -> Result<Webhook, sqlx::Error>;
+
+    // Find all webhooks
+    async fn find_all(&self) -> Result<Vec<Webhook>, sqlx::Error>;
+
+    // Find webhook by id
+    async fn find_by_id(&self, id: Uuid) -> Result<Webhook, sqlx::Error>;
+
+    // Update webhook, leaving fields that are not provided unchanged
+    async fn update(&self, id: Uuid, webhook: UpdateWebhook) -> Result<Webhook, sqlx::Error>;
+
+    // Delete webhook along with its deliveries
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error>;
+
+    // Queue a delivery of `payload` to a webhook
+    async fn create_delivery(
+        &self,
+        webhook_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<WebhookDelivery, sqlx::Error>;
+
+    // Queue a delivery of outbox message `outbox_id` to a webhook, unless one
+    // was queued for it already
+    async fn create_outbox_delivery(
+        &self,
+        webhook_id: Uuid,
+        outbox_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error>;
+
+    // Find one delivery of a webhook
+    async fn find_delivery(
+        &self,
+        webhook_id: Uuid,
+        id: Uuid,
+    ) -> Result<WebhookDelivery, sqlx::Error>;
+
+    // Find the latest deliveries of a webhook, newest first
+    async fn find_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error>;
+
+    // Find pending deliveries whose next attempt is due, oldest first
+    async fn find_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error>;
+
+    // Push a due delivery's next attempt back to `lease_until`, returning false
+    // when another worker claimed it first
+    async fn claim_delivery(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error>;
+
+    // Record the outcome of an attempt and count it
+    async fn record_attempt(&self, id: Uuid, attempt: DeliveryAttempt) -> Result<(), sqlx::Error>;
+
+    // Count the deliveries still waiting to succeed or give up
+    async fn count_pending(&self) -> Result<i64, sqlx::Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MySqlWebhookRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlWebhookRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for MySqlWebhookRepository {
+    // Create webhook
+    #[instrument(level = "debug", skip(self, secret))]
+    async fn create(
+        &self,
+        url: &str,
+        secret: &str,
+        events: &[WebhookEvent],
+        active: bool,
+    ) -> Result<Webhook, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let id_bytes = id.as_bytes().to_vec();
+        let events = join_events(events);
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, url, secret, events, active)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id_bytes)
+        .bind(url)
+        .bind(secret)
+        .bind(events)
+        .bind(active)
+        .execute(&self.pool)
+        .await?;
+        self.find_by_id(id).await
+    }
+
+    // Find all webhooks
+    #[instrument(level = "debug", skip(self))]
+    async fn find_all(&self) -> Result<Vec<Webhook>, sqlx::Error> {
+        sqlx::query_as::<_, Webhook>(&format!(
+            "SELECT {} FROM webhooks ORDER BY created_at",
+            WEBHOOK_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Find webhook by id
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: Uuid) -> Result<Webhook, sqlx::Error> {
+        sqlx::query_as::<_, Webhook>(&format!(
+            "SELECT {} FROM webhooks WHERE id = ?",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(id.as_bytes().to_vec())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // Update webhook, leaving fields that are not provided unchanged
+    #[instrument(level = "debug", skip(self, webhook))]
+    async fn update(&self, id: Uuid, webhook: UpdateWebhook) -> Result<Webhook, sqlx::Error> {
+        let id_bytes = id.as_bytes().to_vec();
+        let events = webhook.events.as_deref().map(join_events);
+        sqlx::query(
+            r#"
+            UPDATE webhooks
+            SET
+                url = COALESCE(?, url),
+                secret = COALESCE(?, secret),
+                events = COALESCE(?, events),
+                active = COALESCE(?, active)
+            WHERE id = ?
+            "#,
+        )
+        .bind(webhook.url)
+        .bind(webhook.secret)
+        .bind(events)
+        .bind(webhook.active)
+        .bind(id_bytes)
+        .execute(&self.pool)
+        .await?;
+        self.find_by_id(id).await
+    }
+
+    // Delete webhook along with its deliveries
+    #[instrument(level = "debug", skip(self))]
+    async fn delete(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        let id_bytes = id.as_bytes().to_vec();
+        let result = sqlx::query(
+            r#"
+            DELETE FROM webhooks
+            WHERE id = ?
+            "#,
+        )
+        .bind(id_bytes)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    // Queue a delivery of `payload` to a webhook
+    #[instrument(level = "debug", skip(self, payload))]
+    async fn create_delivery(
+        &self,
+        webhook_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let id_bytes = id.as_bytes().to_vec();
+        let webhook_id_bytes = webhook_id.as_bytes().to_vec();
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries (id, webhook_id, event, payload, next_attempt_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id_bytes)
+        .bind(webhook_id_bytes)
+        .bind(event.as_str())
+        .bind(payload)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+        self.find_delivery(webhook_id, id).await
+    }
+
+    // Queue a delivery of outbox message `outbox_id` to a webhook, unless one
+    // was queued for it already
+    #[instrument(level = "debug", skip(self, payload))]
+    async fn create_outbox_delivery(
+        &self,
+        webhook_id: Uuid,
+        outbox_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+                (id, webhook_id, outbox_id, event, payload, next_attempt_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE id = id
+            "#,
+        )
+        .bind(Uuid::new_v4().as_bytes().to_vec())
+        .bind(webhook_id.as_bytes().to_vec())
+        .bind(outbox_id.as_bytes().to_vec())
+        .bind(event.as_str())
+        .bind(payload)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Find one delivery of a webhook
+    #[instrument(level = "debug", skip(self))]
+    async fn find_delivery(
+        &self,
+        webhook_id: Uuid,
+        id: Uuid,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE id = ? AND webhook_id = ?",
+            DELIVERY_COLUMNS
+        ))
+        .bind(id.as_bytes().to_vec())
+        .bind(webhook_id.as_bytes().to_vec())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // Find the latest deliveries of a webhook, newest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE webhook_id = ? \
+             ORDER BY created_at DESC LIMIT ?",
+            DELIVERY_COLUMNS
+        ))
+        .bind(webhook_id.as_bytes().to_vec())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Find pending deliveries whose next attempt is due, oldest first
+    #[instrument(level = "debug", skip(self))]
+    async fn find_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE status = 'pending' AND next_attempt_at <= ? \
+             ORDER BY next_attempt_at LIMIT ?",
+            DELIVERY_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Push a due delivery's next attempt back to `lease_until`, returning false
+    // when another worker claimed it first
+    #[instrument(level = "debug", skip(self))]
+    async fn claim_delivery(
+        &self,
+        id: Uuid,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let id_bytes = id.as_bytes().to_vec();
+        let result = sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET next_attempt_at = ?
+            WHERE id = ? AND status = 'pending' AND next_attempt_at <= ?
+            "#,
+        )
+        .bind(lease_until)
+        .bind(id_bytes)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    // Record the outcome of an attempt and count it
+    #[instrument(level = "debug", skip(self, attempt))]
+    async fn record_attempt(&self, id: Uuid, attempt: DeliveryAttempt) -> Result<(), sqlx::Error> {
+        let id_bytes = id.as_bytes().to_vec();
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries
+            SET
+                status = ?,
+                attempts = attempts + 1,
+                response_code = ?,
+                error = ?,
+                next_attempt_at = ?,
+                delivered_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(attempt.status.as_str())
+        .bind(attempt.response_code)
+        .bind(attempt.error)
+        .bind(attempt.next_attempt_at)
+        .bind(attempt.delivered_at)
+        .bind(id_bytes)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Count the deliveries still waiting to succeed or give up
+    #[instrument(level = "debug", skip(self))]
+    async fn count_pending(&self) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) AS count
+            FROM webhook_deliveries
+            WHERE status = 'pending'
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+}
@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+
+use crate::{handlers::get_audit_log, services::ServiceContainer};
+
+pub fn create_audit_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/", get(get_audit_log))
+        .with_state(services)
+}
@@ -0,0 +1,15 @@
+use axum::{routing::post, Router};
+
+use crate::{
+    handlers::{change_password, forgot_password, login, reset_password},
+    services::ServiceContainer,
+};
+
+pub fn create_auth_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/login", post(login))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route("/password/change", post(change_password))
+        .with_state(services)
+}
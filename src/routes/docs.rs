@@ -0,0 +1,11 @@
+use axum::Router;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{config::FeatureConfig, handlers::create_openapi_spec};
+
+// The OpenAPI document and a Swagger UI to browse it, both bundled into the binary
+pub fn create_docs_routes(features: &FeatureConfig) -> Router {
+    SwaggerUi::new("/api/docs")
+        .url("/api/openapi.json", create_openapi_spec(features))
+        .into()
+}
@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+
+use crate::{handlers::stream_events, services::ServiceContainer};
+
+pub fn create_event_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/", get(stream_events))
+        .with_state(services)
+}
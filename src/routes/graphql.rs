@@ -0,0 +1,19 @@
+use axum::{routing::get, Router};
+
+use crate::{
+    config::GraphqlConfig,
+    graphql::build_schema,
+    handlers::{graphiql, graphql_handler, graphql_ws, GraphqlState},
+    services::ServiceContainer,
+};
+
+pub fn create_graphql_routes(services: ServiceContainer, config: &GraphqlConfig) -> Router {
+    let state = GraphqlState {
+        auth_service: services.auth_service.clone(),
+        schema: build_schema(services, config),
+    };
+    Router::new()
+        .route("/", get(graphiql).post(graphql_handler))
+        .route("/ws", get(graphql_ws))
+        .with_state(state)
+}
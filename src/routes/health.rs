@@ -1,7 +1,14 @@
 use axum::{routing::get, Router};
 
-use crate::handlers::check_app_health;
+use crate::{
+    handlers::{check_app_health, check_app_liveness, check_app_readiness},
+    services::ServiceContainer,
+};
 
-pub fn create_health_routes() -> Router {
-    Router::new().route("/", get(check_app_health))
+pub fn create_health_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/", get(check_app_health))
+        .route("/live", get(check_app_liveness))
+        .route("/ready", get(check_app_readiness))
+        .with_state(services)
 }
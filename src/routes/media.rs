@@ -0,0 +1,9 @@
+use axum::{routing::post, Router};
+
+use crate::{handlers::create_media, services::ServiceContainer};
+
+pub fn create_media_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/", post(create_media))
+        .with_state(services)
+}
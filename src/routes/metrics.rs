@@ -0,0 +1,9 @@
+use axum::{routing::get, Router};
+
+use crate::{handlers::get_metrics, services::ServiceContainer};
+
+pub fn create_metrics_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .with_state(services)
+}
@@ -1,23 +1,175 @@
-use axum::Router;
+use audit::create_audit_routes;
+use auth::create_auth_routes;
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware, Router,
+};
+use docs::create_docs_routes;
+use events::create_event_routes;
+use graphql::create_graphql_routes;
 use health::create_health_routes;
+use media::create_media_routes;
+use metrics::create_metrics_routes;
 use role::create_role_routes;
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    timeout::TimeoutLayer,
+    trace::{DefaultOnFailure, DefaultOnResponse, TraceLayer},
+};
+use tracing::Level;
+use trash::create_trash_routes;
+use webhook::create_webhook_routes;
 
-use crate::services::ServiceContainer;
+use crate::{
+    audit::ContextSources,
+    config::{Config, CorsConfig},
+    proxy::TrustedProxies,
+    ratelimit::{self, RateLimiter},
+    services::ServiceContainer,
+    telemetry::{self, REQUEST_ID_HEADER},
+};
 
+mod audit;
+mod auth;
+mod docs;
+mod events;
+mod graphql;
 mod health;
+mod media;
+mod metrics;
 mod role;
 mod user;
 mod post;
+mod trash;
+mod webhook;
 
-pub fn create_api_routes(services: ServiceContainer) -> Router {
+pub fn create_api_routes(services: ServiceContainer, config: &Config) -> Router {
     let role_routes = Router::new().nest("/role", create_role_routes(services.clone()));
-    let health_routes = Router::new().nest("/health", create_health_routes());
-    let user_routes = Router::new().nest("/user", user::create_user_routes(services.clone()));
+    let health_routes = Router::new().nest("/health", create_health_routes(services.clone()));
+    let user_routes = Router::new().nest(
+        "/user",
+        user::create_user_routes(services.clone(), config.features()),
+    );
     let post_routes = Router::new().nest("/post", post::create_post_routes(services.clone()));
+    let media_routes = Router::new().nest("/media", create_media_routes(services.clone()));
+    let auth_routes = Router::new().nest("/auth", create_auth_routes(services.clone()));
+    let webhook_routes = Router::new().nest("/webhook", create_webhook_routes(services.clone()));
+    let event_routes = Router::new().nest("/events", create_event_routes(services.clone()));
+    let audit_routes = Router::new().nest("/audit", create_audit_routes(services.clone()));
+    let trash_routes = Router::new().nest("/trash", create_trash_routes(services.clone()));
     let merged_routes = Router::new()
         .merge(role_routes)
-        .merge(health_routes)
         .merge(user_routes)
-        .merge(post_routes);
-    Router::new().nest("/api", merged_routes)
+        .merge(post_routes)
+        .merge(media_routes)
+        .merge(auth_routes)
+        .merge(webhook_routes)
+        .merge(event_routes)
+        .merge(audit_routes)
+        .merge(trash_routes);
+    let merged_routes = if config.features().graphql {
+        merged_routes.nest(
+            "/graphql",
+            create_graphql_routes(services.clone(), config.graphql()),
+        )
+    } else {
+        merged_routes
+    };
+    // Every route but the health checks counts against the caller's quota
+    let merged_routes = match RateLimiter::from_config(
+        config.rate_limit(),
+        services.auth_service.clone(),
+    ) {
+        Some(limiter) => {
+            merged_routes.route_layer(middleware::from_fn_with_state(limiter, ratelimit::limit))
+        }
+        None => merged_routes,
+    };
+    let merged_routes = merged_routes.merge(health_routes);
+    // Changes made by API requests are audited with who made them and from where
+    let audit_sources = ContextSources {
+        auth: services.auth_service.clone(),
+        trusted_proxies: TrustedProxies::new(&config.rate_limit().trusted_proxies),
+    };
+    let merged_routes = merged_routes.layer(middleware::from_fn_with_state(
+        audit_sources,
+        crate::audit::record_context,
+    ));
+    let mut router = Router::new().nest("/api", merged_routes);
+    if config.features().metrics {
+        router = router.merge(create_metrics_routes(services));
+    }
+    if config.features().docs {
+        router = router.merge(create_docs_routes(config.features()));
+    }
+    let router = router
+        .layer(DefaultBodyLimit::max(config.server().upload_limit_bytes))
+        .layer(TimeoutLayer::new(config.server().request_timeout()));
+    // Keep a client's reads on the primary right after its own writes
+    let router = if config.database().replica_urls.is_empty() {
+        router
+    } else {
+        router.layer(middleware::from_fn_with_state(
+            config.database().read_your_writes(),
+            crate::db::route_reads,
+        ))
+    };
+    let router = match create_cors_layer(config.cors()) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    let router = router.layer(middleware::from_fn(crate::metrics::track_http));
+
+    // Layers run outermost-last: the request id is assigned first, so the
+    // trace span and the response can both carry it.
+    let request_id_header = HeaderName::from_static(REQUEST_ID_HEADER);
+    router
+        .layer(PropagateRequestIdLayer::new(request_id_header.clone()))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::make_request_span)
+                .on_response(DefaultOnResponse::new().level(Level::INFO))
+                .on_failure(DefaultOnFailure::new().level(Level::ERROR)),
+        )
+        .layer(SetRequestIdLayer::new(request_id_header, MakeRequestUuid))
+}
+
+// Cross-origin requests are only allowed when origins are configured
+fn create_cors_layer(cors: &CorsConfig) -> Option<CorsLayer> {
+    if cors.allowed_origins.is_empty() {
+        return None;
+    }
+    let allow_origin = if cors.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            cors.allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    let layer = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::ACCEPT,
+            header::IF_MATCH,
+            header::IF_NONE_MATCH,
+            header::IF_MODIFIED_SINCE,
+        ])
+        .expose_headers([
+            header::ETAG,
+            header::LAST_MODIFIED,
+            header::RETRY_AFTER,
+            ratelimit::RATELIMIT_LIMIT,
+            ratelimit::RATELIMIT_REMAINING,
+            ratelimit::RATELIMIT_RESET,
+        ])
+        .allow_credentials(cors.allow_credentials)
+        .max_age(std::time::Duration::from_secs(cors.max_age_secs));
+    Some(layer)
 }
@@ -4,7 +4,9 @@ use axum::{
 };
 
 use crate::{
-    handlers::{create_post, delete_post_by_id, get_post_by_id, get_posts, update_post_by_id},
+    handlers::{
+        create_post, delete_post_by_id, get_post_by_id, get_posts, search_posts, update_post_by_id,
+    },
     services::ServiceContainer,
 };
 
@@ -12,6 +14,7 @@ pub fn create_post_routes(services: ServiceContainer) -> Router {
     Router::new()
         .route("/", post(create_post))
         .route("/", get(get_posts))
+        .route("/search", get(search_posts))
         .route("/:id", get(get_post_by_id))
         .route("/:id", put(update_post_by_id))
         .route("/:id", delete(delete_post_by_id))
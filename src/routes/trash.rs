@@ -0,0 +1,17 @@
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::{
+    handlers::{get_trash, purge_trash_item, restore_trash_item},
+    services::ServiceContainer,
+};
+
+pub fn create_trash_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/", get(get_trash))
+        .route("/:item_type/:id/restore", post(restore_trash_item))
+        .route("/:item_type/:id", delete(purge_trash_item))
+        .with_state(services)
+}
@@ -4,17 +4,23 @@ use axum::{
 };
 
 use crate::{
+    config::FeatureConfig,
     handlers::{create_user, delete_user_by_id, get_posts_by_user_id, get_user_by_id, get_users, update_user_by_id},
     services::ServiceContainer,
 };
 
-pub fn create_user_routes(services: ServiceContainer) -> Router {
-    Router::new()
-        .route("/", post(create_user))
+pub fn create_user_routes(services: ServiceContainer, features: &FeatureConfig) -> Router {
+    let router = Router::new()
         .route("/", get(get_users))
         .route("/:id", get(get_user_by_id))
         .route("/:id", put(update_user_by_id))
         .route("/:id", delete(delete_user_by_id))
-        .route("/:id/posts", get(get_posts_by_user_id))
-        .with_state(services)
+        .route("/:id/posts", get(get_posts_by_user_id));
+    // Public sign-up can be switched off, leaving user creation to the admin CLI
+    let router = if features.registration {
+        router.route("/", post(create_user))
+    } else {
+        router
+    };
+    router.with_state(services)
 }
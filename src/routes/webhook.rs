@@ -0,0 +1,27 @@
+use axum::{
+    routing::{delete, get, post, put},
+    Router,
+};
+
+use crate::{
+    handlers::{
+        create_webhook, delete_webhook_by_id, get_webhook_by_id, get_webhook_deliveries,
+        get_webhooks, redeliver_webhook_delivery, update_webhook_by_id,
+    },
+    services::ServiceContainer,
+};
+
+pub fn create_webhook_routes(services: ServiceContainer) -> Router {
+    Router::new()
+        .route("/", post(create_webhook))
+        .route("/", get(get_webhooks))
+        .route("/:id", get(get_webhook_by_id))
+        .route("/:id", put(update_webhook_by_id))
+        .route("/:id", delete(delete_webhook_by_id))
+        .route("/:id/deliveries", get(get_webhook_deliveries))
+        .route(
+            "/:id/deliveries/:delivery_id/redeliver",
+            post(redeliver_webhook_delivery),
+        )
+        .with_state(services)
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use tracing::instrument;
+
+use crate::{
+    models::{AuditListResponse, AuditQuery},
+    repositories::{AuditFilter, AuditRepository},
+};
+
+// Entries listed when no limit is given, and the most that can be asked for
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone)]
+pub struct AuditService {
+    audit_repo: Arc<dyn AuditRepository>,
+}
+
+impl AuditService {
+    pub fn new(audit_repo: Arc<dyn AuditRepository>) -> Self {
+        Self { audit_repo }
+    }
+}
+
+impl AuditService {
+    // Find recorded changes, newest first
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find(&self, query: AuditQuery) -> Result<AuditListResponse, sqlx::Error> {
+        let filter = AuditFilter {
+            actor_id: query.actor_id,
+            target_type: query.target_type,
+            target_id: query.target_id,
+            from: query.from,
+            to: query.to,
+            limit: query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+        };
+        let entries = self.audit_repo.find(&filter).await?;
+        Ok(AuditListResponse {
+            entries: entries.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+impl AuditService {
+    // Every recorded entry, newest first
+    pub async fn entries(&self) -> Vec<crate::models::AuditEntryResponse> {
+        self.find(AuditQuery::default()).await.unwrap().entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        audit::{with_context, AuditContext},
+        models::{AuditAction, AuditTargetType},
+        repositories::{
+            memory::{InMemoryAuditRepository, InMemoryRoleRepository, InMemoryUserRepository},
+            RoleRepository, UserRepository,
+        },
+    };
+
+    #[tokio::test]
+    async fn entries_carry_the_request_context_and_snapshots() {
+        let audit_repo = Arc::new(InMemoryAuditRepository::new());
+        let roles = InMemoryRoleRepository::with_audit(audit_repo.clone());
+        let role = roles.create("editor", "Edits posts").await.unwrap();
+        let actor_id = Uuid::new_v4();
+        let context = AuditContext {
+            actor_id: Some(actor_id),
+            ip: Some(String::from("10.0.0.1")),
+            request_id: Some(String::from("req-1")),
+        };
+        with_context(
+            context,
+            roles.update(role.id, Some(String::from("author")), None, None),
+        )
+        .await
+        .unwrap();
+
+        let entries = AuditService::new(audit_repo).entries().await;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, AuditAction::Update);
+        assert_eq!(entries[0].actor_id, Some(actor_id));
+        assert_eq!(entries[0].ip.as_deref(), Some("10.0.0.1"));
+        assert_eq!(entries[0].request_id.as_deref(), Some("req-1"));
+        assert_eq!(entries[0].before.as_ref().unwrap()["role_name"], "editor");
+        assert_eq!(entries[0].after.as_ref().unwrap()["role_name"], "author");
+        // Changes made outside a request have no actor
+        assert_eq!(entries[1].actor_id, None);
+    }
+
+    #[tokio::test]
+    async fn entries_can_be_filtered_by_target() {
+        let audit_repo = Arc::new(InMemoryAuditRepository::new());
+        let roles = InMemoryRoleRepository::with_audit(audit_repo.clone());
+        let users = InMemoryUserRepository::with_audit(audit_repo.clone());
+        let role = roles.create("editor", "Edits posts").await.unwrap();
+        users
+            .create("alice", "alice@example.com", "hash", role.id)
+            .await
+            .unwrap();
+
+        let query = AuditQuery {
+            target_type: Some(AuditTargetType::Role),
+            ..AuditQuery::default()
+        };
+        let entries = AuditService::new(audit_repo)
+            .find(query)
+            .await
+            .unwrap()
+            .entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].target_id, role.id);
+    }
+}
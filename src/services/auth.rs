@@ -0,0 +1,239 @@
+use std::{fmt, sync::Arc};
+
+use bcrypt::BcryptError;
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{config::JwtConfig, models::TokenResponse, password, repositories::UserRepository};
+
+// Payload of the bearer tokens handed out at login
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: Uuid,
+    pub iss: String,
+    pub iat: i64,
+    pub exp: i64,
+    // The user's session version when the token was issued; tokens from
+    // before the latest password change are refused
+    #[serde(default)]
+    pub ver: i64,
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    // No JWT secret is configured, so tokens can be neither issued nor checked
+    Disabled,
+    InvalidCredentials,
+    InvalidToken,
+    Revoked,
+    Database(sqlx::Error),
+    Hash(BcryptError),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Disabled => f.write_str("authentication is not configured"),
+            AuthError::InvalidCredentials => f.write_str("invalid login or password"),
+            AuthError::InvalidToken => f.write_str("invalid or expired token"),
+            AuthError::Revoked => f.write_str("token was revoked by a password change"),
+            AuthError::Database(e) => write!(f, "database error: {}", e),
+            AuthError::Hash(e) => write!(f, "password hashing error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+struct TokenKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    validation: Validation,
+}
+
+#[derive(Clone)]
+pub struct AuthService {
+    user_repo: Arc<dyn UserRepository>,
+    keys: Option<Arc<TokenKeys>>,
+    issuer: String,
+    expiration_secs: u64,
+}
+
+impl fmt::Debug for AuthService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthService")
+            .field("user_repo", &self.user_repo)
+            .field("enabled", &self.keys.is_some())
+            .field("issuer", &self.issuer)
+            .field("expiration_secs", &self.expiration_secs)
+            .finish()
+    }
+}
+
+impl AuthService {
+    pub fn new(user_repo: Arc<dyn UserRepository>, jwt: &JwtConfig) -> Self {
+        let keys = jwt.secret.as_ref().map(|secret| {
+            let mut validation = Validation::new(Algorithm::HS256);
+            validation.set_issuer(&[&jwt.issuer]);
+            Arc::new(TokenKeys {
+                encoding: EncodingKey::from_secret(secret.expose().as_bytes()),
+                decoding: DecodingKey::from_secret(secret.expose().as_bytes()),
+                validation,
+            })
+        });
+        Self {
+            user_repo,
+            keys,
+            issuer: jwt.issuer.clone(),
+            expiration_secs: jwt.expiration_secs,
+        }
+    }
+}
+
+impl AuthService {
+    // Exchange a username or email and password for a bearer token
+    #[instrument(skip(self, password), err(Display, level = "warn"))]
+    pub async fn login(&self, login: &str, password: &str) -> Result<TokenResponse, AuthError> {
+        if self.keys.is_none() {
+            return Err(AuthError::Disabled);
+        }
+        let user = match self.user_repo.find_by_login(login).await {
+            Ok(user) => user,
+            Err(sqlx::Error::RowNotFound) => return Err(AuthError::InvalidCredentials),
+            Err(e) => return Err(AuthError::Database(e)),
+        };
+        let hash = self
+            .user_repo
+            .find_password_hash(user.id)
+            .await
+            .map_err(AuthError::Database)?;
+        if !password::verify(password, &hash).map_err(AuthError::Hash)? {
+            return Err(AuthError::InvalidCredentials);
+        }
+        self.issue(user.id).await
+    }
+
+    // Issue a bearer token for a user's current session
+    pub async fn issue(&self, user_id: Uuid) -> Result<TokenResponse, AuthError> {
+        let keys = self.keys.as_ref().ok_or(AuthError::Disabled)?;
+        let ver = match self.user_repo.find_session_version(user_id).await {
+            Ok(ver) => ver,
+            Err(sqlx::Error::RowNotFound) => return Err(AuthError::InvalidCredentials),
+            Err(e) => return Err(AuthError::Database(e)),
+        };
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id,
+            iss: self.issuer.clone(),
+            iat: now,
+            exp: now + self.expiration_secs as i64,
+            ver,
+        };
+        // HMAC signing with a key of the matching family cannot fail
+        let access_token = jsonwebtoken::encode(&Header::default(), &claims, &keys.encoding)
+            .expect("token signing failed");
+        Ok(TokenResponse {
+            access_token,
+            token_type: String::from("Bearer"),
+            expires_in: self.expiration_secs,
+        })
+    }
+
+    // Check a bearer token's signature, issuer and expiry
+    pub fn authenticate(&self, token: &str) -> Result<Claims, AuthError> {
+        let keys = self.keys.as_ref().ok_or(AuthError::Disabled)?;
+        jsonwebtoken::decode::<Claims>(token, &keys.decoding, &keys.validation)
+            .map(|data| data.claims)
+            .map_err(|_| AuthError::InvalidToken)
+    }
+
+    // Check a bearer token like `authenticate`, and that no password change
+    // has revoked it since. Tokens of users that no longer exist are invalid.
+    pub async fn verify(&self, token: &str) -> Result<Claims, AuthError> {
+        let claims = self.authenticate(token)?;
+        match self.user_repo.find_session_version(claims.sub).await {
+            Ok(ver) if ver == claims.ver => Ok(claims),
+            Ok(_) => Err(AuthError::Revoked),
+            Err(sqlx::Error::RowNotFound) => Err(AuthError::InvalidToken),
+            Err(e) => Err(AuthError::Database(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Secret, repositories::memory::InMemoryUserRepository};
+
+    async fn service(secret: Option<&str>) -> AuthService {
+        let repo = Arc::new(InMemoryUserRepository::new());
+        let hash = bcrypt::hash("hunter2", 4).unwrap();
+        repo.create("alice", "alice@example.com", &hash, Uuid::new_v4())
+            .await
+            .unwrap();
+        let jwt = JwtConfig {
+            secret: secret.map(|secret| Secret::new(secret.to_string())),
+            expiration_secs: 60,
+            issuer: String::from("blog-cms"),
+        };
+        AuthService::new(repo, &jwt)
+    }
+
+    #[tokio::test]
+    async fn issued_tokens_authenticate_the_user() {
+        let service = service(Some("0123456789abcdef0123456789abcdef")).await;
+
+        let token = service.login("alice@example.com", "hunter2").await.unwrap();
+
+        let claims = service.authenticate(&token.access_token).unwrap();
+        let user = service.user_repo.find_by_login("alice").await.unwrap();
+        assert_eq!(claims.sub, user.id);
+        assert!(matches!(
+            service.login("alice", "wrong").await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            service.authenticate(&format!("{}x", token.access_token)),
+            Err(AuthError::InvalidToken)
+        ));
+    }
+
+    #[tokio::test]
+    async fn password_changes_revoke_earlier_tokens() {
+        let service = service(Some("0123456789abcdef0123456789abcdef")).await;
+        let token = service.login("alice", "hunter2").await.unwrap();
+        let claims = service.verify(&token.access_token).await.unwrap();
+
+        service
+            .user_repo
+            .update_password(claims.sub, "hash")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service.verify(&token.access_token).await,
+            Err(AuthError::Revoked)
+        ));
+        // Still a well-formed token, just no longer a valid session
+        assert!(service.authenticate(&token.access_token).is_ok());
+        let token = service.issue(claims.sub).await.unwrap();
+        assert!(service.verify(&token.access_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn tokens_need_a_secret() {
+        let service = service(None).await;
+
+        assert!(matches!(
+            service.login("alice", "hunter2").await,
+            Err(AuthError::Disabled)
+        ));
+        assert!(matches!(
+            service.authenticate("token"),
+            Err(AuthError::Disabled)
+        ));
+    }
+}
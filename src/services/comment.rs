@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{CommentResponse, CreateComment, EventTopic},
+    repositories::CommentRepository,
+    services::{EventService, OutboxRelay},
+};
+
+// Comments buffered per subscriber before a slow one starts missing some
+const SUBSCRIBER_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct CommentService {
+    comment_repo: Arc<dyn CommentRepository>,
+    created: broadcast::Sender<CommentResponse>,
+    outbox: OutboxRelay,
+    events: EventService,
+}
+
+impl CommentService {
+    pub fn new(
+        comment_repo: Arc<dyn CommentRepository>,
+        outbox: OutboxRelay,
+        events: EventService,
+    ) -> Self {
+        let (created, _) = broadcast::channel(SUBSCRIBER_CAPACITY);
+        Self {
+            comment_repo,
+            created,
+            outbox,
+            events,
+        }
+    }
+}
+
+impl CommentService {
+    // Create comment and announce it to subscribers
+    #[instrument(skip(self, comment), err(Display, level = "warn"))]
+    pub async fn create(&self, comment: CreateComment) -> Result<CommentResponse, sqlx::Error> {
+        let comment = self.comment_repo.create(comment).await?;
+        // Sending only fails when nobody is subscribed
+        let _ = self.created.send(comment.clone());
+        self.events
+            .publish(EventTopic::Comments, "comment.created", &comment, None);
+        // Webhooks hear of it from the outbox, where it was recorded with the comment
+        self.outbox.notify();
+        Ok(comment)
+    }
+
+    // Find comment by id
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_id(&self, id: Uuid) -> Result<CommentResponse, sqlx::Error> {
+        self.comment_repo.find_by_id(id).await
+    }
+
+    // Find the comments on several posts at once, oldest first
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_post_ids(
+        &self,
+        post_ids: &[Uuid],
+    ) -> Result<Vec<CommentResponse>, sqlx::Error> {
+        self.comment_repo.find_by_post_ids(post_ids).await
+    }
+
+    // Delete comment by id
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn delete_by_id(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        self.comment_repo.delete(id).await
+    }
+
+    // Comments created from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<CommentResponse> {
+        self.created.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{
+        entities::OutboxEvent,
+        repositories::{
+            memory::{InMemoryCommentRepository, InMemoryOutboxRepository},
+            OutboxRepository,
+        },
+    };
+
+    #[tokio::test]
+    async fn subscribers_receive_created_comments() {
+        let outbox = Arc::new(InMemoryOutboxRepository::new());
+        let service = CommentService::new(
+            Arc::new(InMemoryCommentRepository::with_outbox(outbox.clone())),
+            OutboxRelay::new(outbox.clone()),
+            EventService::in_memory(),
+        );
+        let mut created = service.subscribe();
+        let post_id = Uuid::new_v4();
+
+        let comment = service
+            .create(CreateComment {
+                content: String::from("First!"),
+                user_id: Uuid::new_v4(),
+                post_id,
+            })
+            .await
+            .unwrap();
+
+        let received = created.recv().await.unwrap();
+        assert_eq!(received.id, comment.id);
+        let on_post = service.find_by_post_ids(&[post_id]).await.unwrap();
+        assert_eq!(on_post.len(), 1);
+        // Webhooks are queued from the outbox
+        let recorded = outbox.find_due(Utc::now(), 10).await.unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].event, OutboxEvent::CommentCreated);
+        assert_eq!(recorded[0].aggregate_id, comment.id);
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::EventsConfig,
+    entities::{OutboxMessage, PostStatus},
+    models::{EventTopic, LiveEvent, PostResponse},
+    services::{outbox::OutboxSubscriber, post::fill_excerpt},
+    shutdown::Shutdown,
+};
+
+// Fans changes out to live event streams, keeping the most recent ones so a
+// client that reconnects can catch up on what it missed
+#[derive(Debug, Clone)]
+pub struct EventService {
+    replay: Arc<Mutex<Replay>>,
+    sender: broadcast::Sender<Arc<LiveEvent>>,
+    keepalive: Duration,
+    // Cancelled on shutdown so open streams end instead of holding up the
+    // drain
+    closed: CancellationToken,
+}
+
+#[derive(Debug)]
+struct Replay {
+    next_id: u64,
+    capacity: usize,
+    events: VecDeque<Arc<LiveEvent>>,
+}
+
+// What a new subscriber receives: the buffered events it missed, then
+// everything published afterwards
+#[derive(Debug)]
+pub struct EventSubscription {
+    pub missed: Vec<Arc<LiveEvent>>,
+    // False when some of the events after the requested one are no longer
+    // buffered, or were published by an earlier run of the process
+    pub complete: bool,
+    pub receiver: broadcast::Receiver<Arc<LiveEvent>>,
+    pub closed: CancellationToken,
+}
+
+impl EventService {
+    pub fn new(config: &EventsConfig) -> Self {
+        let (sender, _) = broadcast::channel(config.replay_capacity);
+        Self {
+            replay: Arc::new(Mutex::new(Replay {
+                next_id: 1,
+                capacity: config.replay_capacity,
+                events: VecDeque::with_capacity(config.replay_capacity),
+            })),
+            sender,
+            keepalive: config.keepalive(),
+            closed: CancellationToken::new(),
+        }
+    }
+}
+
+impl EventService {
+    // How often idle streams are sent a ping to keep them open
+    pub fn keepalive(&self) -> Duration {
+        self.keepalive
+    }
+
+    // End every open stream once shutdown starts
+    pub fn close_on_shutdown(&self, shutdown: &Shutdown) {
+        let closed = self.closed.clone();
+        shutdown.spawn_worker(|token| async move {
+            token.cancelled().await;
+            closed.cancel();
+        });
+    }
+
+    // Announce a change to `data`, visible to subscribers of `topic` that
+    // hold `permission`
+    pub fn publish(
+        &self,
+        topic: EventTopic,
+        event: &'static str,
+        data: &impl Serialize,
+        permission: Option<&'static str>,
+    ) {
+        let data = match serde_json::to_string(data) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(event, error = %e, "Failed to serialize live event");
+                return;
+            }
+        };
+        let mut replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        let event = Arc::new(LiveEvent {
+            id: replay.next_id,
+            topic,
+            event,
+            data,
+            permission,
+        });
+        replay.next_id += 1;
+        if replay.events.len() == replay.capacity {
+            replay.events.pop_front();
+        }
+        replay.events.push_back(event.clone());
+        // Sent under the lock so a subscriber never sees an event both
+        // replayed and live. Sending only fails when nobody is subscribed
+        let _ = self.sender.send(event);
+    }
+
+    // Subscribe to events published from now on, along with the buffered
+    // events after `last_event_id`
+    pub fn subscribe(&self, last_event_id: Option<u64>) -> EventSubscription {
+        let replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.sender.subscribe();
+        let closed = self.closed.clone();
+        let Some(last_event_id) = last_event_id else {
+            return EventSubscription {
+                missed: Vec::new(),
+                complete: true,
+                receiver,
+                closed,
+            };
+        };
+        let oldest = replay
+            .events
+            .front()
+            .map_or(replay.next_id, |event| event.id);
+        let complete = last_event_id < replay.next_id && last_event_id.saturating_add(1) >= oldest;
+        let missed = replay
+            .events
+            .iter()
+            .filter(|event| event.id > last_event_id)
+            .cloned()
+            .collect();
+        EventSubscription {
+            missed,
+            complete,
+            receiver,
+            closed,
+        }
+    }
+}
+
+// Post events come from the outbox, so only committed changes are announced
+#[async_trait]
+impl OutboxSubscriber for EventService {
+    fn name(&self) -> &'static str {
+        "events"
+    }
+
+    async fn handle(&self, message: &OutboxMessage) -> Result<(), sqlx::Error> {
+        // Users and comments are published as they are written
+        if !message.event.is_post_event() {
+            return Ok(());
+        }
+        let mut post: PostResponse =
+            serde_json::from_str(&message.payload).map_err(|e| sqlx::Error::Decode(e.into()))?;
+        fill_excerpt(&mut post);
+        // Unpublished posts are only visible to those who may read them
+        let permission = (post.status != PostStatus::Published).then_some("post:read");
+        self.publish(EventTopic::Posts, message.event.as_str(), &post, permission);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl EventService {
+    pub fn in_memory() -> Self {
+        Self::new(&EventsConfig {
+            replay_capacity: 16,
+            keepalive_secs: 15,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn service(replay_capacity: usize) -> EventService {
+        EventService::new(&EventsConfig {
+            replay_capacity,
+            keepalive_secs: 15,
+        })
+    }
+
+    fn ids(events: &[Arc<LiveEvent>]) -> Vec<u64> {
+        events.iter().map(|event| event.id).collect()
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_events_published_afterwards() {
+        let service = service(4);
+        service.publish(EventTopic::Users, "user.created", &json!({}), None);
+        let mut subscription = service.subscribe(None);
+        assert!(subscription.missed.is_empty());
+        assert!(subscription.complete);
+
+        service.publish(
+            EventTopic::Comments,
+            "comment.created",
+            &json!({ "id": 1 }),
+            None,
+        );
+        let event = subscription.receiver.recv().await.unwrap();
+        assert_eq!(event.id, 2);
+        assert_eq!(event.topic, EventTopic::Comments);
+        assert_eq!(event.data, r#"{"id":1}"#);
+    }
+
+    #[test]
+    fn resuming_replays_buffered_events() {
+        let service = service(3);
+        for _ in 0..5 {
+            service.publish(EventTopic::Posts, "post.updated", &json!({}), None);
+        }
+
+        // Events 3 to 5 are still buffered
+        let resumed = service.subscribe(Some(3));
+        assert_eq!(ids(&resumed.missed), [4, 5]);
+        assert!(resumed.complete);
+        let caught_up = service.subscribe(Some(5));
+        assert!(caught_up.missed.is_empty());
+        assert!(caught_up.complete);
+
+        // Event 2 was evicted, so resuming after event 1 leaves a gap
+        let evicted = service.subscribe(Some(1));
+        assert_eq!(ids(&evicted.missed), [3, 4, 5]);
+        assert!(!evicted.complete);
+        // Ids from before a restart are unknown
+        let unknown = service.subscribe(Some(40));
+        assert!(unknown.missed.is_empty());
+        assert!(!unknown.complete);
+    }
+}
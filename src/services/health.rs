@@ -0,0 +1,62 @@
+use crate::{
+    heartbeat::Heartbeats,
+    models::{CheckStatus, ReadinessResponse, WorkerCheck},
+    repositories::HealthRepository,
+};
+
+#[derive(Debug, Clone)]
+pub struct HealthService {
+    health_repo: HealthRepository,
+    heartbeats: Heartbeats,
+}
+
+impl HealthService {
+    pub fn new(health_repo: HealthRepository, heartbeats: Heartbeats) -> Self {
+        Self {
+            health_repo,
+            heartbeats,
+        }
+    }
+}
+
+impl HealthService {
+    // Check every dependency the app needs to serve traffic
+    pub async fn check_readiness(&self) -> ReadinessResponse {
+        let (database, migrations) = tokio::join!(
+            self.health_repo.check_database(),
+            self.health_repo.check_migrations()
+        );
+        let workers = self
+            .heartbeats
+            .statuses()
+            .into_iter()
+            .map(|heartbeat| WorkerCheck {
+                status: if heartbeat.is_alive() {
+                    CheckStatus::Up
+                } else {
+                    CheckStatus::Down
+                },
+                seconds_since_heartbeat: heartbeat.since_last_beat.as_secs_f64(),
+                max_interval_seconds: heartbeat.max_interval.as_secs_f64(),
+                name: heartbeat.name,
+            })
+            .collect();
+        ReadinessResponse {
+            database,
+            migrations,
+            pool: self.health_repo.check_pool(),
+            workers,
+        }
+    }
+
+    // Publish connection pool gauges; refreshed on every metrics scrape
+    pub async fn record_pool_metrics(&self) {
+        let pool = self.health_repo.check_pool();
+        metrics::gauge!("db_pool_connections").set(pool.size as f64);
+        metrics::gauge!("db_pool_idle_connections").set(pool.idle as f64);
+        metrics::gauge!("db_pool_max_connections").set(pool.max_connections as f64);
+        if let Some(wait) = self.health_repo.measure_acquire_wait().await {
+            metrics::gauge!("db_pool_acquire_wait_seconds").set(wait.as_secs_f64());
+        }
+    }
+}
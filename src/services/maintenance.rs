@@ -0,0 +1,21 @@
+use crate::repositories::MaintenanceRepository;
+use tracing::instrument;
+
+#[derive(Debug, Clone)]
+pub struct MaintenanceService {
+    maintenance_repo: MaintenanceRepository,
+}
+
+impl MaintenanceService {
+    pub fn new(maintenance_repo: MaintenanceRepository) -> Self {
+        Self { maintenance_repo }
+    }
+}
+
+impl MaintenanceService {
+    // Rebuild table indexes
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn reindex(&self) -> Result<Vec<String>, sqlx::Error> {
+        self.maintenance_repo.rebuild_indexes().await
+    }
+}
@@ -1,26 +1,134 @@
-use roles::RoleService;
-use user::UserService;
+use std::sync::Arc;
 
+pub use audit::AuditService;
+pub use auth::AuthService;
+pub use comment::CommentService;
+pub use events::EventService;
+use health::HealthService;
+use maintenance::MaintenanceService;
+pub use outbox::{OutboxRelay, OutboxSubscriber};
+pub use password::PasswordService;
+use permission::PermissionService;
+pub use roles::RoleService;
+pub use trash::TrashService;
+pub use user::UserService;
+pub use webhook::WebhookService;
+
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::heartbeat::Heartbeats;
+use crate::mail;
 use crate::repositories::RepositoryContainer;
-use crate::services::post::PostService;
+use crate::shutdown::Shutdown;
+pub use crate::services::post::PostService;
 
+mod audit;
+mod auth;
+mod comment;
+mod events;
+mod health;
+mod maintenance;
+mod outbox;
+mod password;
+mod permission;
 mod post;
 mod roles;
+mod trash;
 mod user;
+mod webhook;
+
+pub use auth::{AuthError, Claims};
+pub use password::PasswordError;
+pub use user::{CreateError, DeleteError, LoginHolder, UpdateError};
+pub use webhook::WebhookError;
 
 #[derive(Debug, Clone)]
 pub struct ServiceContainer {
+    pub audit_service: AuditService,
     pub role_service: RoleService,
     pub user_service: UserService,
     pub post_service: PostService,
+    pub comment_service: CommentService,
+    pub trash_service: TrashService,
+    pub auth_service: AuthService,
+    pub password_service: PasswordService,
+    pub webhook_service: WebhookService,
+    pub event_service: EventService,
+    pub outbox_relay: OutboxRelay,
+    pub permission_service: PermissionService,
+    pub maintenance_service: MaintenanceService,
+    pub health_service: HealthService,
 }
 
 impl ServiceContainer {
-    pub fn new(repository_container: RepositoryContainer) -> Self {
+    pub fn new(
+        repository_container: RepositoryContainer,
+        heartbeats: Heartbeats,
+        post_cache: Cache,
+        shutdown: &Shutdown,
+        config: &Config,
+    ) -> Self {
+        let webhook_service =
+            WebhookService::new(repository_container.webhook_repository, config.webhooks());
+        let event_service = EventService::new(config.events());
+        let outbox_relay = OutboxRelay::new(repository_container.outbox_repository);
+        let audit_service = AuditService::new(repository_container.audit_repository);
+        let auth_service =
+            AuthService::new(repository_container.user_repository.clone(), config.jwt());
         ServiceContainer {
             role_service: RoleService::new(repository_container.role_repository),
-            user_service: UserService::new(repository_container.user_repository),
-            post_service: PostService::new(repository_container.post_repository),
+            user_service: UserService::new(
+                repository_container.user_repository.clone(),
+                post_cache.clone(),
+                outbox_relay.clone(),
+                event_service.clone(),
+            ),
+            trash_service: TrashService::new(
+                repository_container.trash_repository,
+                post_cache.clone(),
+                config.trash(),
+            ),
+            post_service: PostService::new(
+                repository_container.post_repository,
+                post_cache,
+                outbox_relay.clone(),
+            ),
+            comment_service: CommentService::new(
+                repository_container.comment_repository,
+                outbox_relay.clone(),
+                event_service.clone(),
+            ),
+            password_service: PasswordService::new(
+                repository_container.user_repository,
+                repository_container.password_reset_repository,
+                mail::from_config(config.mail()),
+                auth_service.clone(),
+                shutdown.clone(),
+                config.password_reset(),
+            ),
+            auth_service,
+            webhook_service,
+            event_service,
+            audit_service,
+            outbox_relay,
+            permission_service: PermissionService::new(
+                repository_container.permission_repository,
+            ),
+            maintenance_service: MaintenanceService::new(
+                repository_container.maintenance_repository,
+            ),
+            health_service: HealthService::new(repository_container.health_repository, heartbeats),
         }
     }
 }
+
+impl ServiceContainer {
+    // Everything that reacts to events recorded in the outbox
+    pub fn outbox_subscribers(&self) -> Vec<Arc<dyn OutboxSubscriber>> {
+        vec![
+            Arc::new(self.post_service.clone()),
+            Arc::new(self.webhook_service.clone()),
+            Arc::new(self.event_service.clone()),
+        ]
+    }
+}
@@ -0,0 +1,387 @@
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::sync::Notify;
+use tracing::instrument;
+
+use crate::{
+    entities::OutboxMessage, heartbeat::Heartbeats, repositories::OutboxRepository,
+    shutdown::Shutdown,
+};
+
+// How often the relay looks for messages that have become due
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// Messages relayed per round, in the order they were recorded. A message
+// waiting for a retry holds back the later ones about the same row, so
+// subscribers see each row's events in order
+const BATCH_SIZE: i64 = 50;
+// How long a claimed message stays hidden from other relays; should this one
+// die mid-relay the message becomes due again afterwards
+const LEASE: Duration = Duration::from_secs(60);
+// Backoff between attempts at a message a subscriber failed to handle
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+// Relayed messages are kept this long for inspection, then deleted
+const RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Receives every event recorded in the outbox. Delivery is at least once: a
+// message is handed to all subscribers again when any of them fails, or when
+// the relay dies before marking it processed, so handling must be idempotent.
+#[async_trait]
+pub trait OutboxSubscriber: Debug + Send + Sync {
+    // Name used in logs
+    fn name(&self) -> &'static str;
+
+    async fn handle(&self, message: &OutboxMessage) -> Result<(), sqlx::Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxRelay {
+    outbox_repo: Arc<dyn OutboxRepository>,
+    // Wakes the worker as soon as events are recorded
+    queued: Arc<Notify>,
+}
+
+impl OutboxRelay {
+    pub fn new(outbox_repo: Arc<dyn OutboxRepository>) -> Self {
+        Self {
+            outbox_repo,
+            queued: Arc::new(Notify::new()),
+        }
+    }
+}
+
+impl OutboxRelay {
+    // Relay recorded events without waiting for the next poll
+    pub fn notify(&self) {
+        self.queued.notify_one();
+    }
+
+    // Relay recorded events to `subscribers` in the background until shutdown
+    pub fn spawn_worker(
+        &self,
+        subscribers: Vec<Arc<dyn OutboxSubscriber>>,
+        shutdown: &Shutdown,
+        heartbeats: &Heartbeats,
+    ) {
+        let relay = self.clone();
+        let heartbeat = heartbeats.register("outbox_relay", POLL_INTERVAL * 3);
+        shutdown.spawn_worker(|token| async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            let mut last_cleanup: Option<Instant> = None;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = relay.queued.notified() => {}
+                    _ = token.cancelled() => break,
+                }
+                if let Err(e) = relay.relay_due(&subscribers).await {
+                    tracing::warn!(error = %e, "Failed to relay outbox messages");
+                }
+                if last_cleanup.is_none_or(|at| at.elapsed() >= CLEANUP_INTERVAL) {
+                    last_cleanup = Some(Instant::now());
+                    if let Err(e) = relay.clean_up().await {
+                        tracing::warn!(error = %e, "Failed to clean up the outbox");
+                    }
+                }
+                heartbeat.beat();
+            }
+            heartbeat.deregister();
+        });
+    }
+
+    // Relay every message that is due, up to one batch
+    async fn relay_due(
+        &self,
+        subscribers: &[Arc<dyn OutboxSubscriber>],
+    ) -> Result<(), sqlx::Error> {
+        let due = self.outbox_repo.find_due(Utc::now(), BATCH_SIZE).await?;
+        if due.len() as i64 == BATCH_SIZE {
+            // Come straight back for the rest
+            self.queued.notify_one();
+        }
+        // Rows with a message that wasn't relayed this round
+        let mut held_back = HashSet::new();
+        for message in due {
+            if held_back.contains(&message.aggregate_id) {
+                continue;
+            }
+            match self.relay(&message, subscribers).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    held_back.insert(message.aggregate_id);
+                }
+                Err(e) => {
+                    held_back.insert(message.aggregate_id);
+                    tracing::warn!(message_id = %message.id, error = %e, "Failed to relay outbox message");
+                }
+            }
+        }
+        let pending = self.outbox_repo.count_pending().await?;
+        crate::metrics::set_queue_depth("outbox", pending as usize);
+        Ok(())
+    }
+
+    // Hand a message to every subscriber, returning whether it was relayed
+    // rather than claimed by another relay or left for a retry
+    #[instrument(skip_all, fields(message_id = %message.id, event = %message.event))]
+    async fn relay(
+        &self,
+        message: &OutboxMessage,
+        subscribers: &[Arc<dyn OutboxSubscriber>],
+    ) -> Result<bool, sqlx::Error> {
+        let now = Utc::now();
+        if !self.outbox_repo.claim(message.id, now, now + LEASE).await? {
+            return Ok(false);
+        }
+        for subscriber in subscribers {
+            if let Err(e) = subscriber.handle(message).await {
+                let attempts = message.attempts as u32 + 1;
+                tracing::warn!(
+                    subscriber = subscriber.name(),
+                    attempts,
+                    error = %e,
+                    "Outbox subscriber failed, retrying later"
+                );
+                metrics::counter!("outbox_messages_total", "outcome" => "retrying").increment(1);
+                let error = format!("{}: {}", subscriber.name(), e);
+                let available_at = Utc::now() + retry_delay(attempts);
+                self.outbox_repo
+                    .record_failure(message.id, &error, available_at)
+                    .await?;
+                return Ok(false);
+            }
+        }
+        metrics::counter!("outbox_messages_total", "outcome" => "relayed").increment(1);
+        self.outbox_repo
+            .mark_processed(message.id, Utc::now())
+            .await?;
+        Ok(true)
+    }
+
+    // Delete messages relayed longer ago than the retention period
+    async fn clean_up(&self) -> Result<(), sqlx::Error> {
+        let deleted = self
+            .outbox_repo
+            .delete_processed(Utc::now() - RETENTION)
+            .await?;
+        if deleted > 0 {
+            tracing::debug!(deleted, "Deleted relayed outbox messages");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl OutboxRelay {
+    // A relay over an empty in-memory outbox, for other services' tests
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(
+            crate::repositories::memory::InMemoryOutboxRepository::new(),
+        ))
+    }
+}
+
+// Wait before attempt `attempts + 1`: the base delay, doubled after every
+// failed attempt, up to five minutes
+fn retry_delay(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    RETRY_BASE_DELAY.saturating_mul(factor).min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    };
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        entities::{OutboxEvent, PostStatus},
+        models::{CreatePost, UpdatePost},
+        repositories::{
+            memory::{InMemoryOutboxRepository, InMemoryPostRepository},
+            PostRepository,
+        },
+    };
+
+    // Records the events it sees, failing while `failing` is set
+    #[derive(Debug, Default)]
+    struct Recorder {
+        events: Mutex<Vec<OutboxEvent>>,
+        failing: AtomicBool,
+    }
+
+    #[async_trait]
+    impl OutboxSubscriber for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+
+        async fn handle(&self, message: &OutboxMessage) -> Result<(), sqlx::Error> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(sqlx::Error::PoolTimedOut);
+            }
+            self.events.lock().unwrap().push(message.event);
+            Ok(())
+        }
+    }
+
+    fn new_post(status: PostStatus) -> CreatePost {
+        CreatePost {
+            title: String::from("Hello"),
+            content: String::from("Body"),
+            status,
+            published_at: None,
+            user_id: Uuid::new_v4(),
+            featured_media_id: None,
+            excerpt: None,
+            meta_description: None,
+            canonical_url: None,
+            og_title: None,
+            og_description: None,
+            og_image_url: None,
+        }
+    }
+
+    fn publish(id: Uuid) -> UpdatePost {
+        UpdatePost {
+            id,
+            title: None,
+            content: None,
+            status: Some(PostStatus::Published),
+            published_at: None,
+            user_id: None,
+            featured_media_id: None,
+            excerpt: None,
+            meta_description: None,
+            canonical_url: None,
+            og_title: None,
+            og_description: None,
+            og_image_url: None,
+        }
+    }
+
+    fn setup() -> (
+        OutboxRelay,
+        Arc<InMemoryOutboxRepository>,
+        InMemoryPostRepository,
+    ) {
+        let outbox = Arc::new(InMemoryOutboxRepository::new());
+        let posts = InMemoryPostRepository::with_outbox(outbox.clone());
+        (OutboxRelay::new(outbox.clone()), outbox, posts)
+    }
+
+    #[tokio::test]
+    async fn post_writes_are_relayed_once() {
+        let (relay, outbox, posts) = setup();
+        let recorder = Arc::new(Recorder::default());
+        let subscribers: Vec<Arc<dyn OutboxSubscriber>> = vec![recorder.clone()];
+
+        let post = posts.create(new_post(PostStatus::Draft)).await.unwrap();
+        posts.update(publish(post.id), None).await.unwrap();
+        // Publishing an already published post is just an update
+        posts.update(publish(post.id), None).await.unwrap();
+        relay.relay_due(&subscribers).await.unwrap();
+        relay.relay_due(&subscribers).await.unwrap();
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [
+                OutboxEvent::PostCreated,
+                OutboxEvent::PostUpdated,
+                OutboxEvent::PostPublished,
+                OutboxEvent::PostUpdated,
+            ]
+        );
+        assert_eq!(outbox.count_pending().await.unwrap(), 0);
+        let later = Utc::now() + chrono::Duration::seconds(1);
+        assert_eq!(outbox.delete_processed(later).await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn failed_messages_are_retried_with_backoff() {
+        let (relay, outbox, posts) = setup();
+        let recorder = Arc::new(Recorder::default());
+        recorder.failing.store(true, Ordering::SeqCst);
+        let subscribers: Vec<Arc<dyn OutboxSubscriber>> = vec![recorder.clone()];
+
+        posts.create(new_post(PostStatus::Published)).await.unwrap();
+        relay.relay_due(&subscribers).await.unwrap();
+        let pending = outbox
+            .find_due(Utc::now() + RETRY_BASE_DELAY * 2, BATCH_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 2);
+        // The second message waited for the first rather than fail too
+        assert_eq!(pending[0].attempts, 1);
+        assert_eq!(pending[1].attempts, 0);
+        assert_eq!(
+            outbox.error(pending[0].id).as_deref(),
+            Some("recorder: pool timed out while waiting for an open connection")
+        );
+        // Not due again until the backoff has passed
+        assert!(outbox
+            .find_due(Utc::now(), BATCH_SIZE)
+            .await
+            .unwrap()
+            .is_empty());
+
+        recorder.failing.store(false, Ordering::SeqCst);
+        tokio::time::sleep(RETRY_BASE_DELAY).await;
+        relay.relay_due(&subscribers).await.unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [OutboxEvent::PostCreated, OutboxEvent::PostPublished]
+        );
+        assert_eq!(outbox.count_pending().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn later_messages_wait_for_an_earlier_retry() {
+        let (relay, outbox, posts) = setup();
+        let recorder = Arc::new(Recorder::default());
+        recorder.failing.store(true, Ordering::SeqCst);
+        let subscribers: Vec<Arc<dyn OutboxSubscriber>> = vec![recorder.clone()];
+
+        let post = posts.create(new_post(PostStatus::Draft)).await.unwrap();
+        relay.relay_due(&subscribers).await.unwrap();
+        recorder.failing.store(false, Ordering::SeqCst);
+        posts.update(publish(post.id), None).await.unwrap();
+        let other = posts.create(new_post(PostStatus::Draft)).await.unwrap();
+
+        // Only the other post's message is due while the first one backs off
+        let due = outbox.find_due(Utc::now(), BATCH_SIZE).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].aggregate_id, other.id);
+
+        tokio::time::sleep(RETRY_BASE_DELAY).await;
+        relay.relay_due(&subscribers).await.unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [
+                OutboxEvent::PostCreated,
+                OutboxEvent::PostUpdated,
+                OutboxEvent::PostPublished,
+                OutboxEvent::PostCreated,
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_five_minutes() {
+        assert_eq!(retry_delay(1), RETRY_BASE_DELAY);
+        assert_eq!(retry_delay(3), RETRY_BASE_DELAY * 4);
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+    }
+}
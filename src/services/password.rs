@@ -0,0 +1,190 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use bcrypt::BcryptError;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::PasswordResetConfig,
+    mail::{Email, Mailer},
+    models::{TokenResponse, UserResponse},
+    password,
+    repositories::{PasswordResetRepository, UserRepository},
+    services::{AuthError, AuthService},
+    shutdown::Shutdown,
+};
+
+#[derive(Debug)]
+pub enum PasswordError {
+    // The reset token is unknown, used or expired
+    InvalidToken,
+    // The current password given for a change is wrong
+    InvalidCredentials,
+    Auth(AuthError),
+    Database(sqlx::Error),
+    Hash(BcryptError),
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::InvalidToken => f.write_str("invalid, used or expired reset token"),
+            PasswordError::InvalidCredentials => f.write_str("current password is wrong"),
+            PasswordError::Auth(e) => write!(f, "{}", e),
+            PasswordError::Database(e) => write!(f, "database error: {}", e),
+            PasswordError::Hash(e) => write!(f, "password hashing error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+#[derive(Debug, Clone)]
+pub struct PasswordService {
+    user_repo: Arc<dyn UserRepository>,
+    reset_repo: Arc<dyn PasswordResetRepository>,
+    mailer: Arc<dyn Mailer>,
+    auth: AuthService,
+    // Reset links are mailed by workers that shutdown waits for
+    shutdown: Shutdown,
+    token_ttl: Duration,
+    reset_url: String,
+}
+
+impl PasswordService {
+    pub fn new(
+        user_repo: Arc<dyn UserRepository>,
+        reset_repo: Arc<dyn PasswordResetRepository>,
+        mailer: Arc<dyn Mailer>,
+        auth: AuthService,
+        shutdown: Shutdown,
+        config: &PasswordResetConfig,
+    ) -> Self {
+        Self {
+            user_repo,
+            reset_repo,
+            mailer,
+            auth,
+            shutdown,
+            token_ttl: config.token_ttl(),
+            reset_url: config.url.clone(),
+        }
+    }
+}
+
+impl PasswordService {
+    // Mail a reset link to the user with this email address. Unknown
+    // addresses succeed all the same, so the answer doesn't reveal who has an
+    // account, and the token is stored and mailed in the background so the
+    // response time doesn't either.
+    #[instrument(skip_all, err(Display, level = "warn"))]
+    pub async fn forgot(&self, email: &str) -> Result<(), sqlx::Error> {
+        let user = match self.user_repo.find_by_login(email).await {
+            Ok(user) if user.email.eq_ignore_ascii_case(email) => user,
+            Ok(_) | Err(sqlx::Error::RowNotFound) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let service = self.clone();
+        self.shutdown
+            .spawn_worker(|_| async move { service.send_reset_link(user).await });
+        Ok(())
+    }
+
+    // Store a new reset token for `user` and mail them a link with it,
+    // logging rather than returning failures since nobody waits for this
+    async fn send_reset_link(&self, user: UserResponse) {
+        let token: String = rand::random::<[u8; 32]>()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let expires_at = Utc::now()
+            + chrono::Duration::from_std(self.token_ttl).unwrap_or(chrono::Duration::MAX);
+        if let Err(e) = self
+            .reset_repo
+            .create(user.id, &token_hash(&token), expires_at)
+            .await
+        {
+            tracing::warn!(
+                error = %e,
+                user_id = %user.id,
+                "Failed to store password reset token"
+            );
+            return;
+        }
+
+        let email = Email {
+            to: user.email.clone(),
+            subject: String::from("Reset your password"),
+            body: format!(
+                "Someone asked to reset the password of your account {}.\n\n\
+                 Follow this link within {} minutes to choose a new one:\n\n{}\n\n\
+                 If that wasn't you, ignore this message and your password stays as it is.\n",
+                user.username,
+                self.token_ttl.as_secs().div_ceil(60),
+                self.reset_url.replace("{token}", &token)
+            ),
+        };
+        if let Err(e) = self.mailer.send(&email).await {
+            tracing::warn!(
+                error = %e,
+                user_id = %user.id,
+                "Failed to send password reset mail"
+            );
+        }
+    }
+
+    // Set a new password with a mailed reset token, signing the user out
+    // everywhere
+    #[instrument(skip_all, err(Display, level = "warn"))]
+    pub async fn reset(&self, token: &str, new_password: &str) -> Result<(), PasswordError> {
+        let password_hash = password::hash(new_password).map_err(PasswordError::Hash)?;
+        match self
+            .reset_repo
+            .reset_password(&token_hash(token), &password_hash)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(sqlx::Error::RowNotFound) => Err(PasswordError::InvalidToken),
+            Err(e) => Err(PasswordError::Database(e)),
+        }
+    }
+
+    // Change a signed-in user's password. Every token issued before is
+    // revoked, so the caller gets a fresh one to stay signed in with.
+    #[instrument(
+        skip(self, current_password, new_password),
+        err(Display, level = "warn")
+    )]
+    pub async fn change(
+        &self,
+        user_id: Uuid,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<TokenResponse, PasswordError> {
+        let hash = self
+            .user_repo
+            .find_password_hash(user_id)
+            .await
+            .map_err(PasswordError::Database)?;
+        if !password::verify(current_password, &hash).map_err(PasswordError::Hash)? {
+            return Err(PasswordError::InvalidCredentials);
+        }
+        let password_hash = password::hash(new_password).map_err(PasswordError::Hash)?;
+        self.user_repo
+            .update_password(user_id, &password_hash)
+            .await
+            .map_err(PasswordError::Database)?;
+        self.auth.issue(user_id).await.map_err(PasswordError::Auth)
+    }
+}
+
+// Reset tokens are stored as their hex SHA-256, so a leaked table can't be
+// used to take over accounts
+fn token_hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::{PermissionListResponse, PermissionResponse},
+    repositories::PermissionRepository,
+};
+
+#[derive(Debug, Clone)]
+pub struct PermissionService {
+    permission_repo: Arc<dyn PermissionRepository>,
+}
+
+impl PermissionService {
+    pub fn new(permission_repo: Arc<dyn PermissionRepository>) -> Self {
+        Self { permission_repo }
+    }
+}
+
+impl PermissionService {
+    // Find all permissions
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_all(&self) -> Result<PermissionListResponse, sqlx::Error> {
+        self.permission_repo.find_all().await
+    }
+
+    // Create permission
+    #[instrument(skip(self, description), err(Display, level = "warn"))]
+    pub async fn create(
+        &self,
+        permission_name: &str,
+        description: &str,
+    ) -> Result<PermissionResponse, sqlx::Error> {
+        self.permission_repo
+            .create(permission_name, description)
+            .await
+    }
+
+    // Find permission by name
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_name(
+        &self,
+        permission_name: &str,
+    ) -> Result<PermissionResponse, sqlx::Error> {
+        self.permission_repo.find_by_name(permission_name).await
+    }
+
+    // Find all permissions granted to a role
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_role_id(
+        &self,
+        role_id: Uuid,
+    ) -> Result<PermissionListResponse, sqlx::Error> {
+        self.permission_repo.find_by_role_id(role_id).await
+    }
+
+    // Grant a permission to a role
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn assign_to_role(
+        &self,
+        role_id: Uuid,
+        permission_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        self.permission_repo
+            .assign_to_role(role_id, permission_id)
+            .await
+    }
+
+    // Names of the permissions granted to a role
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_names_by_role(&self, role_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        self.permission_repo.find_names_by_role(role_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::memory::InMemoryPermissionRepository;
+
+    #[tokio::test]
+    async fn permissions_can_be_created_and_granted() {
+        let repo = Arc::new(InMemoryPermissionRepository::new());
+        let service = PermissionService::new(repo.clone());
+        let created = service.create("post:create", "Create posts").await.unwrap();
+
+        let found = service.find_by_name("post:create").await.unwrap();
+        assert_eq!(found.id, created.id);
+        assert_eq!(service.find_all().await.unwrap().permissions.len(), 1);
+
+        let role_id = Uuid::new_v4();
+        service.assign_to_role(role_id, created.id).await.unwrap();
+        // Granting twice is a no-op, as with INSERT IGNORE
+        service.assign_to_role(role_id, created.id).await.unwrap();
+        assert!(repo.is_granted(role_id, created.id));
+        assert_eq!(
+            service.find_names_by_role(role_id).await.unwrap(),
+            ["post:create"]
+        );
+        let granted = service.find_by_role_id(role_id).await.unwrap().permissions;
+        assert_eq!(granted.len(), 1);
+        assert_eq!(granted[0].id, created.id);
+        assert!(service
+            .find_names_by_role(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn missing_permission_is_not_found() {
+        let service = PermissionService::new(Arc::new(InMemoryPermissionRepository::new()));
+        assert!(matches!(
+            service.find_by_name("post:create").await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+}
@@ -1,70 +1,544 @@
-use crate::entities::PostStatus;
-use crate::models::{PostListResponse, PostResponse};
+use std::sync::Arc;
+
+use crate::cache::Cache;
+use crate::db::read_from_primary;
+use crate::entities::{OutboxEvent, OutboxMessage, PostStatus};
+use crate::models::{
+    CreateMedia, CreatePost, MediaResponse, PostListResponse, PostResponse, UpdatePost,
+};
 use crate::repositories::PostRepository;
-use chrono::{DateTime, Utc};
+use crate::services::outbox::{OutboxRelay, OutboxSubscriber};
+use crate::services::user::{check_deleted, DeleteError, UpdateError};
+use async_trait::async_trait;
+use tracing::instrument;
 use uuid::Uuid;
 
+// Maximum number of characters in an auto-generated excerpt
+const EXCERPT_LENGTH: usize = 160;
+// Cache key of the listing of published posts; single posts are keyed by id.
+// Only published posts are cached, drafts and archived posts are always read
+// from the database.
+const PUBLISHED_POSTS_KEY: &str = "published";
+
 #[derive(Debug, Clone)]
 pub struct PostService {
-    post_repo: PostRepository,
+    post_repo: Arc<dyn PostRepository>,
+    cache: Cache,
+    outbox: OutboxRelay,
 }
 
 impl PostService {
-    pub fn new(post_repo: PostRepository) -> Self {
-        Self { post_repo }
+    pub fn new(post_repo: Arc<dyn PostRepository>, cache: Cache, outbox: OutboxRelay) -> Self {
+        Self {
+            post_repo,
+            cache,
+            outbox,
+        }
     }
 }
 
 impl PostService {
     // Find all posts
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn find_all(&self) -> Result<PostListResponse, sqlx::Error> {
-        self.post_repo.find_all().await
+        // Cached values are loaded from the primary, a lagging replica could
+        // otherwise repopulate the cache with data from before an invalidation
+        let mut posts = self
+            .cache
+            .get_or_load(
+                PUBLISHED_POSTS_KEY,
+                read_from_primary(async {
+                    let mut posts = self.post_repo.find_all(true).await?;
+                    posts.posts.iter_mut().for_each(fill_excerpt);
+                    Ok::<_, sqlx::Error>(posts)
+                }),
+            )
+            .await?;
+        let mut unpublished = self.post_repo.find_all(false).await?;
+        unpublished.posts.iter_mut().for_each(fill_excerpt);
+        posts.posts.extend(unpublished.posts);
+        Ok(posts)
     }
 
     // Create Post
-    pub async fn create(
-        &self,
-        title: String,
-        content: String,
-        status: PostStatus,
-        published_at: Option<DateTime<Utc>>,
-        user_id: Uuid,
-    ) -> Result<PostResponse, sqlx::Error> {
-        self.post_repo
-            .create(title, content, status, published_at, user_id)
-            .await
+    #[instrument(skip(self, post), err(Display, level = "warn"))]
+    pub async fn create(&self, post: CreatePost) -> Result<PostResponse, sqlx::Error> {
+        let post = self.post_repo.create(post).await;
+        self.cache.invalidate(&[PUBLISHED_POSTS_KEY]).await;
+        let mut post = post?;
+        self.outbox.notify();
+        fill_excerpt(&mut post);
+        Ok(post)
     }
 
     // Find post by id
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn find_by_id(&self, id: Uuid) -> Result<PostResponse, sqlx::Error> {
-        self.post_repo.find_by_id(id).await
+        self.cache
+            .get_or_load_if(
+                &id.to_string(),
+                read_from_primary(async {
+                    let mut post = self.post_repo.find_by_id(id).await?;
+                    fill_excerpt(&mut post);
+                    Ok(post)
+                }),
+                |post: &PostResponse| post.status == PostStatus::Published,
+            )
+            .await
     }
 
-    // Update post by id
+    // Update post by id, provided it is still at the `expected` version
+    #[instrument(skip(self, post), fields(post_id = %post.id), err(Display, level = "warn"))]
     pub async fn update_by_id(
         &self,
-        id: Uuid,
-        title: Option<String>,
-        content: Option<String>,
-        status: Option<PostStatus>,
-        published_at: Option<DateTime<Utc>>,
-        user_id: Option<Uuid>,
-    ) -> Result<PostResponse, sqlx::Error> {
-        self.post_repo
-            .update(id, title, content, status, published_at, user_id)
-            .await
+        post: UpdatePost,
+        expected: Option<i64>,
+    ) -> Result<PostResponse, UpdateError> {
+        let id = post.id;
+        let post = self.post_repo.update(post, expected).await;
+        self.cache
+            .invalidate(&[&id.to_string(), PUBLISHED_POSTS_KEY])
+            .await;
+        let mut post = post?.ok_or(UpdateError::Modified)?;
+        self.outbox.notify();
+        fill_excerpt(&mut post);
+        Ok(post)
     }
 
-    // Delete post by id
-    pub async fn delete_by_id(&self, id: Uuid) -> Result<(), sqlx::Error> {
-        self.post_repo.delete(id).await
+    // Delete post by id, provided it is still at the `expected` version
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn delete_by_id(&self, id: Uuid, expected: Option<i64>) -> Result<(), DeleteError> {
+        let result = self.post_repo.delete(id, expected).await;
+        self.cache
+            .invalidate(&[&id.to_string(), PUBLISHED_POSTS_KEY])
+            .await;
+        check_deleted(result?)?;
+        Ok(())
     }
 
     // Find all posts by user id
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn find_all_by_user_id(
         &self,
         user_id: Uuid,
     ) -> Result<PostListResponse, sqlx::Error> {
-        self.post_repo.find_by_user_id(user_id).await
+        let mut posts = self.post_repo.find_by_user_id(user_id).await?;
+        posts.posts.iter_mut().for_each(fill_excerpt);
+        Ok(posts)
+    }
+
+    // Find posts by ids, skipping ids that don't exist
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<PostResponse>, sqlx::Error> {
+        let mut posts = self.post_repo.find_by_ids(ids).await?;
+        posts.iter_mut().for_each(fill_excerpt);
+        Ok(posts)
+    }
+
+    // Find the posts of several users at once
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_user_ids(
+        &self,
+        user_ids: &[Uuid],
+    ) -> Result<Vec<PostResponse>, sqlx::Error> {
+        let mut posts = self.post_repo.find_by_user_ids(user_ids).await?;
+        posts.iter_mut().for_each(fill_excerpt);
+        Ok(posts)
+    }
+
+    // Search posts by title, excerpt and content
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn search(&self, query: &str) -> Result<PostListResponse, sqlx::Error> {
+        let mut posts = self.post_repo.search(query).await?;
+        posts.posts.iter_mut().for_each(fill_excerpt);
+        Ok(posts)
+    }
+
+    // Register a media item that posts may feature
+    #[instrument(skip(self, media), err(Display, level = "warn"))]
+    pub async fn create_media(&self, media: CreateMedia) -> Result<MediaResponse, sqlx::Error> {
+        self.post_repo.create_media(media).await
+    }
+
+    // Check that a featured media item exists
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn featured_media_exists(&self, media_id: Uuid) -> Result<bool, sqlx::Error> {
+        self.post_repo.media_exists(media_id).await
+    }
+}
+
+// Invalidates the cached copies of posts named by outbox events, so a change
+// whose writer died before invalidating, or that was made by another instance
+// sharing the cache, is still picked up
+#[async_trait]
+impl OutboxSubscriber for PostService {
+    fn name(&self) -> &'static str {
+        "post_cache"
+    }
+
+    async fn handle(&self, message: &OutboxMessage) -> Result<(), sqlx::Error> {
+        if !message.event.is_post_event() {
+            return Ok(());
+        }
+        let id = message.aggregate_id.to_string();
+        self.cache.invalidate(&[&id, PUBLISHED_POSTS_KEY]).await;
+        if message.event == OutboxEvent::PostPublished {
+            metrics::counter!("posts_published_total").increment(1);
+        }
+        Ok(())
+    }
+}
+
+// Posts without a stored excerpt get one derived from their content, so an
+// explicit excerpt always wins and a generated one never goes stale.
+pub(crate) fn fill_excerpt(post: &mut PostResponse) {
+    if post.excerpt.is_none() {
+        post.excerpt = Some(generate_excerpt(&post.content));
+    }
+}
+
+fn generate_excerpt(content: &str) -> String {
+    let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= EXCERPT_LENGTH {
+        return text;
+    }
+    let truncated: String = text.chars().take(EXCERPT_LENGTH).collect();
+    let cut = match truncated.rfind(' ') {
+        Some(index) => &truncated[..index],
+        None => truncated.as_str(),
+    };
+    format!(
+        "{}…",
+        cut.trim_end_matches(|c: char| c.is_ascii_punctuation())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::MemoryStore;
+    use crate::models::{AuditAction, AuditTargetType};
+    use crate::repositories::memory::{InMemoryAuditRepository, InMemoryPostRepository};
+    use crate::services::AuditService;
+    use std::time::Duration;
+
+    fn service() -> (PostService, Arc<InMemoryPostRepository>) {
+        let repo = Arc::new(InMemoryPostRepository::new());
+        (
+            PostService::new(repo.clone(), Cache::disabled(), OutboxRelay::in_memory()),
+            repo,
+        )
+    }
+
+    fn cached_service() -> (PostService, Arc<InMemoryPostRepository>) {
+        let repo = Arc::new(InMemoryPostRepository::new());
+        let cache = Cache::new(
+            "posts",
+            Arc::new(MemoryStore::new(10)),
+            Duration::from_secs(60),
+        );
+        (
+            PostService::new(repo.clone(), cache, OutboxRelay::in_memory()),
+            repo,
+        )
+    }
+
+    fn new_post(user_id: Uuid, content: &str) -> CreatePost {
+        CreatePost {
+            title: String::from("Hello"),
+            content: content.to_string(),
+            status: PostStatus::Draft,
+            published_at: None,
+            user_id,
+            featured_media_id: None,
+            excerpt: None,
+            meta_description: None,
+            canonical_url: None,
+            og_title: None,
+            og_description: None,
+            og_image_url: None,
+        }
+    }
+
+    fn published_post(user_id: Uuid) -> CreatePost {
+        CreatePost {
+            status: PostStatus::Published,
+            published_at: Some(chrono::Utc::now()),
+            ..new_post(user_id, "Body")
+        }
+    }
+
+    fn update(id: Uuid) -> UpdatePost {
+        UpdatePost {
+            id,
+            title: None,
+            content: None,
+            status: None,
+            published_at: None,
+            user_id: None,
+            featured_media_id: None,
+            excerpt: None,
+            meta_description: None,
+            canonical_url: None,
+            og_title: None,
+            og_description: None,
+            og_image_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn created_post_can_be_found() {
+        let (service, _) = service();
+        let created = service
+            .create(new_post(Uuid::new_v4(), "Body"))
+            .await
+            .unwrap();
+
+        let found = service.find_by_id(created.id).await.unwrap();
+        assert_eq!(found.title, "Hello");
+        assert_eq!(found.status, PostStatus::Draft);
+        assert_eq!(service.find_all().await.unwrap().posts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deleted_post_is_audited_with_its_last_state() {
+        let audit_repo = Arc::new(InMemoryAuditRepository::new());
+        let service = PostService::new(
+            Arc::new(InMemoryPostRepository::with_audit(audit_repo.clone())),
+            Cache::disabled(),
+            OutboxRelay::in_memory(),
+        );
+        let created = service
+            .create(new_post(Uuid::new_v4(), "Body"))
+            .await
+            .unwrap();
+
+        service.delete_by_id(created.id, None).await.unwrap();
+        let entries = AuditService::new(audit_repo).entries().await;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, AuditAction::Delete);
+        assert_eq!(entries[0].target_type, AuditTargetType::Post);
+        assert_eq!(entries[0].before.as_ref().unwrap()["content"], "Body");
+    }
+
+    #[tokio::test]
+    async fn missing_post_is_not_found() {
+        let (service, _) = service();
+        let result = service.find_by_id(Uuid::new_v4()).await;
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn excerpt_is_generated_from_content() {
+        let (service, _) = service();
+        let content = "word ".repeat(100);
+        let post = service
+            .create(new_post(Uuid::new_v4(), &content))
+            .await
+            .unwrap();
+
+        let excerpt = post.excerpt.unwrap();
+        assert!(excerpt.ends_with('…'));
+        assert!(excerpt.chars().count() <= EXCERPT_LENGTH + 1);
+    }
+
+    #[tokio::test]
+    async fn explicit_excerpt_is_kept() {
+        let (service, _) = service();
+        let mut post = new_post(Uuid::new_v4(), "Body");
+        post.excerpt = Some(String::from("Custom"));
+        let post = service.create(post).await.unwrap();
+        assert_eq!(post.excerpt.as_deref(), Some("Custom"));
+    }
+
+    #[tokio::test]
+    async fn update_only_changes_provided_fields() {
+        let (service, _) = service();
+        let created = service
+            .create(new_post(Uuid::new_v4(), "Body"))
+            .await
+            .unwrap();
+
+        let mut changes = update(created.id);
+        changes.status = Some(PostStatus::Published);
+        let updated = service.update_by_id(changes, None).await.unwrap();
+        assert_eq!(updated.status, PostStatus::Published);
+        assert_eq!(updated.title, "Hello");
+        assert_eq!(updated.content, "Body");
+    }
+
+    #[tokio::test]
+    async fn updating_missing_post_is_not_found() {
+        let (service, _) = service();
+        let result = service.update_by_id(update(Uuid::new_v4()), None).await;
+        assert!(matches!(
+            result,
+            Err(UpdateError::Database(sqlx::Error::RowNotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn writes_based_on_an_old_version_are_refused() {
+        let (service, _) = service();
+        let created = service
+            .create(new_post(Uuid::new_v4(), "Body"))
+            .await
+            .unwrap();
+        let mut changes = update(created.id);
+        changes.title = Some(String::from("First"));
+        service
+            .update_by_id(changes, Some(created.version))
+            .await
+            .unwrap();
+
+        let mut changes = update(created.id);
+        changes.title = Some(String::from("Second"));
+        assert!(matches!(
+            service.update_by_id(changes, Some(created.version)).await,
+            Err(UpdateError::Modified)
+        ));
+        assert!(matches!(
+            service
+                .delete_by_id(created.id, Some(created.version))
+                .await,
+            Err(DeleteError::Modified)
+        ));
+        assert_eq!(service.find_by_id(created.id).await.unwrap().title, "First");
+    }
+
+    #[tokio::test]
+    async fn deleted_post_is_gone() {
+        let (service, _) = service();
+        let created = service
+            .create(new_post(Uuid::new_v4(), "Body"))
+            .await
+            .unwrap();
+
+        service.delete_by_id(created.id, None).await.unwrap();
+        assert!(service.find_by_id(created.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cached_posts_are_invalidated_by_updates() {
+        let (service, repo) = cached_service();
+        let created = service
+            .create(published_post(Uuid::new_v4()))
+            .await
+            .unwrap();
+        service.find_by_id(created.id).await.unwrap();
+        assert_eq!(service.find_all().await.unwrap().posts.len(), 1);
+
+        // Changes made behind the service's back are hidden by the cache
+        repo.delete(created.id, None).await.unwrap();
+        assert!(service.find_by_id(created.id).await.is_ok());
+        assert_eq!(service.find_all().await.unwrap().posts.len(), 1);
+
+        let recreated = service
+            .create(published_post(Uuid::new_v4()))
+            .await
+            .unwrap();
+        assert_eq!(service.find_all().await.unwrap().posts.len(), 1);
+        let mut changes = update(recreated.id);
+        changes.title = Some(String::from("Updated"));
+        service.update_by_id(changes, None).await.unwrap();
+        let found = service.find_by_id(recreated.id).await.unwrap();
+        assert_eq!(found.title, "Updated");
+
+        service.delete_by_id(recreated.id, None).await.unwrap();
+        assert!(service.find_by_id(recreated.id).await.is_err());
+        assert!(service.find_all().await.unwrap().posts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_published_posts_are_cached() {
+        let (service, repo) = cached_service();
+        let draft = service
+            .create(new_post(Uuid::new_v4(), "Draft"))
+            .await
+            .unwrap();
+        let published = service
+            .create(published_post(Uuid::new_v4()))
+            .await
+            .unwrap();
+        service.find_by_id(draft.id).await.unwrap();
+        service.find_by_id(published.id).await.unwrap();
+        assert_eq!(service.find_all().await.unwrap().posts.len(), 2);
+
+        // Drafts are read from the repository every time
+        repo.delete(draft.id, None).await.unwrap();
+        repo.delete(published.id, None).await.unwrap();
+        assert!(service.find_by_id(draft.id).await.is_err());
+        assert!(service.find_by_id(published.id).await.is_ok());
+        let posts = service.find_all().await.unwrap().posts;
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].id, published.id);
+    }
+
+    #[tokio::test]
+    async fn posts_are_filtered_by_author() {
+        let (service, _) = service();
+        let author = Uuid::new_v4();
+        service.create(new_post(author, "Mine")).await.unwrap();
+        service
+            .create(new_post(Uuid::new_v4(), "Theirs"))
+            .await
+            .unwrap();
+
+        let posts = service.find_all_by_user_id(author).await.unwrap().posts;
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].content, "Mine");
+    }
+
+    #[tokio::test]
+    async fn search_matches_title_and_content() {
+        let (service, _) = service();
+        let author = Uuid::new_v4();
+        service
+            .create(new_post(author, "About Rust"))
+            .await
+            .unwrap();
+        service.create(new_post(author, "About Go")).await.unwrap();
+
+        let posts = service.search("rust").await.unwrap().posts;
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].content, "About Rust");
+        assert_eq!(service.search("hello").await.unwrap().posts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn featured_media_must_exist() {
+        let (service, _) = service();
+        assert!(!service.featured_media_exists(Uuid::new_v4()).await.unwrap());
+        let media = service
+            .create_media(CreateMedia {
+                file_name: String::from("hero.png"),
+                url: String::from("https://cdn.example.com/hero.png"),
+                mime_type: String::from("image/png"),
+                alt_text: None,
+            })
+            .await
+            .unwrap();
+        assert!(service.featured_media_exists(media.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn optional_fields_are_cleared_by_null() {
+        let (service, _) = service();
+        let mut post = new_post(Uuid::new_v4(), "Body");
+        post.og_title = Some(String::from("Shared title"));
+        post.meta_description = Some(String::from("Described"));
+        let created = service.create(post).await.unwrap();
+
+        let mut clear = update(created.id);
+        clear.og_title = Some(None);
+        let updated = service.update_by_id(clear, None).await.unwrap();
+        assert_eq!(updated.og_title, None);
+        // Fields left out keep their value
+        assert_eq!(updated.meta_description.as_deref(), Some("Described"));
+    }
+
+    #[test]
+    fn short_content_is_its_own_excerpt() {
+        assert_eq!(generate_excerpt("  Short\n post "), "Short post");
     }
 }
@@ -1,28 +1,37 @@
+use std::sync::Arc;
+
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
     models::{RoleListResponse, RoleResponse},
     repositories::RoleRepository,
+    services::{
+        user::{check_deleted, existing, UpdateError},
+        DeleteError,
+    },
 };
 
 #[derive(Debug, Clone)]
 pub struct RoleService {
-    role_repo: RoleRepository,
+    role_repo: Arc<dyn RoleRepository>,
 }
 
 impl RoleService {
-    pub fn new(role_repo: RoleRepository) -> Self {
+    pub fn new(role_repo: Arc<dyn RoleRepository>) -> Self {
         Self { role_repo }
     }
 }
 
 impl RoleService {
     // Find all roles
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn find_all(&self) -> Result<RoleListResponse, sqlx::Error> {
         self.role_repo.find_all().await
     }
 
     // Create role
+    #[instrument(skip(self, description), err(Display, level = "warn"))]
     pub async fn create(
         &self,
         role_name: &str,
@@ -32,22 +41,146 @@ impl RoleService {
     }
 
     // Find role by id
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn find_by_id(&self, id: Uuid) -> Result<RoleResponse, sqlx::Error> {
         self.role_repo.find_by_id(id).await
     }
 
-    // Update role by id
+    // Find role by name
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_name(&self, role_name: &str) -> Result<RoleResponse, sqlx::Error> {
+        self.role_repo.find_by_name(role_name).await
+    }
+
+    // Update role by id, provided it is still at the `expected` version
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn update_by_id(
         &self,
         id: Uuid,
         role_name: Option<String>,
         description: Option<String>,
-    ) -> Result<RoleResponse, sqlx::Error> {
-        self.role_repo.update(id, role_name, description).await
+        expected: Option<i64>,
+    ) -> Result<RoleResponse, UpdateError> {
+        self.role_repo
+            .update(id, role_name, description, expected)
+            .await?
+            .ok_or(UpdateError::Modified)
+    }
+
+    // Delete role by id, first moving its users to `reassign_to`. Without one
+    // a role that still has users is not deleted, and neither is one that
+    // changed since the `expected` version.
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn delete_by_id(
+        &self,
+        id: Uuid,
+        reassign_to: Option<Uuid>,
+        expected: Option<i64>,
+    ) -> Result<(), DeleteError> {
+        if let Some(new_role) = reassign_to {
+            if new_role == id {
+                return Err(DeleteError::InvalidReassignment(String::from(
+                    "Users can't be reassigned to the role being deleted",
+                )));
+            }
+            if existing(self.role_repo.find_by_id(new_role).await)?.is_none() {
+                return Err(DeleteError::InvalidReassignment(format!(
+                    "No role with id {} to reassign users to",
+                    new_role
+                )));
+            }
+        }
+        check_deleted(self.role_repo.delete(id, reassign_to, expected).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::AuditAction,
+        repositories::memory::{InMemoryAuditRepository, InMemoryRoleRepository},
+        services::AuditService,
+    };
+
+    fn service() -> RoleService {
+        RoleService::new(Arc::new(InMemoryRoleRepository::new()))
     }
 
-    // Delete role by id
-    pub async fn delete_by_id(&self, id: Uuid) -> Result<(), sqlx::Error> {
-        self.role_repo.delete(id).await
+    #[tokio::test]
+    async fn created_role_can_be_found_by_id_and_name() {
+        let service = service();
+        let created = service.create("editor", "Edits posts").await.unwrap();
+
+        let by_id = service.find_by_id(created.id).await.unwrap();
+        let by_name = service.find_by_name("editor").await.unwrap();
+        assert_eq!(by_id.description.as_deref(), Some("Edits posts"));
+        assert_eq!(by_name.id, created.id);
+        assert_eq!(service.find_all().await.unwrap().roles.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn update_only_changes_provided_fields() {
+        let service = service();
+        let created = service.create("editor", "Edits posts").await.unwrap();
+
+        let updated = service
+            .update_by_id(created.id, Some(String::from("author")), None, None)
+            .await
+            .unwrap();
+        assert_eq!(updated.role_name, "author");
+        assert_eq!(updated.description.as_deref(), Some("Edits posts"));
+    }
+
+    #[tokio::test]
+    async fn missing_role_is_not_found() {
+        let service = service();
+        let id = Uuid::new_v4();
+        assert!(matches!(
+            service.find_by_id(id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert!(matches!(
+            service.update_by_id(id, None, None, None).await,
+            Err(UpdateError::Database(sqlx::Error::RowNotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleted_role_is_gone() {
+        let service = service();
+        let created = service.create("editor", "Edits posts").await.unwrap();
+
+        service.delete_by_id(created.id, None, None).await.unwrap();
+        assert!(service.find_by_name("editor").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn changes_are_audited_with_snapshots() {
+        let audit_repo = Arc::new(InMemoryAuditRepository::new());
+        let service = RoleService::new(Arc::new(InMemoryRoleRepository::with_audit(
+            audit_repo.clone(),
+        )));
+        let created = service.create("editor", "Edits posts").await.unwrap();
+        service
+            .update_by_id(created.id, Some(String::from("author")), None, None)
+            .await
+            .unwrap();
+        service.delete_by_id(created.id, None, None).await.unwrap();
+
+        let entries = AuditService::new(audit_repo).entries().await;
+        let actions: Vec<_> = entries.iter().map(|entry| entry.action).collect();
+        assert_eq!(
+            actions,
+            [
+                AuditAction::Delete,
+                AuditAction::Update,
+                AuditAction::Create
+            ]
+        );
+        let update = &entries[1];
+        assert_eq!(update.before.as_ref().unwrap()["role_name"], "editor");
+        assert_eq!(update.after.as_ref().unwrap()["role_name"], "author");
+        assert!(entries[0].after.is_none());
     }
 }
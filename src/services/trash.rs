@@ -0,0 +1,109 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    cache::Cache,
+    config::TrashConfig,
+    heartbeat::Heartbeats,
+    models::{TrashItemType, TrashListResponse, TrashQuery},
+    repositories::TrashRepository,
+    shutdown::Shutdown,
+};
+
+// Items listed when no limit is given, and the most that can be asked for
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone)]
+pub struct TrashService {
+    trash_repo: Arc<dyn TrashRepository>,
+    // Restored and purged posts change what the post listings show
+    post_cache: Cache,
+    retention: Option<Duration>,
+    purge_interval: Duration,
+}
+
+impl TrashService {
+    pub fn new(
+        trash_repo: Arc<dyn TrashRepository>,
+        post_cache: Cache,
+        config: &TrashConfig,
+    ) -> Self {
+        Self {
+            trash_repo,
+            post_cache,
+            retention: config.retention(),
+            purge_interval: config.purge_interval(),
+        }
+    }
+}
+
+impl TrashService {
+    // Purge items older than the retention period in the background until
+    // shutdown; nothing is started when items are kept until purged by hand
+    pub fn spawn_worker(&self, shutdown: &Shutdown, heartbeats: &Heartbeats) {
+        let Some(retention) = self.retention else {
+            return;
+        };
+        let service = self.clone();
+        let heartbeat = heartbeats.register("trash_purge", self.purge_interval * 3);
+        shutdown.spawn_worker(|token| async move {
+            let mut interval = tokio::time::interval(service.purge_interval);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = token.cancelled() => break,
+                }
+                if let Err(e) = service.purge_expired(retention).await {
+                    tracing::warn!(error = %e, "Failed to purge the trash");
+                }
+                heartbeat.beat();
+            }
+            heartbeat.deregister();
+        });
+    }
+
+    // Permanently delete items trashed longer ago than `retention`
+    async fn purge_expired(&self, retention: Duration) -> Result<(), sqlx::Error> {
+        let cutoff =
+            Utc::now() - chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::MAX);
+        let purged = self.trash_repo.purge_deleted_before(cutoff).await?;
+        if purged > 0 {
+            self.post_cache.clear().await;
+            tracing::info!(purged, "Purged expired items from the trash");
+        }
+        Ok(())
+    }
+
+    // Find trashed items, most recently deleted first
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find(&self, query: TrashQuery) -> Result<TrashListResponse, sqlx::Error> {
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let items = self.trash_repo.find(query.item_type, limit).await?;
+        Ok(TrashListResponse {
+            items: items.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    // Take an item, and whatever was deleted along with it, out of the trash.
+    // Returns false while the item's author or post is still in the trash.
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn restore(&self, item_type: TrashItemType, id: Uuid) -> Result<bool, sqlx::Error> {
+        if !self.trash_repo.restore(item_type, id).await? {
+            return Ok(false);
+        }
+        self.post_cache.clear().await;
+        Ok(true)
+    }
+
+    // Permanently delete a trashed item
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn purge(&self, item_type: TrashItemType, id: Uuid) -> Result<(), sqlx::Error> {
+        self.trash_repo.purge(item_type, id).await?;
+        self.post_cache.clear().await;
+        Ok(())
+    }
+}
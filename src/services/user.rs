@@ -1,58 +1,468 @@
+use std::{fmt, sync::Arc};
+
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
-    models::{UserListResponse, UserResponse},
-    repositories::UserRepository,
+    cache::Cache,
+    models::{EventTopic, OwnedContent, UserListResponse, UserResponse},
+    repositories::{DeleteOutcome, UserRepository},
+    services::{EventService, OutboxRelay},
 };
 
+// Who already has a username or email that a user was to be given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginHolder {
+    Active,
+    // A user in the trash keeps its username and email until it is restored
+    // or purged
+    Trashed(Uuid),
+}
+
+impl fmt::Display for LoginHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginHolder::Active => f.write_str("the username or email is already taken"),
+            LoginHolder::Trashed(id) => write!(
+                f,
+                "the username or email belongs to user {}, which is in the trash",
+                id
+            ),
+        }
+    }
+}
+
+// Why a user could not be created
+#[derive(Debug)]
+pub enum CreateError {
+    Taken(LoginHolder),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for CreateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CreateError::Taken(holder) => write!(f, "{}", holder),
+            CreateError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CreateError {}
+
+impl From<sqlx::Error> for CreateError {
+    fn from(e: sqlx::Error) -> Self {
+        CreateError::Database(e)
+    }
+}
+
+// Why a post, user or role could not be updated
+#[derive(Debug)]
+pub enum UpdateError {
+    // It changed since the version the update was based on
+    Modified,
+    // Users only: another user has the new username or email
+    Taken(LoginHolder),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Modified => f.write_str("it changed since it was read"),
+            UpdateError::Taken(holder) => write!(f, "{}", holder),
+            UpdateError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for UpdateError {
+    fn from(e: sqlx::Error) -> Self {
+        UpdateError::Database(e)
+    }
+}
+
+// Why a post, user or role could not be deleted
+#[derive(Debug)]
+pub enum DeleteError {
+    // Other records still belong to it and have to be reassigned or deleted
+    InUse,
+    // What they were to be reassigned to is not acceptable
+    InvalidReassignment(String),
+    // It changed since the version the delete was based on
+    Modified,
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for DeleteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeleteError::InUse => f.write_str("other records still belong to it"),
+            DeleteError::InvalidReassignment(reason) => f.write_str(reason),
+            DeleteError::Modified => f.write_str("it changed since it was read"),
+            DeleteError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for DeleteError {
+    fn from(e: sqlx::Error) -> Self {
+        DeleteError::Database(e)
+    }
+}
+
+// Why a repository delete that came back with `outcome` left the row in place
+pub(crate) fn check_deleted(outcome: DeleteOutcome) -> Result<(), DeleteError> {
+    match outcome {
+        DeleteOutcome::Deleted => Ok(()),
+        DeleteOutcome::InUse => Err(DeleteError::InUse),
+        DeleteOutcome::Modified => Err(DeleteError::Modified),
+    }
+}
+
+// A row that may not exist; `None` when it doesn't
+pub(crate) fn existing<T>(result: Result<T, sqlx::Error>) -> Result<Option<T>, sqlx::Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(sqlx::Error::RowNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UserService {
-    user_repo: UserRepository,
+    user_repo: Arc<dyn UserRepository>,
+    post_cache: Cache,
+    outbox: OutboxRelay,
+    events: EventService,
 }
 
 impl UserService {
-    pub fn new(user_repo: UserRepository) -> Self {
-        Self { user_repo }
+    pub fn new(
+        user_repo: Arc<dyn UserRepository>,
+        post_cache: Cache,
+        outbox: OutboxRelay,
+        events: EventService,
+    ) -> Self {
+        Self {
+            user_repo,
+            post_cache,
+            outbox,
+            events,
+        }
     }
 }
 
 impl UserService {
     // Find all users
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn find_all(&self) -> Result<UserListResponse, sqlx::Error> {
         self.user_repo.find_all().await
     }
 
     // Create User
+    #[instrument(skip(self, password), err(Display, level = "warn"))]
     pub async fn create(
         &self,
         username: &str,
         email: &str,
         password: &str,
         role_id: Uuid,
-    ) -> Result<UserResponse, sqlx::Error> {
-        self.user_repo
+    ) -> Result<UserResponse, CreateError> {
+        let user = match self
+            .user_repo
             .create(username, email, password, role_id)
             .await
+        {
+            Ok(user) => user,
+            Err(e) => {
+                let holder = self.taken_by(e, &[username, email]).await?;
+                return Err(CreateError::Taken(holder));
+            }
+        };
+        metrics::counter!("users_created_total").increment(1);
+        // Webhooks hear of it from the outbox, where it was recorded with the user
+        self.outbox.notify();
+        self.events
+            .publish(EventTopic::Users, "user.created", &user, None);
+        Ok(user)
     }
 
     // Find user by id
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn find_by_id(&self, id: Uuid) -> Result<UserResponse, sqlx::Error> {
         self.user_repo.find_by_id(id).await
     }
 
-    // Update user by id
+    // Find user by username or email
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_login(&self, login: &str) -> Result<UserResponse, sqlx::Error> {
+        self.user_repo.find_by_login(login).await
+    }
+
+    // Find users by ids, skipping ids that don't exist
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<UserResponse>, sqlx::Error> {
+        self.user_repo.find_by_ids(ids).await
+    }
+
+    // Update user by id, provided it is still at the `expected` version
+    #[instrument(skip(self), err(Display, level = "warn"))]
     pub async fn update_by_id(
         &self,
         id: Uuid,
         username: Option<String>,
         email: Option<String>,
         role_id: Option<Uuid>,
-    ) -> Result<UserResponse, sqlx::Error> {
-        self.user_repo.update(id, username, email, role_id).await
+        expected: Option<i64>,
+    ) -> Result<UserResponse, UpdateError> {
+        let logins: Vec<String> = username.iter().chain(&email).cloned().collect();
+        let user = match self
+            .user_repo
+            .update(id, username, email, role_id, expected)
+            .await
+        {
+            Ok(user) => user.ok_or(UpdateError::Modified)?,
+            Err(e) => {
+                let logins: Vec<&str> = logins.iter().map(String::as_str).collect();
+                let holder = self.taken_by(e, &logins).await?;
+                return Err(UpdateError::Taken(holder));
+            }
+        };
+        self.events
+            .publish(EventTopic::Users, "user.updated", &user, None);
+        Ok(user)
+    }
+
+    // Update user password
+    #[instrument(skip(self, password_hash), err(Display, level = "warn"))]
+    pub async fn update_password(&self, id: Uuid, password_hash: &str) -> Result<(), sqlx::Error> {
+        self.user_repo.update_password(id, password_hash).await
     }
 
-    // Delete user by id
-    pub async fn delete_by_id(&self, id: Uuid) -> Result<(), sqlx::Error> {
-        self.user_repo.delete(id).await
+    // Delete user by id, dealing with their posts and comments as `content`
+    // says, provided the user is still at the `expected` version
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn delete_by_id(
+        &self,
+        id: Uuid,
+        content: OwnedContent,
+        expected: Option<i64>,
+    ) -> Result<(), DeleteError> {
+        if let OwnedContent::ReassignTo(new_owner) = content {
+            if new_owner == id {
+                return Err(DeleteError::InvalidReassignment(String::from(
+                    "Content can't be reassigned to the user being deleted",
+                )));
+            }
+            if existing(self.user_repo.find_by_id(new_owner).await)?.is_none() {
+                return Err(DeleteError::InvalidReassignment(format!(
+                    "No user with id {} to reassign content to",
+                    new_owner
+                )));
+            }
+        }
+        let result = self.user_repo.delete(id, content, expected).await;
+        // The user's posts change owner or are deleted along with them
+        self.post_cache.clear().await;
+        check_deleted(result?)
+    }
+}
+
+impl UserService {
+    // Who holds one of `logins`, when the failed write `e` was refused for
+    // breaking the uniqueness of usernames and emails; `e` otherwise
+    async fn taken_by(&self, e: sqlx::Error, logins: &[&str]) -> Result<LoginHolder, sqlx::Error> {
+        if !matches!(&e, sqlx::Error::Database(db) if db.is_unique_violation()) {
+            return Err(e);
+        }
+        for login in logins {
+            if let Some(id) = self.user_repo.find_trashed_by_login(login).await? {
+                return Ok(LoginHolder::Trashed(id));
+            }
+        }
+        Ok(LoginHolder::Active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{
+        entities::OutboxEvent,
+        models::AuditAction,
+        repositories::{
+            memory::{InMemoryAuditRepository, InMemoryOutboxRepository, InMemoryUserRepository},
+            OutboxRepository,
+        },
+        services::AuditService,
+    };
+
+    fn service() -> (UserService, Arc<InMemoryUserRepository>) {
+        let repo = Arc::new(InMemoryUserRepository::new());
+        (
+            UserService::new(
+                repo.clone(),
+                Cache::disabled(),
+                OutboxRelay::in_memory(),
+                EventService::in_memory(),
+            ),
+            repo,
+        )
+    }
+
+    #[tokio::test]
+    async fn created_user_can_be_found_by_id_and_login() {
+        let (service, _) = service();
+        let role_id = Uuid::new_v4();
+        let created = service
+            .create("alice", "alice@example.com", "hash", role_id)
+            .await
+            .unwrap();
+
+        let found = service.find_by_id(created.id).await.unwrap();
+        assert_eq!(found.username, "alice");
+        assert_eq!(found.role_id, role_id);
+        let by_name = service.find_by_login("alice").await.unwrap();
+        let by_email = service.find_by_login("alice@example.com").await.unwrap();
+        assert_eq!(by_name.id, created.id);
+        assert_eq!(by_email.id, created.id);
+        assert_eq!(service.find_all().await.unwrap().users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn created_users_are_recorded_in_the_outbox() {
+        let outbox = Arc::new(InMemoryOutboxRepository::new());
+        let service = UserService::new(
+            Arc::new(InMemoryUserRepository::with_outbox(outbox.clone())),
+            Cache::disabled(),
+            OutboxRelay::new(outbox.clone()),
+            EventService::in_memory(),
+        );
+        let created = service
+            .create("alice", "alice@example.com", "hash", Uuid::new_v4())
+            .await
+            .unwrap();
+
+        // Webhooks are queued from the outbox
+        let recorded = outbox.find_due(Utc::now(), 10).await.unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].event, OutboxEvent::UserCreated);
+        assert_eq!(recorded[0].aggregate_id, created.id);
+    }
+
+    #[tokio::test]
+    async fn update_only_changes_provided_fields() {
+        let (service, _) = service();
+        let created = service
+            .create("alice", "alice@example.com", "hash", Uuid::new_v4())
+            .await
+            .unwrap();
+
+        let updated = service
+            .update_by_id(
+                created.id,
+                None,
+                Some(String::from("a@example.com")),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.username, "alice");
+        assert_eq!(updated.email, "a@example.com");
+    }
+
+    #[tokio::test]
+    async fn password_can_be_changed() {
+        let (service, repo) = service();
+        let created = service
+            .create("alice", "alice@example.com", "old", Uuid::new_v4())
+            .await
+            .unwrap();
+
+        service.update_password(created.id, "new").await.unwrap();
+        assert_eq!(repo.password_hash(created.id).as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn missing_user_is_not_found() {
+        let (service, _) = service();
+        let id = Uuid::new_v4();
+        assert!(matches!(
+            service.find_by_id(id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert!(matches!(
+            service.update_by_id(id, None, None, None, None).await,
+            Err(UpdateError::Database(sqlx::Error::RowNotFound))
+        ));
+        assert!(matches!(
+            service.update_password(id, "hash").await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+        assert!(matches!(
+            service.delete_by_id(id, OwnedContent::Refuse, None).await,
+            Err(DeleteError::Database(sqlx::Error::RowNotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleted_user_is_gone() {
+        let (service, _) = service();
+        let created = service
+            .create("alice", "alice@example.com", "hash", Uuid::new_v4())
+            .await
+            .unwrap();
+
+        service
+            .delete_by_id(created.id, OwnedContent::Refuse, None)
+            .await
+            .unwrap();
+        assert!(service.find_all().await.unwrap().users.is_empty());
+    }
+
+    #[tokio::test]
+    async fn content_is_only_reassigned_to_another_existing_user() {
+        let (service, _) = service();
+        let created = service
+            .create("alice", "alice@example.com", "hash", Uuid::new_v4())
+            .await
+            .unwrap();
+
+        for new_owner in [created.id, Uuid::new_v4()] {
+            assert!(matches!(
+                service
+                    .delete_by_id(created.id, OwnedContent::ReassignTo(new_owner), None)
+                    .await,
+                Err(DeleteError::InvalidReassignment(_))
+            ));
+        }
+        assert_eq!(service.find_all().await.unwrap().users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn password_changes_are_audited_without_snapshots() {
+        let audit_repo = Arc::new(InMemoryAuditRepository::new());
+        let service = UserService::new(
+            Arc::new(InMemoryUserRepository::with_audit(audit_repo.clone())),
+            Cache::disabled(),
+            OutboxRelay::in_memory(),
+            EventService::in_memory(),
+        );
+        let created = service
+            .create("alice", "alice@example.com", "old", Uuid::new_v4())
+            .await
+            .unwrap();
+
+        service.update_password(created.id, "new").await.unwrap();
+        let entries = AuditService::new(audit_repo).entries().await;
+        assert_eq!(entries[0].action, AuditAction::Update);
+        assert_eq!(entries[0].target_id, created.id);
+        assert!(entries[0].before.is_none() && entries[0].after.is_none());
+        assert_eq!(entries[1].action, AuditAction::Create);
+        assert_eq!(entries[1].after.as_ref().unwrap()["username"], "alice");
     }
 }
@@ -0,0 +1,699 @@
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header, Url,
+};
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use tokio::sync::Notify;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::WebhookConfig,
+    entities::{OutboxEvent, OutboxMessage, Webhook, WebhookDelivery},
+    heartbeat::Heartbeats,
+    models::{
+        CreateWebhook, DeliveryStatus, PostResponse, UpdateWebhook, WebhookDeliveryListResponse,
+        WebhookDeliveryResponse, WebhookEvent, WebhookListResponse, WebhookResponse,
+    },
+    repositories::{DeliveryAttempt, WebhookRepository},
+    services::{outbox::OutboxSubscriber, post::fill_excerpt},
+    shutdown::Shutdown,
+};
+
+// Name of the event being delivered
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+// Id of the delivery, stable across retries of the same delivery
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+// `sha256=` followed by the hex HMAC-SHA256 of the body, keyed with the secret
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+// How often the worker looks for retries that have become due
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// Deliveries attempted concurrently per round
+const BATCH_SIZE: i64 = 20;
+// Upper bound of the exponential backoff between attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+// Deliveries listed per webhook
+const DELIVERY_LOG_LIMIT: i64 = 100;
+// Characters of a failing receiver's response body kept in the delivery log
+const MAX_ERROR_LENGTH: usize = 500;
+
+// Why a webhook URL or delivery was refused
+const PRIVATE_TARGET: &str =
+    "url must not point at a loopback, link-local or private address unless \
+     webhooks.allow_private_targets is set";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug)]
+pub enum WebhookError {
+    // The subscription is not acceptable
+    Invalid(String),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookError::Invalid(reason) => f.write_str(reason),
+            WebhookError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for WebhookError {
+    fn from(e: sqlx::Error) -> Self {
+        WebhookError::Database(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookService {
+    webhook_repo: Arc<dyn WebhookRepository>,
+    client: reqwest::Client,
+    max_attempts: u32,
+    retry_base_delay: Duration,
+    timeout: Duration,
+    allow_private_targets: bool,
+    // Wakes the worker as soon as deliveries are queued
+    queued: Arc<Notify>,
+}
+
+impl WebhookService {
+    pub fn new(webhook_repo: Arc<dyn WebhookRepository>, config: &WebhookConfig) -> Self {
+        // Receivers must answer directly; a redirect is treated as a failure
+        let mut client = reqwest::Client::builder()
+            .timeout(config.timeout())
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent(concat!("blog-cms-webhooks/", env!("CARGO_PKG_VERSION")));
+        if !config.allow_private_targets {
+            client = client.dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = client
+            .build()
+            .expect("Error building the webhook HTTP client");
+        Self {
+            webhook_repo,
+            client,
+            max_attempts: config.max_attempts,
+            retry_base_delay: config.retry_base_delay(),
+            timeout: config.timeout(),
+            allow_private_targets: config.allow_private_targets,
+            queued: Arc::new(Notify::new()),
+        }
+    }
+}
+
+impl WebhookService {
+    // Create webhook
+    #[instrument(skip(self, webhook), err(Display, level = "warn"))]
+    pub async fn create(&self, webhook: CreateWebhook) -> Result<WebhookResponse, WebhookError> {
+        validate_url(&webhook.url, self.allow_private_targets)?;
+        let events = validate_events(webhook.events)?;
+        validate_secret(&webhook.secret)?;
+        let webhook = self
+            .webhook_repo
+            .create(
+                &webhook.url,
+                &webhook.secret,
+                &events,
+                webhook.active.unwrap_or(true),
+            )
+            .await?;
+        Ok(webhook.into())
+    }
+
+    // Find all webhooks
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_all(&self) -> Result<WebhookListResponse, sqlx::Error> {
+        let webhooks = self.webhook_repo.find_all().await?;
+        Ok(WebhookListResponse {
+            webhooks: webhooks.into_iter().map(WebhookResponse::from).collect(),
+        })
+    }
+
+    // Find webhook by id
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_by_id(&self, id: Uuid) -> Result<WebhookResponse, sqlx::Error> {
+        Ok(self.webhook_repo.find_by_id(id).await?.into())
+    }
+
+    // Update webhook by id
+    #[instrument(skip(self, webhook), err(Display, level = "warn"))]
+    pub async fn update_by_id(
+        &self,
+        id: Uuid,
+        mut webhook: UpdateWebhook,
+    ) -> Result<WebhookResponse, WebhookError> {
+        if let Some(url) = &webhook.url {
+            validate_url(url, self.allow_private_targets)?;
+        }
+        if let Some(events) = webhook.events.take() {
+            webhook.events = Some(validate_events(events)?);
+        }
+        if let Some(secret) = &webhook.secret {
+            validate_secret(secret)?;
+        }
+        Ok(self.webhook_repo.update(id, webhook).await?.into())
+    }
+
+    // Delete webhook by id
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn delete_by_id(&self, id: Uuid) -> Result<(), sqlx::Error> {
+        self.webhook_repo.delete(id).await
+    }
+
+    // Find the latest deliveries of a webhook, newest first
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn find_deliveries(
+        &self,
+        webhook_id: Uuid,
+    ) -> Result<WebhookDeliveryListResponse, sqlx::Error> {
+        // An unknown webhook is reported as such rather than as an empty log
+        self.webhook_repo.find_by_id(webhook_id).await?;
+        let deliveries = self
+            .webhook_repo
+            .find_deliveries(webhook_id, DELIVERY_LOG_LIMIT)
+            .await?;
+        Ok(WebhookDeliveryListResponse {
+            deliveries: deliveries
+                .into_iter()
+                .map(WebhookDeliveryResponse::from)
+                .collect(),
+        })
+    }
+
+    // Queue a fresh delivery of an earlier delivery's payload
+    #[instrument(skip(self), err(Display, level = "warn"))]
+    pub async fn redeliver(
+        &self,
+        webhook_id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDeliveryResponse, sqlx::Error> {
+        let original = self
+            .webhook_repo
+            .find_delivery(webhook_id, delivery_id)
+            .await?;
+        let delivery = self
+            .webhook_repo
+            .create_delivery(webhook_id, original.event, &original.payload, Utc::now())
+            .await?;
+        self.queued.notify_one();
+        Ok(delivery.into())
+    }
+
+    // Queue `data` for every active webhook subscribed to `event`, once per
+    // webhook however often the outbox message is relayed
+    async fn enqueue<T: Serialize>(
+        &self,
+        message: &OutboxMessage,
+        event: WebhookEvent,
+        data: &T,
+    ) -> Result<(), sqlx::Error> {
+        let webhooks: Vec<Webhook> = self
+            .webhook_repo
+            .find_all()
+            .await?
+            .into_iter()
+            .filter(|webhook| webhook.subscribes_to(event))
+            .collect();
+        if webhooks.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now();
+        let payload =
+            json!({ "event": event, "created_at": message.created_at, "data": data }).to_string();
+        for webhook in webhooks {
+            self.webhook_repo
+                .create_outbox_delivery(webhook.id, message.id, event, &payload, now)
+                .await?;
+        }
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    // Deliver queued events in the background until shutdown
+    pub fn spawn_worker(&self, shutdown: &Shutdown, heartbeats: &Heartbeats) {
+        let service = self.clone();
+        // A round may spend up to the request timeout waiting on receivers
+        let heartbeat = heartbeats.register("webhook_delivery", POLL_INTERVAL * 3 + self.timeout);
+        shutdown.spawn_worker(|token| async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = service.queued.notified() => {}
+                    _ = token.cancelled() => break,
+                }
+                if let Err(e) = service.deliver_due().await {
+                    tracing::warn!(error = %e, "Failed to deliver webhooks");
+                }
+                heartbeat.beat();
+            }
+            heartbeat.deregister();
+        });
+    }
+
+    // Attempt every delivery that is due, up to one batch
+    async fn deliver_due(&self) -> Result<(), sqlx::Error> {
+        let due = self
+            .webhook_repo
+            .find_due_deliveries(Utc::now(), BATCH_SIZE)
+            .await?;
+        if due.len() as i64 == BATCH_SIZE {
+            // Come straight back for the rest
+            self.queued.notify_one();
+        }
+        join_all(due.into_iter().map(|delivery| self.attempt(delivery))).await;
+        let pending = self.webhook_repo.count_pending().await?;
+        crate::metrics::set_queue_depth("webhooks", pending as usize);
+        Ok(())
+    }
+
+    async fn attempt(&self, delivery: WebhookDelivery) {
+        if let Err(e) = self.try_attempt(&delivery).await {
+            tracing::warn!(delivery_id = %delivery.id, error = %e, "Failed to attempt webhook delivery");
+        }
+    }
+
+    #[instrument(skip_all, fields(delivery_id = %delivery.id, event = %delivery.event))]
+    async fn try_attempt(&self, delivery: &WebhookDelivery) -> Result<(), sqlx::Error> {
+        // Lease the delivery so no other instance sends it meanwhile; should
+        // this one die mid-attempt the lease simply runs out
+        let now = Utc::now();
+        let lease_until = now + self.timeout * 2;
+        if !self
+            .webhook_repo
+            .claim_delivery(delivery.id, now, lease_until)
+            .await?
+        {
+            return Ok(());
+        }
+        let webhook = self.webhook_repo.find_by_id(delivery.webhook_id).await?;
+
+        // Webhooks created before private targets were disallowed are
+        // refused here; names are checked by the resolver as they are looked up
+        let allowed = self.allow_private_targets
+            || Url::parse(&webhook.url).is_ok_and(|url| is_public_host(&url));
+        let response = if allowed {
+            self.client
+                .post(&webhook.url)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, delivery.event.as_str())
+                .header(DELIVERY_HEADER, delivery.id.to_string())
+                .header(
+                    SIGNATURE_HEADER,
+                    format!("sha256={}", sign(&webhook.secret, &delivery.payload)),
+                )
+                .body(delivery.payload.clone())
+                .send()
+                .await
+                .map_err(|e| e.to_string())
+        } else {
+            Err(String::from(PRIVATE_TARGET))
+        };
+        let (response_code, error) = match response {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16() as i32), None)
+            }
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let mut error = format!("Receiver answered {}", status);
+                if !body.trim().is_empty() {
+                    error.push_str(": ");
+                    error.extend(body.trim().chars().take(MAX_ERROR_LENGTH));
+                }
+                (Some(status.as_u16() as i32), Some(error))
+            }
+            Err(e) => (None, Some(e)),
+        };
+
+        let now = Utc::now();
+        let attempts = delivery.attempts as u32 + 1;
+        let attempt = match error {
+            None => DeliveryAttempt {
+                status: DeliveryStatus::Delivered,
+                response_code,
+                error: None,
+                next_attempt_at: now,
+                delivered_at: Some(now),
+            },
+            Some(error) if attempts >= self.max_attempts => DeliveryAttempt {
+                status: DeliveryStatus::Failed,
+                response_code,
+                error: Some(error),
+                next_attempt_at: now,
+                delivered_at: None,
+            },
+            Some(error) => DeliveryAttempt {
+                status: DeliveryStatus::Pending,
+                response_code,
+                error: Some(error),
+                next_attempt_at: now + retry_delay(self.retry_base_delay, attempts),
+                delivered_at: None,
+            },
+        };
+        let outcome = match attempt.status {
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Pending => "retrying",
+            DeliveryStatus::Failed => "failed",
+        };
+        metrics::counter!("webhook_deliveries_total", "outcome" => outcome).increment(1);
+        tracing::debug!(
+            outcome,
+            attempts,
+            ?response_code,
+            "Attempted webhook delivery"
+        );
+        self.webhook_repo.record_attempt(delivery.id, attempt).await
+    }
+}
+
+// Queues webhook deliveries for events recorded in the outbox
+#[async_trait]
+impl OutboxSubscriber for WebhookService {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn handle(&self, message: &OutboxMessage) -> Result<(), sqlx::Error> {
+        let event = match message.event {
+            OutboxEvent::PostPublished => WebhookEvent::PostPublished,
+            OutboxEvent::PostUpdated => WebhookEvent::PostUpdated,
+            OutboxEvent::UserCreated => WebhookEvent::UserCreated,
+            OutboxEvent::CommentCreated => WebhookEvent::CommentCreated,
+            OutboxEvent::PostCreated => return Ok(()),
+        };
+        if message.event.is_post_event() {
+            let mut post: PostResponse = serde_json::from_str(&message.payload)
+                .map_err(|e| sqlx::Error::Decode(e.into()))?;
+            fill_excerpt(&mut post);
+            self.enqueue(message, event, &post).await
+        } else {
+            let data: serde_json::Value = serde_json::from_str(&message.payload)
+                .map_err(|e| sqlx::Error::Decode(e.into()))?;
+            self.enqueue(message, event, &data).await
+        }
+    }
+}
+
+#[cfg(test)]
+impl WebhookService {
+    // A service over an empty in-memory repository, for other services' tests
+    pub fn in_memory() -> Self {
+        let config = WebhookConfig {
+            max_attempts: 3,
+            retry_base_delay_secs: 1,
+            timeout_secs: 1,
+            allow_private_targets: true,
+        };
+        let repo = Arc::new(crate::repositories::memory::InMemoryWebhookRepository::new());
+        Self::new(repo, &config)
+    }
+}
+
+// Hex HMAC-SHA256 of `body`, keyed with the webhook's secret
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+// Wait before attempt `attempts + 1`: the base delay, doubled after every
+// failed attempt, up to an hour
+fn retry_delay(base: Duration, attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    base.saturating_mul(factor).min(MAX_RETRY_DELAY)
+}
+
+fn validate_url(url: &str, allow_private_targets: bool) -> Result<(), WebhookError> {
+    let parsed = Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+    let Some(parsed) = parsed else {
+        return Err(WebhookError::Invalid(format!(
+            "url must be an http(s) URL, got `{}`",
+            url
+        )));
+    };
+    if !allow_private_targets && !is_public_host(&parsed) {
+        return Err(WebhookError::Invalid(format!(
+            "{}, got `{}`",
+            PRIVATE_TARGET, url
+        )));
+    }
+    Ok(())
+}
+
+// Whether `url` names a host webhooks may reach without
+// `allow_private_targets`, as far as can be told without resolving it
+fn is_public_host(url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    // IPv6 hosts come in brackets
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => is_public(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+    }
+}
+
+// Whether `ip` is a public unicast address rather than a loopback,
+// link-local, private or otherwise internal one
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || first == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && second & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local, fc00::/7
+                    || first & 0xfe00 == 0xfc00
+                    // Link-local, fe80::/10
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+// Resolves receiver hosts with the system resolver but drops addresses that
+// aren't public, so a name can't be pointed at internal services after the
+// webhook was accepted
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} resolves to no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn validate_events(mut events: Vec<WebhookEvent>) -> Result<Vec<WebhookEvent>, WebhookError> {
+    events.sort_by_key(|event| event.as_str());
+    events.dedup();
+    if events.is_empty() {
+        return Err(WebhookError::Invalid(String::from(
+            "events must name at least one event",
+        )));
+    }
+    Ok(events)
+}
+
+fn validate_secret(secret: &str) -> Result<(), WebhookError> {
+    if secret.trim().is_empty() {
+        return Err(WebhookError::Invalid(String::from(
+            "secret must not be empty",
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_webhook(events: Vec<WebhookEvent>) -> CreateWebhook {
+        CreateWebhook {
+            url: String::from("http://127.0.0.1:9/hook"),
+            events,
+            secret: String::from("secret"),
+            active: None,
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_an_hour() {
+        let base = Duration::from_secs(30);
+        assert_eq!(retry_delay(base, 1), Duration::from_secs(30));
+        assert_eq!(retry_delay(base, 2), Duration::from_secs(60));
+        assert_eq!(retry_delay(base, 4), Duration::from_secs(240));
+        assert_eq!(retry_delay(base, 40), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn signature_is_hex_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_subscriptions_are_rejected() {
+        let service = WebhookService::in_memory();
+        let mut webhook = new_webhook(vec![WebhookEvent::PostUpdated]);
+        webhook.url = String::from("ftp://example.com");
+        assert!(matches!(
+            service.create(webhook).await,
+            Err(WebhookError::Invalid(_))
+        ));
+        assert!(matches!(
+            service.create(new_webhook(Vec::new())).await,
+            Err(WebhookError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn private_targets_are_refused_unless_allowed() {
+        for url in [
+            "http://127.0.0.1:9/hook",
+            "http://localhost/hook",
+            "http://api.localhost/hook",
+            "http://10.0.0.1/hook",
+            "http://192.168.1.10/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fe80::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(
+                matches!(validate_url(url, false), Err(WebhookError::Invalid(_))),
+                "{}",
+                url
+            );
+            assert!(validate_url(url, true).is_ok(), "{}", url);
+        }
+        assert!(validate_url("https://example.com/hook", false).is_ok());
+        assert!(validate_url("http://93.184.216.34/hook", false).is_ok());
+    }
+
+    fn message(event: OutboxEvent, data: serde_json::Value) -> OutboxMessage {
+        OutboxMessage {
+            id: Uuid::new_v4(),
+            event,
+            aggregate_id: Uuid::new_v4(),
+            payload: data.to_string(),
+            attempts: 0,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn events_are_queued_for_subscribed_webhooks_only() {
+        let service = WebhookService::in_memory();
+        let posts = service
+            .create(new_webhook(vec![WebhookEvent::PostUpdated]))
+            .await
+            .unwrap();
+        let users = service
+            .create(new_webhook(vec![WebhookEvent::UserCreated]))
+            .await
+            .unwrap();
+        let mut inactive = new_webhook(vec![WebhookEvent::UserCreated]);
+        inactive.active = Some(false);
+        let inactive = service.create(inactive).await.unwrap();
+
+        service
+            .handle(&message(
+                OutboxEvent::UserCreated,
+                json!({ "username": "alice" }),
+            ))
+            .await
+            .unwrap();
+
+        let delivered = service.find_deliveries(users.id).await.unwrap().deliveries;
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].event, WebhookEvent::UserCreated);
+        assert_eq!(delivered[0].status, DeliveryStatus::Pending);
+        assert_eq!(delivered[0].payload["data"]["username"], "alice");
+        assert!(service
+            .find_deliveries(posts.id)
+            .await
+            .unwrap()
+            .deliveries
+            .is_empty());
+        assert!(service
+            .find_deliveries(inactive.id)
+            .await
+            .unwrap()
+            .deliveries
+            .is_empty());
+
+        let redelivery = service.redeliver(users.id, delivered[0].id).await.unwrap();
+        assert_ne!(redelivery.id, delivered[0].id);
+        assert_eq!(redelivery.payload, delivered[0].payload);
+    }
+
+    #[tokio::test]
+    async fn each_outbox_message_is_queued_once_per_webhook() {
+        let service = WebhookService::in_memory();
+        let comments = service
+            .create(new_webhook(vec![WebhookEvent::CommentCreated]))
+            .await
+            .unwrap();
+        let created = message(OutboxEvent::CommentCreated, json!({ "content": "Hi" }));
+
+        // A relay that dies before marking the message processed relays it again
+        service.handle(&created).await.unwrap();
+        service.handle(&created).await.unwrap();
+
+        let delivered = service
+            .find_deliveries(comments.id)
+            .await
+            .unwrap()
+            .deliveries;
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].payload["data"]["content"], "Hi");
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    future::{Future, IntoFuture},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+// Coordinates shutdown between the HTTP server and background workers.
+// Workers spawned through `spawn_worker` receive a token that is cancelled
+// once shutdown starts, and are awaited (up to the drain timeout) before the
+// process exits.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    workers: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Begin shutting down
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    // Resolves once shutdown has been triggered
+    pub async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+
+    // Run a background worker that should stop when `token` is cancelled
+    pub fn spawn_worker<F, Fut>(&self, worker: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.workers.spawn(worker(self.token.child_token()));
+    }
+
+    // Wait for all background workers to finish, returning false on timeout
+    pub async fn wait_for_workers(&self, timeout: Duration) -> bool {
+        self.workers.close();
+        tokio::time::timeout(timeout, self.workers.wait())
+            .await
+            .is_ok()
+    }
+}
+
+// Trigger `shutdown` on SIGINT or SIGTERM
+pub fn trigger_on_signal(shutdown: Shutdown) {
+    tokio::spawn(async move {
+        let ctrl_c = async {
+            tokio::signal::ctrl_c()
+                .await
+                .expect("Error installing Ctrl+C handler");
+        };
+
+        #[cfg(unix)]
+        let terminate = async {
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("Error installing SIGTERM handler")
+                .recv()
+                .await;
+        };
+
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = ctrl_c => {},
+            _ = terminate => {},
+            _ = shutdown.triggered() => return,
+        }
+        tracing::info!("Received shutdown signal");
+        shutdown.trigger();
+    });
+}
+
+// Serve `router` until `shutdown` is triggered. New connections are refused
+// from that point on, while in-flight requests get up to `drain_timeout` to
+// complete; background workers are then given the same budget to stop.
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    shutdown: Shutdown,
+    drain_timeout: Duration,
+) -> Result<(), std::io::Error> {
+    let token = shutdown.token.clone();
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move { token.cancelled().await });
+    let mut server = tokio::spawn(server.into_future());
+
+    tokio::select! {
+        // The server only stops on its own when it fails
+        result = &mut server => return result.map_err(std::io::Error::other)?,
+        _ = shutdown.triggered() => {}
+    }
+
+    tracing::info!("Shutting down, draining in-flight requests");
+    match tokio::time::timeout(drain_timeout, &mut server).await {
+        Ok(result) => result.map_err(std::io::Error::other)??,
+        Err(_) => {
+            server.abort();
+            tracing::warn!(
+                ?drain_timeout,
+                "Drain timeout elapsed, dropping remaining connections"
+            );
+        }
+    }
+
+    if !shutdown.wait_for_workers(drain_timeout).await {
+        tracing::warn!(?drain_timeout, "Background workers did not stop in time");
+    }
+    Ok(())
+}
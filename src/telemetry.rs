@@ -0,0 +1,176 @@
+use axum::{
+    extract::MatchedPath,
+    http::{HeaderMap, Request},
+};
+use tracing::Span;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::config::LogConfig;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+// Keeps the OTLP exporter alive; spans still buffered are flushed on drop
+#[derive(Debug, Default)]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otlp")]
+    tracer_provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.tracer_provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Error flushing traces: {}", e);
+            }
+        }
+    }
+}
+
+// Install the global tracing subscriber. `RUST_LOG` overrides the configured
+// level; SQL statements and their timings are logged by sqlx at debug level.
+pub fn init(config: &LogConfig) -> TelemetryGuard {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        EnvFilter::new(format!(
+            "warn,blog_cms={level},tower_http={level},sqlx={level}",
+            level = config.level
+        ))
+    });
+    let fmt_layer = if config.format == "json" {
+        fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed()
+    } else {
+        fmt::layer().boxed()
+    };
+    let registry = tracing_subscriber::registry().with(filter).with(fmt_layer);
+
+    #[cfg(feature = "otlp")]
+    {
+        let (otel_layer, tracer_provider) = match &config.otlp_endpoint {
+            Some(endpoint) => match otlp::layer(endpoint) {
+                Ok((layer, provider)) => (Some(layer), Some(provider)),
+                Err(e) => {
+                    eprintln!("Error creating OTLP exporter for {}: {}", endpoint, e);
+                    (None, None)
+                }
+            },
+            None => (None, None),
+        };
+        // A subscriber may already be installed, e.g. when tests start several apps
+        let _ = registry.with(otel_layer).try_init();
+        TelemetryGuard { tracer_provider }
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        let _ = registry.try_init();
+        if config.otlp_endpoint.is_some() {
+            tracing::warn!("log.otlp_endpoint is set but this build lacks the `otlp` feature");
+        }
+        TelemetryGuard::default()
+    }
+}
+
+// Root span for every HTTP request. The request id is set (or propagated from
+// the client) by the request id middleware before this runs, and an incoming
+// W3C `traceparent` links the span to the caller's trace.
+pub fn make_request_span<B>(request: &Request<B>) -> Span {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let request_id = header_str(request.headers(), REQUEST_ID_HEADER).unwrap_or_default();
+    let span = tracing::info_span!(
+        "http_request",
+        method = %request.method(),
+        path = %path,
+        request_id = %request_id,
+        trace_id = tracing::field::Empty,
+    );
+    if let Some(trace_id) =
+        header_str(request.headers(), TRACEPARENT_HEADER).and_then(parse_traceparent)
+    {
+        span.record("trace_id", trace_id);
+    }
+
+    #[cfg(feature = "otlp")]
+    otlp::set_remote_parent(&span, request.headers());
+
+    span
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+// Extract the trace id from `00-<trace-id>-<parent-id>-<flags>`
+fn parse_traceparent(traceparent: &str) -> Option<&str> {
+    let mut parts = traceparent.split('-');
+    let (version, trace_id, parent_id, flags) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let is_hex = |value: &str, len: usize| {
+        value.len() == len && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+    };
+    let valid = is_hex(version, 2)
+        && is_hex(trace_id, 32)
+        && is_hex(parent_id, 16)
+        && is_hex(flags, 2)
+        && trace_id.bytes().any(|byte| byte != b'0');
+    valid.then_some(trace_id)
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use axum::http::HeaderMap;
+    use opentelemetry::{
+        propagation::Extractor, trace::TracerProvider as _, KeyValue,
+    };
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{propagation::TraceContextPropagator, runtime, trace, Resource};
+    use tracing::Span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::{registry::LookupSpan, Layer};
+
+    pub fn layer<S>(
+        endpoint: &str,
+    ) -> Result<(impl Layer<S>, trace::TracerProvider), opentelemetry::trace::TraceError>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()?;
+        let provider = trace::TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new("service.name", "blog-cms")]))
+            .build();
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer = provider.tracer("blog-cms");
+        Ok((tracing_opentelemetry::layer().with_tracer(tracer), provider))
+    }
+
+    pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(headers))
+        });
+        span.set_parent(parent);
+    }
+
+    struct HeaderExtractor<'a>(&'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|key| key.as_str()).collect()
+        }
+    }
+}
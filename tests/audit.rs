@@ -0,0 +1,153 @@
+mod common;
+
+use common::{data, TestApp, TEST_PASSWORD};
+use reqwest::{header, StatusCode};
+use serde_json::{json, Value};
+
+async fn audit_log(app: &TestApp, token: &str, query: &str) -> Vec<Value> {
+    let response = app
+        .client
+        .get(app.url(&format!("/api/audit{}", query)))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let log = data(response, StatusCode::OK).await;
+    log["entries"].as_array().unwrap().clone()
+}
+
+#[tokio::test]
+pub async fn audit_log_needs_the_audit_read_permission() {
+    let app = TestApp::spawn().await;
+    assert_eq!(
+        app.get("/api/audit").await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let user = app.create_user("alice").await;
+    let token = app.login(&user).await;
+    let response = app
+        .client
+        .get(app.url("/api/audit"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+pub async fn changes_are_recorded_with_actor_ip_and_request_id() {
+    let app = TestApp::spawn().await;
+    let admin = app.create_user("admin").await;
+    app.grant(&admin, &["audit:read", "role:manage"]).await;
+    let token = app.login(&admin).await;
+    let role = app.create_role("reviewer").await;
+
+    let path = format!("/api/role/{}", role["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let response = app
+        .client
+        .put(app.url(&path))
+        .bearer_auth(&token)
+        .header(header::IF_MATCH, etag)
+        .header("x-request-id", "rename-editor")
+        .json(&json!({ "id": role["id"], "role_name": "writer" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    data(response, StatusCode::OK).await;
+
+    let query = format!(
+        "?target_type=role&target_id={}",
+        role["id"].as_str().unwrap()
+    );
+    let entries = audit_log(&app, &token, &query).await;
+    assert_eq!(entries.len(), 2);
+    let update = &entries[0];
+    assert_eq!(update["action"], "update");
+    assert_eq!(update["actor_id"], admin["id"]);
+    assert_eq!(update["ip"], "127.0.0.1");
+    assert_eq!(update["request_id"], "rename-editor");
+    assert_eq!(update["before"]["role_name"], "reviewer");
+    assert_eq!(update["after"]["role_name"], "writer");
+    // The role was created by the harness admin
+    assert_eq!(entries[1]["action"], "create");
+    assert_eq!(entries[1]["actor_id"], app.root().await.0["id"]);
+
+    let query = format!("?actor_id={}", admin["id"].as_str().unwrap());
+    assert_eq!(audit_log(&app, &token, &query).await.len(), 1);
+}
+
+#[tokio::test]
+pub async fn revoked_tokens_and_proxies_are_seen_through() {
+    let app = TestApp::spawn_with_config("[rate_limit]\ntrusted_proxies = [\"127.0.0.1\"]\n").await;
+    let admin = app.create_user("admin").await;
+    app.grant(&admin, &["audit:read"]).await;
+    let token = app.login(&admin).await;
+    let alice = app.create_user("alice").await;
+    let revoked = app.login(&alice).await;
+    let change = json!({ "current_password": TEST_PASSWORD, "new_password": "a new passphrase" });
+    let response = app
+        .client
+        .post(app.url("/api/auth/password/change"))
+        .bearer_auth(&revoked)
+        .json(&change)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    data(response, StatusCode::OK).await;
+
+    let response = app
+        .client
+        .post(app.url("/api/post"))
+        .bearer_auth(&revoked)
+        .header("x-forwarded-for", "203.0.113.7")
+        .json(&json!({
+            "title": "Hello",
+            "content": "Post content",
+            "status": "Draft",
+            "user_id": alice["id"],
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let post = data(response, StatusCode::CREATED).await;
+
+    let query = format!("?target_id={}", post["id"].as_str().unwrap());
+    let entries = audit_log(&app, &token, &query).await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["actor_id"], Value::Null);
+    assert_eq!(entries[0]["ip"], "203.0.113.7");
+}
+
+#[tokio::test]
+pub async fn deleted_users_keep_their_audit_trail() {
+    let app = TestApp::spawn().await;
+    let admin = app.create_user("admin").await;
+    app.grant(&admin, &["audit:read"]).await;
+    let token = app.login(&admin).await;
+    let user = app.create_user("bob").await;
+
+    let path = format!("/api/user/{}", user["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let response = app.delete_if_match_as_root(&path, &etag).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let query = format!(
+        "?target_type=user&target_id={}",
+        user["id"].as_str().unwrap()
+    );
+    let entries = audit_log(&app, &token, &query).await;
+    assert_eq!(entries[0]["action"], "delete");
+    assert_eq!(entries[0]["before"]["username"], "bob");
+    assert_eq!(entries[0]["after"], Value::Null);
+
+    // Nothing about the user was recorded before it was created
+    let to = entries[1]["created_at"]
+        .as_str()
+        .unwrap()
+        .replace('+', "%2B");
+    let query = format!("?target_id={}&to={}", user["id"].as_str().unwrap(), to);
+    assert!(audit_log(&app, &token, &query).await.is_empty());
+}
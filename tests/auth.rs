@@ -0,0 +1,39 @@
+mod common;
+
+use common::{data, TestApp, TEST_PASSWORD};
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+pub async fn login_returns_bearer_token() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+
+    for login in ["alice", "alice@example.com"] {
+        let body = json!({ "login": login, "password": TEST_PASSWORD });
+        let token = data(
+            app.post_json("/api/auth/login", &body).await,
+            StatusCode::OK,
+        )
+        .await;
+
+        assert_eq!(token["token_type"], "Bearer");
+        assert_eq!(token["expires_in"], 3600);
+        assert!(!token["access_token"].as_str().unwrap().is_empty());
+    }
+    assert!(!app.login(&user).await.is_empty());
+}
+
+#[tokio::test]
+pub async fn login_with_bad_credentials_is_rejected() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice").await;
+
+    let wrong_password = json!({ "login": "alice", "password": "wrong" });
+    let unknown_user = json!({ "login": "mallory", "password": TEST_PASSWORD });
+
+    for body in [wrong_password, unknown_user] {
+        let response = app.post_json("/api/auth/login", &body).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{data, TestApp};
+use reqwest::{header, Response, StatusCode};
+use serde_json::{json, Value};
+
+// An event read off the stream
+#[derive(Debug)]
+struct SseEvent {
+    id: Option<String>,
+    event: String,
+    data: Value,
+}
+
+// Reads a text/event-stream response frame by frame
+struct EventStream {
+    response: Response,
+    buffer: String,
+}
+
+impl EventStream {
+    async fn open(app: &TestApp, query: &str, token: &str, last_event_id: Option<&str>) -> Self {
+        let mut request = app
+            .client
+            .get(app.url(&format!("/api/events{}", query)))
+            .bearer_auth(token);
+        if let Some(id) = last_event_id {
+            request = request.header("Last-Event-ID", id);
+        }
+        let response = request.send().await.expect("Failed to execute request.");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        EventStream {
+            response,
+            buffer: String::new(),
+        }
+    }
+
+    // The next frame, without its trailing blank line
+    async fn next_frame(&mut self) -> String {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let frame = self.buffer[..end].to_string();
+                self.buffer.drain(..end + 2);
+                return frame;
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(10), self.response.chunk())
+                .await
+                .expect("No event arrived")
+                .expect("Stream failed")
+                .expect("Stream ended");
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+
+    // The next event, skipping keepalive pings
+    async fn next(&mut self) -> SseEvent {
+        loop {
+            let frame = self.next_frame().await;
+            let mut event = SseEvent {
+                id: None,
+                event: String::new(),
+                data: Value::Null,
+            };
+            for line in frame.lines() {
+                if let Some(id) = line.strip_prefix("id: ") {
+                    event.id = Some(id.to_string());
+                } else if let Some(name) = line.strip_prefix("event: ") {
+                    event.event = name.to_string();
+                } else if let Some(payload) = line.strip_prefix("data: ") {
+                    event.data = serde_json::from_str(payload).unwrap();
+                }
+            }
+            if !event.event.is_empty() {
+                return event;
+            }
+        }
+    }
+}
+
+async fn publish(app: &TestApp, post: &Value, title: &str) {
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let body = json!({ "id": post["id"], "title": title, "status": "Published" });
+    let etag = app.etag(&path).await;
+    data(
+        app.put_json_if_match(&path, &body, &etag).await,
+        StatusCode::OK,
+    )
+    .await;
+}
+
+#[tokio::test]
+pub async fn stream_needs_a_token_and_permitted_topics() {
+    let app = TestApp::spawn().await;
+    let response = app.get("/api/events").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.get("/api/events?access_token=not-a-token").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let user = app.create_user("alice").await;
+    let token = app.login(&user).await;
+    let path = format!("/api/events?topics=posts,media&access_token={}", token);
+    assert_eq!(app.get(&path).await.status(), StatusCode::BAD_REQUEST);
+    let path = format!("/api/events?topics=users&access_token={}", token);
+    assert_eq!(app.get(&path).await.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+pub async fn post_changes_are_streamed_and_can_be_resumed() {
+    let app = TestApp::spawn().await;
+    let author = app.create_user("alice").await;
+    let reader = app.create_user("bob").await;
+    let token = app.login(&reader).await;
+    let mut stream = EventStream::open(&app, "?topics=posts", &token, None).await;
+
+    // Drafts are only streamed to those allowed to read them, so the first
+    // event the reader sees is the update that publishes the post
+    let post = app.create_post(&author, "Draft").await;
+    publish(&app, &post, "Hello").await;
+    let updated = stream.next().await;
+    assert_eq!(updated.event, "post.updated");
+    assert_eq!(updated.data["id"], post["id"]);
+    assert_eq!(updated.data["title"], "Hello");
+    let published = stream.next().await;
+    assert_eq!(published.event, "post.published");
+    let last_id = published.id.unwrap();
+    drop(stream);
+
+    // Changes made while disconnected are replayed on reconnect
+    publish(&app, &post, "Hello again").await;
+    let mut resumed = EventStream::open(&app, "?topics=posts", &token, Some(&last_id)).await;
+    let missed = resumed.next().await;
+    assert_eq!(missed.event, "post.updated");
+    assert_eq!(missed.data["title"], "Hello again");
+
+    // Events that are no longer buffered can't be replayed
+    let mut unknown = EventStream::open(&app, "", &token, Some("999999")).await;
+    assert_eq!(unknown.next().await.event, "resync");
+}
+
+#[tokio::test]
+pub async fn user_events_need_permission_and_idle_streams_are_pinged() {
+    let app = TestApp::spawn_with_config("[events]\nkeepalive_secs = 1\n").await;
+    let admin = app.create_user("admin").await;
+    app.grant(&admin, &["user:manage"]).await;
+    let token = app.login(&admin).await;
+    let mut stream = EventStream::open(&app, "?topics=users", &token, None).await;
+
+    let user = app.create_user("carol").await;
+    let created = stream.next().await;
+    assert_eq!(created.event, "user.created");
+    assert_eq!(created.data["id"], user["id"]);
+
+    assert_eq!(stream.next_frame().await, ": ping");
+}
@@ -0,0 +1,226 @@
+mod common;
+
+use std::time::Duration;
+
+use common::TestApp;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+const CREATE_COMMENT: &str = r#"
+    mutation ($postId: UUID!, $content: String!) {
+        createComment(postId: $postId, content: $content) { id content }
+    }
+"#;
+
+#[tokio::test]
+pub async fn post_with_author_role_and_comments_in_one_query() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let bob = app.create_user("bob").await;
+    let post = app.create_post(&alice, "Hello").await;
+    let token = app.login(&bob).await;
+    let variables = json!({ "postId": post["id"], "content": "Nice post" });
+    let created = app.graphql(CREATE_COMMENT, variables, Some(&token)).await;
+    assert_eq!(created["data"]["createComment"]["content"], "Nice post");
+
+    let query = r#"
+        query ($id: UUID!) {
+            post(id: $id) {
+                title
+                status
+                author { username role { roleName } }
+                comments { content author { username } }
+            }
+        }
+    "#;
+    let body = app.graphql(query, json!({ "id": post["id"] }), None).await;
+
+    assert!(body.get("errors").is_none(), "unexpected errors: {}", body);
+    let post = &body["data"]["post"];
+    assert_eq!(post["title"], "Hello");
+    assert_eq!(post["status"], "DRAFT");
+    assert_eq!(post["author"]["username"], "alice");
+    assert_eq!(post["author"]["role"]["roleName"], "alice-role");
+    assert_eq!(post["comments"][0]["content"], "Nice post");
+    assert_eq!(post["comments"][0]["author"]["username"], "bob");
+}
+
+#[tokio::test]
+pub async fn viewer_comes_from_the_bearer_token() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let token = app.login(&alice).await;
+
+    let anonymous = app
+        .graphql("{ viewer { username } }", json!({}), None)
+        .await;
+    let authenticated = app
+        .graphql("{ viewer { username } }", json!({}), Some(&token))
+        .await;
+
+    assert_eq!(anonymous["data"]["viewer"], Value::Null);
+    assert_eq!(authenticated["data"]["viewer"]["username"], "alice");
+    let response = app
+        .client
+        .post(app.url("/api/graphql"))
+        .bearer_auth(format!("{}x", token))
+        .json(&json!({ "query": "{ viewer { username } }" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+pub async fn mutations_check_the_viewer() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let bob = app.create_user("bob").await;
+    let post = app.create_post(&alice, "Hello").await;
+    let update = r#"
+        mutation ($id: UUID!) {
+            updatePost(id: $id, input: { title: "Changed" }) { title }
+        }
+    "#;
+    let variables = json!({ "id": post["id"] });
+
+    let anonymous = app.graphql(update, variables.clone(), None).await;
+    let bob_token = app.login(&bob).await;
+    let not_author = app
+        .graphql(update, variables.clone(), Some(&bob_token))
+        .await;
+    let alice_token = app.login(&alice).await;
+    let author = app.graphql(update, variables, Some(&alice_token)).await;
+
+    assert_eq!(
+        anonymous["errors"][0]["extensions"]["code"],
+        "UNAUTHENTICATED"
+    );
+    assert_eq!(not_author["errors"][0]["extensions"]["code"], "FORBIDDEN");
+    assert_eq!(author["data"]["updatePost"]["title"], "Changed");
+}
+
+#[tokio::test]
+pub async fn user_and_role_mutations_need_manage_permissions() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let bob = app.create_user("bob").await;
+    app.grant(&alice, &["user:manage", "role:manage"]).await;
+    let alice_token = app.login(&alice).await;
+    let bob_token = app.login(&bob).await;
+    let create_role = r#"
+        mutation { createRole(input: { roleName: "reviewer" }) { id roleName } }
+    "#;
+    let update_user = r#"
+        mutation ($id: UUID!, $roleId: UUID!) {
+            updateUser(id: $id, input: { roleId: $roleId }) { username role { roleName } }
+        }
+    "#;
+
+    let forbidden = app.graphql(create_role, json!({}), Some(&bob_token)).await;
+    let created = app
+        .graphql(create_role, json!({}), Some(&alice_token))
+        .await;
+    let role = &created["data"]["createRole"];
+    let variables = json!({ "id": bob["id"], "roleId": role["id"] });
+    let updated = app
+        .graphql(update_user, variables, Some(&alice_token))
+        .await;
+    let delete_role = app
+        .graphql(
+            "mutation ($id: UUID!) { deleteRole(id: $id) }",
+            json!({ "id": role["id"] }),
+            Some(&alice_token),
+        )
+        .await;
+
+    assert_eq!(forbidden["errors"][0]["extensions"]["code"], "FORBIDDEN");
+    assert_eq!(role["roleName"], "reviewer");
+    assert_eq!(
+        updated["data"]["updateUser"]["role"]["roleName"],
+        "reviewer"
+    );
+    assert_eq!(delete_role["errors"][0]["extensions"]["code"], "CONFLICT");
+}
+
+#[tokio::test]
+pub async fn deeply_nested_queries_are_rejected() {
+    let app = TestApp::spawn().await;
+    let query = "{ posts { author { posts { author { posts { author { posts { author { posts \
+                 { author { posts { id } } } } } } } } } } } }";
+
+    let body = app.graphql(query, json!({}), None).await;
+
+    assert_eq!(body["data"], Value::Null);
+    let message = body["errors"][0]["message"].as_str().unwrap();
+    assert!(message.contains("nested too deep"), "{}", message);
+}
+
+#[tokio::test]
+pub async fn new_comments_are_pushed_to_subscribers() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let post = app.create_post(&alice, "Hello").await;
+    let token = app.login(&alice).await;
+
+    let url = app.url("/api/graphql/ws").replacen("http", "ws", 1);
+    let mut request = url.into_client_request().unwrap();
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        "graphql-transport-ws".parse().unwrap(),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    let init = json!({
+        "type": "connection_init",
+        "payload": { "Authorization": format!("Bearer {}", token) },
+    });
+    socket.send(Message::Text(init.to_string())).await.unwrap();
+    let ack = next_json(&mut socket).await;
+    assert_eq!(ack["type"], "connection_ack");
+    let subscribe = json!({
+        "id": "1",
+        "type": "subscribe",
+        "payload": {
+            "query": "subscription ($postId: UUID!) { commentAdded(postId: $postId) { content author { username } } }",
+            "variables": { "postId": post["id"] },
+        },
+    });
+    socket
+        .send(Message::Text(subscribe.to_string()))
+        .await
+        .unwrap();
+
+    // The protocol doesn't acknowledge subscriptions, so keep commenting
+    // until the subscription is live
+    let variables = json!({ "postId": post["id"], "content": "Nice post" });
+    let mut pushed = None;
+    for _ in 0..25 {
+        app.graphql(CREATE_COMMENT, variables.clone(), Some(&token))
+            .await;
+        let next = tokio::time::timeout(Duration::from_millis(200), next_json(&mut socket));
+        if let Ok(message) = next.await {
+            pushed = Some(message);
+            break;
+        }
+    }
+    let message = pushed.expect("No comment was pushed");
+
+    assert_eq!(message["type"], "next");
+    let comment = &message["payload"]["data"]["commentAdded"];
+    assert_eq!(comment["content"], "Nice post");
+    assert_eq!(comment["author"]["username"], "alice");
+}
+
+async fn next_json<S>(socket: &mut S) -> Value
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        match socket.next().await.expect("Socket closed").unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            _ => continue,
+        }
+    }
+}
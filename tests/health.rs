@@ -1,18 +1,36 @@
+mod common;
+
+use common::TestApp;
+use reqwest::StatusCode;
+use serde_json::Value;
+
 #[tokio::test]
 pub async fn health_check() {
-    spawn_app();
+    let app = TestApp::spawn().await;
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get("http://localhost:3000/api/health")
-        .send()
-        .await
-        .expect("Failed to execute request.");
+    let response = app.get("/api/health").await;
 
     // Ensure the response is successful
     assert!(response.status().is_success());
 }
 
-pub fn spawn_app() {
-    let _ = tokio::spawn(blog_cms::run_app());
+#[tokio::test]
+pub async fn liveness_probe_succeeds() {
+    let app = TestApp::spawn().await;
+
+    let response = app.get("/api/health/live").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+pub async fn readiness_probe_reports_checks() {
+    let app = TestApp::spawn().await;
+
+    let response = app.get("/api/health/ready").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["checks"]["database"]["status"], "up");
+    assert_eq!(body["checks"]["migrations"]["status"], "up");
 }
@@ -0,0 +1,34 @@
+use axum::{middleware, routing::get, Router};
+use blog_cms::metrics;
+
+#[tokio::test]
+pub async fn requests_are_counted_by_route_template() {
+    metrics::install();
+    let app = Router::new()
+        .route("/items/:id", get(|| async { "item" }))
+        .layer(middleware::from_fn(metrics::track_http));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let client = reqwest::Client::new();
+    for id in ["1", "2"] {
+        client
+            .get(format!("http://{}/items/{}", address, id))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+    }
+    client
+        .get(format!("http://{}/missing", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    let output = metrics::render();
+    assert!(
+        output.contains(r#"http_requests_total{method="GET",path="/items/:id",status="200"} 2"#)
+    );
+    assert!(output.contains(r#"http_requests_total{method="GET",path="unmatched",status="404"} 1"#));
+    assert!(output.contains("http_request_duration_seconds_bucket"));
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use common::{data, TestApp};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::PATCH,
+];
+
+async fn spec(app: &TestApp) -> Value {
+    let response = app.get("/api/openapi.json").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.expect("Spec is not JSON")
+}
+
+// Every documented operation must be routed, and every other method on a
+// documented path must not be
+async fn assert_spec_matches_router(app: &TestApp) {
+    let spec = spec(app).await;
+    let paths = spec["paths"].as_object().expect("Spec has no paths");
+    assert!(!paths.is_empty());
+
+    for (path, item) in paths {
+        let url = path.replace("{id}", &Uuid::new_v4().to_string());
+        for method in &METHODS {
+            let response = app
+                .client
+                .request(method.clone(), app.url(&url))
+                .json(&json!({}))
+                .send()
+                .await
+                .expect("Failed to execute request.");
+            let status = response.status();
+            let body = response.bytes().await.unwrap();
+            // The router answers unknown paths with an empty 404, while
+            // handlers always send a body
+            let routed = status != StatusCode::METHOD_NOT_ALLOWED
+                && !(status == StatusCode::NOT_FOUND && body.is_empty());
+            let documented = item.get(method.as_str().to_lowercase()).is_some();
+            assert_eq!(
+                routed, documented,
+                "{} {}: routed {}, documented {}",
+                method, path, routed, documented
+            );
+        }
+    }
+}
+
+// Response fields must be exactly the documented properties
+fn assert_matches_schema(spec: &Value, schema: &str, value: &Value) {
+    let properties = spec["components"]["schemas"][schema]["properties"]
+        .as_object()
+        .unwrap_or_else(|| panic!("{} is not documented", schema));
+    let mut documented: Vec<&String> = properties.keys().collect();
+    let mut returned: Vec<&String> = value.as_object().unwrap().keys().collect();
+    documented.sort();
+    returned.sort();
+    assert_eq!(documented, returned, "{} drifted from the spec", schema);
+}
+
+#[tokio::test]
+pub async fn spec_matches_router() {
+    let app = TestApp::spawn().await;
+
+    assert_spec_matches_router(&app).await;
+}
+
+#[tokio::test]
+pub async fn spec_leaves_out_disabled_registration() {
+    let app = TestApp::spawn_with_config("[features]\nregistration = false\n").await;
+
+    let spec = spec(&app).await;
+    assert!(spec["paths"]["/api/user"].get("post").is_none());
+    assert_spec_matches_router(&app).await;
+}
+
+#[tokio::test]
+pub async fn disabled_features_are_not_routed() {
+    let app = TestApp::spawn_with_config("[features]\ngraphql = false\nmetrics = false\n").await;
+
+    let spec = spec(&app).await;
+    assert!(spec["paths"].get("/metrics").is_none());
+    assert_spec_matches_router(&app).await;
+    assert_eq!(app.get("/metrics").await.status(), StatusCode::NOT_FOUND);
+    let query = json!({ "query": "{ posts { id } }" });
+    let graphql = app.post_json("/api/graphql", &query).await;
+    assert_eq!(graphql.status(), StatusCode::NOT_FOUND);
+
+    let app = TestApp::spawn_with_config("[features]\ndocs = false\n").await;
+    let openapi = app.get("/api/openapi.json").await;
+    assert_eq!(openapi.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+pub async fn spec_matches_response_shapes() {
+    let app = TestApp::spawn().await;
+    let spec = spec(&app).await;
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3.1"));
+
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+    let role = data(
+        app.get(&format!("/api/role/{}", user["role_id"].as_str().unwrap()))
+            .await,
+        StatusCode::OK,
+    )
+    .await;
+    assert_matches_schema(&spec, "UserResponse", &user);
+    assert_matches_schema(&spec, "PostResponse", &post);
+    assert_matches_schema(&spec, "RoleResponse", &role);
+
+    let body: Value = app.get("/api/post").await.json().await.unwrap();
+    assert_matches_schema(&spec, "ApiResponse_PostListResponse", &body);
+    assert_matches_schema(&spec, "PostListResponse", &body["data"]);
+    let error: Value = app
+        .get("/api/post/search?q=%20")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_matches_schema(&spec, "ErrorResponse", &error);
+}
+
+#[tokio::test]
+pub async fn docs_are_served() {
+    let app = TestApp::spawn().await;
+
+    let response = app.get("/api/docs/").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = response.text().await.unwrap();
+    assert!(page.contains("swagger-ui"));
+}
@@ -0,0 +1,244 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{data, TestApp, TEST_PASSWORD};
+use reqwest::{Response, StatusCode};
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+const NEW_PASSWORD: &str = "tr0ub4dor&3";
+
+// A message accepted by the local SMTP sink
+struct Mail {
+    recipient: String,
+    data: String,
+}
+
+impl Mail {
+    // The reset token from the link in the message
+    fn token(&self) -> String {
+        let start = self.data.find("token=").expect("No reset link in mail") + "token=".len();
+        self.data[start..]
+            .chars()
+            .take_while(char::is_ascii_hexdigit)
+            .collect()
+    }
+}
+
+// Accepts every message sent to it, speaking just enough SMTP for the app
+struct SmtpSink {
+    url: String,
+    messages: mpsc::UnboundedReceiver<Mail>,
+}
+
+impl SmtpSink {
+    async fn spawn() -> SmtpSink {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind SMTP sink");
+        let url = format!("smtp://{}", listener.local_addr().unwrap());
+        let (sender, messages) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(session(stream, sender.clone()));
+            }
+        });
+        SmtpSink { url, messages }
+    }
+
+    async fn next(&mut self) -> Mail {
+        tokio::time::timeout(Duration::from_secs(10), self.messages.recv())
+            .await
+            .expect("No mail arrived")
+            .expect("SMTP sink stopped")
+    }
+}
+
+async fn session(stream: TcpStream, messages: mpsc::UnboundedSender<Mail>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let _ = writer.write_all(b"220 sink ready\r\n").await;
+    let mut recipient = String::new();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let reply: &[u8] = if line.starts_with("EHLO") {
+            b"250-sink\r\n250 8BITMIME\r\n"
+        } else if let Some(to) = line.strip_prefix("RCPT TO:") {
+            recipient = to.trim_matches(['<', '>']).to_string();
+            b"250 ok\r\n"
+        } else if line == "DATA" {
+            let _ = writer.write_all(b"354 go ahead\r\n").await;
+            let mut data = String::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line == "." {
+                    break;
+                }
+                data.push_str(&line);
+                data.push('\n');
+            }
+            let _ = messages.send(Mail {
+                recipient: recipient.clone(),
+                data: decode_body(data),
+            });
+            b"250 queued\r\n"
+        } else if line == "QUIT" {
+            let _ = writer.write_all(b"221 bye\r\n").await;
+            return;
+        } else {
+            b"250 ok\r\n"
+        };
+        let _ = writer.write_all(reply).await;
+    }
+}
+
+// The message with a quoted-printable body decoded; bodies with lines as long
+// as the reset link are sent that way
+fn decode_body(data: String) -> String {
+    let Some((headers, body)) = data.split_once("\n\n") else {
+        return data;
+    };
+    if !headers.contains("Content-Transfer-Encoding: quoted-printable") {
+        return data;
+    }
+    let body = body.replace("=\n", "");
+    let mut decoded = Vec::new();
+    let mut bytes = body.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'=' {
+            let hex: String = bytes.by_ref().take(2).map(char::from).collect();
+            decoded.push(u8::from_str_radix(&hex, 16).expect("Bad quoted-printable escape"));
+        } else {
+            decoded.push(byte);
+        }
+    }
+    format!("{}\n\n{}", headers, String::from_utf8(decoded).unwrap())
+}
+
+async fn spawn_app(sink: &SmtpSink) -> TestApp {
+    TestApp::spawn_with_config(&format!(
+        "[mail]\nsmtp_url = \"{}\"\n\
+         [password_reset]\nurl = \"https://blog.example.com/reset?token={{token}}\"\n",
+        sink.url
+    ))
+    .await
+}
+
+async fn post_with_token(
+    app: &TestApp,
+    path: &str,
+    token: &str,
+    body: serde_json::Value,
+) -> Response {
+    app.client
+        .post(app.url(path))
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+// 401 for a revoked token, 403 for a valid one without the audit permission
+async fn audit_status(app: &TestApp, token: &str) -> StatusCode {
+    app.client
+        .get(app.url("/api/audit"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+#[tokio::test]
+pub async fn forgotten_passwords_are_reset_with_a_mailed_token() {
+    let mut sink = SmtpSink::spawn().await;
+    let app = spawn_app(&sink).await;
+    let user = app.create_user("alice").await;
+    let session = app.login(&user).await;
+
+    // Unknown addresses get the same answer, and no mail
+    for email in ["nobody@example.com", "alice@example.com"] {
+        let response = app
+            .post_json("/api/auth/password/forgot", &json!({ "email": email }))
+            .await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+    }
+    let mail = sink.next().await;
+    assert_eq!(mail.recipient, "alice@example.com");
+    assert!(mail.data.contains("https://blog.example.com/reset?token="));
+    let token = mail.token();
+    assert_eq!(token.len(), 64);
+
+    let body = json!({ "token": token, "new_password": NEW_PASSWORD });
+    let response = app.post_json("/api/auth/password/reset", &body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // Tokens work once
+    let response = app.post_json("/api/auth/password/reset", &body).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(audit_status(&app, &session).await, StatusCode::UNAUTHORIZED);
+    let login = |password: &str| json!({ "login": "alice", "password": password });
+    let response = app
+        .post_json("/api/auth/login", &login(TEST_PASSWORD))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.post_json("/api/auth/login", &login(NEW_PASSWORD)).await;
+    data(response, StatusCode::OK).await;
+}
+
+#[tokio::test]
+pub async fn passwords_are_changed_with_the_current_one() {
+    let mut sink = SmtpSink::spawn().await;
+    let app = spawn_app(&sink).await;
+    let user = app.create_user("alice").await;
+    let session = app.login(&user).await;
+    let response = app
+        .post_json(
+            "/api/auth/password/forgot",
+            &json!({ "email": "alice@example.com" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let reset_token = sink.next().await.token();
+    let change =
+        |current: &str| json!({ "current_password": current, "new_password": NEW_PASSWORD });
+
+    let response = app
+        .post_json("/api/auth/password/change", &change(TEST_PASSWORD))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response =
+        post_with_token(&app, "/api/auth/password/change", &session, change("wrong")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = post_with_token(
+        &app,
+        "/api/auth/password/change",
+        &session,
+        change(TEST_PASSWORD),
+    )
+    .await;
+    let token = data(response, StatusCode::OK).await;
+    let new_session = token["access_token"].as_str().unwrap();
+
+    // Only the token handed out with the change still works
+    assert_eq!(audit_status(&app, &session).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(audit_status(&app, new_session).await, StatusCode::FORBIDDEN);
+    let response = post_with_token(
+        &app,
+        "/api/graphql",
+        &session,
+        json!({ "query": "{ viewer { id } }" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Reset links mailed before the change no longer work
+    let body = json!({ "token": reset_token, "new_password": TEST_PASSWORD });
+    let response = app.post_json("/api/auth/password/reset", &body).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
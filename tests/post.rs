@@ -0,0 +1,263 @@
+mod common;
+
+use common::{data, TestApp};
+use reqwest::{header, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+pub async fn create_post() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+
+    let post = app.create_post(&user, "Hello").await;
+
+    assert_eq!(post["title"], "Hello");
+    assert_eq!(post["status"], "Draft");
+    assert_eq!(post["user_id"], user["id"]);
+    // Posts without an explicit excerpt get one generated from the content
+    assert_eq!(post["excerpt"], "Post content");
+}
+
+#[tokio::test]
+pub async fn create_post_with_unknown_featured_media_is_rejected() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+
+    let body = json!({
+        "title": "Hello",
+        "content": "Post content",
+        "status": "Draft",
+        "user_id": user["id"],
+        "featured_media_id": Uuid::new_v4(),
+    });
+    let response = app.post_json("/api/post", &body).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+pub async fn registered_media_can_be_featured_and_cleared() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let media = json!({
+        "file_name": "hero.png",
+        "url": "https://cdn.example.com/hero.png",
+        "mime_type": "image/png",
+        "alt_text": "A hero image",
+    });
+    let response = app.post_json("/api/media", &media).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.post_json_as_root("/api/media", &media).await;
+    let media = data(response, StatusCode::CREATED).await;
+    assert_eq!(media["url"], "https://cdn.example.com/hero.png");
+
+    let body = json!({
+        "title": "Hello",
+        "content": "Post content",
+        "status": "Draft",
+        "user_id": user["id"],
+        "featured_media_id": media["id"],
+        "og_title": "Shared title",
+    });
+    let response = app.post_json("/api/post", &body).await;
+    let post = data(response, StatusCode::CREATED).await;
+    assert_eq!(post["featured_media_id"], media["id"]);
+
+    // An explicit null clears a field, leaving it out keeps it
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let body = json!({ "id": post["id"], "featured_media_id": null });
+    let etag = app.etag(&path).await;
+    let response = app.put_json_if_match(&path, &body, &etag).await;
+    let updated = data(response, StatusCode::OK).await;
+    assert!(updated["featured_media_id"].is_null());
+    assert_eq!(updated["og_title"], "Shared title");
+}
+
+#[tokio::test]
+pub async fn list_posts() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    app.create_post(&user, "First").await;
+    app.create_post(&user, "Second").await;
+
+    let posts = data(app.get("/api/post").await, StatusCode::OK).await;
+
+    assert_eq!(posts["posts"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+pub async fn search_posts() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    app.create_post(&user, "Gardening for beginners").await;
+    app.create_post(&user, "Baking bread").await;
+
+    let posts = data(
+        app.get("/api/post/search?q=gardening").await,
+        StatusCode::OK,
+    )
+    .await;
+    let posts = posts["posts"].as_array().unwrap();
+
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0]["title"], "Gardening for beginners");
+}
+
+#[tokio::test]
+pub async fn search_requires_a_query() {
+    let app = TestApp::spawn().await;
+
+    let response = app.get("/api/post/search?q=").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+pub async fn get_post_by_id() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let found = data(app.get(&path).await, StatusCode::OK).await;
+
+    assert_eq!(found, post);
+}
+
+#[tokio::test]
+pub async fn update_post() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let body = json!({
+        "id": post["id"],
+        "status": "Published",
+        "meta_description": "A greeting",
+    });
+    let etag = app.etag(&path).await;
+    let response = app.put_json_if_match(&path, &body, &etag).await;
+    let updated = data(response, StatusCode::OK).await;
+
+    assert_eq!(updated["status"], "Published");
+    assert_eq!(updated["meta_description"], "A greeting");
+    assert_eq!(updated["title"], "Hello");
+}
+
+#[tokio::test]
+pub async fn delete_post() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let response = app.delete_if_match(&path, &etag).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let posts = data(app.get("/api/post").await, StatusCode::OK).await;
+    assert!(posts["posts"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+pub async fn unchanged_post_is_not_modified() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let response = app.get(&path).await;
+    let etag = response.headers()[header::ETAG].clone();
+    let last_modified = response.headers()[header::LAST_MODIFIED].clone();
+
+    let revalidate = |name, value| app.client.get(app.url(&path)).header(name, value).send();
+    let response = revalidate(header::IF_NONE_MATCH, etag.clone())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], etag);
+    let response = revalidate(header::IF_MODIFIED_SINCE, last_modified)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    let list_etag = app.etag("/api/post").await;
+    app.create_post(&user, "Again").await;
+    let response = app
+        .client
+        .get(app.url("/api/post"))
+        .header(header::IF_NONE_MATCH, list_etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+pub async fn stale_update_is_rejected() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let first = json!({ "id": post["id"], "title": "First edit" });
+    let response = app.put_json_if_match(&path, &first, &etag).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers()[header::ETAG], etag.as_str());
+
+    let second = json!({ "id": post["id"], "title": "Second edit" });
+    let response = app.put_json_if_match(&path, &second, &etag).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let response = app.delete_if_match(&path, &etag).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    let found = data(app.get(&path).await, StatusCode::OK).await;
+    assert_eq!(found["title"], "First edit");
+}
+
+#[tokio::test]
+pub async fn writes_that_change_nothing_still_get_a_new_etag() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let same = json!({ "id": post["id"], "title": "Hello" });
+    let response = app.put_json_if_match(&path, &same, &etag).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers()[header::ETAG], etag.as_str());
+
+    // Within the same second, so `updated_at` alone couldn't tell them apart
+    let response = app.put_json_if_match(&path, &same, &etag).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+}
+
+#[tokio::test]
+pub async fn writes_to_missing_post_are_not_found() {
+    let app = TestApp::spawn().await;
+    let id = Uuid::new_v4();
+    let path = format!("/api/post/{}", id);
+    let body = json!({ "id": id, "title": "Edited" });
+    let response = app.put_json_if_match(&path, &body, "*").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.delete_if_match(&path, "*").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+pub async fn writes_require_if_match() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let body = json!({ "id": post["id"], "title": "Edited" });
+    let response = app.put_json(&path, &body).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+    let response = app.delete(&path).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+}
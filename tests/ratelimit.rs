@@ -0,0 +1,83 @@
+mod common;
+
+use common::{data, TestApp, TEST_PASSWORD};
+use reqwest::{Response, StatusCode};
+use serde_json::json;
+
+fn header<'a>(response: &'a Response, name: &str) -> &'a str {
+    response.headers()[name].to_str().unwrap()
+}
+
+#[tokio::test]
+pub async fn logins_have_a_stricter_limit() {
+    let app = TestApp::spawn_with_config("[rate_limit]\nlogin_requests = 3\n").await;
+    // The admin that seeds alice takes the first login
+    let user = app.create_user("alice").await;
+    app.login(&user).await;
+
+    let body = json!({ "login": "alice", "password": TEST_PASSWORD });
+    let response = app.post_json("/api/auth/login", &body).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "ratelimit-limit"), "3");
+    assert_eq!(header(&response, "ratelimit-remaining"), "0");
+
+    let response = app.post_json("/api/auth/login", &body).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    // One request every 20 seconds, less the time the logins took
+    let retry_after: u64 = header(&response, "retry-after").parse().unwrap();
+    assert!((1..=20).contains(&retry_after));
+    let reset: u64 = header(&response, "ratelimit-reset").parse().unwrap();
+    assert!((41..=60).contains(&reset));
+
+    // Other route groups have quotas of their own
+    let response = app.get("/api/post").await;
+    assert_eq!(header(&response, "ratelimit-limit"), "600");
+    data(response, StatusCode::OK).await;
+}
+
+#[tokio::test]
+pub async fn signed_in_users_have_a_quota_of_their_own() {
+    let app = TestApp::spawn_with_config("[rate_limit]\nrequests = 1\n").await;
+    let user = app.create_user("alice").await;
+    let token = app.login(&user).await;
+
+    assert_eq!(app.get("/api/post").await.status(), StatusCode::OK);
+    assert_eq!(
+        app.get("/api/post").await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    let response = app
+        .client
+        .get(app.url("/api/post"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Health checks are never limited
+    for _ in 0..3 {
+        assert_eq!(app.get("/api/health/live").await.status(), StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+pub async fn clients_behind_a_trusted_proxy_are_told_apart() {
+    let app = TestApp::spawn_with_config(
+        "[rate_limit]\nrequests = 1\ntrusted_proxies = [\"127.0.0.0/8\"]\n",
+    )
+    .await;
+    let get = |client_ip: &'static str| {
+        app.client
+            .get(app.url("/api/post"))
+            .header("X-Forwarded-For", format!("{}, 127.0.0.2", client_ip))
+            .send()
+    };
+
+    let response = get("192.0.2.1").await.expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = get("192.0.2.1").await.expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let response = get("192.0.2.2").await.expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::OK);
+}
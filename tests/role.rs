@@ -0,0 +1,136 @@
+mod common;
+
+use common::{data, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+#[tokio::test]
+pub async fn create_role() {
+    let app = TestApp::spawn().await;
+
+    let role = app.create_role("reviewer").await;
+
+    assert_eq!(role["role_name"], "reviewer");
+    assert_eq!(role["description"], "Test role");
+}
+
+#[tokio::test]
+pub async fn list_roles() {
+    let app = TestApp::spawn().await;
+    app.create_role("reviewer").await;
+    app.create_role("writer").await;
+
+    let roles = data(app.get("/api/role").await, StatusCode::OK).await;
+
+    // Besides the four seeded ones
+    assert_eq!(roles["roles"].as_array().unwrap().len(), 6);
+}
+
+#[tokio::test]
+pub async fn get_role_by_id() {
+    let app = TestApp::spawn().await;
+    let role = app.create_role("reviewer").await;
+
+    let path = format!("/api/role/{}", role["id"].as_str().unwrap());
+    let found = data(app.get(&path).await, StatusCode::OK).await;
+
+    assert_eq!(found, role);
+}
+
+#[tokio::test]
+pub async fn get_role_with_invalid_id_is_rejected() {
+    let app = TestApp::spawn().await;
+
+    let response = app.get("/api/role/not-a-uuid").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+pub async fn update_role() {
+    let app = TestApp::spawn().await;
+    let role = app.create_role("reviewer").await;
+
+    let path = format!("/api/role/{}", role["id"].as_str().unwrap());
+    let body = json!({ "id": role["id"], "role_name": "chief-reviewer" });
+    let etag = app.etag(&path).await;
+    let response = app.put_json_if_match_as_root(&path, &body, &etag).await;
+    let updated = data(response, StatusCode::OK).await;
+
+    assert_eq!(updated["role_name"], "chief-reviewer");
+    assert_eq!(updated["description"], "Test role");
+}
+
+#[tokio::test]
+pub async fn delete_role() {
+    let app = TestApp::spawn().await;
+    let role = app.create_role("reviewer").await;
+
+    let path = format!("/api/role/{}", role["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let response = app.delete_if_match_as_root(&path, &etag).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let roles = data(app.get("/api/role").await, StatusCode::OK).await;
+    let roles = roles["roles"].as_array().unwrap();
+    assert!(roles.iter().all(|found| found["id"] != role["id"]));
+}
+
+#[tokio::test]
+pub async fn writes_to_missing_role_are_not_found() {
+    let app = TestApp::spawn().await;
+    let id = Uuid::new_v4();
+    let path = format!("/api/role/{}", id);
+    let body = json!({ "id": id, "role_name": "reviewer" });
+    let response = app.put_json_if_match_as_root(&path, &body, "*").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = app.delete_if_match_as_root(&path, "*").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+pub async fn deleting_a_role_with_users_needs_reassign_to() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let other = app.create_role("other").await;
+
+    let path = format!("/api/role/{}", alice["role_id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let response = app.delete_if_match_as_root(&path, &etag).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let to_other = format!("{}?reassign_to={}", path, other["id"].as_str().unwrap());
+    let response = app.delete_if_match_as_root(&to_other, &etag).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let user_path = format!("/api/user/{}", alice["id"].as_str().unwrap());
+    let user = data(app.get(&user_path).await, StatusCode::OK).await;
+    assert_eq!(user["role_id"], other["id"]);
+}
+
+#[tokio::test]
+pub async fn role_writes_need_role_manage() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let alice_token = app.login(&alice).await;
+    let path = format!("/api/role/{}", alice["role_id"].as_str().unwrap());
+    let body = json!({ "role_name": "reviewer", "description": "Test role" });
+    let etag = app.etag(&path).await;
+
+    let anonymous = app.post_json("/api/role", &body).await;
+    let forbidden = app
+        .client
+        .put(app.url(&path))
+        .bearer_auth(&alice_token)
+        .header("If-Match", &etag)
+        .json(&json!({ "id": alice["role_id"], "role_name": "reviewer" }))
+        .send()
+        .await
+        .unwrap();
+    let anonymous_delete = app.delete_if_match(&path, &etag).await;
+
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+    assert_eq!(anonymous_delete.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(app.etag(&path).await, etag);
+}
@@ -0,0 +1,78 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{routing::get, Router};
+use blog_cms::shutdown::{self, Shutdown};
+use tokio::sync::Notify;
+
+#[tokio::test]
+pub async fn in_flight_requests_complete_during_shutdown() {
+    let started = Arc::new(Notify::new());
+    let handler_started = started.clone();
+    let router = Router::new().route(
+        "/slow",
+        get(move || async move {
+            handler_started.notify_one();
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            "done"
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind listener");
+    let address = listener.local_addr().unwrap();
+    let shutdown = Shutdown::new();
+    let server = tokio::spawn(shutdown::serve(
+        listener,
+        router,
+        shutdown.clone(),
+        Duration::from_secs(5),
+    ));
+
+    let request = tokio::spawn(async move {
+        reqwest::get(format!("http://{}/slow", address))
+            .await
+            .expect("Failed to execute request.")
+    });
+    // Shut down while the request is being handled
+    started.notified().await;
+    shutdown.trigger();
+
+    let response = request.await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.text().await.unwrap(), "done");
+
+    server
+        .await
+        .unwrap()
+        .expect("Server failed to shut down cleanly");
+    assert!(reqwest::get(format!("http://{}/slow", address))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+pub async fn workers_are_stopped_during_shutdown() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind listener");
+    let shutdown = Shutdown::new();
+    let (stopped_tx, stopped_rx) = tokio::sync::oneshot::channel();
+    shutdown.spawn_worker(|token| async move {
+        token.cancelled().await;
+        stopped_tx.send(()).unwrap();
+    });
+
+    let server = tokio::spawn(shutdown::serve(
+        listener,
+        Router::new(),
+        shutdown.clone(),
+        Duration::from_secs(5),
+    ));
+    shutdown.trigger();
+
+    server
+        .await
+        .unwrap()
+        .expect("Server failed to shut down cleanly");
+    stopped_rx.await.expect("Worker was not stopped");
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use common::{data, TestApp, TEST_PASSWORD};
+use reqwest::{Response, StatusCode};
+use serde_json::{json, Value};
+
+const CREATE_COMMENT: &str = r#"
+    mutation ($postId: UUID!, $content: String!) {
+        createComment(postId: $postId, content: $content) { id content }
+    }
+"#;
+
+// A user allowed to manage the trash, and their bearer token
+async fn trash_manager(app: &TestApp) -> (Value, String) {
+    let admin = app.create_user("admin").await;
+    app.grant(&admin, &["trash:manage"]).await;
+    let token = app.login(&admin).await;
+    (admin, token)
+}
+
+async fn trash(app: &TestApp, token: &str, query: &str) -> Vec<Value> {
+    let response = app
+        .client
+        .get(app.url(&format!("/api/trash{}", query)))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let trash = data(response, StatusCode::OK).await;
+    trash["items"].as_array().unwrap().clone()
+}
+
+async fn restore(app: &TestApp, token: &str, item_type: &str, item: &Value) -> Response {
+    let path = format!(
+        "/api/trash/{}/{}/restore",
+        item_type,
+        item["id"].as_str().unwrap()
+    );
+    app.client
+        .post(app.url(&path))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn delete(app: &TestApp, path: &str) {
+    let etag = app.etag(path).await;
+    let response = app.delete_if_match_as_root(path, &etag).await;
+    assert!(response.status().is_success());
+}
+
+#[tokio::test]
+pub async fn trash_needs_the_trash_manage_permission() {
+    let app = TestApp::spawn().await;
+    assert_eq!(
+        app.get("/api/trash").await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let user = app.create_user("alice").await;
+    let token = app.login(&user).await;
+    let response = app
+        .client
+        .get(app.url("/api/trash"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+pub async fn deleted_posts_are_hidden_until_restored() {
+    let app = TestApp::spawn().await;
+    let (_, token) = trash_manager(&app).await;
+    let alice = app.create_user("alice").await;
+    let post = app.create_post(&alice, "Hello").await;
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+
+    delete(&app, &path).await;
+    let posts = data(app.get("/api/post").await, StatusCode::OK).await;
+    assert!(posts["posts"].as_array().unwrap().is_empty());
+    let items = trash(&app, &token, "?type=post").await;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["type"], "post");
+    assert_eq!(items[0]["id"], post["id"]);
+    assert_eq!(items[0]["label"], "Hello");
+
+    data(restore(&app, &token, "post", &post).await, StatusCode::OK).await;
+    let restored = data(app.get(&path).await, StatusCode::OK).await;
+    assert_eq!(restored["title"], "Hello");
+    assert!(trash(&app, &token, "").await.is_empty());
+    assert_eq!(
+        restore(&app, &token, "post", &post).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+pub async fn deleting_a_user_trashes_their_content_until_they_are_restored() {
+    let app = TestApp::spawn().await;
+    let (_, token) = trash_manager(&app).await;
+    let alice = app.create_user("alice").await;
+    let bob = app.create_user("bob").await;
+    let post = app.create_post(&alice, "Hello").await;
+    let variables = json!({ "postId": post["id"], "content": "Nice post" });
+    let created = app
+        .graphql(CREATE_COMMENT, variables, Some(&app.login(&bob).await))
+        .await;
+    let comment = &created["data"]["createComment"];
+
+    let path = format!("/api/user/{}?cascade=true", alice["id"].as_str().unwrap());
+    delete(&app, &path).await;
+    let posts = data(app.get("/api/post").await, StatusCode::OK).await;
+    assert!(posts["posts"].as_array().unwrap().is_empty());
+    let items = trash(&app, &token, "").await;
+    assert_eq!(items.len(), 3);
+    assert!(items
+        .iter()
+        .all(|item| item["deleted_at"] == items[0]["deleted_at"]));
+
+    // Nothing of alice's comes back on its own
+    assert_eq!(
+        restore(&app, &token, "post", &post).await.status(),
+        StatusCode::CONFLICT
+    );
+    assert_eq!(
+        restore(&app, &token, "comment", comment).await.status(),
+        StatusCode::CONFLICT
+    );
+
+    data(restore(&app, &token, "user", &alice).await, StatusCode::OK).await;
+    assert!(trash(&app, &token, "").await.is_empty());
+    let query = "query ($id: UUID!) { post(id: $id) { comments { content } } }";
+    let body = app.graphql(query, json!({ "id": post["id"] }), None).await;
+    assert_eq!(body["data"]["post"]["comments"][0]["content"], "Nice post");
+}
+
+#[tokio::test]
+pub async fn purged_items_are_gone_for_good() {
+    let app = TestApp::spawn().await;
+    let (_, token) = trash_manager(&app).await;
+    let alice = app.create_user("alice").await;
+    let path = format!("/api/user/{}", alice["id"].as_str().unwrap());
+
+    // Only trashed items can be purged
+    let purge_path = format!("/api/trash/user/{}", alice["id"].as_str().unwrap());
+    let purge = || {
+        app.client
+            .delete(app.url(&purge_path))
+            .bearer_auth(&token)
+            .send()
+    };
+    let response = purge().await.expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    delete(&app, &path).await;
+    let response = purge().await.expect("Failed to execute request.");
+    data(response, StatusCode::OK).await;
+    assert!(trash(&app, &token, "").await.is_empty());
+    assert_eq!(
+        restore(&app, &token, "user", &alice).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+pub async fn trashed_users_keep_their_username_and_email_until_purged() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let body = json!({
+        "username": "alice",
+        "email": "alice@example.com",
+        "password": TEST_PASSWORD,
+        "role_id": alice["role_id"],
+    });
+    let response = app.post_json("/api/user", &body).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let error: Value = response.json().await.unwrap();
+    assert!(!error["errors"].as_str().unwrap().contains("trash"));
+
+    let id = alice["id"].as_str().unwrap();
+    delete(&app, &format!("/api/user/{}", id)).await;
+    let response = app.post_json("/api/user", &body).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let error: Value = response.json().await.unwrap();
+    let errors = error["errors"].as_str().unwrap();
+    assert!(errors.contains(&format!("/api/trash/user/{}/restore", id)));
+}
@@ -0,0 +1,207 @@
+mod common;
+
+use common::{data, TestApp};
+use reqwest::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+pub async fn create_user() {
+    let app = TestApp::spawn().await;
+
+    let user = app.create_user("alice").await;
+
+    assert_eq!(user["username"], "alice");
+    assert_eq!(user["email"], "alice@example.com");
+    // The password hash never leaves the server
+    assert!(user.get("password").is_none());
+    assert!(user.get("password_hash").is_none());
+}
+
+#[tokio::test]
+pub async fn list_users() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice").await;
+    app.create_user("bob").await;
+
+    let users = data(app.get("/api/user").await, StatusCode::OK).await;
+
+    // Besides the admin that created them
+    assert_eq!(users["users"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+pub async fn get_user_by_id() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+
+    let path = format!("/api/user/{}", user["id"].as_str().unwrap());
+    let found = data(app.get(&path).await, StatusCode::OK).await;
+
+    assert_eq!(found, user);
+}
+
+#[tokio::test]
+pub async fn get_user_with_invalid_id_is_rejected() {
+    let app = TestApp::spawn().await;
+
+    let response = app.get("/api/user/not-a-uuid").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+pub async fn update_user() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+
+    let path = format!("/api/user/{}", user["id"].as_str().unwrap());
+    let body = json!({ "id": user["id"], "email": "alice@example.org" });
+    let etag = app.etag(&path).await;
+    let response = app.put_json_if_match_as_root(&path, &body, &etag).await;
+    let updated = data(response, StatusCode::OK).await;
+
+    assert_eq!(updated["username"], "alice");
+    assert_eq!(updated["email"], "alice@example.org");
+}
+
+#[tokio::test]
+pub async fn delete_user() {
+    let app = TestApp::spawn().await;
+    let user = app.create_user("alice").await;
+
+    let path = format!("/api/user/{}", user["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let response = app.delete_if_match_as_root(&path, &etag).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let users = data(app.get("/api/user").await, StatusCode::OK).await;
+    let users = users["users"].as_array().unwrap();
+    assert!(users.iter().all(|found| found["id"] != user["id"]));
+}
+
+#[tokio::test]
+pub async fn deleting_a_user_with_content_needs_reassign_to_or_cascade() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let bob = app.create_user("bob").await;
+    app.create_post(&alice, "Hello").await;
+    let path = format!("/api/user/{}", alice["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+
+    let response = app.delete_if_match_as_root(&path, &etag).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let to_self = format!("{}?reassign_to={}", path, alice["id"].as_str().unwrap());
+    let response = app.delete_if_match_as_root(&to_self, &etag).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let both = format!(
+        "{}?reassign_to={}&cascade=true",
+        path,
+        bob["id"].as_str().unwrap()
+    );
+    let response = app.delete_if_match_as_root(&both, &etag).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let to_bob = format!("{}?reassign_to={}", path, bob["id"].as_str().unwrap());
+    let response = app.delete_if_match_as_root(&to_bob, &etag).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bobs_posts = format!("/api/user/{}/posts", bob["id"].as_str().unwrap());
+    let posts = data(app.get(&bobs_posts).await, StatusCode::OK).await;
+    assert_eq!(posts["posts"][0]["title"], "Hello");
+}
+
+#[tokio::test]
+pub async fn cascading_user_deletion_trashes_their_content() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    app.create_post(&alice, "Hello").await;
+
+    let path = format!("/api/user/{}", alice["id"].as_str().unwrap());
+    let etag = app.etag(&path).await;
+    let response = app
+        .delete_if_match_as_root(&format!("{}?cascade=true", path), &etag)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let posts = data(app.get("/api/post").await, StatusCode::OK).await;
+    assert!(posts["posts"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+pub async fn get_posts_by_user() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let bob = app.create_user("bob").await;
+    app.create_post(&alice, "First").await;
+    app.create_post(&alice, "Second").await;
+    app.create_post(&bob, "Other").await;
+
+    let path = format!("/api/user/{}/posts", alice["id"].as_str().unwrap());
+    let posts = data(app.get(&path).await, StatusCode::OK).await;
+
+    assert_eq!(posts["posts"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+pub async fn registration_can_be_disabled() {
+    // A config file rather than FEATURE_REGISTRATION, which would leak into
+    // tests running in parallel
+    let app = TestApp::spawn_with_config("[features]\nregistration = false\n").await;
+    let role = app.create_role("writer").await;
+
+    let body = json!({
+        "username": "alice",
+        "email": "alice@example.com",
+        "password": "correct horse battery staple",
+        "role_id": role["id"],
+    });
+    let response = app.post_json("/api/user", &body).await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+pub async fn sign_up_ignores_role_id_without_user_manage() {
+    let app = TestApp::spawn().await;
+    app.seed().await;
+    let roles = data(app.get("/api/role").await, StatusCode::OK).await;
+    let role_id = |name: &str| {
+        let roles = roles["roles"].as_array().unwrap();
+        let role = roles.iter().find(|role| role["role_name"] == name);
+        role.unwrap()["id"].clone()
+    };
+
+    let body = json!({
+        "username": "mallory",
+        "email": "mallory@example.com",
+        "password": "correct horse battery staple",
+        "role_id": role_id("admin"),
+    });
+    let user = data(app.post_json("/api/user", &body).await, StatusCode::CREATED).await;
+
+    assert_eq!(user["role_id"], role_id("subscriber"));
+}
+
+#[tokio::test]
+pub async fn user_writes_need_user_manage() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice").await;
+    let bob = app.create_user("bob").await;
+    let bob_token = app.login(&bob).await;
+    let path = format!("/api/user/{}", alice["id"].as_str().unwrap());
+    let body = json!({ "id": alice["id"], "role_id": bob["role_id"] });
+    let etag = app.etag(&path).await;
+
+    let anonymous = app.put_json_if_match(&path, &body, &etag).await;
+    let forbidden = app
+        .client
+        .delete(app.url(&path))
+        .bearer_auth(&bob_token)
+        .header("If-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+    assert_eq!(app.etag(&path).await, etag);
+}
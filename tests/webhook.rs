@@ -0,0 +1,359 @@
+mod common;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode as ReceiverStatus},
+    routing::post,
+    Router,
+};
+use common::{data, TestApp};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, Response, StatusCode};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tokio::sync::mpsc;
+
+const SECRET: &str = "receiver-secret";
+
+// A request received by the local webhook receiver
+struct Received {
+    headers: HeaderMap,
+    body: String,
+}
+
+struct Receiver {
+    url: String,
+    requests: mpsc::UnboundedReceiver<Received>,
+    // Answer 200 when set, 500 otherwise
+    healthy: Arc<AtomicBool>,
+}
+
+#[derive(Clone)]
+struct ReceiverState {
+    requests: mpsc::UnboundedSender<Received>,
+    healthy: Arc<AtomicBool>,
+}
+
+impl Receiver {
+    async fn spawn(healthy: bool) -> Receiver {
+        let (sender, requests) = mpsc::unbounded_channel();
+        let healthy = Arc::new(AtomicBool::new(healthy));
+        let state = ReceiverState {
+            requests: sender,
+            healthy: healthy.clone(),
+        };
+        let router = Router::new()
+            .route("/hook", post(receive))
+            .with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind receiver");
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        Receiver {
+            url,
+            requests,
+            healthy,
+        }
+    }
+
+    async fn next(&mut self) -> Received {
+        tokio::time::timeout(Duration::from_secs(10), self.requests.recv())
+            .await
+            .expect("No delivery arrived")
+            .expect("Receiver stopped")
+    }
+}
+
+async fn receive(
+    State(state): State<ReceiverState>,
+    headers: HeaderMap,
+    body: String,
+) -> ReceiverStatus {
+    let _ = state.requests.send(Received { headers, body });
+    if state.healthy.load(Ordering::SeqCst) {
+        ReceiverStatus::OK
+    } else {
+        ReceiverStatus::INTERNAL_SERVER_ERROR
+    }
+}
+
+// An app whose webhooks may reach the local receiver, and the bearer token of
+// a user allowed to manage them
+async fn spawn_app(config: &str) -> (TestApp, String) {
+    let app = TestApp::spawn_with_config(&format!(
+        "[webhooks]\nallow_private_targets = true\n{}",
+        config
+    ))
+    .await;
+    let token = webhook_manager(&app).await;
+    (app, token)
+}
+
+async fn webhook_manager(app: &TestApp) -> String {
+    let admin = app.create_user("admin").await;
+    app.grant(&admin, &["webhook:manage"]).await;
+    app.login(&admin).await
+}
+
+async fn send(
+    app: &TestApp,
+    method: Method,
+    path: &str,
+    token: &str,
+    body: Option<Value>,
+) -> Response {
+    let mut request = app.client.request(method, app.url(path)).bearer_auth(token);
+    if let Some(body) = body {
+        request = request.json(&body);
+    }
+    request.send().await.expect("Failed to execute request.")
+}
+
+async fn create_webhook(app: &TestApp, token: &str, url: &str, events: Value) -> Value {
+    let body = json!({ "url": url, "events": events, "secret": SECRET });
+    data(
+        send(app, Method::POST, "/api/webhook", token, Some(body)).await,
+        StatusCode::CREATED,
+    )
+    .await
+}
+
+// Poll the delivery log until `done` accepts it
+async fn wait_for_deliveries(
+    app: &TestApp,
+    token: &str,
+    webhook: &Value,
+    done: impl Fn(&[Value]) -> bool,
+) {
+    let path = format!(
+        "/api/webhook/{}/deliveries",
+        webhook["id"].as_str().unwrap()
+    );
+    for _ in 0..100 {
+        let log = data(
+            send(app, Method::GET, &path, token, None).await,
+            StatusCode::OK,
+        )
+        .await;
+        let deliveries = log["deliveries"].as_array().unwrap().clone();
+        if done(&deliveries) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Deliveries never reached the expected state");
+}
+
+#[tokio::test]
+pub async fn webhooks_can_be_managed() {
+    let (app, token) = spawn_app("").await;
+    let webhook = create_webhook(
+        &app,
+        &token,
+        "http://127.0.0.1:9/hook",
+        json!(["post.updated"]),
+    )
+    .await;
+    assert_eq!(webhook["active"], true);
+    assert!(webhook.get("secret").is_none());
+
+    let path = format!("/api/webhook/{}", webhook["id"].as_str().unwrap());
+    let body = json!({ "events": ["user.created", "comment.created"], "active": false });
+    let updated = data(
+        send(&app, Method::PUT, &path, &token, Some(body)).await,
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(
+        updated["events"],
+        json!(["comment.created", "user.created"])
+    );
+    assert_eq!(updated["active"], false);
+
+    let listed = data(
+        send(&app, Method::GET, "/api/webhook", &token, None).await,
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(listed["webhooks"].as_array().unwrap().len(), 1);
+
+    let invalid = json!({ "url": "ftp://example.com", "events": ["post.updated"], "secret": "s" });
+    let response = send(&app, Method::POST, "/api/webhook", &token, Some(invalid)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let no_events = json!({ "url": "http://example.com", "events": [], "secret": "s" });
+    let response = send(&app, Method::POST, "/api/webhook", &token, Some(no_events)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    data(
+        send(&app, Method::DELETE, &path, &token, None).await,
+        StatusCode::OK,
+    )
+    .await;
+    let response = send(&app, Method::GET, &path, &token, None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+pub async fn webhooks_need_permission_and_public_targets() {
+    let app = TestApp::spawn().await;
+    let body =
+        json!({ "url": "https://example.com/hook", "events": ["user.created"], "secret": "s" });
+    let response = app.post_json("/api/webhook", &body).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        app.get("/api/webhook").await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let user = app.create_user("alice").await;
+    let token = app.login(&user).await;
+    let response = send(
+        &app,
+        Method::POST,
+        "/api/webhook",
+        &token,
+        Some(body.clone()),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let token = webhook_manager(&app).await;
+    create_webhook(
+        &app,
+        &token,
+        "https://example.com/hook",
+        json!(["user.created"]),
+    )
+    .await;
+    for url in [
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://10.1.2.3/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/hook",
+    ] {
+        let body = json!({ "url": url, "events": ["user.created"], "secret": "s" });
+        let response = send(&app, Method::POST, "/api/webhook", &token, Some(body)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", url);
+    }
+}
+
+#[tokio::test]
+pub async fn published_post_is_delivered_signed() {
+    let (app, token) = spawn_app("").await;
+    let mut receiver = Receiver::spawn(true).await;
+    let webhook = create_webhook(
+        &app,
+        &token,
+        &receiver.url,
+        json!(["post.published", "post.updated"]),
+    )
+    .await;
+    let user = app.create_user("alice").await;
+    let post = app.create_post(&user, "Hello").await;
+
+    let path = format!("/api/post/{}", post["id"].as_str().unwrap());
+    let body = json!({ "id": post["id"], "status": "Published" });
+    let etag = app.etag(&path).await;
+    data(
+        app.put_json_if_match(&path, &body, &etag).await,
+        StatusCode::OK,
+    )
+    .await;
+
+    let mut events = Vec::new();
+    for _ in 0..2 {
+        let request = receiver.next().await;
+        let signature = request.headers["x-webhook-signature"].to_str().unwrap();
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(request.body.as_bytes());
+        let expected = format!("sha256={:x}", mac.finalize().into_bytes());
+        assert_eq!(signature, expected);
+
+        let payload: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(
+            request.headers["x-webhook-event"],
+            payload["event"].as_str().unwrap()
+        );
+        assert_eq!(payload["data"]["id"], post["id"]);
+        assert_eq!(payload["data"]["status"], "Published");
+        events.push(payload["event"].as_str().unwrap().to_string());
+    }
+    events.sort();
+    assert_eq!(events, ["post.published", "post.updated"]);
+
+    wait_for_deliveries(&app, &token, &webhook, |deliveries| {
+        deliveries.len() == 2
+            && deliveries.iter().all(|delivery| {
+                delivery["status"] == "delivered"
+                    && delivery["response_code"] == 200
+                    && delivery["attempts"] == 1
+            })
+    })
+    .await;
+}
+
+#[tokio::test]
+pub async fn failed_delivery_is_retried_and_can_be_redelivered() {
+    let (app, token) = spawn_app("max_attempts = 2\nretry_base_delay_secs = 1\n").await;
+    let mut receiver = Receiver::spawn(false).await;
+    let webhook = create_webhook(&app, &token, &receiver.url, json!(["user.created"])).await;
+
+    let user = app.create_user("alice").await;
+    let first = receiver.next().await;
+    let second = receiver.next().await;
+    // Retries carry the same delivery id and body
+    assert_eq!(
+        first.headers["x-webhook-delivery"],
+        second.headers["x-webhook-delivery"]
+    );
+    assert_eq!(first.body, second.body);
+    let payload: Value = serde_json::from_str(&first.body).unwrap();
+    assert_eq!(payload["event"], "user.created");
+    assert_eq!(payload["data"]["id"], user["id"]);
+
+    wait_for_deliveries(&app, &token, &webhook, |deliveries| {
+        deliveries.len() == 1
+            && deliveries[0]["status"] == "failed"
+            && deliveries[0]["attempts"] == 2
+            && deliveries[0]["response_code"] == 500
+    })
+    .await;
+
+    // Once the receiver recovers, the payload can be sent again
+    receiver.healthy.store(true, Ordering::SeqCst);
+    let path = format!(
+        "/api/webhook/{}/deliveries",
+        webhook["id"].as_str().unwrap()
+    );
+    let log = data(
+        send(&app, Method::GET, &path, &token, None).await,
+        StatusCode::OK,
+    )
+    .await;
+    let failed = &log["deliveries"][0];
+    let redeliver = format!("{}/{}/redeliver", path, failed["id"].as_str().unwrap());
+    let redelivery = data(
+        send(&app, Method::POST, &redeliver, &token, None).await,
+        StatusCode::ACCEPTED,
+    )
+    .await;
+    assert_ne!(redelivery["id"], failed["id"]);
+
+    assert_eq!(receiver.next().await.body, first.body);
+    wait_for_deliveries(&app, &token, &webhook, |deliveries| {
+        deliveries
+            .iter()
+            .any(|delivery| delivery["id"] == redelivery["id"] && delivery["status"] == "delivered")
+    })
+    .await;
+}